@@ -3,15 +3,17 @@
 use super::{state::CodebaseApp, AppAction};
 use crate::{
     external,
-    fs::{scanner, token_worker},
+    fs::{git, scan_index, scanner, token_worker, watcher},
     llm::gemini_service,
     model::Check,
-    report::{self, ReportFormat, ReportOptions},
+    report::{self, ReportOptions},
     selection,
+    snapshot::{Snapshot, SnapshotDiff},
     task::TaskMessage,
+    workspace::{self, Workspace},
 };
 use arboard::Clipboard;
-use std::{fs as std_fs, path::PathBuf, sync::atomic::Ordering, thread};
+use std::{collections::HashSet, fs as std_fs, path::PathBuf, sync::atomic::Ordering, thread};
 use tokio::runtime::Builder;
 
 impl CodebaseApp {
@@ -27,6 +29,7 @@ impl CodebaseApp {
                 AppAction::DeselectAllNodes => self.perform_deselect_all_nodes(),
                 AppAction::ExpandAllNodes => self.perform_expand_all_nodes(),
                 AppAction::CollapseAllNodes => self.perform_collapse_all_nodes(),
+                AppAction::SetTreeSort(column) => self.perform_set_tree_sort(column),
                 AppAction::SelectAllChildren(id) => self.perform_select_all_children(id),
                 AppAction::DeselectAllChildren(id) => self.perform_deselect_all_children(id),
                 AppAction::OpenNodeExternally(id) => self.perform_open_node_externally(id),
@@ -34,73 +37,370 @@ impl CodebaseApp {
                 AppAction::LoadSelection => self.perform_load_selection(),
                 AppAction::GenerateReport(opts) => self.perform_generate_report(opts),
                 AppAction::CopyReport(opts) => self.perform_copy_report(opts),
+                AppAction::CopyNextReportPart => self.perform_copy_next_report_part(),
                 AppAction::StartScan(path) => self.perform_start_scan(path),
+                AppAction::OpenWorkspace(path) => self.perform_open_workspace(path),
+                AppAction::AddWorkspaceRoot(path) => self.perform_add_workspace_root(path),
+                AppAction::RemoveWorkspaceRoot(path) => self.perform_remove_workspace_root(path),
+                AppAction::SaveWorkspace => self.perform_save_workspace(),
+                AppAction::Rescan => self.perform_rescan(),
                 AppAction::CancelScan => self.perform_cancel_scan(),
+                AppAction::StartWatching => self.perform_start_watching(),
+                AppAction::StopWatching => self.stop_watching(),
+                AppAction::ClearScanIndex => self.perform_clear_scan_index(),
+                AppAction::ClearAllScanIndexes => self.perform_clear_all_scan_indexes(),
+                AppAction::RefreshGitStatus => self.perform_refresh_git_status(),
+                AppAction::SelectChangedFiles(reference) => {
+                    self.perform_select_changed_files(reference)
+                }
+                AppAction::SelectMatching { segment, replace } => {
+                    self.perform_select_matching(segment, replace)
+                }
                 AppAction::CalculateTokens => self.perform_calculate_tokens(),
                 AppAction::CancelTokenCalculation => self.cancel_token_calculation(),
                 AppAction::FocusSearchBox => self.perform_focus_search_box(),
                 AppAction::QueryAI(query) => self.perform_query_ai(query),
+                AppAction::ExportScanErrors => self.perform_export_scan_errors(),
+                AppAction::SaveSnapshot => self.perform_save_snapshot(),
+                AppAction::CompareWithSnapshot => self.perform_compare_with_snapshot(),
+                AppAction::CompareSnapshots => self.perform_compare_snapshots(),
             }
         }
     }
 
     // --- Action Implementations ---
 
+    /// Opens a single directory, replacing any open workspace.
     fn perform_start_scan(&mut self, path: PathBuf) {
-        if self.is_scanning || self.is_generating_report {
-            log::warn!("Cannot start scan: Another background task is running.");
-            self.status_message = "Busy with another task (scan/report).".to_string();
+        if self.is_busy() {
             return;
         }
-        self.cancel_token_calculation();
-        log::info!("Starting scan for directory: {}", path.display());
-        self.root_path = Some(path.clone());
+        // Canonical like workspace roots, so the same directory reached through a symlink
+        // or `..` isn't mistaken for a different root when folders are added later.
+        let path = path.canonicalize().unwrap_or(path);
+        self.workspace = None;
         self.config.add_recent_project(path.clone());
         if let Err(e) = self.config.save() {
             log::error!("Failed to save config after adding recent project: {e}");
         }
+        self.scan_roots(vec![path]);
+    }
+
+    fn perform_open_workspace(&mut self, path: PathBuf) {
+        if self.is_busy() {
+            return;
+        }
+        match Workspace::load(&path) {
+            Ok(loaded) => {
+                let roots = loaded.roots.clone();
+                self.workspace = Some(loaded);
+                self.config.add_recent_project(path);
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config after adding recent project: {e}");
+                }
+                self.scan_roots(roots);
+            }
+            Err(e) => {
+                log::error!("Failed to open workspace: {e}");
+                self.status_message = format!("Error opening workspace: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Open Workspace Failed")
+                    .set_description(format!("Could not open workspace:\n{e}"))
+                    .show();
+            }
+        }
+    }
+
+    /// Adds a directory to the open project, turning it into a workspace if needed.
+    fn perform_add_workspace_root(&mut self, path: PathBuf) {
+        if self.is_busy() {
+            return;
+        }
+        let path = path.canonicalize().unwrap_or(path);
+        let mut roots = self.roots.clone();
+        roots.push(path);
+        if let Err(e) = workspace::validate_roots(&roots) {
+            self.status_message = format!("Cannot add folder: {e}");
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Add Folder to Workspace")
+                .set_description(format!("{e}"))
+                .show();
+            return;
+        }
+        let name = self.project_name();
+        self.workspace
+            .get_or_insert_with(|| Workspace::new(name, Vec::new()))
+            .roots
+            .clone_from(&roots);
+        self.scan_roots(roots);
+    }
+
+    fn perform_remove_workspace_root(&mut self, path: PathBuf) {
+        if self.is_busy() || self.roots.len() < 2 {
+            return;
+        }
+        let roots: Vec<PathBuf> = self.roots.iter().filter(|r| **r != path).cloned().collect();
+        if let Some(workspace) = self.workspace.as_mut() {
+            workspace.roots.clone_from(&roots);
+        }
+        self.scan_roots(roots);
+    }
+
+    fn perform_save_workspace(&mut self) {
+        if self.roots.is_empty() {
+            self.status_message = "No directory open to save as a workspace.".to_string();
+            return;
+        }
+        let name = self.project_name();
+        let default_name = format!("{name}.{}", workspace::WORKSPACE_EXTENSION);
+        let Some(save_path) = rfd::FileDialog::new()
+            .add_filter(
+                "Codebase Viewer Workspace",
+                &[workspace::WORKSPACE_EXTENSION],
+            )
+            .set_file_name(&default_name)
+            .save_file()
+        else {
+            self.status_message = "Save workspace cancelled.".to_string();
+            return;
+        };
+        let roots = self.roots.clone();
+        let workspace = self
+            .workspace
+            .get_or_insert_with(|| Workspace::new(name, roots));
+        match workspace.save(&save_path) {
+            Ok(()) => {
+                self.status_message = format!("Workspace saved to {}", save_path.display());
+                self.config.add_recent_project(save_path);
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config after adding recent project: {e}");
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to save workspace: {e}");
+                self.status_message = format!("Error saving workspace: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Save Workspace Failed")
+                    .set_description(format!("Could not save workspace:\n{e}"))
+                    .show();
+            }
+        }
+    }
+
+    /// Rescans all current roots, e.g. after a scan setting changed.
+    fn perform_rescan(&mut self) {
+        if self.roots.is_empty() || self.is_busy() {
+            return;
+        }
+        self.scan_roots(self.roots.clone());
+    }
+
+    /// Returns `true` (and says so in the status bar) if a scan or report is running.
+    fn is_busy(&mut self) -> bool {
+        if self.is_scanning || self.is_generating_report {
+            log::warn!("Cannot start scan: Another background task is running.");
+            self.status_message = "Busy with another task (scan/report).".to_string();
+            return true;
+        }
+        false
+    }
+
+    /// Clears the tree and scans each root in its own scanner thread.
+    fn scan_roots(&mut self, roots: Vec<PathBuf>) {
+        self.cancel_token_calculation();
+        self.stop_watching();
+        self.git_status = None;
+        log::info!("Starting scan for {} root(s): {:?}", roots.len(), roots);
+        self.roots = roots;
         self.nodes.clear();
-        self.root_id = None;
+        self.root_ids.clear();
         self.selected_node_id = None;
+        self.treemap_root = None;
+        self.treemap_context_node = None;
+        self.marker_list = None;
+        self.preview_target_line = None;
         self.scan_stats = Some(Default::default());
+        self.scan_progress.clear();
         self.preview_cache = None;
         self.content_cache.clear();
-        self.orphaned_children.clear();
+        self.scanned_node_ids.clear();
         self.path_to_id_map.clear();
+        self.watch_token_ids.clear();
         self.tree_rows_cache.clear();
         self.tree_rows_search.clear();
-        self.tree_rows_root_id = None;
+        self.tree_rows_root_ids.clear();
         self.tree_rows_dirty = true;
         self.mark_report_preview_dirty();
-        self.status_message = format!(
-            "Scanning {}...",
-            path.file_name().map_or_else(
-                || path.display().to_string(),
-                |n| n.to_string_lossy().to_string()
-            )
-        );
+        self.status_message = format!("Scanning {}...", self.project_name());
         self.is_scanning = true;
+        self.pending_scans = self.roots.len();
         let (sender, receiver) = crossbeam_channel::unbounded();
         self.scan_receiver = Some(receiver);
-        let (handle, cancel_signal) = scanner::scan(
-            path,
-            self.config.show_hidden_files,
-            self.config.respect_cbvignore,
-            sender,
-        );
-        self.background_task = Some(super::state::BackgroundTask::Scan(handle, cancel_signal));
+        let options = scanner::ScanOptions::from_config(&self.config);
+        let scans = self
+            .roots
+            .iter()
+            .map(|root| scanner::scan(root.clone(), options.clone(), sender.clone()))
+            .collect();
+        self.background_task = Some(super::state::BackgroundTask::Scan(scans));
     }
 
     fn perform_cancel_scan(&mut self) {
-        if let Some(super::state::BackgroundTask::Scan(_, cancel_signal)) = &self.background_task {
+        if let Some(super::state::BackgroundTask::Scan(scans)) = &self.background_task {
             log::info!("Requesting scan cancellation...");
-            cancel_signal.store(true, Ordering::Relaxed);
+            for (_, cancel_signal) in scans {
+                cancel_signal.store(true, Ordering::Relaxed);
+            }
             self.status_message = "Scan cancellation requested...".to_string();
         } else {
             log::warn!("No active scan to cancel.");
         }
     }
 
+    fn perform_start_watching(&mut self) {
+        if !self.watchers.is_empty() || self.is_scanning || self.roots.is_empty() {
+            return;
+        }
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let options = scanner::ScanOptions::from_config(&self.config);
+        for root in self.roots.clone() {
+            match watcher::watch(root.clone(), options.clone(), sender.clone()) {
+                Ok(watch_handle) => {
+                    self.watchers.push(watch_handle);
+                    log::info!("Watching {} for changes.", root.display());
+                }
+                Err(e) => {
+                    log::error!("Failed to start filesystem watcher: {e}");
+                    self.status_message = format!("Could not watch for changes: {e}");
+                }
+            }
+        }
+        if !self.watchers.is_empty() {
+            self.watch_receiver = Some(receiver);
+        }
+    }
+
+    fn perform_clear_scan_index(&mut self) {
+        let mut removed = 0;
+        for root in &self.roots {
+            match scan_index::clear(root) {
+                Ok(cleared) => removed += usize::from(cleared),
+                Err(e) => {
+                    log::error!("Failed to clear scan index: {e}");
+                    self.status_message = format!("Error clearing scan index: {e}");
+                    return;
+                }
+            }
+        }
+        self.status_message = if removed == 0 {
+            "This project has no scan index to clear.".to_string()
+        } else {
+            format!("Cleared the scan index of {removed} root(s).")
+        };
+    }
+
+    fn perform_clear_all_scan_indexes(&mut self) {
+        match scan_index::clear_all() {
+            Ok(count) => {
+                self.status_message = format!("Cleared {count} scan index file(s).");
+            }
+            Err(e) => {
+                log::error!("Failed to clear scan index: {e}");
+                self.status_message = format!("Error clearing scan index: {e}");
+            }
+        }
+    }
+
+    fn perform_refresh_git_status(&mut self) {
+        if self.roots.is_empty() {
+            return;
+        }
+        let roots = self.roots.clone();
+        if !self.config.show_git_status {
+            self.apply_git_status(None);
+            return;
+        }
+        let Some(task_sender) = self.task_sender.clone() else {
+            return;
+        };
+        let result = thread::Builder::new()
+            .name("git_status".to_string())
+            .spawn(move || {
+                let result = git::status_for_roots(&roots).map_err(|e| e.to_string());
+                let _ = task_sender.send(TaskMessage::GitStatus { roots, result });
+            });
+        if let Err(e) = result {
+            log::error!("Failed to spawn git status thread: {e}");
+        }
+    }
+
+    fn perform_select_changed_files(&mut self, reference: String) {
+        if self.roots.is_empty() {
+            return;
+        }
+        let roots = self.roots.clone();
+        let Some(task_sender) = self.task_sender.clone() else {
+            return;
+        };
+        self.status_message = format!("Finding files changed since '{reference}'...");
+        let result = thread::Builder::new()
+            .name("git_changed_files".to_string())
+            .spawn(move || {
+                let result =
+                    git::changed_since_for_roots(&roots, &reference).map_err(|e| e.to_string());
+                let _ = task_sender.send(TaskMessage::ChangedFiles {
+                    roots,
+                    reference,
+                    result,
+                });
+            });
+        if let Err(e) = result {
+            log::error!("Failed to spawn git diff thread: {e}");
+            self.status_message = format!("Error: {e}");
+        }
+    }
+
+    /// Adds the files belonging to a statistics dashboard chart segment to the selection,
+    /// or selects only them with `replace`.
+    fn perform_select_matching(
+        &mut self,
+        segment: crate::ui::dashboard::ChartSegment,
+        replace: bool,
+    ) {
+        let now = std::time::SystemTime::now();
+        let paths: HashSet<PathBuf> = self
+            .nodes
+            .iter()
+            .filter(|node| segment.matches(node, now))
+            .map(|node| node.path().to_path_buf())
+            .collect();
+        let selected = if replace {
+            self.select_only_paths(&paths)
+        } else {
+            self.add_paths_to_selection(&paths)
+        };
+        self.tree_rows_dirty = true;
+        self.mark_report_preview_dirty();
+        self.status_message = if replace {
+            format!("Selected only {selected} {}.", segment.describe())
+        } else {
+            format!("Added {selected} {} to the selection.", segment.describe())
+        };
+        log::info!("{}", self.status_message);
+    }
+
+    /// Stops the filesystem watchers, if any are running.
+    pub(crate) fn stop_watching(&mut self) {
+        for (_, cancel) in self.watchers.drain(..) {
+            log::info!("Stopping filesystem watcher.");
+            cancel.store(true, Ordering::Relaxed);
+        }
+        self.watch_receiver = None;
+    }
+
     fn perform_calculate_tokens(&mut self) {
         if self.is_scanning {
             log::info!("Skipping token calculation while scan is running.");
@@ -109,7 +409,7 @@ impl CodebaseApp {
         if self.is_calculating_tokens {
             return;
         }
-        if self.root_id.is_none() {
+        if self.root_ids.is_empty() {
             return;
         }
         let task_sender = match self.task_sender.clone() {
@@ -120,12 +420,16 @@ impl CodebaseApp {
             }
         };
 
-        for node in &mut self.nodes {
-            node.token_count = None;
-        }
-
         let max_count_size = self.config.max_file_size_token_count;
         let max_cache_size = self.config.max_file_size_token_cache;
+        let reuse_indexed = self.config.use_scan_index;
+
+        for node in &mut self.nodes {
+            // File counts restored from the scan index stay valid; only recount the rest.
+            if !reuse_indexed || node.is_dir() || node.info.size > max_count_size {
+                node.token_count = None;
+            }
+        }
 
         if max_cache_size == 0 {
             self.content_cache.clear();
@@ -151,17 +455,111 @@ impl CodebaseApp {
                     && !node.info.is_binary
                     && max_count_size > 0
                     && node.info.size <= max_count_size
+                    && node.token_count.is_none()
             })
             .map(|(id, node)| token_worker::TokenWorkItem {
                 id,
                 path: node.path().to_path_buf(),
                 is_binary: node.info.is_binary,
+                encoding: node.info.encoding,
             })
             .collect();
 
         if files.is_empty() {
+            log::info!("All token counts restored from the scan index.");
+            self.rollup_root_metrics();
             return;
         }
+        self.spawn_token_job(files, task_sender);
+        if reuse_indexed {
+            self.index_token_job_id = self.token_worker_job_id;
+        }
+    }
+
+    /// Writes the token counts of all files back to the scan index of each root
+    /// in a background thread, so the next scan can restore them. `scan_index::update`
+    /// keeps this from interleaving with a scan saving the same index.
+    pub(super) fn persist_token_counts(&self) {
+        let roots = self.roots.clone();
+        let counts: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|node| !node.is_dir())
+            .filter_map(|node| {
+                node.token_count.map(|count| {
+                    (
+                        node.path().to_path_buf(),
+                        node.info.size,
+                        node.info.modified,
+                        count,
+                    )
+                })
+            })
+            .collect();
+        if counts.is_empty() {
+            return;
+        }
+
+        let result = thread::Builder::new()
+            .name("scan_index_writer".to_string())
+            .spawn(move || {
+                for root in &roots {
+                    let updated = scan_index::update(root, |index| {
+                        for (path, size, modified, count) in &counts {
+                            if path.starts_with(root) {
+                                index.set_token_count(path, *size, *modified, *count);
+                            }
+                        }
+                    });
+                    if let Err(e) = updated {
+                        log::warn!("Failed to save token counts to scan index: {e}");
+                    }
+                }
+            });
+        if let Err(e) = result {
+            log::error!("Failed to spawn scan index writer thread: {e}");
+        }
+    }
+
+    /// Recounts tokens for individual files changed by the filesystem watcher.
+    pub(super) fn recount_watched_tokens(&mut self) {
+        if !self.config.show_token_counts
+            || self.is_calculating_tokens
+            || self.watch_token_ids.is_empty()
+        {
+            return;
+        }
+        let Some(task_sender) = self.task_sender.clone() else {
+            return;
+        };
+        let max_count_size = self.config.max_file_size_token_count;
+        let ids = std::mem::take(&mut self.watch_token_ids);
+        let files: Vec<_> = ids
+            .into_iter()
+            .filter_map(|id| self.nodes.get(id).map(|node| (id, node)))
+            .filter(|(_, node)| {
+                !node.is_dir() && !node.info.is_binary && node.info.size <= max_count_size
+            })
+            .map(|(id, node)| token_worker::TokenWorkItem {
+                id,
+                path: node.path().to_path_buf(),
+                is_binary: node.info.is_binary,
+                encoding: node.info.encoding,
+            })
+            .collect();
+        self.spawn_token_job(files, task_sender);
+    }
+
+    fn spawn_token_job(
+        &mut self,
+        files: Vec<token_worker::TokenWorkItem>,
+        task_sender: crossbeam_channel::Sender<TaskMessage>,
+    ) {
+        if files.is_empty() {
+            return;
+        }
+        let max_count_size = self.config.max_file_size_token_count;
+        let max_cache_size = self.config.max_file_size_token_cache;
 
         let job_id = self.next_token_worker_job_id;
         self.next_token_worker_job_id = self.next_token_worker_job_id.wrapping_add(1);
@@ -180,7 +578,7 @@ impl CodebaseApp {
         );
     }
 
-    fn cancel_token_calculation(&mut self) {
+    pub(super) fn cancel_token_calculation(&mut self) {
         if let Some(cancel) = &self.token_worker_cancel {
             cancel.store(true, Ordering::Relaxed);
         }
@@ -217,36 +615,55 @@ impl CodebaseApp {
     }
 
     fn perform_select_all_nodes(&mut self) {
-        if let Some(root_id) = self.root_id {
+        for root_id in self.root_ids.clone() {
             self.set_node_state_recursive(root_id, Check::Checked);
-            log::info!("Selected all nodes.");
         }
+        log::info!("Selected all nodes.");
     }
 
     fn perform_deselect_all_nodes(&mut self) {
-        if let Some(root_id) = self.root_id {
+        for root_id in self.root_ids.clone() {
             self.set_node_state_recursive(root_id, Check::Unchecked);
-            log::info!("Deselected all nodes.");
         }
+        log::info!("Deselected all nodes.");
     }
 
     fn perform_expand_all_nodes(&mut self) {
-        if let Some(root_id) = self.root_id {
+        for root_id in self.root_ids.clone() {
             self.set_node_expansion_recursive(root_id, true);
-            self.tree_rows_dirty = true;
-            log::info!("Expanded all nodes.");
         }
+        self.tree_rows_dirty = true;
+        log::info!("Expanded all nodes.");
     }
 
     fn perform_collapse_all_nodes(&mut self) {
-        if let Some(root_id) = self.root_id {
+        for root_id in self.root_ids.clone() {
             self.set_node_expansion_recursive(root_id, false);
             if let Some(node) = self.nodes.get_mut(root_id) {
                 node.is_expanded = true;
             }
-            self.tree_rows_dirty = true;
-            log::info!("Collapsed all nodes (except root).");
         }
+        self.tree_rows_dirty = true;
+        log::info!("Collapsed all nodes (except roots).");
+    }
+
+    /// Sorts the tree by a metric column (largest first), or by name if `column` is `None`.
+    fn perform_set_tree_sort(&mut self, column: Option<crate::model::TreeColumn>) {
+        if self.config.tree_sort == column {
+            return;
+        }
+        self.config.tree_sort = column;
+        if let Err(e) = self.config.save() {
+            log::error!("Failed to save config after changing the tree sort: {e}");
+        }
+        for root_id in self.root_ids.clone() {
+            self.sort_nodes_recursively(Some(root_id));
+        }
+        self.tree_rows_dirty = true;
+        log::info!(
+            "Sorted tree by {}.",
+            column.map_or("Name", crate::model::TreeColumn::label)
+        );
     }
 
     fn perform_select_all_children(&mut self, node_id: crate::model::FileId) {
@@ -296,19 +713,12 @@ impl CodebaseApp {
     }
 
     fn perform_save_selection(&mut self) {
-        if self.root_path.is_none() || self.root_id.is_none() {
+        if self.roots.is_empty() || self.root_ids.is_empty() {
             self.status_message = "No directory open to save selection from.".to_string();
             log::warn!("Save selection attempted with no directory open.");
             return;
         }
-        let default_name = format!(
-            "{}_selection.json",
-            self.root_path
-                .as_ref()
-                .unwrap()
-                .file_name()
-                .map_or("codebase", |n| n.to_str().unwrap_or("codebase"))
-        );
+        let default_name = format!("{}_selection.json", self.project_name());
         if let Some(save_path) = rfd::FileDialog::new()
             .add_filter("JSON Files", &["json"])
             .set_file_name(&default_name)
@@ -316,8 +726,8 @@ impl CodebaseApp {
         {
             match selection::save_selection_to_file(
                 &self.nodes,
-                self.root_id,
-                self.root_path.as_ref().unwrap(),
+                &self.root_ids,
+                &self.roots,
                 &save_path,
             ) {
                 Ok(_) => {
@@ -339,7 +749,7 @@ impl CodebaseApp {
     }
 
     fn perform_load_selection(&mut self) {
-        if self.root_path.is_none() || self.root_id.is_none() {
+        if self.roots.is_empty() || self.root_ids.is_empty() {
             self.status_message = "No directory open to load selection into.".to_string();
             log::warn!("Load selection attempted with no directory open.");
             return;
@@ -348,10 +758,13 @@ impl CodebaseApp {
             .add_filter("JSON Files", &["json"])
             .pick_file()
         {
-            match selection::load_selection_from_file(&mut self.nodes, self.root_id, &load_path) {
-                Ok(saved_root_path_str) => {
-                    let current_root_str = self.root_path.as_ref().unwrap().display().to_string();
-                    if saved_root_path_str != current_root_str {
+            match selection::load_selection_from_file(&mut self.nodes, &self.root_ids, &load_path) {
+                Ok(saved_roots) => {
+                    let current_roots: Vec<String> =
+                        self.roots.iter().map(|r| r.display().to_string()).collect();
+                    if saved_roots != current_roots {
+                        let saved_root_path_str = saved_roots.join(", ");
+                        let current_root_str = current_roots.join(", ");
                         log::warn!(
                             "Loaded selection for different root ('{saved_root_path_str}') than current ('{current_root_str}')."
                         );
@@ -363,7 +776,7 @@ impl CodebaseApp {
                         self.status_message =
                             format!("Selection loaded from {}", load_path.display());
                     }
-                    if let Some(root_id) = self.root_id {
+                    for root_id in self.root_ids.clone() {
                         self.recalculate_all_parent_states(root_id);
                     }
                     self.mark_report_preview_dirty();
@@ -389,26 +802,20 @@ impl CodebaseApp {
             self.status_message = "Busy with another task (scan/report).".to_string();
             return;
         }
-        if self.root_path.is_none() || self.root_id.is_none() {
+        if self.roots.is_empty() || self.root_ids.is_empty() {
             self.status_message = "No directory open to generate report from.".to_string();
             log::warn!("Generate report attempted with no directory open.");
             return;
         }
-        let default_ext = options.format.extension();
-        let default_name = format!(
-            "{}_report.{}",
-            self.root_path
-                .as_ref()
-                .unwrap()
-                .file_name()
-                .map_or("codebase", |n| n.to_str().unwrap_or("codebase")),
-            default_ext
-        );
+        let default_ext = options.output_extension();
+        let default_name = format!("{}_report.{}", self.project_name(), default_ext);
+        let filter_name = if options.template.is_some() {
+            "Templated Report".to_string()
+        } else {
+            format!("{:?} Report", options.format)
+        };
         if let Some(save_path) = rfd::FileDialog::new()
-            .add_filter(
-                format!("{:?} Report", options.format).as_str(),
-                &[default_ext],
-            )
+            .add_filter(filter_name.as_str(), &[default_ext])
             .set_file_name(&default_name)
             .save_file()
         {
@@ -417,26 +824,53 @@ impl CodebaseApp {
             let report_data_result = report::collect_report_data(self, &options);
             let task_sender = self.task_sender.clone().expect("Task sender should exist");
             let report_options = options.clone();
+            let low_priority_dirs = self.config.low_priority_dirs.clone();
             let handle = thread::Builder::new()
                 .name("report_generator".to_string())
                 .spawn(move || match report_data_result {
-                    Ok(data) => {
-                        let _ = task_sender.send(TaskMessage::ReportProgress(
-                            "Formatting report...".to_string(),
-                        ));
-                        match report::format_report_content(&data, &report_options) {
-                            Ok(report_content) => {
+                    Ok(mut data) => {
+                        if data.pending_manifests.is_some() {
+                            let _ = task_sender.send(TaskMessage::ReportProgress(
+                                "Parsing dependency manifests...".to_string(),
+                            ));
+                            report::parse_report_dependencies(&mut data);
+                        }
+                        if report_options.max_tokens.is_some() {
+                            let _ = task_sender.send(TaskMessage::ReportProgress(
+                                "Fitting report to the token budget...".to_string(),
+                            ));
+                        }
+                        let formatted =
+                            report::fit_report_data(&mut data, &report_options, &low_priority_dirs)
+                                .and_then(|()| {
+                                    let _ = task_sender.send(TaskMessage::ReportProgress(
+                                        "Formatting report...".to_string(),
+                                    ));
+                                    report::format_report_parts(&data, &report_options)
+                                });
+                        match formatted {
+                            Ok(parts) => {
                                 let _ = task_sender.send(TaskMessage::ReportProgress(format!(
                                     "Saving report to {}...",
                                     save_path.display()
                                 )));
-                                match std_fs::write(&save_path, report_content) {
-                                    Ok(_) => {
+                                let part_paths = report::split::part_paths(&save_path, parts.len());
+                                let written = parts.into_iter().zip(&part_paths).try_for_each(
+                                    |(content, path)| {
+                                        std_fs::write(path, content).map_err(|e| {
+                                            format!(
+                                                "Failed to write report file {}: {e}",
+                                                path.display()
+                                            )
+                                        })
+                                    },
+                                );
+                                match written {
+                                    Ok(()) => {
                                         let _ = task_sender
-                                            .send(TaskMessage::ReportFinished(Ok(save_path)));
+                                            .send(TaskMessage::ReportFinished(Ok(part_paths)));
                                     }
-                                    Err(e) => {
-                                        let err_msg = format!("Failed to write report file: {e}");
+                                    Err(err_msg) => {
                                         log::error!("{err_msg}");
                                         let _ = task_sender
                                             .send(TaskMessage::ReportFinished(Err(err_msg)));
@@ -469,48 +903,222 @@ impl CodebaseApp {
             self.status_message = "Busy with another task (scan/report).".to_string();
             return;
         }
-        if self.root_path.is_none() || self.root_id.is_none() {
+        if self.roots.is_empty() || self.root_ids.is_empty() {
             self.status_message = "No directory open to generate report from.".to_string();
             log::warn!("Copy report attempted with no directory open.");
             return;
         }
-        match report::generate_report(self, &options) {
-            Ok(content) => match Clipboard::new() {
-                Ok(mut clipboard) => {
-                    if let Err(e) = clipboard.set_text(content) {
-                        log::error!("Failed to copy report to clipboard: {e}");
-                        self.status_message = format!("Error copying report: {e}");
-                        rfd::MessageDialog::new()
-                            .set_level(rfd::MessageLevel::Error)
-                            .set_title("Copy Report Failed")
-                            .set_description(format!("Could not copy report:\n{e}"))
-                            .show();
-                    } else {
-                        self.status_message = "Report copied to clipboard.".to_string();
-                    }
+        self.pending_report_parts = None;
+        match report::generate_report_parts(self, &options) {
+            Ok(mut parts) if parts.len() == 1 => {
+                if self.copy_text_to_clipboard(parts.remove(0)) {
+                    self.status_message = "Report copied to clipboard.".to_string();
                 }
+            }
+            Ok(parts) => {
+                let total = parts.len();
+                if self.copy_text_to_clipboard(parts[0].clone()) {
+                    self.status_message =
+                        format!("Part 1 of {total} of the report copied to clipboard.");
+                    self.pending_report_parts =
+                        Some(super::state::PendingReportParts { parts, copied: 1 });
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to generate report for clipboard: {e}");
+                self.status_message = format!("Error generating report: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Report Generation Failed")
+                    .set_description(format!("Could not generate report:\n{e}"))
+                    .show();
+            }
+        }
+    }
+
+    /// Copies the next part of a report split by `perform_copy_report`.
+    fn perform_copy_next_report_part(&mut self) {
+        let Some(pending) = self.pending_report_parts.as_ref() else {
+            return;
+        };
+        let Some(part) = pending.parts.get(pending.copied).cloned() else {
+            return;
+        };
+        let (number, total) = (pending.copied + 1, pending.parts.len());
+        if self.copy_text_to_clipboard(part) {
+            self.status_message =
+                format!("Part {number} of {total} of the report copied to clipboard.");
+            if let Some(pending) = self.pending_report_parts.as_mut() {
+                pending.copied = number;
+            }
+        }
+    }
+
+    /// Puts `text` on the clipboard, reporting failures in the status bar and a dialog.
+    /// Returns whether it was copied.
+    fn copy_text_to_clipboard(&mut self, text: String) -> bool {
+        match Clipboard::new() {
+            Ok(mut clipboard) => match clipboard.set_text(text) {
+                Ok(()) => true,
                 Err(e) => {
-                    log::error!("Failed to access clipboard: {e}");
-                    self.status_message = format!("Clipboard error: {e}");
+                    log::error!("Failed to copy report to clipboard: {e}");
+                    self.status_message = format!("Error copying report: {e}");
                     rfd::MessageDialog::new()
                         .set_level(rfd::MessageLevel::Error)
-                        .set_title("Clipboard Error")
-                        .set_description(format!("Could not access clipboard:\n{e}"))
+                        .set_title("Copy Report Failed")
+                        .set_description(format!("Could not copy report:\n{e}"))
                         .show();
+                    false
                 }
             },
             Err(e) => {
-                log::error!("Failed to generate report for clipboard: {e}");
-                self.status_message = format!("Error generating report: {e}");
+                log::error!("Failed to access clipboard: {e}");
+                self.status_message = format!("Clipboard error: {e}");
                 rfd::MessageDialog::new()
                     .set_level(rfd::MessageLevel::Error)
-                    .set_title("Report Generation Failed")
-                    .set_description(format!("Could not generate report:\n{e}"))
+                    .set_title("Clipboard Error")
+                    .set_description(format!("Could not access clipboard:\n{e}"))
+                    .show();
+                false
+            }
+        }
+    }
+
+    /// Saves the scan errors currently shown in the scan errors window as JSON.
+    fn perform_export_scan_errors(&mut self) {
+        let errors: Vec<_> = self.filtered_scan_errors().into_iter().cloned().collect();
+        if errors.is_empty() {
+            self.status_message = "No scan errors to export.".to_string();
+            return;
+        }
+        let default_name = format!("{}_scan_errors.json", self.project_name());
+        let Some(save_path) = rfd::FileDialog::new()
+            .add_filter("JSON Files", &["json"])
+            .set_file_name(&default_name)
+            .save_file()
+        else {
+            self.status_message = "Export scan errors cancelled.".to_string();
+            return;
+        };
+        let result = serde_json::to_string_pretty(&errors)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| std_fs::write(&save_path, json).map_err(anyhow::Error::from));
+        match result {
+            Ok(()) => {
+                self.status_message = format!(
+                    "Exported {} scan error(s) to {}",
+                    errors.len(),
+                    save_path.display()
+                );
+            }
+            Err(e) => {
+                log::error!("Failed to export scan errors: {e}");
+                self.status_message = format!("Error exporting scan errors: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Export Scan Errors Failed")
+                    .set_description(format!("Could not export scan errors:\n{e}"))
+                    .show();
+            }
+        }
+    }
+
+    fn perform_save_snapshot(&mut self) {
+        let Some(snapshot) = self.capture_snapshot() else {
+            self.status_message = "No scan to save a snapshot of.".to_string();
+            return;
+        };
+        let default_name = format!(
+            "{}_snapshot_{}.json",
+            self.project_name(),
+            chrono::Local::now().format("%Y%m%d")
+        );
+        let Some(save_path) = rfd::FileDialog::new()
+            .add_filter("JSON Files", &["json"])
+            .set_file_name(&default_name)
+            .save_file()
+        else {
+            self.status_message = "Save snapshot cancelled.".to_string();
+            return;
+        };
+        match snapshot.save(&save_path) {
+            Ok(()) => {
+                self.status_message = format!(
+                    "Snapshot of {} file(s) saved to {}",
+                    snapshot.files.len(),
+                    save_path.display()
+                );
+            }
+            Err(e) => {
+                log::error!("Failed to save snapshot: {e}");
+                self.status_message = format!("Error saving snapshot: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Save Snapshot Failed")
+                    .set_description(format!("Could not save snapshot:\n{e}"))
+                    .show();
+            }
+        }
+    }
+
+    /// Compares a snapshot file (as the old side) with the current scan.
+    fn perform_compare_with_snapshot(&mut self) {
+        let Some(current) = self.capture_snapshot() else {
+            self.status_message = "No scan to compare with a snapshot.".to_string();
+            return;
+        };
+        let Some(old) = self.pick_snapshot("Open Older Snapshot") else {
+            return;
+        };
+        self.show_snapshot_diff(SnapshotDiff::new(&old, &current));
+    }
+
+    fn perform_compare_snapshots(&mut self) {
+        let Some(old) = self.pick_snapshot("Open Older Snapshot") else {
+            return;
+        };
+        let Some(new) = self.pick_snapshot("Open Newer Snapshot") else {
+            return;
+        };
+        self.show_snapshot_diff(SnapshotDiff::new(&old, &new));
+    }
+
+    /// Asks for a snapshot file and loads it, reporting failures to the user.
+    fn pick_snapshot(&mut self, title: &str) -> Option<Snapshot> {
+        let Some(path) = rfd::FileDialog::new()
+            .set_title(title)
+            .add_filter("JSON Files", &["json"])
+            .pick_file()
+        else {
+            self.status_message = "Snapshot comparison cancelled.".to_string();
+            return None;
+        };
+        match Snapshot::load(&path) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                log::error!("Failed to load snapshot: {e}");
+                self.status_message = format!("Error loading snapshot: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Load Snapshot Failed")
+                    .set_description(format!("Could not load snapshot:\n{e}"))
                     .show();
+                None
             }
         }
     }
 
+    fn show_snapshot_diff(&mut self, diff: SnapshotDiff) {
+        self.status_message = format!(
+            "Snapshot diff: {} added, {} removed, {} changed file(s).",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        );
+        self.snapshot_diff = Some(diff);
+        self.show_snapshot_diff_window = true;
+    }
+
     fn perform_focus_search_box(&mut self) {
         self.focus_search_box = true;
     }
@@ -521,7 +1129,7 @@ impl CodebaseApp {
             return;
         }
 
-        if self.root_path.is_none() {
+        if self.roots.is_empty() {
             self.status_message = "Open a directory before querying Gemini.".to_string();
             return;
         }
@@ -555,10 +1163,16 @@ impl CodebaseApp {
 
         let prompt = trimmed.to_owned();
         let report_options = ReportOptions {
-            format: ReportFormat::Markdown,
+            format: self.config.ai_context_format,
             include_stats: true,
             include_contents: true,
             include_line_numbers: false,
+            include_markers: false,
+            include_dependencies: true,
+            template: None,
+            max_tokens: self.config.export_max_tokens,
+            fit_strategy: self.config.export_fit_strategy,
+            split: None,
         };
         let context_result = report::generate_report(self, &report_options);
 
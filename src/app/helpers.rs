@@ -2,10 +2,17 @@
 
 use super::state::CodebaseApp;
 use crate::{
-    model::{Check, FileId},
+    fs::{git::GitStatusMap, ScanError},
+    model::{Check, FileId, NodeMetrics},
     preview,
+    snapshot::Snapshot,
+    workspace,
 };
 use egui::{Context, Visuals};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 
 impl CodebaseApp {
     /// Sets the egui visual theme (light/dark) based on the configuration.
@@ -92,6 +99,7 @@ impl CodebaseApp {
 
             log::trace!("Initiating preview load for node {node_id}");
             let path = node.path().to_path_buf();
+            let encoding = node.info.encoding;
             let cfg = self.config.clone();
             let ss = self.syntax_set;
             let ts = self.theme_set;
@@ -104,7 +112,7 @@ impl CodebaseApp {
 
             rayon::spawn_fifo(move || {
                 let cache_entry =
-                    preview::generate_preview(&cfg, ss, ts, &path, node_id, &ctx_clone);
+                    preview::generate_preview(&cfg, ss, ts, &path, encoding, node_id, &ctx_clone);
                 if tx.send((node_id, cache_entry)).is_err() {
                     log::warn!("Failed to send preview result: Channel closed.");
                 }
@@ -128,6 +136,106 @@ impl CodebaseApp {
         self.mark_report_preview_dirty();
     }
 
+    /// Selects exactly the files in `paths`, deselecting everything else.
+    /// Returns the number of files that were selected.
+    pub(crate) fn select_only_paths(&mut self, paths: &HashSet<PathBuf>) -> usize {
+        if self.root_ids.is_empty() {
+            return 0;
+        }
+        for root_id in self.root_ids.clone() {
+            self.set_node_state_recursive(root_id, Check::Unchecked);
+        }
+        let mut selected = 0;
+        for node in &mut self.nodes {
+            if !node.is_dir() && paths.contains(node.path()) {
+                node.state = Check::Checked;
+                selected += 1;
+            }
+        }
+        for root_id in self.root_ids.clone() {
+            self.recalculate_all_parent_states(root_id);
+        }
+        selected
+    }
+
+    /// Checks the files whose paths are in `paths`, keeping the rest of the selection.
+    /// Returns the number of matching files.
+    pub(crate) fn add_paths_to_selection(&mut self, paths: &HashSet<PathBuf>) -> usize {
+        let mut matched = 0;
+        for node in &mut self.nodes {
+            if !node.is_dir() && paths.contains(node.path()) {
+                node.state = Check::Checked;
+                matched += 1;
+            }
+        }
+        for root_id in self.root_ids.clone() {
+            self.recalculate_all_parent_states(root_id);
+        }
+        matched
+    }
+
+    /// Returns `path` relative to its root, prefixed by the root name in a multi-root workspace.
+    pub(crate) fn relative_path(&self, path: &Path) -> PathBuf {
+        workspace::relative_path(path, &self.roots)
+    }
+
+    /// Scan errors matching the kind and text filters of the scan errors window.
+    pub(crate) fn filtered_scan_errors(&self) -> Vec<&ScanError> {
+        let Some(stats) = &self.scan_stats else {
+            return Vec::new();
+        };
+        let needle = self.scan_error_filter.trim().to_lowercase();
+        stats
+            .errors
+            .iter()
+            .filter(|error| {
+                self.scan_error_kind_filter
+                    .map_or(true, |kind| error.kind == kind)
+            })
+            .filter(|error| {
+                needle.is_empty()
+                    || error.message.to_lowercase().contains(&needle)
+                    || error
+                        .path
+                        .as_ref()
+                        .is_some_and(|p| p.to_string_lossy().to_lowercase().contains(&needle))
+            })
+            .collect()
+    }
+
+    /// Name of the open project: the workspace name, or the single root's directory name.
+    pub(crate) fn project_name(&self) -> String {
+        if let Some(workspace) = &self.workspace {
+            return workspace.name.clone();
+        }
+        self.roots
+            .first()
+            .map(|root| workspace::root_label(root))
+            .unwrap_or_else(|| "Unknown Project".to_string())
+    }
+
+    /// Captures a snapshot of the current scan, or `None` if nothing has been scanned.
+    pub(crate) fn capture_snapshot(&self) -> Option<Snapshot> {
+        let stats = self.scan_stats.as_ref()?;
+        Some(Snapshot::capture(
+            self.project_name(),
+            &self.nodes,
+            &self.roots,
+            stats,
+        ))
+    }
+
+    /// Stores the git status of the open directory and copies it onto the tree nodes.
+    /// `None` clears all statuses (git status disabled or not a repository).
+    pub(crate) fn apply_git_status(&mut self, status: Option<GitStatusMap>) {
+        for node in &mut self.nodes {
+            node.git_status = status
+                .as_ref()
+                .and_then(|map| map.get(node.path()).copied());
+        }
+        self.git_status = status;
+    }
+
     /// Recursively sets the expansion state for a node and all its descendants.
     pub(crate) fn set_node_expansion_recursive(&mut self, node_id: FileId, expand: bool) {
         let mut queue = vec![node_id];
@@ -182,6 +290,17 @@ impl CodebaseApp {
         self.mark_report_preview_dirty();
     }
 
+    /// Selects a node and expands its ancestors so it is visible in the tree.
+    pub(crate) fn reveal_node(&mut self, node_id: FileId) {
+        let mut ancestor = self.find_parent_id(node_id);
+        while let Some(id) = ancestor {
+            self.nodes[id].is_expanded = true;
+            ancestor = self.find_parent_id(id);
+        }
+        self.selected_node_id = Some(node_id);
+        self.tree_rows_dirty = true;
+    }
+
     /// Finds the `FileId` of the parent node containing `child_id`.
     pub(super) fn find_parent_id(&self, child_id: FileId) -> Option<FileId> {
         // Get the child node's data.
@@ -230,29 +349,38 @@ impl CodebaseApp {
         }
     }
 
-    /// Recursively sums token counts for directories.
-    pub(crate) fn sum_directory_tokens(&mut self, node_id: Option<FileId>) -> usize {
-        let Some(id) = node_id else {
-            return 0;
-        };
-        let children = self
-            .nodes
-            .get(id)
-            .map_or(Vec::new(), |node| node.children.clone());
-        let mut sum = 0;
-        for child_id in children {
-            if self.nodes.get(child_id).is_some_and(|node| node.is_dir()) {
-                sum += self.sum_directory_tokens(Some(child_id));
-            } else {
-                sum += self.nodes[child_id].token_count.unwrap_or(0);
+    /// Recomputes the rolled-up metrics of every directory below every root.
+    /// Re-sorts the tree afterwards when it is sorted by a metric.
+    pub(crate) fn rollup_root_metrics(&mut self) {
+        for root_id in self.root_ids.clone() {
+            self.rollup_metrics(root_id);
+            if self.config.tree_sort.is_some() {
+                self.sort_nodes_recursively(Some(root_id));
             }
         }
-        if let Some(node) = self.nodes.get_mut(id) {
-            if node.is_dir() {
-                node.token_count = Some(sum);
-            }
+        if self.config.tree_sort.is_some() {
+            self.tree_rows_dirty = true;
         }
-        sum
+    }
+
+    /// Recursively sums file counts, sizes, line counts and tokens into each directory.
+    /// Directory token counts are set to the sum of their files' known counts.
+    pub(crate) fn rollup_metrics(&mut self, node_id: FileId) -> NodeMetrics {
+        let Some(node) = self.nodes.get(node_id) else {
+            return NodeMetrics::default();
+        };
+        let metrics = if node.is_dir() {
+            let mut metrics = NodeMetrics::default();
+            for child_id in node.children.clone() {
+                metrics.add(&self.rollup_metrics(child_id));
+            }
+            self.nodes[node_id].token_count = metrics.tokens;
+            metrics
+        } else {
+            NodeMetrics::of_file(&node.info, node.token_count)
+        };
+        self.nodes[node_id].metrics = metrics;
+        metrics
     }
 
     /// Counts the total number of files and the number of selected files.
@@ -273,24 +401,179 @@ impl CodebaseApp {
     /// Recursively sorts the children of each node.
     pub(super) fn sort_nodes_recursively(&mut self, node_id_opt: Option<FileId>) {
         if let Some(node_id) = node_id_opt {
-            if let Some(node) = self.nodes.get_mut(node_id) {
-                if node.is_dir() && !node.children.is_empty() {
-                    let mut children = std::mem::take(&mut node.children);
-                    children.sort_by_cached_key(|&child_id| {
-                        let child = &self.nodes[child_id];
-                        (!child.is_dir(), child.name().to_lowercase())
-                    });
-                    if let Some(node) = self.nodes.get_mut(node_id) {
-                        node.children = children.clone();
-                    }
-                    for child_id in children {
-                        self.sort_nodes_recursively(Some(child_id));
-                    }
+            self.sort_children(node_id);
+            let children = self
+                .nodes
+                .get(node_id)
+                .map_or(Vec::new(), |node| node.children.clone());
+            for child_id in children {
+                if self.nodes[child_id].is_dir() {
+                    self.sort_nodes_recursively(Some(child_id));
                 }
             }
         }
     }
 
+    /// Sorts the direct children of a node: directories first, then by the configured
+    /// tree sort metric (largest first), then by name.
+    pub(super) fn sort_children(&mut self, node_id: FileId) {
+        let sort = self.config.tree_sort;
+        if let Some(node) = self.nodes.get_mut(node_id) {
+            if node.is_dir() && !node.children.is_empty() {
+                let mut children = std::mem::take(&mut node.children);
+                children.sort_by_cached_key(|&child_id| {
+                    let child = &self.nodes[child_id];
+                    let value = sort.map_or(0, |column| column.value(&child.metrics));
+                    (
+                        !child.is_dir(),
+                        std::cmp::Reverse(value),
+                        child.name().to_lowercase(),
+                    )
+                });
+                self.nodes[node_id].children = children;
+            }
+        }
+    }
+
+    /// Removes the nodes at `paths` and all of their descendants from the arena. Paths
+    /// that aren't in the tree are ignored.
+    pub(crate) fn remove_paths(&mut self, paths: &[PathBuf]) {
+        let node_ids: Vec<FileId> = paths
+            .iter()
+            .filter_map(|path| {
+                let id = self.path_to_id_map.get(path).copied();
+                if id.is_some() {
+                    log::debug!("Removing watched path: {}", path.display());
+                }
+                id
+            })
+            .collect();
+        self.remove_subtrees(&node_ids);
+    }
+
+    /// Removes nodes and all of their descendants from the arena.
+    ///
+    /// The arena is compacted once afterwards, so every `FileId` held by the app
+    /// (selection, caches, root) is remapped. Any running token calculation is
+    /// restarted because its IDs are no longer valid.
+    pub(crate) fn remove_subtrees(&mut self, node_ids: &[FileId]) {
+        let node_ids: Vec<FileId> = node_ids
+            .iter()
+            .copied()
+            .filter(|&id| id < self.nodes.len())
+            .collect();
+        if node_ids.is_empty() {
+            return;
+        }
+        let parent_ids: Vec<FileId> = node_ids
+            .iter()
+            .filter_map(|&id| self.find_parent_id(id))
+            .collect();
+
+        let mut removed = vec![false; self.nodes.len()];
+        let mut queue = node_ids;
+        while let Some(current_id) = queue.pop() {
+            if !removed[current_id] {
+                removed[current_id] = true;
+                queue.extend(self.nodes[current_id].children.iter().copied());
+            }
+        }
+
+        if let Some(stats) = self.scan_stats.as_mut() {
+            for (id, node) in self.nodes.iter().enumerate() {
+                if removed[id] {
+                    stats.remove_file(&node.info, &self.roots);
+                }
+            }
+        }
+
+        let mut remap: Vec<Option<FileId>> = Vec::with_capacity(self.nodes.len());
+        let mut next_id = 0;
+        for &is_removed in &removed {
+            if is_removed {
+                remap.push(None);
+            } else {
+                remap.push(Some(next_id));
+                next_id += 1;
+            }
+        }
+        let remap_id = |id: FileId| remap.get(id).copied().flatten();
+
+        let old_nodes = std::mem::take(&mut self.nodes);
+        self.nodes = old_nodes
+            .into_iter()
+            .enumerate()
+            .filter(|(id, _)| !removed[*id])
+            .map(|(_, mut node)| {
+                node.children = node.children.iter().filter_map(|&c| remap_id(c)).collect();
+                node
+            })
+            .collect();
+        self.path_to_id_map = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (node.path().to_path_buf(), id))
+            .collect();
+        self.content_cache = std::mem::take(&mut self.content_cache)
+            .into_iter()
+            .filter_map(|(id, content)| remap_id(id).map(|new_id| (new_id, content)))
+            .collect();
+        self.watch_token_ids = self
+            .watch_token_ids
+            .iter()
+            .filter_map(|&id| remap_id(id))
+            .collect();
+        self.root_ids = self
+            .root_ids
+            .iter()
+            .filter_map(|&id| remap_id(id))
+            .collect();
+        // Actions queued before the removal still refer to the old ids.
+        self.deferred_actions = std::mem::take(&mut self.deferred_actions)
+            .into_iter()
+            .filter_map(|action| action.remap_ids(remap_id))
+            .collect();
+        self.selected_node_id = self.selected_node_id.and_then(remap_id);
+        self.treemap_root = self.treemap_root.and_then(remap_id);
+        self.treemap_context_node = None;
+        self.preview_target_line = None;
+        match self.selected_node_id {
+            Some(new_id) => {
+                if let Some(cache) = &self.preview_cache {
+                    cache.lock().node_id = new_id;
+                }
+            }
+            None => self.preview_cache = None,
+        }
+        self.tree_rows_cache.clear();
+        self.tree_rows_root_ids.clear();
+        self.tree_rows_dirty = true;
+
+        if self.is_calculating_tokens {
+            self.cancel_token_calculation();
+            if self.config.show_token_counts {
+                self.queue_action(super::AppAction::CalculateTokens);
+            }
+        }
+
+        let mut parent_ids: Vec<FileId> = parent_ids.into_iter().filter_map(remap_id).collect();
+        parent_ids.sort_unstable();
+        parent_ids.dedup();
+        for &parent_id in &parent_ids {
+            let new_state = self.calculate_parent_check_state(parent_id);
+            self.nodes[parent_id].state = new_state;
+            self.update_parent_states(parent_id);
+        }
+        if !parent_ids.is_empty() {
+            self.rollup_root_metrics();
+        }
+        if let Some(stats) = self.scan_stats.as_mut() {
+            stats.refill_largest_files(self.nodes.iter().map(|n| &n.info), &self.roots);
+        }
+        self.mark_report_preview_dirty();
+    }
+
     /// Helper to get the name of the syntax theme currently in use.
     pub(super) fn get_current_syntax_theme_name(&self) -> String {
         match self.config.theme.as_str() {
@@ -303,3 +586,88 @@ impl CodebaseApp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::AppAction,
+        config::AppConfig,
+        fs::FileInfo,
+        model::FileNode,
+        task::{LinkedNode, ScanMessage},
+    };
+
+    /// Builds an app holding a scanned tempdir with `a/x.rs`, `a/y.rs`, `b.rs` and `c.rs`.
+    fn scanned_app(dir: &Path) -> CodebaseApp {
+        std::fs::create_dir(dir.join("a")).unwrap();
+        for file in ["a/x.rs", "a/y.rs", "b.rs", "c.rs"] {
+            std::fs::write(dir.join(file), "fn f() {}\n").unwrap();
+        }
+        let mut app = CodebaseApp::headless_from_config(AppConfig::default());
+        app.roots = vec![dir.to_path_buf()];
+        let entries = [
+            ("", None),
+            ("a", Some(0)),
+            ("a/x.rs", Some(1)),
+            ("a/y.rs", Some(1)),
+            ("b.rs", Some(0)),
+            ("c.rs", Some(0)),
+        ];
+        let nodes = entries
+            .into_iter()
+            .map(|(relative, parent)| {
+                let path = dir.join(relative);
+                let metadata = std::fs::metadata(&path).unwrap();
+                let path = if relative.is_empty() {
+                    dir.to_path_buf()
+                } else {
+                    path
+                };
+                LinkedNode {
+                    node: FileNode::new(FileInfo::from_metadata_unanalyzed(path, &metadata)),
+                    parent,
+                }
+            })
+            .collect();
+        app.process_scan_message(ScanMessage::AddNodes {
+            root: dir.to_path_buf(),
+            nodes,
+        });
+        app
+    }
+
+    #[test]
+    fn removes_several_subtrees_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut app = scanned_app(root);
+        let b_id = app.path_to_id_map[&root.join("b.rs")];
+        let c_id = app.path_to_id_map[&root.join("c.rs")];
+        app.selected_node_id = Some(b_id);
+        app.queue_action(AppAction::ToggleCheckState(c_id));
+        app.queue_action(AppAction::ToggleCheckState(b_id));
+        app.queue_action(AppAction::SelectAllNodes);
+
+        app.remove_paths(&[
+            root.join("a/x.rs"),
+            root.join("a"),
+            root.join("c.rs"),
+            root.join("missing.rs"),
+        ]);
+
+        let paths: Vec<PathBuf> = app.nodes.iter().map(|n| n.path().to_path_buf()).collect();
+        assert_eq!(paths, [root.to_path_buf(), root.join("b.rs")]);
+        for (id, node) in app.nodes.iter().enumerate() {
+            assert_eq!(app.path_to_id_map[node.path()], id);
+        }
+        assert_eq!(app.path_to_id_map.len(), 2);
+        assert_eq!(app.root_ids, [0]);
+        assert_eq!(app.nodes[0].children, [1]);
+        assert_eq!(app.selected_node_id, Some(1));
+        assert!(matches!(
+            app.deferred_actions[..],
+            [AppAction::ToggleCheckState(1), AppAction::SelectAllNodes]
+        ));
+    }
+}
@@ -4,10 +4,11 @@ use super::state::{CodebaseApp, TokenStatus};
 use crate::external;
 use crate::{
     llm::token_counter::{TokenCountError, TokenCountSummary},
-    model::FileNode,
-    task::{ScanMessage, TaskMessage},
+    model::{Check, FileId, FileNode},
+    task::{LinkedNode, ScanMessage, TaskMessage},
 };
 use rfd::MessageDialogResult;
+use std::path::Path;
 
 impl CodebaseApp {
     /// Central place to handle all background messages.
@@ -25,6 +26,30 @@ impl CodebaseApp {
                 }
             }
         }
+        if let Some(receiver) = self.watch_receiver.clone() {
+            let mut changed = false;
+            // Consecutive removals are applied together, so a burst of deletions compacts
+            // the node arena once instead of once per path.
+            let mut removals = Vec::new();
+            while let Ok(msg) = receiver.try_recv() {
+                changed = true;
+                match msg {
+                    ScanMessage::RemovePath(path) => removals.push(path),
+                    msg => {
+                        self.remove_paths(&std::mem::take(&mut removals));
+                        self.process_scan_message(msg);
+                    }
+                }
+            }
+            self.remove_paths(&removals);
+            self.recount_watched_tokens();
+            if changed {
+                self.rollup_root_metrics();
+                if self.config.show_git_status {
+                    self.queue_action(super::AppAction::RefreshGitStatus);
+                }
+            }
+        }
     }
 
     fn handle_task_messages(&mut self) {
@@ -36,26 +61,49 @@ impl CodebaseApp {
                         self.is_generating_report = false;
                         self.background_task = None;
                         match result {
-                            Ok(save_path) => {
-                                self.status_message =
-                                    format!("Report saved successfully to {}", save_path.display());
+                            Ok(save_paths) => {
+                                let Some(save_path) = save_paths.first() else {
+                                    continue;
+                                };
+                                let description = if save_paths.len() > 1 {
+                                    self.status_message = format!(
+                                        "Report saved in {} parts to {}",
+                                        save_paths.len(),
+                                        save_path.parent().unwrap_or(save_path).display()
+                                    );
+                                    format!(
+                                        "Report saved in {} parts:\n{}\n\nWould you like to open the first part?",
+                                        save_paths.len(),
+                                        save_paths
+                                            .iter()
+                                            .map(|path| path.display().to_string())
+                                            .collect::<Vec<_>>()
+                                            .join("\n")
+                                    )
+                                } else {
+                                    self.status_message = format!(
+                                        "Report saved successfully to {}",
+                                        save_path.display()
+                                    );
+                                    format!(
+                                        "Report saved to:\n{}\n\nWould you like to open it?",
+                                        save_path.display()
+                                    )
+                                };
                                 log::info!(
-                                    "Report generated successfully to {}",
-                                    save_path.display()
+                                    "Report generated successfully to {} ({} part(s))",
+                                    save_path.display(),
+                                    save_paths.len()
                                 );
                                 if rfd::MessageDialog::new()
                                     .set_level(rfd::MessageLevel::Info)
                                     .set_title("Report Generated")
-                                    .set_description(format!(
-                                        "Report saved to:\n{}\n\nWould you like to open it?",
-                                        save_path.display()
-                                    ))
+                                    .set_description(description)
                                     .set_buttons(rfd::MessageButtons::YesNo)
                                     .show()
                                     == MessageDialogResult::Yes
                                 {
-                                    if let Err(e) = external::open_path_in_external_app(&save_path)
-                                    {
+                                    if let Err(e) = external::open_path_in_external_app(save_path) {
                                         log::error!("Failed to open generated report file: {e}");
                                         self.status_message = format!("Error opening report: {e}");
                                         rfd::MessageDialog::new()
@@ -118,7 +166,49 @@ impl CodebaseApp {
                             self.is_calculating_tokens = false;
                             self.token_worker_job_id = None;
                             self.token_worker_cancel = None;
-                            self.sum_directory_tokens(self.root_id);
+                            self.rollup_root_metrics();
+                            if self.index_token_job_id.take() == Some(job_id) {
+                                self.persist_token_counts();
+                            }
+                        }
+                    }
+                    TaskMessage::GitStatus { roots, result } => {
+                        if self.roots != roots {
+                            continue;
+                        }
+                        match result {
+                            Ok(status) => self.apply_git_status(status),
+                            Err(e) => {
+                                log::warn!("Failed to read git status: {e}");
+                                self.apply_git_status(None);
+                            }
+                        }
+                    }
+                    TaskMessage::ChangedFiles {
+                        roots,
+                        reference,
+                        result,
+                    } => {
+                        if self.roots != roots {
+                            continue;
+                        }
+                        match result {
+                            Ok(paths) => {
+                                let selected = self.select_only_paths(&paths);
+                                self.status_message = format!(
+                                    "Selected {selected} file(s) changed since '{reference}'."
+                                );
+                            }
+                            Err(e) => {
+                                self.status_message =
+                                    format!("Could not select changed files: {e}");
+                                log::error!("{}", self.status_message);
+                                rfd::MessageDialog::new()
+                                    .set_level(rfd::MessageLevel::Error)
+                                    .set_title("Select Changed Files Failed")
+                                    .set_description(e)
+                                    .show();
+                            }
                         }
                     }
                 }
@@ -175,28 +265,41 @@ impl CodebaseApp {
 
     pub(crate) fn process_scan_message(&mut self, msg: ScanMessage) -> bool {
         match msg {
-            ScanMessage::AddNode(node) => self.add_single_node(node),
-            ScanMessage::AddNodes(nodes) => {
-                for node in nodes {
-                    self.add_single_node(node);
+            ScanMessage::AddNode(node) => self.add_single_node(*node),
+            ScanMessage::RemovePath(path) => self.remove_paths(&[path]),
+            ScanMessage::AddNodes { root, nodes } => {
+                for linked in nodes {
+                    self.add_linked_node(&root, linked);
                 }
             }
-            ScanMessage::Error(err_msg) => {
-                log::error!("Scan error reported: {err_msg}");
-                self.status_message = format!("Scan error: {err_msg}");
+            ScanMessage::Error(error) => {
+                log::error!("Scan error reported: {error}");
+                self.status_message = format!("Scan error: {error}");
                 if let Some(stats) = self.scan_stats.as_mut() {
-                    stats.add_error(err_msg);
+                    stats.add_error(error);
                 }
             }
-            ScanMessage::Progress(msg) => self.status_message = msg,
+            ScanMessage::Progress(progress) => {
+                self.scan_progress.retain(|p| p.root != progress.root);
+                self.scan_progress.push(progress);
+            }
             ScanMessage::Stats(partial_stats) => {
                 if let Some(total_stats) = self.scan_stats.as_mut() {
                     total_stats.merge(partial_stats);
                 }
             }
             ScanMessage::Finished => {
+                self.pending_scans = self.pending_scans.saturating_sub(1);
+                if self.pending_scans > 0 {
+                    log::info!(
+                        "Root scan finished; waiting for {} more.",
+                        self.pending_scans
+                    );
+                    return false;
+                }
                 log::info!("Scan finished message received.");
                 self.is_scanning = false;
+                self.scan_progress.clear();
                 self.background_task = None;
                 self.scan_receiver = None;
                 if let Some(stats) = self.scan_stats.as_mut() {
@@ -210,29 +313,24 @@ impl CodebaseApp {
                 } else {
                     self.status_message = "Scan complete.".to_string();
                 }
-                if !self.orphaned_children.is_empty() {
-                    log::warn!(
-                        "Scan finished with {} unresolved orphan parent path(s).",
-                        self.orphaned_children.len()
-                    );
-                    for (parent, children) in &self.orphaned_children {
-                        log::warn!(" - Missing Parent: {}", parent.display());
-                        for (_, child_path) in children.iter().take(5) {
-                            log::warn!("   - Orphaned Child: {}", child_path.display());
-                        }
-                        if children.len() > 5 {
-                            log::warn!(
-                                "   - ...and {} more orphans for this parent",
-                                children.len() - 5
-                            );
-                        }
+                self.scanned_node_ids.clear();
+                for root_id in self.root_ids.clone() {
+                    self.rollup_metrics(root_id);
+                    self.sort_nodes_recursively(Some(root_id));
+                    // Directories holding auto-deselected files become partially selected.
+                    if !self.config.deselect_file_classes.is_empty() {
+                        self.recalculate_all_parent_states(root_id);
                     }
-                    self.orphaned_children.clear();
                 }
-                self.sort_nodes_recursively(self.root_id);
                 if self.config.show_token_counts {
                     self.queue_action(super::AppAction::CalculateTokens);
                 }
+                if self.config.watch_filesystem {
+                    self.queue_action(super::AppAction::StartWatching);
+                }
+                if self.config.show_git_status {
+                    self.queue_action(super::AppAction::RefreshGitStatus);
+                }
                 if let Some(stats) = &self.scan_stats {
                     if self.config.auto_expand_limit > 0
                         && stats.total_files <= self.config.auto_expand_limit
@@ -242,7 +340,7 @@ impl CodebaseApp {
                             stats.total_files,
                             self.config.auto_expand_limit
                         );
-                        if let Some(root_id) = self.root_id {
+                        for root_id in self.root_ids.clone() {
                             if let Some(root_node) = self.nodes.get(root_id) {
                                 let children = root_node.children.clone();
                                 for child_id in children {
@@ -260,71 +358,141 @@ impl CodebaseApp {
         false
     }
 
-    /// Helper function to process the addition of a single FileNode.
+    /// Adds a node reported by the filesystem watcher, or updates it if it is already known.
     fn add_single_node(&mut self, node: FileNode) {
+        if let Some(&existing_id) = self.path_to_id_map.get(node.path()) {
+            self.update_existing_node(existing_id, node);
+        } else {
+            self.add_watched_node(node);
+        }
+    }
+
+    /// Adds a node sent by the scan of `root`, attaching it to its already-added parent.
+    fn add_linked_node(&mut self, root: &Path, linked: LinkedNode) {
+        let LinkedNode { mut node, parent } = linked;
         let node_path = node.path().to_path_buf();
+        if let Some(&existing_id) = self.path_to_id_map.get(&node_path) {
+            self.update_existing_node(existing_id, node);
+            self.scanned_node_ids
+                .entry(root.to_path_buf())
+                .or_default()
+                .push(existing_id);
+            return;
+        }
+        if self.starts_deselected(&node) {
+            node.state = Check::Unchecked;
+        }
         let node_id = self.nodes.len();
         self.nodes.push(node);
         self.path_to_id_map.insert(node_path.clone(), node_id);
+        let scanned = self.scanned_node_ids.entry(root.to_path_buf()).or_default();
+        let parent_id = parent.and_then(|position| scanned.get(position).copied());
+        scanned.push(node_id);
         self.tree_rows_dirty = true;
-        if self.root_id.is_none() && Some(&node_path) == self.root_path.as_ref() {
-            self.root_id = Some(node_id);
-            if let Some(root_node) = self.nodes.get_mut(node_id) {
-                root_node.is_expanded = true;
-            }
-            log::debug!(
-                "Root node added: ID {}, Path: {}",
-                node_id,
+
+        match (parent, parent_id) {
+            (None, _) => self.add_root_node(node_id),
+            (Some(_), Some(parent_id)) => self.nodes[parent_id].children.push(node_id),
+            (Some(position), None) => log::error!(
+                "Scanner sent {} with unknown parent position {position}.",
                 node_path.display()
-            );
+            ),
         }
-        let mut parent_found = false;
-        if let Some(parent_path) = node_path.parent().map(|p| p.to_path_buf()) {
-            if let Some(&parent_id) = self.path_to_id_map.get(&parent_path) {
-                if let Some(parent_node) = self.nodes.get_mut(parent_id) {
-                    parent_node.children.push(node_id);
-                    parent_found = true;
-                    if let Some(orphans) = self.orphaned_children.remove(&node_path) {
-                        log::debug!(
-                            "Node {} ({}) resolved {} orphans.",
-                            node_id,
-                            node_path.display(),
-                            orphans.len()
-                        );
-                        if let Some(new_parent_node) = self.nodes.get_mut(node_id) {
-                            for (orphan_id, _) in orphans {
-                                new_parent_node.children.push(orphan_id);
-                            }
-                        }
-                    }
-                }
-            }
+        if let Some(stats) = self.scan_stats.as_mut() {
+            stats.add_file(&self.nodes[node_id].info, &self.roots);
         }
-        if !parent_found && self.root_id != Some(node_id) {
-            if let Some(parent_path) = node_path.parent() {
-                log::trace!(
-                    "Orphaned node {} (parent {} not found yet). Storing.",
-                    node_path.display(),
-                    parent_path.display()
-                );
-                self.orphaned_children
-                    .entry(parent_path.to_path_buf())
-                    .or_default()
-                    .push((node_id, node_path.clone()));
-            } else if self.root_id.is_none() {
-                log::error!(
-                    "Node {} has no parent but is not root, and root not yet found.",
-                    node_path.display()
-                );
+        self.mark_report_preview_dirty();
+    }
+
+    /// Returns `true` if the node's class is one the user wants deselected after a scan.
+    fn starts_deselected(&self, node: &FileNode) -> bool {
+        node.info
+            .class
+            .is_some_and(|class| self.config.deselect_file_classes.contains(&class))
+    }
+
+    /// Registers a scanned root node, keeping `root_ids` in the same order as `roots`
+    /// whichever scan gets there first.
+    fn add_root_node(&mut self, node_id: FileId) {
+        let root_position = |app: &Self, id: FileId| {
+            app.roots
+                .iter()
+                .position(|root| root.as_path() == app.nodes[id].path())
+        };
+        let position = root_position(self, node_id);
+        let insert_at = self
+            .root_ids
+            .iter()
+            .take_while(|&&id| root_position(self, id) < position)
+            .count();
+        self.root_ids.insert(insert_at, node_id);
+        self.nodes[node_id].is_expanded = true;
+        log::debug!(
+            "Root node added: ID {}, Path: {}",
+            node_id,
+            self.nodes[node_id].path().display()
+        );
+    }
+
+    /// Replaces the metadata of an existing node after the watcher reported a modification.
+    fn update_existing_node(&mut self, node_id: FileId, node: FileNode) {
+        let Some(existing) = self.nodes.get_mut(node_id) else {
+            return;
+        };
+        let old_info = std::mem::replace(&mut existing.info, node.info);
+        let is_dir = existing.is_dir();
+        if !is_dir {
+            existing.token_count = None;
+            self.content_cache.remove(&node_id);
+            self.watch_token_ids.push(node_id);
+            if self.selected_node_id == Some(node_id) {
+                self.preview_cache = None;
+                self.preview_reload_requested = true;
             }
         }
         if let Some(stats) = self.scan_stats.as_mut() {
-            if let Some(root_p) = &self.root_path {
-                if let Some(added_node_info) = self.nodes.get(node_id).map(|n| &n.info) {
-                    stats.add_file(added_node_info, root_p);
-                }
-            }
+            stats.remove_file(&old_info, &self.roots);
+            stats.add_file(&self.nodes[node_id].info, &self.roots);
         }
         self.mark_report_preview_dirty();
     }
+
+    /// Inserts a node reported by the watcher into an already-built tree.
+    /// Nodes whose parent is not part of the tree (e.g. inside ignored directories) are dropped.
+    fn add_watched_node(&mut self, mut node: FileNode) {
+        let Some(parent_id) = node
+            .path()
+            .parent()
+            .and_then(|parent| self.path_to_id_map.get(parent).copied())
+        else {
+            log::trace!(
+                "Ignoring watched node outside the tree: {}",
+                node.path().display()
+            );
+            return;
+        };
+        // New entries inherit the parent's selection so they don't silently join a report.
+        node.state =
+            if self.nodes[parent_id].state == Check::Checked && !self.starts_deselected(&node) {
+                Check::Checked
+            } else {
+                Check::Unchecked
+            };
+        let node_id = self.nodes.len();
+        let is_dir = node.is_dir();
+        self.path_to_id_map
+            .insert(node.path().to_path_buf(), node_id);
+        self.nodes.push(node);
+        self.nodes[parent_id].children.push(node_id);
+        self.sort_children(parent_id);
+        if !is_dir {
+            self.watch_token_ids.push(node_id);
+        }
+        if let Some(stats) = self.scan_stats.as_mut() {
+            stats.add_file(&self.nodes[node_id].info, &self.roots);
+        }
+        self.update_parent_states(node_id);
+        self.tree_rows_dirty = true;
+        self.mark_report_preview_dirty();
+    }
 }
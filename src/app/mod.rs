@@ -30,6 +30,7 @@ pub(crate) enum AppAction {
     DeselectAllNodes,
     ExpandAllNodes,
     CollapseAllNodes,
+    SetTreeSort(Option<crate::model::TreeColumn>),
     SelectAllChildren(crate::model::FileId),
     DeselectAllChildren(crate::model::FileId),
     OpenNodeExternally(crate::model::FileId),
@@ -37,12 +38,54 @@ pub(crate) enum AppAction {
     LoadSelection,
     GenerateReport(crate::report::ReportOptions),
     CopyReport(crate::report::ReportOptions),
+    CopyNextReportPart,
     StartScan(std::path::PathBuf),
+    OpenWorkspace(std::path::PathBuf),
+    AddWorkspaceRoot(std::path::PathBuf),
+    RemoveWorkspaceRoot(std::path::PathBuf),
+    SaveWorkspace,
+    Rescan,
     CancelScan,
+    StartWatching,
+    StopWatching,
+    /// Deletes the scan index of the open project's roots.
+    ClearScanIndex,
+    /// Deletes the scan indexes of all projects.
+    ClearAllScanIndexes,
+    RefreshGitStatus,
+    SelectChangedFiles(String),
+    /// Selects the files of a dashboard chart segment, adding them to the selection or,
+    /// with `replace`, selecting only them.
+    SelectMatching {
+        segment: crate::ui::dashboard::ChartSegment,
+        replace: bool,
+    },
     CalculateTokens,
     CancelTokenCalculation,
     FocusSearchBox,
     QueryAI(String),
+    ExportScanErrors,
+    SaveSnapshot,
+    CompareWithSnapshot,
+    CompareSnapshots,
+}
+
+impl AppAction {
+    /// Rewrites the node ids the action refers to with `remap`, as when the node arena is
+    /// compacted. Returns `None` if one of its nodes was removed.
+    pub(crate) fn remap_ids(
+        self,
+        remap: impl Fn(crate::model::FileId) -> Option<crate::model::FileId>,
+    ) -> Option<Self> {
+        Some(match self {
+            AppAction::ToggleCheckState(id) => AppAction::ToggleCheckState(remap(id)?),
+            AppAction::ToggleExpandState(id) => AppAction::ToggleExpandState(remap(id)?),
+            AppAction::SelectAllChildren(id) => AppAction::SelectAllChildren(remap(id)?),
+            AppAction::DeselectAllChildren(id) => AppAction::DeselectAllChildren(remap(id)?),
+            AppAction::OpenNodeExternally(id) => AppAction::OpenNodeExternally(remap(id)?),
+            action => action,
+        })
+    }
 }
 
 impl eframe::App for CodebaseApp {
@@ -51,6 +94,13 @@ impl eframe::App for CodebaseApp {
         // 1. Handle messages from background tasks (scanner, report generator)
         self.handle_background_messages();
 
+        // Reload the preview if the watcher reported a change to the selected file
+        if std::mem::take(&mut self.preview_reload_requested) && self.show_preview_panel {
+            if let Some(selected_id) = self.selected_node_id {
+                self.trigger_preview_load(selected_id, ctx);
+            }
+        }
+
         // 2. Handle global keyboard shortcuts
         self.handle_keyboard_shortcuts(ctx);
 
@@ -69,6 +119,9 @@ impl eframe::App for CodebaseApp {
             || self.focus_search_box
         {
             ctx.request_repaint_after(std::time::Duration::from_millis(30));
+        } else if !self.watchers.is_empty() {
+            // Poll for filesystem watcher updates while otherwise idle.
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
         }
     }
 
@@ -126,6 +179,13 @@ impl CodebaseApp {
         ui::draw_preferences_window(self, ctx);
         ui::draw_report_options_window(self, ctx);
         ui::draw_ai_query_window(self, ctx);
+        ui::draw_changed_since_window(self, ctx);
+        ui::draw_report_parts_window(self, ctx);
+        ui::draw_scan_errors_window(self, ctx);
+        ui::draw_stats_dashboard_window(self, ctx);
+        ui::draw_treemap_window(self, ctx);
+        ui::draw_snapshot_diff_window(self, ctx);
+        ui::draw_markers_window(self, ctx);
         ui::draw_about_window(self, ctx);
         ui::draw_shortcuts_window(self, ctx);
     }
@@ -155,25 +215,25 @@ impl CodebaseApp {
                 if let Some(path) = rfd::FileDialog::new().pick_folder() {
                     self.queue_action(AppAction::StartScan(path));
                 }
-            } else if i.consume_shortcut(&save_sel_shortcut) && self.root_path.is_some() {
+            } else if i.consume_shortcut(&save_sel_shortcut) && !self.roots.is_empty() {
                 self.queue_action(AppAction::SaveSelection);
-            } else if i.consume_shortcut(&load_sel_shortcut) && self.root_path.is_some() {
+            } else if i.consume_shortcut(&load_sel_shortcut) && !self.roots.is_empty() {
                 self.queue_action(AppAction::LoadSelection);
             } else if i.consume_shortcut(&report_shortcut)
-                && self.root_path.is_some()
+                && !self.roots.is_empty()
                 && !self.is_scanning
                 && !self.is_generating_report
             {
                 self.show_report_options_window = true;
-            } else if i.consume_shortcut(&select_all_shortcut) && self.root_id.is_some() {
+            } else if i.consume_shortcut(&select_all_shortcut) && !self.root_ids.is_empty() {
                 self.queue_action(AppAction::SelectAllNodes);
-            } else if i.consume_shortcut(&deselect_all_shortcut) && self.root_id.is_some() {
+            } else if i.consume_shortcut(&deselect_all_shortcut) && !self.root_ids.is_empty() {
                 self.queue_action(AppAction::DeselectAllNodes);
-            } else if i.consume_shortcut(&find_shortcut) && self.root_id.is_some() {
+            } else if i.consume_shortcut(&find_shortcut) && !self.root_ids.is_empty() {
                 self.queue_action(AppAction::FocusSearchBox);
-            } else if i.consume_shortcut(&expand_all_shortcut) && self.root_id.is_some() {
+            } else if i.consume_shortcut(&expand_all_shortcut) && !self.root_ids.is_empty() {
                 self.queue_action(AppAction::ExpandAllNodes);
-            } else if i.consume_shortcut(&collapse_all_shortcut) && self.root_id.is_some() {
+            } else if i.consume_shortcut(&collapse_all_shortcut) && !self.root_ids.is_empty() {
                 self.queue_action(AppAction::CollapseAllNodes);
             } else if i.consume_shortcut(&toggle_preview_shortcut) {
                 self.show_preview_panel = !self.show_preview_panel;
@@ -2,11 +2,14 @@
 
 use crate::{
     config::AppConfig,
-    fs::ScanStats,
-    model::{FileId, FileNode},
+    fs::{git::GitStatusMap, ScanErrorKind, ScanProgress, ScanStats},
+    model::{FileId, FileNode, TreeColumn},
     preview::PreviewCache,
     report::ReportOptions,
+    snapshot::SnapshotDiff,
     task::{ScanMessage, TaskMessage},
+    ui::{dashboard::DashboardData, dialogs::SnapshotDiffSection, markers::MarkerList},
+    workspace::Workspace,
 };
 use crossbeam_channel::{Receiver, Sender};
 use parking_lot::Mutex;
@@ -26,7 +29,8 @@ use syntect::parsing::SyntaxSet;
 #[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) enum BackgroundTask {
-    Scan(JoinHandle<()>, Arc<AtomicBool>),
+    /// One scanner thread and cancel flag per workspace root.
+    Scan(Vec<(JoinHandle<()>, Arc<AtomicBool>)>),
     Report(JoinHandle<()>),
 }
 
@@ -62,6 +66,14 @@ pub struct ReportPreviewState {
     pub pending_job_id: Option<u64>,
 }
 
+/// A report split into parts by "Copy Report", copied to the clipboard one part at a time.
+#[derive(Debug, Clone)]
+pub struct PendingReportParts {
+    pub parts: Vec<String>,
+    /// Number of parts copied so far.
+    pub copied: usize,
+}
+
 /// The main application struct, holding all state.
 pub struct CodebaseApp {
     // --- Configuration ---
@@ -74,12 +86,49 @@ pub struct CodebaseApp {
     pub(crate) preview_cache: Option<Arc<Mutex<PreviewCache>>>,
     pub(crate) show_preview_panel: bool,
     pub(crate) preview_word_wrap: bool,
+    /// Line to highlight in the preview of a file, set when jumping to a marker.
+    pub(crate) preview_target_line: Option<(FileId, usize)>,
+    /// Whether the preview still has to scroll to `preview_target_line`.
+    pub(crate) preview_scroll_pending: bool,
     pub(crate) preview_selectable_line_numbers: bool,
     pub(crate) show_preferences_window: bool,
     pub(crate) show_report_options_window: bool,
     pub(crate) show_about_window: bool,
     pub(crate) show_shortcuts_window: bool,
     pub(crate) show_ai_query_window: bool,
+    pub(crate) show_changed_since_window: bool,
+    /// Parts of a split report still being copied; shows the report parts window.
+    pub(crate) pending_report_parts: Option<PendingReportParts>,
+    pub(crate) show_scan_errors_window: bool,
+    pub(crate) show_stats_dashboard: bool,
+    /// Chart data of the statistics dashboard, computed while it is open.
+    pub(crate) dashboard_data: Option<DashboardData>,
+    pub(crate) show_treemap_window: bool,
+    /// Metric the treemap sizes its tiles by: `Size`, `Code` or `Tokens`.
+    pub(crate) treemap_metric: TreeColumn,
+    /// Directory the treemap is zoomed into; `None` shows every root.
+    pub(crate) treemap_root: Option<FileId>,
+    /// Tile the treemap's context menu was opened on.
+    pub(crate) treemap_context_node: Option<FileId>,
+    pub(crate) show_snapshot_diff_window: bool,
+    pub(crate) show_markers_window: bool,
+    /// Marker rows of the open markers window, computed while it is open.
+    pub(crate) marker_list: Option<MarkerList>,
+    /// Text filter for the open markers window, matched against paths and marker text.
+    pub(crate) marker_filter: String,
+    /// Keyword filter for the open markers window; `None` shows every keyword.
+    pub(crate) marker_keyword_filter: Option<String>,
+    pub(crate) marker_selected_only: bool,
+    /// Whether the tree only shows files with comment markers (and their directories).
+    pub(crate) tree_markers_only: bool,
+    /// Result of the last snapshot comparison, shown in the snapshot diff window.
+    pub(crate) snapshot_diff: Option<SnapshotDiff>,
+    pub(crate) snapshot_diff_section: SnapshotDiffSection,
+    /// Text filter for the scan errors window, matched against paths and messages.
+    pub(crate) scan_error_filter: String,
+    /// Kind filter for the scan errors window; `None` shows every kind.
+    pub(crate) scan_error_kind_filter: Option<ScanErrorKind>,
+    pub(crate) changed_since_ref: String,
     pub(crate) ai_query_text: String,
     pub(crate) ai_response_text: Option<String>,
     pub(crate) is_querying_ai: bool,
@@ -94,20 +143,32 @@ pub struct CodebaseApp {
 
     // --- Data State ---
     pub(crate) nodes: Vec<FileNode>,
-    pub(crate) root_id: Option<FileId>,
-    pub(crate) root_path: Option<PathBuf>,
+    /// Top-level directories being viewed, in display order. More than one for a workspace.
+    pub(crate) roots: Vec<PathBuf>,
+    /// Node IDs of the entries in `roots` that have been scanned, in the same order.
+    pub(crate) root_ids: Vec<FileId>,
+    pub(crate) workspace: Option<Workspace>,
     pub(crate) scan_stats: Option<ScanStats>,
-    pub(crate) orphaned_children: HashMap<PathBuf, Vec<(FileId, PathBuf)>>,
+    /// Latest progress of each running root scan, most recently updated last.
+    pub(crate) scan_progress: Vec<ScanProgress>,
+    pub(crate) pending_scans: usize,
+    /// Node IDs in the order each running root scan sent them, for resolving
+    /// `LinkedNode::parent`. Cleared when all scans have finished.
+    pub(crate) scanned_node_ids: HashMap<PathBuf, Vec<FileId>>,
     pub(crate) path_to_id_map: HashMap<PathBuf, FileId>,
     pub(crate) content_cache: HashMap<FileId, Arc<String>>,
     pub(crate) is_calculating_tokens: bool,
     pub(crate) token_worker_job_id: Option<u64>,
     pub(crate) next_token_worker_job_id: u64,
+    pub(crate) index_token_job_id: Option<u64>,
     pub(crate) token_worker_cancel: Option<Arc<AtomicBool>>,
     pub(crate) tree_rows_cache: Vec<(FileId, usize)>,
     pub(crate) tree_rows_dirty: bool,
     pub(crate) tree_rows_search: String,
-    pub(crate) tree_rows_root_id: Option<FileId>,
+    pub(crate) tree_rows_root_ids: Vec<FileId>,
+    pub(crate) watch_token_ids: Vec<FileId>,
+    pub(crate) preview_reload_requested: bool,
+    pub(crate) git_status: Option<GitStatusMap>,
 
     // --- Background Task State ---
     pub(crate) scan_receiver: Option<Receiver<ScanMessage>>,
@@ -116,6 +177,8 @@ pub struct CodebaseApp {
     pub(crate) task_receiver: Option<Receiver<TaskMessage>>,
     pub(crate) task_sender: Option<Sender<TaskMessage>>,
     pub(crate) background_task: Option<BackgroundTask>,
+    pub(crate) watchers: Vec<(JoinHandle<()>, Arc<AtomicBool>)>,
+    pub(crate) watch_receiver: Option<Receiver<ScanMessage>>,
     pub(crate) is_scanning: bool,
     pub(crate) is_generating_report: bool,
 
@@ -151,6 +214,8 @@ impl CodebaseApp {
                 .to_string(),
             preview_cache: None,
             show_preview_panel: true,
+            preview_target_line: None,
+            preview_scroll_pending: false,
             preview_word_wrap: false,
             preview_selectable_line_numbers: false,
             show_preferences_window: false,
@@ -158,36 +223,66 @@ impl CodebaseApp {
             show_about_window: false,
             show_shortcuts_window: false,
             show_ai_query_window: false,
+            show_changed_since_window: false,
+            pending_report_parts: None,
+            show_scan_errors_window: false,
+            show_stats_dashboard: false,
+            dashboard_data: None,
+            show_treemap_window: false,
+            treemap_metric: TreeColumn::Size,
+            treemap_root: None,
+            treemap_context_node: None,
+            show_snapshot_diff_window: false,
+            show_markers_window: false,
+            marker_list: None,
+            marker_filter: String::new(),
+            marker_keyword_filter: None,
+            marker_selected_only: false,
+            tree_markers_only: false,
+            snapshot_diff: None,
+            snapshot_diff_section: SnapshotDiffSection::Languages,
+            scan_error_filter: String::new(),
+            scan_error_kind_filter: None,
+            changed_since_ref: String::new(),
             ai_query_text: String::new(),
             ai_response_text: None,
             is_querying_ai: false,
             focus_search_box: false,
             nodes: Vec::new(),
-            root_id: None,
-            root_path: None,
+            roots: Vec::new(),
+            root_ids: Vec::new(),
+            workspace: None,
             scan_stats: Some(ScanStats::default()),
+            scan_progress: Vec::new(),
+            pending_scans: 0,
             scan_receiver: None,
             preview_receiver: Some(preview_rx),
             preview_sender: Some(preview_tx),
             task_receiver: Some(task_rx),
             task_sender: Some(task_tx),
             background_task: None,
+            watchers: Vec::new(),
+            watch_receiver: None,
             is_scanning: false,
             is_generating_report: false,
             syntax_set,
             theme_set,
             deferred_actions: Vec::new(),
-            orphaned_children: HashMap::new(),
+            scanned_node_ids: HashMap::new(),
             path_to_id_map: HashMap::new(),
             content_cache: HashMap::new(),
             is_calculating_tokens: false,
             token_worker_job_id: None,
             next_token_worker_job_id: 1,
+            index_token_job_id: None,
             token_worker_cancel: None,
             tree_rows_cache: Vec::new(),
             tree_rows_dirty: true,
             tree_rows_search: String::new(),
-            tree_rows_root_id: None,
+            tree_rows_root_ids: Vec::new(),
+            watch_token_ids: Vec::new(),
+            preview_reload_requested: false,
+            git_status: None,
             prefs_draft: None,
             report_options_draft: None,
             report_preview_state: None,
@@ -208,6 +303,8 @@ impl CodebaseApp {
             status_message: String::new(),
             preview_cache: None,
             show_preview_panel: false,
+            preview_target_line: None,
+            preview_scroll_pending: false,
             preview_word_wrap: false,
             preview_selectable_line_numbers: false,
             show_preferences_window: false,
@@ -215,6 +312,27 @@ impl CodebaseApp {
             show_about_window: false,
             show_shortcuts_window: false,
             show_ai_query_window: false,
+            show_changed_since_window: false,
+            pending_report_parts: None,
+            show_scan_errors_window: false,
+            show_stats_dashboard: false,
+            dashboard_data: None,
+            show_treemap_window: false,
+            treemap_metric: TreeColumn::Size,
+            treemap_root: None,
+            treemap_context_node: None,
+            show_snapshot_diff_window: false,
+            show_markers_window: false,
+            marker_list: None,
+            marker_filter: String::new(),
+            marker_keyword_filter: None,
+            marker_selected_only: false,
+            tree_markers_only: false,
+            snapshot_diff: None,
+            snapshot_diff_section: SnapshotDiffSection::Languages,
+            scan_error_filter: String::new(),
+            scan_error_kind_filter: None,
+            changed_since_ref: String::new(),
             ai_query_text: String::new(),
             ai_response_text: None,
             is_querying_ai: false,
@@ -227,26 +345,35 @@ impl CodebaseApp {
             report_preview_enabled: false,
             next_token_job_id: 1,
             nodes: Vec::new(),
-            root_id: None,
-            root_path: None,
+            roots: Vec::new(),
+            root_ids: Vec::new(),
+            workspace: None,
             scan_stats: Some(ScanStats::default()),
-            orphaned_children: HashMap::new(),
+            scan_progress: Vec::new(),
+            pending_scans: 0,
+            scanned_node_ids: HashMap::new(),
             path_to_id_map: HashMap::new(),
             content_cache: HashMap::new(),
             is_calculating_tokens: false,
             token_worker_job_id: None,
             next_token_worker_job_id: 1,
+            index_token_job_id: None,
             token_worker_cancel: None,
             tree_rows_cache: Vec::new(),
             tree_rows_dirty: true,
             tree_rows_search: String::new(),
-            tree_rows_root_id: None,
+            tree_rows_root_ids: Vec::new(),
+            watch_token_ids: Vec::new(),
+            preview_reload_requested: false,
+            git_status: None,
             scan_receiver: None,
             preview_receiver: None,
             preview_sender: None,
             task_receiver: None,
             task_sender: None,
             background_task: None,
+            watchers: Vec::new(),
+            watch_receiver: None,
             is_scanning: false,
             is_generating_report: false,
             syntax_set,
@@ -270,10 +397,13 @@ impl CodebaseApp {
     /// Cleans up background tasks on application exit.
     pub(crate) fn cleanup_on_exit(&mut self) {
         log::info!("Exit requested. Cleaning up background tasks...");
-        if let Some(BackgroundTask::Scan(_, cancel)) = &self.background_task {
+        if let Some(BackgroundTask::Scan(scans)) = &self.background_task {
             log::info!("Requesting scan cancellation on exit...");
-            cancel.store(true, Ordering::Relaxed);
+            for (_, cancel) in scans {
+                cancel.store(true, Ordering::Relaxed);
+            }
         }
+        self.stop_watching();
         if let Some(cancel) = &self.token_worker_cancel {
             log::info!("Requesting token worker cancellation on exit...");
             cancel.store(true, Ordering::Relaxed);
@@ -1,6 +1,10 @@
 #![allow(clippy::disallowed_macros)]
 
-use std::{env, fs, path::PathBuf};
+use std::{
+    env, fs,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, bail, Context};
 use arboard::Clipboard;
@@ -10,10 +14,17 @@ use crossbeam_channel::unbounded;
 use crate::{
     app::state::CodebaseApp,
     config::AppConfig,
-    fs::scanner,
+    fs::{
+        archive::ArchiveMember, encoding, git, license, scan_index, scanner, ScanError,
+        ScanProgress,
+    },
+    llm::token_counter::count_tokens_local,
     model::Check,
-    report::{self, ReportFormat, ReportOptions},
+    report::{self, split, FitStrategy, ReportFormat, ReportOptions, SplitLimit},
     selection,
+    snapshot::{Snapshot, SnapshotDiff},
+    task::ScanMessage,
+    workspace::{self, Workspace},
 };
 
 #[derive(Parser, Debug)]
@@ -27,7 +38,7 @@ pub struct Cli {
 pub enum Commands {
     /// Generate a report and copy it to the clipboard.
     Copy {
-        /// The root directory to scan. Defaults to the current directory.
+        /// The root directory or `.cbv-workspace` file to scan. Defaults to the current directory.
         #[arg(default_value = ".")]
         path: PathBuf,
 
@@ -39,12 +50,19 @@ pub enum Commands {
         #[arg(long, short)]
         selection: Option<PathBuf>,
 
+        /// Select only files changed since this git ref (e.g. `main`, `HEAD~3`), including uncommitted and untracked changes.
+        #[arg(long, value_name = "REF", conflicts_with_all = ["all", "selection"])]
+        changed_since: Option<String>,
+
+        #[command(flatten)]
+        scan_opts: ScanCliOptions,
+
         #[command(flatten)]
         report_opts: ReportCliOptions,
     },
     /// Generate a report and save it to a file.
     Generate {
-        /// The root directory to scan. Defaults to the current directory.
+        /// The root directory or `.cbv-workspace` file to scan. Defaults to the current directory.
         #[arg(default_value = ".")]
         path: PathBuf,
 
@@ -60,6 +78,22 @@ pub enum Commands {
         #[arg(long, short)]
         selection: Option<PathBuf>,
 
+        /// Select only files changed since this git ref (e.g. `main`, `HEAD~3`), including uncommitted and untracked changes.
+        #[arg(long, value_name = "REF", conflicts_with_all = ["all", "selection"])]
+        changed_since: Option<String>,
+
+        #[command(flatten)]
+        scan_opts: ScanCliOptions,
+
+        /// Split the report into numbered files of at most this many tokens each, breaking
+        /// only between files (`report_part1.md`, `report_part2.md`, ...).
+        #[arg(long, value_name = "TOKENS", conflicts_with = "split_bytes")]
+        split_tokens: Option<usize>,
+
+        /// Split the report into numbered files of at most this many bytes each.
+        #[arg(long, value_name = "BYTES")]
+        split_bytes: Option<usize>,
+
         #[command(flatten)]
         report_opts: ReportCliOptions,
     },
@@ -68,7 +102,7 @@ pub enum Commands {
         /// The query/prompt to send to the AI.
         prompt: String,
 
-        /// The root directory to scan. Defaults to the current directory.
+        /// The root directory or `.cbv-workspace` file to scan. Defaults to the current directory.
         #[arg(default_value = ".")]
         path: PathBuf,
 
@@ -80,9 +114,101 @@ pub enum Commands {
         #[arg(long, short)]
         selection: Option<PathBuf>,
 
+        /// Select only files changed since this git ref (e.g. `main`, `HEAD~3`), including uncommitted and untracked changes.
+        #[arg(long, value_name = "REF", conflicts_with_all = ["all", "selection"])]
+        changed_since: Option<String>,
+
+        #[command(flatten)]
+        scan_opts: ScanCliOptions,
+
         #[command(flatten)]
         report_opts: ReportCliOptions,
     },
+    /// Save the scan statistics and per-file metrics to a JSON snapshot file.
+    Snapshot {
+        /// The root directory or `.cbv-workspace` file to scan. Defaults to the current directory.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// The output file path for the snapshot.
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Count tokens for files whose count isn't in the scan index yet.
+        #[arg(long, default_value_t = false)]
+        count_tokens: bool,
+
+        #[command(flatten)]
+        scan_opts: ScanCliOptions,
+    },
+    /// Compare a snapshot with another snapshot or with a fresh scan.
+    Diff {
+        /// The older snapshot file.
+        old: PathBuf,
+
+        /// A newer snapshot file, or a directory or `.cbv-workspace` file to scan. Defaults to the current directory.
+        #[arg(default_value = ".")]
+        new: PathBuf,
+
+        /// Print the diff as JSON instead of text.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+
+        /// Maximum number of files listed per section of the text output.
+        #[arg(long, default_value_t = 25)]
+        limit: usize,
+
+        /// Count tokens for files whose count isn't in the scan index yet (when scanning).
+        #[arg(long, default_value_t = false)]
+        count_tokens: bool,
+
+        #[command(flatten)]
+        scan_opts: ScanCliOptions,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ScanCliOptions {
+    /// Discard the project's scan index and rebuild it from scratch.
+    #[arg(long, default_value_t = false)]
+    pub rebuild_index: bool,
+
+    /// Follow symbolic links while scanning, regardless of the configured setting.
+    #[arg(long, default_value_t = false)]
+    pub follow_symlinks: bool,
+
+    /// List the contents of zip and tar archives as directories, regardless of the configured setting.
+    #[arg(long, default_value_t = false)]
+    pub scan_archives: bool,
+
+    /// Only scan files matching this glob (repeatable). Added to the configured include globs.
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip files and directories matching this glob (repeatable). Added to the configured exclude globs.
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// List scan errors (unreadable files, walk failures, ...) on stderr in this format.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub errors: Option<CliErrorFormat>,
+
+    /// Exit with an error instead of producing a report if the scan reported any errors.
+    #[arg(long, default_value_t = false)]
+    pub fail_on_errors: bool,
+
+    /// Exit with an error, listing the files on stderr, if any source file lacks an SPDX
+    /// tag or license header. Generated, vendored and documentation files are exempt.
+    #[arg(long, default_value_t = false)]
+    pub require_license_header: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum CliErrorFormat {
+    /// One error per line.
+    Text,
+    /// A JSON array of error objects (`kind`, `path`, `message`, `os_error`).
+    Json,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -102,6 +228,28 @@ pub struct ReportCliOptions {
     /// Exclude line numbers from file content in the report context.
     #[arg(long, default_value_t = false)]
     pub no_line_numbers: bool,
+
+    /// Add an "Open Markers" section listing the TODO/FIXME/... comments of selected files.
+    #[arg(long, default_value_t = false)]
+    pub markers: bool,
+
+    /// Add a "Dependencies" section listing the dependencies declared in the project's
+    /// manifests (Cargo.toml, package.json, pyproject.toml, requirements.txt, go.mod).
+    #[arg(long, default_value_t = false)]
+    pub dependencies: bool,
+
+    /// Render the report with this Jinja template instead of the built-in format. The
+    /// output keeps the template's extension (`pr.md.j2` produces Markdown).
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Fit the report under this many tokens, listing what was dropped or truncated.
+    #[arg(long, value_name = "TOKENS")]
+    pub max_tokens: Option<usize>,
+
+    /// How to fit the report under `--max-tokens`.
+    #[arg(long, value_enum, default_value_t = CliFitStrategy::DropLargest)]
+    pub fit_strategy: CliFitStrategy,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
@@ -109,6 +257,21 @@ pub enum CliReportFormat {
     Markdown,
     Html,
     Text,
+    /// Machine-readable JSON with a versioned schema.
+    Json,
+    /// XML-tagged context for pasting into LLM prompts.
+    Llm,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum CliFitStrategy {
+    /// Leave out the files with the most tokens first.
+    DropLargest,
+    /// Leave out whole directories: low-priority ones (tests, docs, ...) first, then the
+    /// deepest ones.
+    DropLowPriorityDirs,
+    /// Shorten the longest file bodies, marking where they were cut.
+    Truncate,
 }
 
 pub async fn run_cli_command(command: Commands) -> anyhow::Result<()> {
@@ -117,11 +280,20 @@ pub async fn run_cli_command(command: Commands) -> anyhow::Result<()> {
             path,
             all,
             selection,
+            changed_since,
+            scan_opts,
             report_opts,
         } => {
             let config = AppConfig::load();
-            let report_content =
-                generate_report_headless(config, path, all, selection, &report_opts)?;
+            let report_content = generate_report_headless(
+                config,
+                path,
+                all,
+                selection,
+                changed_since,
+                &scan_opts,
+                &report_opts,
+            )?;
             let mut clipboard = Clipboard::new().context("Failed to access system clipboard")?;
             clipboard
                 .set_text(report_content)
@@ -133,20 +305,48 @@ pub async fn run_cli_command(command: Commands) -> anyhow::Result<()> {
             output,
             all,
             selection,
+            changed_since,
+            split_tokens,
+            split_bytes,
+            scan_opts,
             report_opts,
         } => {
             let config = AppConfig::load();
-            let report_content =
-                generate_report_headless(config, path, all, selection, &report_opts)?;
-            fs::write(&output, report_content)
-                .with_context(|| format!("Failed to write report to '{}'", output.display()))?;
-            println!("Report saved to {}", output.display());
+            let (app_state, mut report_options) = prepare_report_headless(
+                config,
+                path,
+                all,
+                selection,
+                changed_since,
+                &scan_opts,
+                &report_opts,
+            )?;
+            report_options.split = split_tokens
+                .map(SplitLimit::Tokens)
+                .or(split_bytes.map(SplitLimit::Bytes));
+            let parts = report::generate_report_parts(&app_state, &report_options)?;
+            let paths = split::part_paths(&output, parts.len());
+            for (part, part_path) in parts.into_iter().zip(&paths) {
+                fs::write(part_path, part).with_context(|| {
+                    format!("Failed to write report to '{}'", part_path.display())
+                })?;
+            }
+            if let [single] = paths.as_slice() {
+                println!("Report saved to {}", single.display());
+            } else {
+                println!("Report saved in {} parts:", paths.len());
+                for part_path in &paths {
+                    println!("  {}", part_path.display());
+                }
+            }
         }
         Commands::Query {
             prompt,
             path,
             all,
             selection,
+            changed_since,
+            scan_opts,
             report_opts,
         } => {
             let trimmed_prompt = prompt.trim().to_owned();
@@ -158,8 +358,15 @@ pub async fn run_cli_command(command: Commands) -> anyhow::Result<()> {
                 .ok()
                 .or_else(|| config.gemini_api_key.clone())
                 .context("GEMINI_API_KEY not found in environment or configuration")?;
-            let report_content =
-                generate_report_headless(config, path, all, selection, &report_opts)?;
+            let report_content = generate_report_headless(
+                config,
+                path,
+                all,
+                selection,
+                changed_since,
+                &scan_opts,
+                &report_opts,
+            )?;
             println!("Sending query to Gemini...");
             let response = crate::llm::gemini_service::query_codebase(
                 &api_key,
@@ -172,75 +379,324 @@ pub async fn run_cli_command(command: Commands) -> anyhow::Result<()> {
             println!("\n--- AI Response ---\n");
             println!("{response}");
         }
+        Commands::Snapshot {
+            path,
+            output,
+            count_tokens,
+            scan_opts,
+        } => {
+            let snapshot = snapshot_headless(AppConfig::load(), &path, count_tokens, &scan_opts)?;
+            snapshot.save(&output)?;
+            println!(
+                "Snapshot of {} file(s) saved to {}",
+                snapshot.files.len(),
+                output.display()
+            );
+        }
+        Commands::Diff {
+            old,
+            new,
+            json,
+            limit,
+            count_tokens,
+            scan_opts,
+        } => {
+            let old_snapshot = Snapshot::load(&old)?;
+            let new_snapshot = if new.is_file() && !workspace::is_workspace_file(&new) {
+                Snapshot::load(&new)?
+            } else {
+                snapshot_headless(AppConfig::load(), &new, count_tokens, &scan_opts)?
+            };
+            let diff = SnapshotDiff::new(&old_snapshot, &new_snapshot);
+            if json {
+                let json =
+                    serde_json::to_string_pretty(&diff).context("Failed to serialize diff")?;
+                println!("{json}");
+            } else {
+                print!("{}", diff.to_text(limit));
+            }
+        }
     }
 
     Ok(())
 }
 
-fn generate_report_headless(
+/// Scans a directory or workspace file without a UI and returns the populated app state.
+fn scan_headless(
     config: AppConfig,
-    path: PathBuf,
-    select_all: bool,
-    selection_file: Option<PathBuf>,
-    report_opts: &ReportCliOptions,
-) -> anyhow::Result<String> {
+    path: &Path,
+    scan_opts: &ScanCliOptions,
+) -> anyhow::Result<CodebaseApp> {
     let mut app_state = CodebaseApp::headless_from_config(config);
-    let absolute_path = path
-        .canonicalize()
-        .with_context(|| format!("Failed to resolve provided path '{}'", path.display()))?;
-    let show_hidden = app_state.config.show_hidden_files;
-    let respect_cbvignore = app_state.config.respect_cbvignore;
+    let roots = if workspace::is_workspace_file(path) {
+        let loaded = Workspace::load(path)?;
+        let roots = loaded.roots.clone();
+        app_state.workspace = Some(loaded);
+        roots
+    } else {
+        vec![path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve provided path '{}'", path.display()))?]
+    };
+    if scan_opts.rebuild_index {
+        let mut cleared = false;
+        for root in &roots {
+            cleared |= scan_index::clear(root)?;
+        }
+        if cleared {
+            println!("Scan index cleared; rebuilding.");
+        }
+    }
+    let mut scan_options = scanner::ScanOptions::from_config(&app_state.config);
+    scan_options.follow_symlinks |= scan_opts.follow_symlinks;
+    scan_options.scan_archives |= scan_opts.scan_archives;
+    scan_options
+        .include_globs
+        .extend(scan_opts.include.iter().cloned());
+    scan_options
+        .exclude_globs
+        .extend(scan_opts.exclude.iter().cloned());
+    // Validate the globs up front so a typo fails loudly instead of producing an empty scan.
+    for root in &roots {
+        scan_options.build_overrides(root)?;
+    }
 
     let (sender, receiver) = unbounded();
-    let (handle, _cancel_signal) = scanner::scan(
-        absolute_path.clone(),
-        show_hidden,
-        respect_cbvignore,
-        sender,
-    );
-
-    app_state.root_path = Some(absolute_path.clone());
-
+    let handles: Vec<_> = roots
+        .iter()
+        .map(|root| scanner::scan(root.clone(), scan_options.clone(), sender.clone()).0)
+        .collect();
+    drop(sender);
+
+    app_state.pending_scans = roots.len();
+    app_state.roots = roots;
+
+    // Progress goes to stderr so it never ends up in piped or redirected reports.
+    let show_progress = std::io::stderr().is_terminal();
+    let mut progress_shown = false;
     while let Ok(message) = receiver.recv() {
+        let is_progress = matches!(message, ScanMessage::Progress(_));
         if app_state.process_scan_message(message) {
             break;
         }
+        if show_progress && is_progress {
+            if let Some(progress) = ScanProgress::combine(&app_state.scan_progress) {
+                print_scan_progress(&progress);
+                progress_shown = true;
+            }
+        }
+    }
+    if progress_shown {
+        eprintln!();
     }
 
-    handle
-        .join()
-        .map_err(|_| anyhow!("Scanner thread panicked"))?;
+    for handle in handles {
+        handle
+            .join()
+            .map_err(|_| anyhow!("Scanner thread panicked"))?;
+    }
 
-    if let Some(root_id) = app_state.root_id {
-        if select_all {
-            app_state.set_node_state_recursive(root_id, Check::Checked);
-        } else if let Some(selection_path) = selection_file {
-            let selection_applied = selection::load_selection_from_file(
-                &mut app_state.nodes,
-                app_state.root_id,
-                &selection_path,
-            )?;
-            app_state.recalculate_all_parent_states(root_id);
-            println!("Selection loaded for root: {selection_applied}");
+    let (scan_errors, error_count) = app_state.scan_stats.as_ref().map_or((&[][..], 0), |stats| {
+        (stats.errors.as_slice(), stats.error_count())
+    });
+    print_scan_errors(scan_errors, error_count, scan_opts.errors)?;
+    if scan_opts.fail_on_errors && error_count > 0 {
+        bail!("Scan reported {error_count} error(s)");
+    }
+    if scan_opts.require_license_header {
+        let mut missing: Vec<String> = app_state
+            .nodes
+            .iter()
+            .filter(|node| license::is_missing_header(&node.info))
+            .map(|node| app_state.relative_path(node.path()).display().to_string())
+            .collect();
+        if !missing.is_empty() {
+            missing.sort_unstable();
+            eprintln!("Files without a license header:");
+            for path in &missing {
+                eprintln!("  {path}");
+            }
+            bail!("{} source file(s) have no license header", missing.len());
         }
-    } else {
+    }
+
+    if app_state.root_ids.is_empty() {
         bail!(
             "Scan completed but no root node was created. Ensure the directory contains readable files."
         );
     }
+    Ok(app_state)
+}
+
+fn generate_report_headless(
+    config: AppConfig,
+    path: PathBuf,
+    select_all: bool,
+    selection_file: Option<PathBuf>,
+    changed_since: Option<String>,
+    scan_opts: &ScanCliOptions,
+    report_opts: &ReportCliOptions,
+) -> anyhow::Result<String> {
+    let (app_state, report_options) = prepare_report_headless(
+        config,
+        path,
+        select_all,
+        selection_file,
+        changed_since,
+        scan_opts,
+        report_opts,
+    )?;
+    report::generate_report(&app_state, &report_options)
+}
+
+/// Scans `path`, applies the requested selection and returns the app state together with
+/// the report options built from the CLI flags.
+fn prepare_report_headless(
+    config: AppConfig,
+    path: PathBuf,
+    select_all: bool,
+    selection_file: Option<PathBuf>,
+    changed_since: Option<String>,
+    scan_opts: &ScanCliOptions,
+    report_opts: &ReportCliOptions,
+) -> anyhow::Result<(CodebaseApp, ReportOptions)> {
+    let mut app_state = scan_headless(config, &path, scan_opts)?;
+    if select_all {
+        for root_id in app_state.root_ids.clone() {
+            app_state.set_node_state_recursive(root_id, Check::Checked);
+        }
+    } else if let Some(reference) = changed_since {
+        let changed = git::changed_since_for_roots(&app_state.roots, &reference)?;
+        let selected = app_state.select_only_paths(&changed);
+        println!("Selected {selected} file(s) changed since '{reference}'.");
+    } else if let Some(selection_path) = selection_file {
+        let saved_roots = selection::load_selection_from_file(
+            &mut app_state.nodes,
+            &app_state.root_ids,
+            &selection_path,
+        )?;
+        for root_id in app_state.root_ids.clone() {
+            app_state.recalculate_all_parent_states(root_id);
+        }
+        println!("Selection loaded for root: {}", saved_roots.join(", "));
+    }
 
     let report_options = ReportOptions {
         format: match report_opts.format {
             CliReportFormat::Markdown => ReportFormat::Markdown,
             CliReportFormat::Html => ReportFormat::Html,
             CliReportFormat::Text => ReportFormat::Text,
+            CliReportFormat::Json => ReportFormat::Json,
+            CliReportFormat::Llm => ReportFormat::Llm,
         },
         include_stats: !report_opts.no_stats,
         include_contents: !report_opts.no_contents,
         include_line_numbers: !report_opts.no_line_numbers,
+        include_markers: report_opts.markers,
+        include_dependencies: report_opts.dependencies,
+        template: report_opts.template.clone(),
+        max_tokens: report_opts.max_tokens,
+        fit_strategy: match report_opts.fit_strategy {
+            CliFitStrategy::DropLargest => FitStrategy::DropLargest,
+            CliFitStrategy::DropLowPriorityDirs => FitStrategy::DropLowPriorityDirs,
+            CliFitStrategy::Truncate => FitStrategy::TruncateFiles,
+        },
+        split: None,
     };
 
     app_state.last_report_options = report_options.clone();
 
-    report::generate_report(&app_state, &report_options)
+    Ok((app_state, report_options))
+}
+
+/// Scans a directory or workspace file and captures a snapshot of it, optionally
+/// counting the tokens the scan index didn't already provide.
+fn snapshot_headless(
+    config: AppConfig,
+    path: &Path,
+    count_tokens: bool,
+    scan_opts: &ScanCliOptions,
+) -> anyhow::Result<Snapshot> {
+    let mut app_state = scan_headless(config, path, scan_opts)?;
+    if count_tokens {
+        let max_count_size = app_state.config.max_file_size_token_count;
+        for node in &mut app_state.nodes {
+            if node.is_dir()
+                || node.info.is_binary
+                || node.info.size > max_count_size
+                || node.token_count.is_some()
+            {
+                continue;
+            }
+            let bytes = match fs::metadata(node.path()) {
+                Ok(_) => fs::read(node.path()).ok(),
+                // Archive members are read straight from their archive.
+                Err(_) => ArchiveMember::from_virtual_path(node.path())
+                    .and_then(|member| member.read(max_count_size).ok()),
+            };
+            match bytes {
+                Some(bytes) => {
+                    let content = encoding::decode_text(bytes, node.info.encoding);
+                    node.token_count = Some(count_tokens_local(&content));
+                }
+                None => log::warn!(
+                    "Could not read '{}' to count its tokens",
+                    node.path().display()
+                ),
+            }
+        }
+        app_state.rollup_root_metrics();
+    }
+    app_state
+        .capture_snapshot()
+        .context("Scan completed without statistics")
+}
+
+/// Writes the stored scan errors, out of `error_count` in total, to stderr in the requested
+/// format, or a one-line hint if no format was requested.
+fn print_scan_errors(
+    errors: &[ScanError],
+    error_count: usize,
+    format: Option<CliErrorFormat>,
+) -> anyhow::Result<()> {
+    match format {
+        Some(CliErrorFormat::Json) => {
+            let json =
+                serde_json::to_string_pretty(errors).context("Failed to serialize scan errors")?;
+            eprintln!("{json}");
+        }
+        Some(CliErrorFormat::Text) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            if error_count > errors.len() {
+                eprintln!("... and {} more", error_count - errors.len());
+            }
+        }
+        None if error_count > 0 => {
+            eprintln!(
+                "Warning: the scan reported {error_count} error(s); use --errors text or --errors json to list them."
+            );
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Redraws the single-line scan progress indicator on stderr.
+fn print_scan_progress(progress: &ScanProgress) {
+    let mut line = format!("Scanning: {}", progress.summary());
+    if let Some(fraction) = progress.fraction() {
+        line = format!("[{:>3.0}%] {line}", fraction * 100.0);
+    }
+    if let Some(dir) = &progress.current_dir {
+        line.push_str(&format!(" · {}", dir.display()));
+    }
+    // Keep the line within a typical terminal width so `\r` can overwrite it.
+    if line.chars().count() > 120 {
+        line = line.chars().take(119).collect::<String>() + "…";
+    }
+    let mut stderr = std::io::stderr().lock();
+    let _ = write!(stderr, "\r\x1b[2K{line}");
+    let _ = stderr.flush();
 }
@@ -1,5 +1,10 @@
 //! Handles loading, saving, and managing application configuration.
 
+use crate::{
+    fs::{markers, FileClass},
+    model::TreeColumn,
+    report::{FitStrategy, ReportFormat, SplitLimit},
+};
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
@@ -27,7 +32,26 @@ pub struct AppConfig {
     pub show_hidden_files: bool,
     /// Whether the scanner should respect .cbvignore files.
     pub respect_cbvignore: bool,
-    /// Default format for generated reports: "markdown", "html", "text".
+    /// Whether the scanner should follow symbolic links (loops are detected and skipped).
+    pub follow_symlinks: bool,
+    /// Whether the scanner should list the contents of zip and tar archives as directories.
+    pub scan_archives: bool,
+    /// Whether to watch the opened directory for changes and update the tree live.
+    pub watch_filesystem: bool,
+    /// Whether to reuse per-file analysis (binary check, LOC, tokens) from the on-disk scan index.
+    pub use_scan_index: bool,
+    /// Glob patterns a file must match to be scanned (e.g. `src/**/*.rs`). Empty includes everything.
+    pub include_globs: Vec<String>,
+    /// Glob patterns for files and directories to exclude from scans (e.g. `**/generated/**`).
+    pub exclude_globs: Vec<String>,
+    /// Whether to flag generated, vendored and documentation files. Flagged files are shown
+    /// greyed out and left out of the file type, language and largest-file statistics.
+    pub classify_files: bool,
+    /// Classes of flagged files that start out deselected after a scan.
+    pub deselect_file_classes: Vec<FileClass>,
+    /// Keywords collected from comments as open markers (e.g. `TODO`). Empty disables markers.
+    pub marker_keywords: Vec<String>,
+    /// Default format for generated reports: "markdown", "html", "text", "json", "llm".
     pub export_format: String,
     /// Default setting for including scan statistics in reports.
     pub export_include_stats: bool,
@@ -35,8 +59,31 @@ pub struct AppConfig {
     pub export_include_contents: bool,
     /// Default setting for including line numbers in report file contents.
     pub export_include_line_numbers: bool,
+    /// Default setting for including the open markers of selected files in reports.
+    pub export_include_markers: bool,
+    /// Default setting for including the project's declared dependencies in reports.
+    pub export_include_dependencies: bool,
+    /// Report template files offered in the report options window.
+    pub report_templates: Vec<PathBuf>,
+    /// Default token budget for reports, or `None` for no limit.
+    pub export_max_tokens: Option<usize>,
+    /// Default way of fitting a report under its token budget.
+    pub export_fit_strategy: FitStrategy,
+    /// Default limit for splitting reports into parts, or `None` to keep them whole.
+    pub export_split: Option<SplitLimit>,
+    /// Globs for directories (relative to the project, e.g. `**/tests`) whose files are left
+    /// out first when fitting a report by dropping directories. Earlier entries go first.
+    pub low_priority_dirs: Vec<String>,
+    /// Whether to read the git status of the scanned directory and show it in the tree.
+    pub show_git_status: bool,
+    /// Whether to show each file's detected license next to it in the tree.
+    pub show_license_badges: bool,
     /// Whether to show token counts in the file tree.
     pub show_token_counts: bool,
+    /// Metric columns shown next to each entry in the file tree.
+    pub tree_columns: Vec<TreeColumn>,
+    /// Metric the file tree is sorted by (largest first), or `None` to sort by name.
+    pub tree_sort: Option<TreeColumn>,
     /// Maximum file size (bytes) to include in token counting per file.
     pub max_file_size_token_count: u64,
     /// Maximum file size (bytes) to cache in memory per file during token counting.
@@ -45,6 +92,8 @@ pub struct AppConfig {
     pub recent_projects: Vec<PathBuf>,
     /// Stored Gemini API key fallback when environment variable is not set.
     pub gemini_api_key: Option<String>,
+    /// Format of the project context sent along with AI queries.
+    pub ai_context_format: ReportFormat,
 }
 
 impl Default for AppConfig {
@@ -57,15 +106,49 @@ impl Default for AppConfig {
             max_file_size_preview: 1_048_576, // 1 MiB
             show_hidden_files: false,
             respect_cbvignore: true,
+            follow_symlinks: false,
+            scan_archives: false,
+            watch_filesystem: false,
+            use_scan_index: true,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            classify_files: true,
+            deselect_file_classes: vec![FileClass::Generated, FileClass::Vendored],
+            marker_keywords: markers::DEFAULT_KEYWORDS
+                .iter()
+                .map(|keyword| keyword.to_string())
+                .collect(),
             export_format: "markdown".to_string(),
             export_include_stats: true,
             export_include_contents: true,
             export_include_line_numbers: false,
+            export_include_markers: false,
+            export_include_dependencies: false,
+            report_templates: Vec::new(),
+            export_max_tokens: None,
+            export_fit_strategy: FitStrategy::DropLargest,
+            export_split: None,
+            low_priority_dirs: [
+                "**/tests",
+                "**/test",
+                "**/benches",
+                "**/examples",
+                "**/docs",
+                "**/fixtures",
+            ]
+            .iter()
+            .map(|glob| glob.to_string())
+            .collect(),
+            show_git_status: true,
+            show_license_badges: false,
             show_token_counts: false,
+            tree_columns: Vec::new(),
+            tree_sort: None,
             max_file_size_token_count: 10 * 1024 * 1024, // 10 MiB
             max_file_size_token_cache: 500 * 1024,       // 500 KiB
             recent_projects: Vec::new(),
             gemini_api_key: None,
+            ai_context_format: ReportFormat::Markdown,
         }
     }
 }
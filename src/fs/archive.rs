@@ -0,0 +1,388 @@
+//! Reads `.zip`, `.tar`, `.tar.gz` and `.tgz` archives as virtual directories.
+//!
+//! When archive scanning is enabled, an archive found by the scanner becomes a
+//! directory node whose children are the archive's members. Member nodes use
+//! virtual paths of the form `<archive path>/<entry path>`, so they fit into the
+//! tree like regular files. Their contents are read straight from the archive on
+//! demand; nothing is ever extracted to disk.
+
+use crate::{
+    fs::{encoding, file_info::loc_stats_from_bytes, FileInfo},
+    model::FileNode,
+};
+use flate2::read::GzDecoder;
+use humansize::{format_size, DECIMAL};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Members larger than this are sniffed for binary content but not line-counted.
+const MAX_ANALYZED_MEMBER_SIZE: u64 = 4 * 1024 * 1024;
+/// Upper bound on the buffer reserved up front for a member, whatever its header claims.
+const MAX_PREALLOCATED_MEMBER_SIZE: u64 = 16 * 1024 * 1024;
+/// Stop listing an archive after this many members to keep the tree responsive.
+const MAX_ARCHIVE_MEMBERS: usize = 50_000;
+
+/// Supported archive formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Detects the archive format from a file name, if it is a supported archive.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Locates a node inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveMember {
+    /// Path of the archive file on disk.
+    pub archive_path: PathBuf,
+    pub kind: ArchiveKind,
+    /// `/`-separated path of the member inside the archive. Empty for the archive itself.
+    pub entry: String,
+}
+
+impl ArchiveMember {
+    /// Resolves a virtual path such as `/repo/sdk.zip/include/api.h` to the archive
+    /// that contains it. Returns `None` if no ancestor of `path` is an archive file.
+    pub fn from_virtual_path(path: &Path) -> Option<Self> {
+        for ancestor in path.ancestors().skip(1) {
+            let Some(kind) = ArchiveKind::from_path(ancestor) else {
+                continue;
+            };
+            if !ancestor.is_file() {
+                continue;
+            }
+            let entry = path
+                .strip_prefix(ancestor)
+                .ok()?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            return Some(Self {
+                archive_path: ancestor.to_path_buf(),
+                kind,
+                entry,
+            });
+        }
+        None
+    }
+
+    /// Reads the member's contents, failing if it is larger than `max_size` bytes.
+    pub fn read(&self, max_size: u64) -> anyhow::Result<Vec<u8>> {
+        let mut found = None;
+        visit_members(&self.archive_path, self.kind, |name, _, size, reader| {
+            if name != self.entry {
+                return Ok(true);
+            }
+            if size > max_size {
+                found = Some(Err(anyhow::anyhow!(
+                    "Archive member size ({}) exceeds the limit ({})",
+                    format_size(size, DECIMAL),
+                    format_size(max_size, DECIMAL)
+                )));
+            } else {
+                // The header's size is only a hint: a crafted archive can claim any size,
+                // so never allocate from it and never read past the limit.
+                let mut bytes = Vec::with_capacity(size.min(MAX_PREALLOCATED_MEMBER_SIZE) as usize);
+                reader
+                    .take(max_size.saturating_add(1))
+                    .read_to_end(&mut bytes)?;
+                found = Some(if bytes.len() as u64 > max_size {
+                    Err(anyhow::anyhow!(
+                        "Archive member exceeds the limit ({})",
+                        format_size(max_size, DECIMAL)
+                    ))
+                } else {
+                    Ok(bytes)
+                });
+            }
+            Ok(false)
+        })?;
+        found.unwrap_or_else(|| {
+            Err(anyhow::anyhow!(
+                "'{}' not found in archive '{}'",
+                self.entry,
+                self.archive_path.display()
+            ))
+        })
+    }
+}
+
+/// Turns a scanned archive file node into a virtual directory and returns nodes for
+/// all of its members, including directories implied by member paths.
+///
+/// On error the node is left untouched, so the archive still appears as a plain file.
+pub fn expand_node(node: &mut FileNode) -> anyhow::Result<Vec<FileNode>> {
+    let archive_path = node.path().to_path_buf();
+    let kind = ArchiveKind::from_path(&archive_path)
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not an archive", archive_path.display()))?;
+
+    let mut files: Vec<FileInfo> = Vec::new();
+    let mut dirs: BTreeMap<String, Option<SystemTime>> = BTreeMap::new();
+    let mut truncated = false;
+
+    visit_members(&archive_path, kind, |name, modified, size, reader| {
+        if files.len() + dirs.len() >= MAX_ARCHIVE_MEMBERS {
+            truncated = true;
+            return Ok(false);
+        }
+        // Record every parent directory, since archives don't always list them.
+        let mut parent = name;
+        while let Some((dir, _)) = parent.rsplit_once('/') {
+            dirs.entry(dir.to_string()).or_insert(None);
+            parent = dir;
+        }
+        if let Some(dir) = name.strip_suffix('/') {
+            dirs.insert(dir.to_string(), modified);
+            return Ok(true);
+        }
+        let info = analyze_member(&archive_path, kind, name, modified, size, reader)?;
+        files.push(info);
+        Ok(true)
+    })?;
+
+    if truncated {
+        log::warn!(
+            "Archive '{}' has more than {} members; the rest are not shown.",
+            archive_path.display(),
+            MAX_ARCHIVE_MEMBERS
+        );
+    }
+
+    let mut members = Vec::with_capacity(dirs.len() + files.len());
+    for (dir, modified) in dirs {
+        if dir.is_empty() {
+            continue;
+        }
+        members.push(FileNode::new(FileInfo {
+            path: virtual_path(&archive_path, &dir),
+            is_dir: true,
+            size: 0,
+            human_size: format_size(0u64, DECIMAL),
+            is_binary: false,
+            encoding: None,
+            modified,
+            extension: None,
+            loc_stats: None,
+            symlink_target: None,
+            class: None,
+            generated_marker: false,
+            markers: None,
+            license: None,
+            decode_error: None,
+            archive: Some(ArchiveMember {
+                archive_path: archive_path.clone(),
+                kind,
+                entry: dir,
+            }),
+        }));
+    }
+    members.extend(files.into_iter().map(FileNode::new));
+
+    node.info.is_dir = true;
+    node.info.is_binary = false;
+    node.info.encoding = None;
+    node.info.loc_stats = None;
+    node.info.archive = Some(ArchiveMember {
+        archive_path,
+        kind,
+        entry: String::new(),
+    });
+    node.token_count = None;
+    Ok(members)
+}
+
+/// Builds the `FileInfo` for a file member, sniffing and line-counting it in memory.
+fn analyze_member(
+    archive_path: &Path,
+    kind: ArchiveKind,
+    name: &str,
+    modified: Option<SystemTime>,
+    size: u64,
+    reader: &mut dyn Read,
+) -> anyhow::Result<FileInfo> {
+    let path = virtual_path(archive_path, name);
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+
+    let read_limit = if size <= MAX_ANALYZED_MEMBER_SIZE {
+        size
+    } else {
+        encoding::SNIFF_LEN as u64
+    };
+    let mut bytes = Vec::with_capacity(read_limit as usize);
+    reader.take(read_limit).read_to_end(&mut bytes)?;
+
+    let sniff_len = bytes.len().min(encoding::SNIFF_LEN);
+    let text_encoding = encoding::detect(&bytes[..sniff_len]);
+    let loc_stats = match text_encoding {
+        Some(text_encoding) if size <= MAX_ANALYZED_MEMBER_SIZE => {
+            loc_stats_from_bytes(&path, encoding::decode(&bytes, text_encoding).as_bytes())
+        }
+        _ => None,
+    };
+
+    Ok(FileInfo {
+        path,
+        is_dir: false,
+        size,
+        human_size: format_size(size, DECIMAL),
+        is_binary: text_encoding.is_none(),
+        encoding: text_encoding,
+        modified,
+        extension,
+        loc_stats,
+        symlink_target: None,
+        class: None,
+        generated_marker: false,
+        // Members aren't searched for comment markers.
+        markers: Some(Vec::new()),
+        license: None,
+        decode_error: None,
+        archive: Some(ArchiveMember {
+            archive_path: archive_path.to_path_buf(),
+            kind,
+            entry: name.to_string(),
+        }),
+    })
+}
+
+/// Joins an archive path and a `/`-separated member path into a virtual path.
+fn virtual_path(archive_path: &Path, entry: &str) -> PathBuf {
+    entry
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .fold(archive_path.to_path_buf(), |path, part| path.join(part))
+}
+
+/// Calls `visit(name, modified, size, reader)` for each member of an archive, in
+/// archive order. Directory names end with `/`. `visit` returns `false` to stop early.
+/// Members with unsafe names (absolute or containing `..`) are skipped.
+fn visit_members<F>(archive_path: &Path, kind: ArchiveKind, mut visit: F) -> anyhow::Result<()>
+where
+    F: FnMut(&str, Option<SystemTime>, u64, &mut dyn Read) -> anyhow::Result<bool>,
+{
+    let file = File::open(archive_path).map_err(|e| {
+        anyhow::anyhow!("Failed to open archive '{}': {}", archive_path.display(), e)
+    })?;
+    let reader = BufReader::new(file);
+    let context = |e: &dyn std::fmt::Display| {
+        anyhow::anyhow!("Failed to read archive '{}': {}", archive_path.display(), e)
+    };
+
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(reader).map_err(|e| context(&e))?;
+            for i in 0..zip.len() {
+                let mut member = zip.by_index(i).map_err(|e| context(&e))?;
+                let Some(name) = member.enclosed_name().map(|p| normalize_name(&p)) else {
+                    continue;
+                };
+                let name = if member.is_dir() {
+                    format!("{name}/")
+                } else {
+                    name
+                };
+                let modified = member.last_modified().and_then(zip_time_to_system);
+                let size = member.size();
+                if !visit(&name, modified, size, &mut member)? {
+                    break;
+                }
+            }
+        }
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let reader: Box<dyn Read> = if kind == ArchiveKind::TarGz {
+                Box::new(GzDecoder::new(reader))
+            } else {
+                Box::new(reader)
+            };
+            let mut tar = tar::Archive::new(reader);
+            for member in tar.entries().map_err(|e| context(&e))? {
+                let mut member = member.map_err(|e| context(&e))?;
+                let entry_type = member.header().entry_type();
+                if !entry_type.is_file() && !entry_type.is_dir() {
+                    continue;
+                }
+                let path = member.path().map_err(|e| context(&e))?.into_owned();
+                if path.is_absolute()
+                    || path
+                        .components()
+                        .any(|c| matches!(c, std::path::Component::ParentDir))
+                {
+                    continue;
+                }
+                let name = normalize_name(&path);
+                if name.is_empty() {
+                    continue;
+                }
+                let name = if entry_type.is_dir() {
+                    format!("{name}/")
+                } else {
+                    name
+                };
+                let modified = member
+                    .header()
+                    .mtime()
+                    .ok()
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+                let size = member.size();
+                if !visit(&name, modified, size, &mut member)? {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Converts a member path to a `/`-separated name without leading `./`.
+fn normalize_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Converts a zip timestamp (local time without a zone) to a `SystemTime`.
+fn zip_time_to_system(time: zip::DateTime) -> Option<SystemTime> {
+    use chrono::TimeZone;
+    let naive = chrono::NaiveDate::from_ymd_opt(
+        i32::from(time.year()),
+        u32::from(time.month()),
+        u32::from(time.day()),
+    )?
+    .and_hms_opt(
+        u32::from(time.hour()),
+        u32::from(time.minute()),
+        u32::from(time.second()),
+    )?;
+    let local = chrono::Local.from_local_datetime(&naive).earliest()?;
+    Some(local.into())
+}
@@ -0,0 +1,485 @@
+//! Linguist-style classification of generated, vendored and documentation files.
+//!
+//! A file's class is decided, in order of precedence, by:
+//!
+//! 1. `linguist-generated`, `linguist-vendored` and `linguist-documentation` attributes
+//!    in `.gitattributes` files between the scan root and the file. An attribute that is
+//!    explicitly unset (`-linguist-vendored` or `linguist-vendored=false`) overrides the
+//!    heuristics below.
+//! 2. A generated-code marker such as `@generated` or `DO NOT EDIT` near the top of the file.
+//! 3. Path heuristics: lockfiles, minified bundles, protobuf output, `vendor/` trees,
+//!    `docs/` and the like.
+
+use crate::fs::encoding;
+use globset::{GlobBuilder, GlobMatcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// Why a file is usually not part of the code a reader (or an LLM) cares about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileClass {
+    /// Produced by a tool: lockfiles, minified bundles, protobuf output, ...
+    Generated,
+    /// Third-party code checked into the repository.
+    Vendored,
+    /// Documentation, licences and examples.
+    Documentation,
+}
+
+impl FileClass {
+    /// All classes, in display order.
+    pub const ALL: [FileClass; 3] = [
+        FileClass::Generated,
+        FileClass::Vendored,
+        FileClass::Documentation,
+    ];
+
+    /// Human-readable name of the class.
+    pub fn label(self) -> &'static str {
+        match self {
+            FileClass::Generated => "Generated",
+            FileClass::Vendored => "Vendored",
+            FileClass::Documentation => "Documentation",
+        }
+    }
+
+    /// The `.gitattributes` attribute that sets or unsets this class.
+    pub fn attribute(self) -> &'static str {
+        match self {
+            FileClass::Generated => "linguist-generated",
+            FileClass::Vendored => "linguist-vendored",
+            FileClass::Documentation => "linguist-documentation",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Number of leading lines searched for a generated-code marker.
+const MARKER_LINES: usize = 5;
+/// Number of leading bytes searched for a generated-code marker.
+const MARKER_SAMPLE_SIZE: usize = 2048;
+
+/// Case-sensitive markers tools put in the header of files they generate.
+const GENERATED_MARKERS: &[&str] = &["@generated", "DO NOT EDIT", "Code generated by"];
+/// Lowercase markers matched case-insensitively.
+const GENERATED_MARKERS_LOWER: &[&str] =
+    &["autogenerated", "auto-generated", "automatically generated"];
+
+/// Returns `true` if one of the first few lines of `text` carries a generated-code marker.
+pub fn contains_generated_marker(text: &str) -> bool {
+    let sample = encoding::text_prefix(text, MARKER_SAMPLE_SIZE);
+    sample.lines().take(MARKER_LINES).any(|line| {
+        let lower = line.to_lowercase();
+        GENERATED_MARKERS.iter().any(|marker| line.contains(marker))
+            || GENERATED_MARKERS_LOWER
+                .iter()
+                .any(|marker| lower.contains(marker))
+    })
+}
+
+/// Exact file names of lockfiles and other tool output.
+const GENERATED_FILE_NAMES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "composer.lock",
+    "Gemfile.lock",
+    "Pipfile.lock",
+    "poetry.lock",
+    "uv.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+    "Podfile.lock",
+    "Package.resolved",
+];
+
+/// File name suffixes of minified bundles, source maps and generated bindings.
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".min.js",
+    "-min.js",
+    ".min.css",
+    "-min.css",
+    ".js.map",
+    ".css.map",
+    ".pb.go",
+    ".pb.gw.go",
+    ".pb.cc",
+    ".pb.h",
+    ".pb.swift",
+    "_pb2.py",
+    "_pb2.pyi",
+    "_pb2_grpc.py",
+    "_pb.js",
+    "_pb.d.ts",
+    "_grpc_pb.js",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+    ".g.cs",
+];
+
+/// Directory names holding generated output at any depth.
+const GENERATED_DIRS: &[&str] = &["__generated__"];
+
+/// Directory names holding third-party code at any depth.
+const VENDORED_DIRS: &[&str] = &[
+    "vendor",
+    "vendors",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "node_modules",
+    "bower_components",
+    "Pods",
+    "Carthage",
+];
+
+/// Directory names holding documentation at the top of the scan root.
+const DOCUMENTATION_ROOT_DIRS: &[&str] =
+    &["doc", "docs", "Doc", "Docs", "man", "examples", "Examples"];
+/// Directory names holding documentation at any depth.
+const DOCUMENTATION_DIRS: &[&str] = &[
+    "Documentation",
+    "documentation",
+    "javadoc",
+    "sample",
+    "samples",
+    "Sample",
+    "Samples",
+];
+/// File stems (the name up to the first `.`) of documentation files, compared case-insensitively.
+const DOCUMENTATION_STEMS: &[&str] = &["readme", "changelog", "contributing", "license", "licence"];
+/// Documentation file stems that only count in upper case, since `install.sh` is no manual.
+const DOCUMENTATION_UPPER_STEMS: &[&str] = &["AUTHORS", "CHANGES", "COPYING", "INSTALL", "NOTICE"];
+
+/// Classifies a path relative to the scan root by its name alone.
+pub fn classify_path(relative: &Path) -> Option<FileClass> {
+    let components: Vec<&str> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    let &name = components.last()?;
+
+    if components.iter().any(|dir| VENDORED_DIRS.contains(dir)) {
+        return Some(FileClass::Vendored);
+    }
+    if GENERATED_FILE_NAMES.contains(&name)
+        || GENERATED_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+        || components.iter().any(|dir| GENERATED_DIRS.contains(dir))
+    {
+        return Some(FileClass::Generated);
+    }
+    let stem = name.split('.').next().unwrap_or(name);
+    if components
+        .first()
+        .is_some_and(|top| DOCUMENTATION_ROOT_DIRS.contains(top))
+        || components
+            .iter()
+            .any(|dir| DOCUMENTATION_DIRS.contains(dir))
+        || DOCUMENTATION_STEMS.contains(&stem.to_lowercase().as_str())
+        || DOCUMENTATION_UPPER_STEMS.contains(&stem)
+    {
+        return Some(FileClass::Documentation);
+    }
+    None
+}
+
+/// One line of a `.gitattributes` file that mentions a linguist attribute.
+#[derive(Debug)]
+struct AttributeRule {
+    matcher: GlobMatcher,
+    /// Whether the pattern matches the file name only, at any depth.
+    basename_only: bool,
+    /// Attribute values indexed by `FileClass::index`; `None` leaves the class unspecified.
+    values: [Option<bool>; 3],
+}
+
+/// Parses the linguist attributes from a `.gitattributes` file. Lines that don't mention
+/// them, and patterns that can't be compiled, are skipped.
+fn parse_gitattributes(contents: &str, source: &Path) -> Vec<AttributeRule> {
+    let mut rules = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        let Some(pattern) = tokens.next() else {
+            continue;
+        };
+        let mut values = [None; 3];
+        for token in tokens {
+            let (name, value) = if let Some(name) = token.strip_prefix('-') {
+                (name, false)
+            } else if let Some((name, value)) = token.split_once('=') {
+                (name, !matches!(value, "false" | "0"))
+            } else {
+                (token, true)
+            };
+            if let Some(class) = FileClass::ALL.iter().find(|c| c.attribute() == name) {
+                values[class.index()] = Some(value);
+            }
+        }
+        // Git ignores directory patterns in attribute files, so they can't match a file.
+        if values.iter().all(Option::is_none) || pattern.ends_with('/') {
+            continue;
+        }
+        let basename_only = !pattern.contains('/');
+        let glob = pattern.trim_start_matches('/');
+        match GlobBuilder::new(glob).literal_separator(true).build() {
+            Ok(glob) => rules.push(AttributeRule {
+                matcher: glob.compile_matcher(),
+                basename_only,
+                values,
+            }),
+            Err(e) => log::warn!(
+                "Skipping invalid pattern '{pattern}' in '{}': {e}",
+                source.display()
+            ),
+        }
+    }
+    rules
+}
+
+/// Classifies the files of one scan root. Shared between the scanner's walker threads.
+#[derive(Debug)]
+pub struct FileClassifier {
+    root: PathBuf,
+    /// Parsed `.gitattributes` rules per directory, `None` if the directory has none.
+    attributes: Mutex<HashMap<PathBuf, Option<Arc<Vec<AttributeRule>>>>>,
+}
+
+impl FileClassifier {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            attributes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the class of the entry at `path`. `generated_marker` is the result of
+    /// `contains_generated_marker` for the file's text, or `false` for directories.
+    pub fn classify(&self, path: &Path, generated_marker: bool) -> Option<FileClass> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        if relative.as_os_str().is_empty() {
+            return None;
+        }
+        let values = self.attribute_values(relative);
+        if let Some(class) = FileClass::ALL
+            .into_iter()
+            .find(|class| values[class.index()] == Some(true))
+        {
+            return Some(class);
+        }
+        let not_unset = |class: &FileClass| values[class.index()] != Some(false);
+        generated_marker
+            .then_some(FileClass::Generated)
+            .filter(not_unset)
+            .or_else(|| classify_path(relative).filter(not_unset))
+    }
+
+    /// Forgets the cached `.gitattributes` rules of `dir`, e.g. after the file changed.
+    pub fn invalidate(&self, dir: &Path) {
+        self.attributes.lock().remove(dir);
+    }
+
+    /// Resolves the linguist attributes for `relative`, applying `.gitattributes` files from
+    /// the root downwards so that deeper files and later lines take precedence.
+    fn attribute_values(&self, relative: &Path) -> [Option<bool>; 3] {
+        let mut values = [None; 3];
+        let mut dir = self.root.clone();
+        let mut dir_relative = PathBuf::new();
+        let parent_components = relative.parent().map_or(0, |p| p.components().count());
+        let mut components = relative.components();
+        for depth in 0..=parent_components {
+            if depth > 0 {
+                let Some(component) = components.next() else {
+                    break;
+                };
+                dir.push(component);
+                dir_relative.push(component);
+            }
+            let Some(rules) = self.rules_for(&dir) else {
+                continue;
+            };
+            let Ok(path_in_dir) = relative.strip_prefix(&dir_relative) else {
+                continue;
+            };
+            for rule in rules.iter() {
+                let is_match = if rule.basename_only {
+                    path_in_dir
+                        .file_name()
+                        .is_some_and(|name| rule.matcher.is_match(name))
+                } else {
+                    rule.matcher.is_match(path_in_dir)
+                };
+                if is_match {
+                    for (value, rule_value) in values.iter_mut().zip(rule.values) {
+                        if rule_value.is_some() {
+                            *value = rule_value;
+                        }
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    /// Returns the parsed `.gitattributes` rules of `dir`, loading them on first use.
+    fn rules_for(&self, dir: &Path) -> Option<Arc<Vec<AttributeRule>>> {
+        if let Some(cached) = self.attributes.lock().get(dir) {
+            return cached.clone();
+        }
+        let file = dir.join(".gitattributes");
+        let rules = match fs::read_to_string(&file) {
+            Ok(contents) => {
+                let rules = parse_gitattributes(&contents, &file);
+                log::debug!(
+                    "Loaded {} linguist rule(s) from {}",
+                    rules.len(),
+                    file.display()
+                );
+                (!rules.is_empty()).then(|| Arc::new(rules))
+            }
+            // Archives scanned as directories can't hold attribute files.
+            Err(e) if e.kind() == ErrorKind::NotFound || !dir.is_dir() => None,
+            Err(e) => {
+                log::warn!("Failed to read '{}': {}", file.display(), e);
+                None
+            }
+        };
+        self.attributes
+            .lock()
+            .insert(dir.to_path_buf(), rules.clone());
+        rules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class_of(path: &str) -> Option<FileClass> {
+        classify_path(Path::new(path))
+    }
+
+    #[test]
+    fn classifies_paths_by_name() {
+        assert_eq!(class_of("Cargo.lock"), Some(FileClass::Generated));
+        assert_eq!(class_of("web/app.min.js"), Some(FileClass::Generated));
+        assert_eq!(
+            class_of("src/__generated__/api.ts"),
+            Some(FileClass::Generated)
+        );
+        assert_eq!(class_of("vendor/lib/util.c"), Some(FileClass::Vendored));
+        assert_eq!(
+            class_of("ui/node_modules/x/index.js"),
+            Some(FileClass::Vendored)
+        );
+        assert_eq!(class_of("docs/guide.md"), Some(FileClass::Documentation));
+        assert_eq!(class_of("README.md"), Some(FileClass::Documentation));
+        assert_eq!(class_of("INSTALL"), Some(FileClass::Documentation));
+        assert_eq!(class_of("src/main.rs"), None);
+        // `docs` only counts at the top of the root, `INSTALL` only in upper case.
+        assert_eq!(class_of("src/docs/mod.rs"), None);
+        assert_eq!(class_of("install.sh"), None);
+    }
+
+    #[test]
+    fn vendored_takes_precedence_over_generated() {
+        assert_eq!(class_of("vendor/dep/Cargo.lock"), Some(FileClass::Vendored));
+    }
+
+    #[test]
+    fn parses_linguist_attributes() {
+        let rules = parse_gitattributes(
+            "# comment\n\
+             *.pb.rs linguist-generated\n\
+             /third/** linguist-vendored=true\n\
+             docs/*.md -linguist-documentation\n\
+             api.rs linguist-generated=false linguist-vendored\n\
+             *.txt text eol=lf\n\
+             build/ linguist-generated\n\
+             [ linguist-generated\n",
+            Path::new(".gitattributes"),
+        );
+        assert_eq!(rules.len(), 4);
+
+        assert!(rules[0].basename_only);
+        assert!(rules[0].matcher.is_match("model.pb.rs"));
+        assert_eq!(rules[0].values, [Some(true), None, None]);
+
+        assert!(!rules[1].basename_only);
+        assert!(rules[1].matcher.is_match("third/a/b.c"));
+        assert_eq!(rules[1].values, [None, Some(true), None]);
+
+        assert!(!rules[2].basename_only);
+        assert!(rules[2].matcher.is_match("docs/a.md"));
+        assert!(!rules[2].matcher.is_match("docs/sub/a.md"));
+        assert_eq!(rules[2].values, [None, None, Some(false)]);
+
+        assert_eq!(rules[3].values, [Some(false), Some(true), None]);
+    }
+
+    #[test]
+    fn classifier_applies_attributes_and_picks_up_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(".gitattributes"), "*.gen.rs linguist-generated\n").unwrap();
+        fs::write(
+            root.join("src/.gitattributes"),
+            "Cargo.lock -linguist-generated\n",
+        )
+        .unwrap();
+        let classifier = FileClassifier::new(root);
+
+        let file = root.join("src/api.gen.rs");
+        assert_eq!(
+            classifier.classify(&file, false),
+            Some(FileClass::Generated)
+        );
+        assert_eq!(
+            classifier.classify(&root.join("src/lib.rs"), true),
+            Some(FileClass::Generated)
+        );
+        // A deeper attribute file unsets the built-in lockfile rule.
+        assert_eq!(
+            classifier.classify(&root.join("src/Cargo.lock"), false),
+            None
+        );
+        assert_eq!(
+            classifier.classify(&root.join("Cargo.lock"), false),
+            Some(FileClass::Generated)
+        );
+
+        fs::write(root.join(".gitattributes"), "*.gen.rs linguist-vendored\n").unwrap();
+        assert_eq!(
+            classifier.classify(&file, false),
+            Some(FileClass::Generated)
+        );
+        classifier.invalidate(root);
+        assert_eq!(classifier.classify(&file, false), Some(FileClass::Vendored));
+    }
+}
@@ -0,0 +1,269 @@
+//! Text encoding detection and transcoding to UTF-8.
+//!
+//! Files are sniffed from their first few KiB: a byte order mark wins, then UTF-16
+//! without a BOM is recognised by its pattern of NUL bytes, and anything else that
+//! contains NUL bytes is binary. Text that isn't valid UTF-8 is read as Windows-1252,
+//! the superset of Latin-1 that legacy Windows and Western European files use. Since the
+//! sniffed sample may end before the first non-ASCII byte, content sniffed as UTF-8 falls
+//! back to Windows-1252 when it is decoded in full and turns out not to be UTF-8.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// Number of leading bytes examined when detecting a file's encoding.
+pub const SNIFF_LEN: usize = 8192;
+
+/// The character encoding of a text file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+impl TextEncoding {
+    /// Human-readable name of the encoding.
+    pub fn label(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf8Bom => "UTF-8 (BOM)",
+            TextEncoding::Utf16Le => "UTF-16 LE",
+            TextEncoding::Utf16Be => "UTF-16 BE",
+            TextEncoding::Windows1252 => "Windows-1252",
+        }
+    }
+
+    fn codec(self) -> &'static encoding_rs::Encoding {
+        match self {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => encoding_rs::UTF_8,
+            TextEncoding::Utf16Le => encoding_rs::UTF_16LE,
+            TextEncoding::Utf16Be => encoding_rs::UTF_16BE,
+            TextEncoding::Windows1252 => encoding_rs::WINDOWS_1252,
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf8Bom => b"\xEF\xBB\xBF",
+            TextEncoding::Utf16Le => b"\xFF\xFE",
+            TextEncoding::Utf16Be => b"\xFE\xFF",
+            TextEncoding::Utf8 | TextEncoding::Windows1252 => b"",
+        }
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Detects the encoding of text starting with `sample`, or returns `None` if it looks binary.
+pub fn detect(sample: &[u8]) -> Option<TextEncoding> {
+    if sample.starts_with(TextEncoding::Utf8Bom.bom()) {
+        return Some(TextEncoding::Utf8Bom);
+    }
+    if sample.starts_with(TextEncoding::Utf16Le.bom()) {
+        return Some(TextEncoding::Utf16Le);
+    }
+    if sample.starts_with(TextEncoding::Utf16Be.bom()) {
+        return Some(TextEncoding::Utf16Be);
+    }
+    if let Some(encoding) = detect_bomless_utf16(sample) {
+        return Some(encoding);
+    }
+    if sample.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => Some(TextEncoding::Utf8),
+        // The sample may end in the middle of a multi-byte character.
+        Err(e) if e.error_len().is_none() => Some(TextEncoding::Utf8),
+        Err(_) => Some(TextEncoding::Windows1252),
+    }
+}
+
+/// Recognises UTF-16 without a BOM by the NUL high bytes of its ASCII characters: in
+/// mostly-ASCII text nearly every other byte is NUL, and the other half almost never is.
+fn detect_bomless_utf16(sample: &[u8]) -> Option<TextEncoding> {
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let (mut even_nuls, mut odd_nuls) = (0usize, 0usize);
+    for pair in sample.chunks_exact(2) {
+        even_nuls += usize::from(pair[0] == 0);
+        odd_nuls += usize::from(pair[1] == 0);
+    }
+    let looks_like = |high: usize, low: usize| high * 10 >= units * 7 && low * 20 <= units;
+    if looks_like(odd_nuls, even_nuls) {
+        Some(TextEncoding::Utf16Le)
+    } else if looks_like(even_nuls, odd_nuls) {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Reads the text file at `path`, detecting its encoding from the first bytes. Binary
+/// files are only read as far as that sample.
+/// Returns `Ok(None)` for binary files and for files that no longer exist.
+pub fn read_text_file(path: &Path) -> io::Result<Option<(TextEncoding, Vec<u8>)>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut bytes = Vec::with_capacity(SNIFF_LEN);
+    (&mut file).take(SNIFF_LEN as u64).read_to_end(&mut bytes)?;
+    let Some(encoding) = detect(&bytes) else {
+        return Ok(None);
+    };
+    file.read_to_end(&mut bytes)?;
+    Ok(Some((encoding, bytes)))
+}
+
+/// Transcodes `bytes` in `encoding` to UTF-8, dropping any byte order mark.
+/// Malformed sequences are replaced with U+FFFD.
+pub fn decode(bytes: &[u8], encoding: TextEncoding) -> String {
+    decode_lossy(bytes, encoding).0
+}
+
+/// Transcodes like `decode`, also returning whether malformed sequences were replaced.
+pub fn decode_lossy(bytes: &[u8], encoding: TextEncoding) -> (String, bool) {
+    let encoding = effective_encoding(bytes, encoding);
+    let bytes = bytes.strip_prefix(encoding.bom()).unwrap_or(bytes);
+    let (text, malformed) = encoding.codec().decode_without_bom_handling(bytes);
+    (text.into_owned(), malformed)
+}
+
+/// Returns the encoding to decode all of `bytes` with: `encoding`, unless it is UTF-8
+/// and the bytes hold a sequence that is invalid in UTF-8, in which case they are taken
+/// to be Windows-1252. A multi-byte character cut off at the end, as at the end of a
+/// sample, doesn't count.
+pub fn effective_encoding(bytes: &[u8], encoding: TextEncoding) -> TextEncoding {
+    match encoding {
+        TextEncoding::Utf8 => match std::str::from_utf8(bytes) {
+            Err(e) if e.error_len().is_some() => TextEncoding::Windows1252,
+            _ => TextEncoding::Utf8,
+        },
+        _ => encoding,
+    }
+}
+
+/// Returns the longest prefix of `text` that is at most `max_len` bytes long.
+pub fn text_prefix(text: &str, max_len: usize) -> &str {
+    let mut end = text.len().min(max_len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Transcodes `bytes` to UTF-8 using `encoding`, or the encoding detected from the bytes
+/// themselves if it is unknown. Content that looks binary is decoded as lossy UTF-8.
+pub fn decode_text(bytes: Vec<u8>, encoding: Option<TextEncoding>) -> String {
+    let encoding = encoding.or_else(|| detect(&bytes[..bytes.len().min(SNIFF_LEN)]));
+    match encoding {
+        Some(TextEncoding::Utf8) => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => decode(e.as_bytes(), TextEncoding::Utf8),
+        },
+        None => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        },
+        Some(encoding) => decode(&bytes, encoding),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// ASCII text longer than the sniffed sample, followed by Latin-1 "café".
+    fn late_latin1() -> Vec<u8> {
+        let mut bytes = "let x = 1;\n".repeat(SNIFF_LEN / 10).into_bytes();
+        bytes.extend_from_slice(b"// caf\xE9\n");
+        bytes
+    }
+
+    #[test]
+    fn detects_encodings_from_the_sample() {
+        assert_eq!(detect(b"plain"), Some(TextEncoding::Utf8));
+        assert_eq!(detect("caf\u{e9}".as_bytes()), Some(TextEncoding::Utf8));
+        assert_eq!(detect(b"caf\xE9!"), Some(TextEncoding::Windows1252));
+        assert_eq!(detect(b"\xEF\xBB\xBFx"), Some(TextEncoding::Utf8Bom));
+        assert_eq!(detect(b"\xFF\xFEa\0"), Some(TextEncoding::Utf16Le));
+        assert_eq!(detect(b"a\0b\0c\0d\0"), Some(TextEncoding::Utf16Le));
+        assert_eq!(detect(b"\0a\0b\0c\0d"), Some(TextEncoding::Utf16Be));
+        assert_eq!(detect(b"\x7FELF\0\x02\x01"), None);
+        // A sample ending inside a multi-byte character is still UTF-8.
+        assert_eq!(
+            detect(&"caf\u{e9}".as_bytes()[..4]),
+            Some(TextEncoding::Utf8)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_windows_1252_past_the_sample() {
+        let bytes = late_latin1();
+        let sniffed = detect(&bytes[..SNIFF_LEN]);
+        assert_eq!(sniffed, Some(TextEncoding::Utf8));
+
+        assert_eq!(
+            effective_encoding(&bytes, TextEncoding::Utf8),
+            TextEncoding::Windows1252
+        );
+        let (text, malformed) = decode_lossy(&bytes, TextEncoding::Utf8);
+        assert!(text.ends_with("// caf\u{e9}\n"));
+        assert!(!malformed);
+        assert!(decode_text(bytes, sniffed).ends_with("// caf\u{e9}\n"));
+    }
+
+    #[test]
+    fn keeps_utf8_cut_off_at_the_end() {
+        let bytes = &"caf\u{e9}".as_bytes()[..4];
+        assert_eq!(
+            effective_encoding(bytes, TextEncoding::Utf8),
+            TextEncoding::Utf8
+        );
+        assert_eq!(decode(bytes, TextEncoding::Utf8), "caf\u{FFFD}");
+        assert_eq!(decode_text("café".into(), Some(TextEncoding::Utf8)), "café");
+    }
+
+    #[test]
+    fn reads_text_files_and_skips_binary_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("late.rs");
+        fs::write(&text, late_latin1()).unwrap();
+        let (encoding, bytes) = read_text_file(&text).unwrap().unwrap();
+        assert_eq!(encoding, TextEncoding::Utf8);
+        assert_eq!(bytes, late_latin1());
+
+        let binary = dir.path().join("blob.bin");
+        fs::write(&binary, b"\x7FELF\0\x02\x01").unwrap();
+        assert!(read_text_file(&binary).unwrap().is_none());
+        assert!(read_text_file(&dir.path().join("missing"))
+            .unwrap()
+            .is_none());
+        assert_eq!(text_prefix("caf\u{e9}", 4), "caf");
+    }
+
+    #[test]
+    fn decodes_utf16_and_drops_the_bom() {
+        assert_eq!(decode(b"\xFF\xFEh\0i\0", TextEncoding::Utf16Le), "hi");
+        assert_eq!(decode(b"\xFE\xFF\0h\0i", TextEncoding::Utf16Be), "hi");
+        assert_eq!(decode(b"\xEF\xBB\xBFhi", TextEncoding::Utf8Bom), "hi");
+    }
+}
@@ -1,10 +1,18 @@
 //! Defines the `FileInfo` struct containing metadata about a file or directory.
 
+use crate::fs::{
+    archive::ArchiveMember,
+    classify::{self, FileClass},
+    encoding::{self, TextEncoding},
+    license::{self, LicenseInfo},
+    markers::{CommentMarker, MarkerMatcher},
+    ScanError, ScanErrorKind,
+};
+use anyhow::Context;
 use humansize::{format_size, DECIMAL};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::Metadata,
-    io::{ErrorKind, Read},
     path::{Path, PathBuf},
     time::SystemTime,
 };
@@ -18,16 +26,65 @@ pub struct FileInfo {
     #[serde(with = "crate::fs::file_info::humansize_serde")]
     pub human_size: String,
     pub is_binary: bool,
+    /// Detected character encoding of a text file; `None` for directories and binary files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<TextEncoding>,
     pub modified: Option<SystemTime>,
     pub extension: Option<String>,
     #[serde(skip)]
     pub loc_stats: Option<tokei::Language>,
+    /// Target of the symbolic link this entry was reached through, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<PathBuf>,
+    /// Location inside an archive, for archives scanned as directories and their members.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveMember>,
+    /// Whether the file is generated, vendored or documentation, if it is any of those.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<FileClass>,
+    /// Whether the file starts with a generated-code marker such as `@generated`.
+    #[serde(skip)]
+    pub generated_marker: bool,
+    /// Comment markers (`TODO`, `FIXME`, ...) in the file; `None` until it has been searched.
+    #[serde(skip)]
+    pub markers: Option<Vec<CommentMarker>>,
+    /// License and copyright header of a text file; `None` until it has been searched.
+    #[serde(skip)]
+    pub license: Option<LicenseInfo>,
+    /// Why the file's text couldn't be read or fully decoded during analysis. The scanner
+    /// takes it out of the node and reports it as a scan error.
+    #[serde(skip)]
+    pub decode_error: Option<ScanError>,
 }
 
 impl FileInfo {
     /// Creates a `FileInfo` instance from an `ignore::DirEntry`.
     pub fn from_entry(entry: &ignore::DirEntry) -> anyhow::Result<Self> {
-        let path = entry.path().to_path_buf();
+        let metadata = Self::entry_metadata(entry)?;
+        let mut info = Self::from_metadata(entry.path().to_path_buf(), metadata, None)?;
+        info.symlink_target = Self::entry_link_target(entry);
+        Ok(info)
+    }
+
+    /// Returns the link target if the entry is a followed symbolic link.
+    pub fn entry_link_target(entry: &ignore::DirEntry) -> Option<PathBuf> {
+        if !entry.path_is_symlink() {
+            return None;
+        }
+        std::fs::read_link(entry.path())
+            .map_err(|e| {
+                log::warn!(
+                    "Failed to read symlink target of '{}': {}",
+                    entry.path().display(),
+                    e
+                );
+            })
+            .ok()
+    }
+
+    /// Fetches the metadata for an `ignore::DirEntry`, falling back to `std::fs::metadata`.
+    pub fn entry_metadata(entry: &ignore::DirEntry) -> anyhow::Result<Metadata> {
+        let path = entry.path();
 
         // Try the first method, and if it fails, try the second.
         let metadata_result = entry.metadata().or_else(|ignore_err| {
@@ -37,63 +94,118 @@ impl FileInfo {
                 ignore_err
             );
             // The second attempt. The error from this will be the final error if it also fails.
-            std::fs::metadata(&path)
+            std::fs::metadata(path)
         });
 
-        // Now, handle the result of the two attempts.
-        match metadata_result {
-            Ok(metadata) => Self::from_metadata(path, metadata),
-            Err(e) => Err(anyhow::anyhow!(
-                "Failed to get metadata for '{}' after fallback: {}",
-                path.display(),
-                e
-            )),
-        }
+        // Keep the I/O error in the chain so callers can classify it.
+        metadata_result.with_context(|| {
+            format!(
+                "Failed to get metadata for '{}' after fallback",
+                path.display()
+            )
+        })
     }
 
-    /// Creates a `FileInfo` instance from a path and `std::fs::Metadata`.
-    pub fn from_metadata(path: PathBuf, metadata: Metadata) -> anyhow::Result<Self> {
-        let is_dir = metadata.is_dir();
-        let size = metadata.len();
-        let human_size = format_size(size, DECIMAL);
-        let modified = metadata.modified().ok();
-
-        let mut file_is_binary = false;
-        let mut extension = None;
-        let mut loc_stats = None;
+    /// Creates a `FileInfo` instance from a path and `std::fs::Metadata`, reading a text
+    /// file once for all of its analysis. Text files are searched for comment markers with
+    /// `markers`, if given.
+    pub fn from_metadata(
+        path: PathBuf,
+        metadata: Metadata,
+        markers: Option<&MarkerMatcher>,
+    ) -> anyhow::Result<Self> {
+        let mut info = Self::from_metadata_unanalyzed(path, &metadata);
 
-        if !is_dir {
-            file_is_binary = match is_binary(&path, 8192) {
-                Ok(b) => b,
+        if !info.is_dir {
+            match encoding::read_text_file(&info.path) {
+                Ok(Some((encoding, bytes))) => info.analyze_text(&bytes, encoding, markers),
+                Ok(None) => info.is_binary = true,
                 Err(e) => {
                     log::warn!(
-                        "Failed to perform binary check for '{}': {}. Assuming text.",
-                        path.display(),
+                        "Failed to read '{}' for analysis: {}. Assuming UTF-8 text.",
+                        info.path.display(),
                         e
                     );
-                    false
+                    info.encoding = Some(TextEncoding::Utf8);
+                    info.decode_error =
+                        Some(ScanError::from_io(&info.path, &e, ScanErrorKind::Decode));
                 }
-            };
-            extension = path
-                .extension()
-                .and_then(|s| s.to_str())
-                .map(|s| s.to_lowercase());
-
-            if !file_is_binary {
-                loc_stats = get_loc_stats(&path);
             }
         }
 
-        Ok(Self {
+        Ok(info)
+    }
+
+    /// Creates a `FileInfo` from metadata alone, without reading the file.
+    /// `is_binary` and `generated_marker` are `false` and `encoding`, `loc_stats` and
+    /// `license` are `None` until filled in by the caller.
+    pub fn from_metadata_unanalyzed(path: PathBuf, metadata: &Metadata) -> Self {
+        let is_dir = metadata.is_dir();
+        let size = metadata.len();
+        let extension = if is_dir {
+            None
+        } else {
+            path.extension()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_lowercase())
+        };
+
+        Self {
             path,
             is_dir,
             size,
-            human_size,
-            is_binary: file_is_binary,
-            modified,
+            human_size: format_size(size, DECIMAL),
+            is_binary: false,
+            encoding: None,
+            modified: metadata.modified().ok(),
             extension,
-            loc_stats,
-        })
+            loc_stats: None,
+            symlink_target: None,
+            archive: None,
+            class: None,
+            generated_marker: false,
+            markers: None,
+            license: None,
+            decode_error: None,
+        }
+    }
+
+    /// Fills in the encoding, line counts, generated-code marker and license header of a
+    /// text file from its content, and its comment markers if `markers` is given. Files
+    /// too large to search for markers are reported as having none. Malformed sequences
+    /// are recorded in `decode_error`.
+    fn analyze_text(
+        &mut self,
+        bytes: &[u8],
+        encoding: TextEncoding,
+        markers: Option<&MarkerMatcher>,
+    ) {
+        // Content sniffed as UTF-8 may turn out to be Windows-1252 further in.
+        let encoding = encoding::effective_encoding(bytes, encoding);
+        self.encoding = Some(encoding);
+        let (text, malformed) = encoding::decode_lossy(bytes, encoding);
+        if malformed {
+            self.decode_error = Some(ScanError::new(
+                ScanErrorKind::Decode,
+                Some(self.path.clone()),
+                format!("Invalid {encoding} sequences were replaced with U+FFFD"),
+            ));
+        }
+        self.loc_stats = loc_stats_from_bytes(&self.path, text.as_bytes());
+        self.markers = markers.map(|matcher| {
+            if MarkerMatcher::searches_size(self.size) {
+                matcher.find(&text)
+            } else {
+                Vec::new()
+            }
+        });
+        self.generated_marker = classify::contains_generated_marker(&text);
+        self.license = Some(license::detect(&text, license::is_license_file(&self.path)));
+    }
+
+    /// Returns the comment markers found in the file, if it has been searched for them.
+    pub fn markers(&self) -> &[CommentMarker] {
+        self.markers.as_deref().unwrap_or_default()
     }
 
     /// Returns the final component of the path (file or directory name) as a string slice.
@@ -102,44 +214,18 @@ impl FileInfo {
     }
 }
 
-/// Checks if a file appears to be binary by reading a sample and looking for null bytes.
-pub fn is_binary(path: &Path, sample_size: usize) -> anyhow::Result<bool> {
-    let mut file = match std::fs::File::open(path) {
-        Ok(f) => f,
-        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to open file '{}' for binary check: {}",
-                path.display(),
-                e
-            ))
-        }
-    };
-    let buffer_size = sample_size.min(8192);
-    let mut buffer = vec![0; buffer_size];
-    let bytes_read = match file.read(&mut buffer) {
-        Ok(n) => n,
-        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to read file '{}' for binary check: {}",
-                path.display(),
-                e
-            ))
-        }
-    };
-    Ok(buffer[..bytes_read].contains(&0))
-}
-
-/// Get LOC stats for a single file, returning the `Language` struct.
-fn get_loc_stats(path: &Path) -> Option<tokei::Language> {
-    let mut languages = tokei::Languages::new();
+/// Line-counts in-memory UTF-8 content the same way `tokei` counts files on disk.
+pub(crate) fn loc_stats_from_bytes(path: &Path, bytes: &[u8]) -> Option<tokei::Language> {
     let config = tokei::Config::default();
-    languages.get_statistics(&[path], &[], &config);
-
-    // MODIFIED: `from_path` returns an Option, which we must handle.
     let language_type = tokei::LanguageType::from_path(path, &config)?;
-    languages.remove(&language_type)
+    let stats = language_type.parse_from_slice(bytes, &config);
+    let mut report = tokei::Report::new(path.to_path_buf());
+    report.stats = stats;
+    let mut language = tokei::Language::new();
+    language.add_report(report);
+    // `add_report` only records the report; the totals are summed separately.
+    language.total();
+    Some(language)
 }
 
 mod humansize_serde {
@@ -157,3 +243,74 @@ mod humansize_serde {
         String::deserialize(deserializer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_lines_of_in_memory_content() {
+        let text = "// A comment\nfn main() {\n\n    println!(\"hi\");\n}\n";
+        let language = loc_stats_from_bytes(Path::new("main.rs"), text.as_bytes()).unwrap();
+        assert_eq!(
+            (language.code, language.comments, language.blanks),
+            (3, 1, 1)
+        );
+        assert!(loc_stats_from_bytes(Path::new("data.unknown"), b"x").is_none());
+    }
+
+    #[test]
+    fn collects_markers_and_line_counts_from_one_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        std::fs::write(&path, "// TODO: split this up\nfn f() {}\n").unwrap();
+        let matcher = MarkerMatcher::new(&["TODO".to_string()]).unwrap();
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        let info = FileInfo::from_metadata(path.clone(), metadata, Some(&matcher)).unwrap();
+        assert_eq!(info.markers().len(), 1);
+        assert_eq!(info.markers()[0].text, "split this up");
+        let loc = info.loc_stats.unwrap();
+        assert_eq!((loc.code, loc.comments), (1, 1));
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        let info = FileInfo::from_metadata(path, metadata, None).unwrap();
+        assert!(info.markers.is_none());
+        assert_eq!(info.loc_stats.unwrap().code, 1);
+    }
+
+    #[test]
+    fn falls_back_to_windows_1252_without_marker_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.py");
+        let mut content = "x = 1\n".repeat(encoding::SNIFF_LEN).into_bytes();
+        content.extend_from_slice(b"name = 'caf\xE9'\n");
+        std::fs::write(&path, content).unwrap();
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        let info = FileInfo::from_metadata(path, metadata, None).unwrap();
+        assert_eq!(info.encoding, Some(TextEncoding::Windows1252));
+        assert!(info.decode_error.is_none());
+        assert_eq!(info.loc_stats.unwrap().code, encoding::SNIFF_LEN + 1);
+    }
+
+    #[test]
+    fn records_malformed_text_as_decode_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        // UTF-16 LE with a BOM, "ab", then an unpaired high surrogate.
+        std::fs::write(&path, b"\xFF\xFEa\0b\0\x00\xD8").unwrap();
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        let info = FileInfo::from_metadata(path.clone(), metadata, None).unwrap();
+        assert_eq!(info.encoding, Some(TextEncoding::Utf16Le));
+        let error = info.decode_error.unwrap();
+        assert_eq!(error.kind, ScanErrorKind::Decode);
+        assert_eq!(error.path.as_deref(), Some(path.as_path()));
+
+        std::fs::write(&path, "plain text\n").unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        let info = FileInfo::from_metadata(path, metadata, None).unwrap();
+        assert!(info.decode_error.is_none());
+    }
+}
@@ -0,0 +1,366 @@
+//! Reads git information for a scanned directory from its local repository.
+//!
+//! Uses `git2` against the repository containing the scan root; no network access
+//! or `git` executable is required. All returned paths are absolute and limited to
+//! files below the scan root.
+
+use git2::{Delta, DiffOptions, Repository, Status, StatusOptions};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// Working tree status of a single file, relative to `HEAD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GitStatus {
+    Modified,
+    Added,
+    Untracked,
+    /// Deleted from the index or working tree. Such files aren't in the tree, so they're
+    /// only counted and listed in the status bar.
+    Deleted,
+    Renamed,
+}
+
+impl GitStatus {
+    /// Short badge text shown next to the file in the tree.
+    pub fn badge(self) -> &'static str {
+        match self {
+            GitStatus::Modified => "M",
+            GitStatus::Added => "A",
+            GitStatus::Untracked => "U",
+            GitStatus::Deleted => "D",
+            GitStatus::Renamed => "R",
+        }
+    }
+
+    /// Human-readable name of the status.
+    pub fn label(self) -> &'static str {
+        match self {
+            GitStatus::Modified => "Modified",
+            GitStatus::Added => "Added",
+            GitStatus::Untracked => "Untracked",
+            GitStatus::Deleted => "Deleted",
+            GitStatus::Renamed => "Renamed",
+        }
+    }
+
+    /// Collapses libgit2's index and worktree flags into a single status.
+    /// Returns `None` for unmodified or ignored entries.
+    fn from_flags(flags: Status) -> Option<Self> {
+        if flags.intersects(Status::INDEX_RENAMED | Status::WT_RENAMED) {
+            Some(GitStatus::Renamed)
+        } else if flags.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+            Some(GitStatus::Deleted)
+        } else if flags.contains(Status::INDEX_NEW) {
+            Some(GitStatus::Added)
+        } else if flags.contains(Status::WT_NEW) {
+            Some(GitStatus::Untracked)
+        } else if flags.intersects(
+            Status::INDEX_MODIFIED
+                | Status::WT_MODIFIED
+                | Status::INDEX_TYPECHANGE
+                | Status::WT_TYPECHANGE
+                | Status::CONFLICTED,
+        ) {
+            Some(GitStatus::Modified)
+        } else {
+            None
+        }
+    }
+}
+
+/// Git status of every changed file below a scan root, keyed by absolute path.
+pub type GitStatusMap = HashMap<PathBuf, GitStatus>;
+
+/// Opens the repository containing `root` and returns it with its canonical work directory.
+/// Returns `Ok(None)` if `root` is not inside a non-bare git repository.
+fn open_repository(root: &Path) -> anyhow::Result<Option<(Repository, PathBuf)>> {
+    let repo = match Repository::discover(root) {
+        Ok(repo) => repo,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(anyhow::anyhow!(
+                "Failed to open git repository for '{}': {}",
+                root.display(),
+                e
+            ))
+        }
+    };
+    let Some(workdir) = repo.workdir() else {
+        return Ok(None);
+    };
+    let workdir = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    Ok(Some((repo, workdir)))
+}
+
+/// Reads the working tree status of all changed and untracked files below `root`.
+///
+/// Returns `Ok(None)` if `root` is not inside a git repository. Files deleted from
+/// the working tree are included even though they no longer exist on disk.
+pub fn status(root: &Path) -> anyhow::Result<Option<GitStatusMap>> {
+    let Some((repo, workdir)) = open_repository(root)? else {
+        return Ok(None);
+    };
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| anyhow::anyhow!("Failed to read git status: {e}"))?;
+
+    // Paths from git are below the canonical work directory; key them by the caller's
+    // root so they match the scanned tree even when `root` goes through a symlink.
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut map = GitStatusMap::new();
+    for entry in statuses.iter() {
+        let Some(status) = GitStatus::from_flags(entry.status()) else {
+            continue;
+        };
+        // `StatusEntry::path` reports the old name of renamed files, so prefer the new side.
+        let relative = entry
+            .index_to_workdir()
+            .and_then(|delta| delta.new_file().path())
+            .or_else(|| {
+                entry
+                    .head_to_index()
+                    .and_then(|delta| delta.new_file().path())
+            });
+        let Some(relative) = relative else {
+            continue;
+        };
+        if let Ok(below_root) = workdir.join(relative).strip_prefix(&canonical_root) {
+            map.insert(root.join(below_root), status);
+        }
+    }
+    log::info!(
+        "Read git status for '{}': {} changed file(s).",
+        root.display(),
+        map.len()
+    );
+    Ok(Some(map))
+}
+
+/// Returns the files below `root` that differ from `reference` (any revision git
+/// understands, such as `HEAD`, `main` or `HEAD~3`).
+///
+/// Staged, unstaged and untracked changes are all included; deleted files are not,
+/// since they can't be selected.
+pub fn changed_since(root: &Path, reference: &str) -> anyhow::Result<HashSet<PathBuf>> {
+    let Some((repo, workdir)) = open_repository(root)? else {
+        return Err(anyhow::anyhow!(
+            "'{}' is not inside a git repository",
+            root.display()
+        ));
+    };
+    let tree = repo
+        .revparse_single(reference)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|e| anyhow::anyhow!("Could not resolve git ref '{reference}': {e}"))?;
+
+    let mut options = DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let mut diff = repo
+        .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))
+        .map_err(|e| anyhow::anyhow!("Failed to diff against '{reference}': {e}"))?;
+    diff.find_similar(None)
+        .map_err(|e| anyhow::anyhow!("Failed to detect renames: {e}"))?;
+
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let changed: HashSet<PathBuf> = diff
+        .deltas()
+        .filter(|delta| delta.status() != Delta::Deleted)
+        .filter_map(|delta| delta.new_file().path().map(|p| workdir.join(p)))
+        .filter_map(|path| {
+            path.strip_prefix(&canonical_root)
+                .ok()
+                .map(|below_root| root.join(below_root))
+        })
+        .collect();
+    log::info!(
+        "{} file(s) below '{}' changed since '{}'.",
+        changed.len(),
+        root.display(),
+        reference
+    );
+    Ok(changed)
+}
+
+/// Reads the git status below each of `roots` and merges the results.
+///
+/// Returns `Ok(None)` if none of the roots is inside a git repository. A root that
+/// fails to read is logged and skipped unless every root fails.
+pub fn status_for_roots(roots: &[PathBuf]) -> anyhow::Result<Option<GitStatusMap>> {
+    let mut merged: Option<GitStatusMap> = None;
+    let mut errors = Vec::new();
+    for root in roots {
+        match status(root) {
+            Ok(Some(map)) => merged.get_or_insert_with(GitStatusMap::new).extend(map),
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("{e}");
+                errors.push(e.to_string());
+            }
+        }
+    }
+    if merged.is_none() && !errors.is_empty() {
+        return Err(anyhow::anyhow!(errors.join("\n")));
+    }
+    Ok(merged)
+}
+
+/// Returns the files below any of `roots` that differ from `reference`.
+///
+/// Roots where the reference can't be resolved (or that aren't repositories) are
+/// logged and skipped; the call only fails if it fails for every root.
+pub fn changed_since_for_roots(
+    roots: &[PathBuf],
+    reference: &str,
+) -> anyhow::Result<HashSet<PathBuf>> {
+    let mut changed = HashSet::new();
+    let mut errors = Vec::new();
+    for root in roots {
+        match changed_since(root, reference) {
+            Ok(paths) => changed.extend(paths),
+            Err(e) => {
+                log::warn!("{e}");
+                errors.push(e.to_string());
+            }
+        }
+    }
+    if errors.len() == roots.len() && !errors.is_empty() {
+        return Err(anyhow::anyhow!(errors.join("\n")));
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{IndexAddOption, Signature};
+    use std::fs;
+
+    /// A repository with `keep.rs`, `edit.rs`, `gone.rs` and `sub/inner.rs` committed.
+    fn fixture_repo() -> (tempfile::TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in ["keep.rs", "edit.rs", "gone.rs", "sub/inner.rs"] {
+            fs::write(dir.path().join(name), format!("// {name}\n")).unwrap();
+        }
+        commit_all(&repo, "initial");
+        (dir, repo)
+    }
+
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn status_reports_changes_below_root() {
+        let (dir, repo) = fixture_repo();
+        let root = dir.path();
+        fs::write(root.join("edit.rs"), "// edited\n").unwrap();
+        fs::remove_file(root.join("gone.rs")).unwrap();
+        fs::write(root.join("new.rs"), "// new\n").unwrap();
+        fs::write(root.join("staged.rs"), "// staged\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged.rs")).unwrap();
+        index.write().unwrap();
+
+        let map = status(root).unwrap().unwrap();
+        assert_eq!(map.get(&root.join("edit.rs")), Some(&GitStatus::Modified));
+        assert_eq!(map.get(&root.join("gone.rs")), Some(&GitStatus::Deleted));
+        assert_eq!(map.get(&root.join("new.rs")), Some(&GitStatus::Untracked));
+        assert_eq!(map.get(&root.join("staged.rs")), Some(&GitStatus::Added));
+        assert_eq!(map.get(&root.join("keep.rs")), None);
+        assert_eq!(map.len(), 4);
+
+        // A root below the work directory only sees its own files
+        fs::write(root.join("sub/inner.rs"), "// edited\n").unwrap();
+        let sub = status(&root.join("sub")).unwrap().unwrap();
+        assert_eq!(sub.keys().collect::<Vec<_>>(), [&root.join("sub/inner.rs")]);
+    }
+
+    #[test]
+    fn status_of_a_directory_outside_git_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(status(dir.path()).unwrap().is_none());
+        assert!(changed_since(dir.path(), "HEAD").is_err());
+    }
+
+    #[test]
+    fn changed_since_includes_commits_and_working_changes() {
+        let (dir, repo) = fixture_repo();
+        let root = dir.path();
+        let base = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+        fs::write(root.join("keep.rs"), "// committed change\n").unwrap();
+        commit_all(&repo, "second");
+        fs::write(root.join("edit.rs"), "// edited\n").unwrap();
+        fs::write(root.join("new.rs"), "// new\n").unwrap();
+        fs::remove_file(root.join("gone.rs")).unwrap();
+
+        let changed = changed_since(root, &base).unwrap();
+        let expected: HashSet<PathBuf> = ["keep.rs", "edit.rs", "new.rs"]
+            .iter()
+            .map(|name| root.join(name))
+            .collect();
+        assert_eq!(changed, expected);
+
+        let since_head = changed_since(root, "HEAD").unwrap();
+        assert!(!since_head.contains(&root.join("keep.rs")));
+        assert!(changed_since(root, "no-such-ref").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_are_keyed_by_the_root_as_given() {
+        let (dir, _repo) = fixture_repo();
+        fs::write(dir.path().join("edit.rs"), "// edited\n").unwrap();
+        let links = tempfile::tempdir().unwrap();
+        let link = links.path().join("project");
+        std::os::unix::fs::symlink(dir.path(), &link).unwrap();
+        let dotted = dir.path().join("sub").join("..");
+
+        for root in [link, dotted] {
+            let map = status(&root).unwrap().unwrap();
+            assert_eq!(
+                map.get(&root.join("edit.rs")),
+                Some(&GitStatus::Modified),
+                "{}",
+                root.display()
+            );
+            let changed = changed_since(&root, "HEAD").unwrap();
+            assert!(changed.contains(&root.join("edit.rs")));
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Detection of license and copyright headers.
+//!
+//! A file's license is taken from an `SPDX-License-Identifier:` tag if it has one, and
+//! otherwise recognised from the wording of common license texts (MIT, Apache-2.0, the GPL
+//! family, BSD, ...). Source files are only searched in their first lines, where headers
+//! live. `LICENSE`, `COPYING` and similar files are identified by the title in their first
+//! non-empty lines rather than by searching the whole text, since the body of one license
+//! often names others: the GPL-3.0 refers to the AGPL, and the MPL-2.0 lists the GPL family
+//! as secondary licenses.
+
+use crate::fs::{encoding, FileInfo};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Bytes at the start of a source file searched for a header.
+const HEADER_SAMPLE_SIZE: usize = 16 * 1024;
+/// Lines at the start of a source file that may hold its header.
+const HEADER_LINES: usize = 60;
+/// Non-empty lines at the start of a license file that may hold its title.
+const TITLE_LINES: usize = 5;
+/// Bytes of a license file searched for an SPDX tag and a copyright line.
+const LICENSE_FILE_SAMPLE_SIZE: usize = 64 * 1024;
+/// Longest license expression or copyright line kept.
+const MAX_TEXT_CHARS: usize = 120;
+
+/// File name prefixes (upper-cased) of files that hold a project's license text.
+const LICENSE_FILE_PREFIXES: &[&str] = &["LICENSE", "LICENCE", "COPYING", "UNLICENSE"];
+
+static SPDX_TAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"SPDX-License-Identifier:\s*([A-Za-z0-9(][A-Za-z0-9.+\-:() ]*)")
+        .expect("valid SPDX pattern")
+});
+static COPYRIGHT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:\bcopyright\b\s*(?:\(c\)|©)?|©)\s*\d{4}").expect("valid copyright pattern")
+});
+
+/// How a file's license was recognised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseSource {
+    /// An `SPDX-License-Identifier:` tag.
+    SpdxTag,
+    /// The wording of a known license text.
+    Text,
+}
+
+impl LicenseSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            LicenseSource::SpdxTag => "SPDX tag",
+            LicenseSource::Text => "license text",
+        }
+    }
+}
+
+/// The license and copyright header found in a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseInfo {
+    /// SPDX license expression, e.g. `MIT` or `Apache-2.0 OR MIT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<LicenseSource>,
+    /// The first copyright line, e.g. `Copyright (c) 2024 Jane Doe`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copyright: Option<String>,
+}
+
+/// A known license text: its SPDX identifier and phrases that must all appear in it.
+struct LicenseText {
+    spdx: &'static str,
+    phrases: &'static [&'static str],
+}
+
+/// Wording of known licenses in source headers, most specific first. Phrases are lowercase
+/// with single spaces. The MPL comes before the GPL family, which it names as secondary
+/// licenses.
+const LICENSE_TEXTS: &[LicenseText] = &[
+    LicenseText {
+        spdx: "MPL-2.0",
+        phrases: &["mozilla public license", "2.0"],
+    },
+    LicenseText {
+        spdx: "AGPL-3.0",
+        phrases: &["gnu affero general public license"],
+    },
+    LicenseText {
+        spdx: "LGPL-3.0",
+        phrases: &["gnu lesser general public license", "version 3"],
+    },
+    LicenseText {
+        spdx: "LGPL-2.1",
+        phrases: &["gnu lesser general public license", "version 2.1"],
+    },
+    LicenseText {
+        spdx: "LGPL-2.0",
+        phrases: &["gnu library general public license", "version 2"],
+    },
+    LicenseText {
+        spdx: "GPL-3.0",
+        phrases: &["gnu general public license", "version 3"],
+    },
+    LicenseText {
+        spdx: "GPL-2.0",
+        phrases: &["gnu general public license", "version 2"],
+    },
+    LicenseText {
+        spdx: "Apache-2.0",
+        phrases: &["apache license", "version 2.0"],
+    },
+    LicenseText {
+        spdx: "EPL-2.0",
+        phrases: &["eclipse public license", "2.0"],
+    },
+    LicenseText {
+        spdx: "BSD-3-Clause",
+        phrases: &[
+            "redistribution and use in source and binary forms",
+            "neither the name",
+        ],
+    },
+    LicenseText {
+        spdx: "BSD-2-Clause",
+        phrases: &["redistribution and use in source and binary forms"],
+    },
+    LicenseText {
+        spdx: "MIT",
+        phrases: &["permission is hereby granted, free of charge"],
+    },
+    LicenseText {
+        spdx: "MIT",
+        phrases: &["licensed under the mit license"],
+    },
+    LicenseText {
+        spdx: "ISC",
+        phrases: &["permission to use, copy, modify, and/or distribute this software"],
+    },
+    LicenseText {
+        spdx: "BSL-1.0",
+        phrases: &["boost software license"],
+    },
+    LicenseText {
+        spdx: "Unlicense",
+        phrases: &["this is free and unencumbered software released into the public domain"],
+    },
+    LicenseText {
+        spdx: "CC0-1.0",
+        phrases: &["cc0 1.0 universal"],
+    },
+];
+
+/// Titles of known license texts, matched in the first lines of a license file.
+const LICENSE_TITLES: &[LicenseText] = &[
+    LicenseText {
+        spdx: "MPL-2.0",
+        phrases: &["mozilla public license", "2.0"],
+    },
+    LicenseText {
+        spdx: "AGPL-3.0",
+        phrases: &["gnu affero general public license", "version 3"],
+    },
+    LicenseText {
+        spdx: "LGPL-3.0",
+        phrases: &["gnu lesser general public license", "version 3"],
+    },
+    LicenseText {
+        spdx: "LGPL-2.1",
+        phrases: &["gnu lesser general public license", "version 2.1"],
+    },
+    LicenseText {
+        spdx: "LGPL-2.0",
+        phrases: &["gnu library general public license", "version 2"],
+    },
+    LicenseText {
+        spdx: "GPL-3.0",
+        phrases: &["gnu general public license", "version 3"],
+    },
+    LicenseText {
+        spdx: "GPL-2.0",
+        phrases: &["gnu general public license", "version 2"],
+    },
+    LicenseText {
+        spdx: "Apache-2.0",
+        phrases: &["apache license", "version 2.0"],
+    },
+    LicenseText {
+        spdx: "EPL-2.0",
+        phrases: &["eclipse public license", "2.0"],
+    },
+    LicenseText {
+        spdx: "BSD-3-Clause",
+        phrases: &["bsd 3-clause"],
+    },
+    LicenseText {
+        spdx: "BSD-2-Clause",
+        phrases: &["bsd 2-clause"],
+    },
+    LicenseText {
+        spdx: "MIT",
+        phrases: &["mit license"],
+    },
+    LicenseText {
+        spdx: "ISC",
+        phrases: &["isc license"],
+    },
+    LicenseText {
+        spdx: "BSL-1.0",
+        phrases: &["boost software license"],
+    },
+    LicenseText {
+        spdx: "CC0-1.0",
+        phrases: &["cc0 1.0 universal"],
+    },
+];
+
+/// Opening wording of short permissive licenses that are often distributed without a
+/// title, matched in the first lines of a license file that has no known title.
+const UNTITLED_LICENSE_TEXTS: &[LicenseText] = &[
+    LicenseText {
+        spdx: "BSD-3-Clause",
+        phrases: &[
+            "redistribution and use in source and binary forms",
+            "neither the name",
+        ],
+    },
+    LicenseText {
+        spdx: "BSD-2-Clause",
+        phrases: &["redistribution and use in source and binary forms"],
+    },
+    LicenseText {
+        spdx: "MIT",
+        phrases: &["permission is hereby granted, free of charge"],
+    },
+    LicenseText {
+        spdx: "ISC",
+        phrases: &["permission to use, copy, modify, and/or distribute this software"],
+    },
+    LicenseText {
+        spdx: "Unlicense",
+        phrases: &["this is free and unencumbered software released into the public domain"],
+    },
+];
+
+/// Returns `true` if `path` names a license file such as `LICENSE`, `LICENSE-MIT` or `COPYING.txt`.
+pub fn is_license_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_uppercase())
+        .is_some_and(|name| {
+            LICENSE_FILE_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+        })
+}
+
+/// Returns `true` if the file is expected to carry a license header: a text file with lines
+/// of code that isn't generated, vendored, documentation or a license file itself.
+pub fn needs_header(info: &FileInfo) -> bool {
+    !info.is_dir
+        && !info.is_binary
+        && info.class.is_none()
+        && info.loc_stats.as_ref().is_some_and(|stats| stats.code > 0)
+        && !is_license_file(&info.path)
+}
+
+/// Returns `true` if the file needs a license header (see `needs_header`), has been
+/// searched for one and has none.
+pub fn is_missing_header(info: &FileInfo) -> bool {
+    needs_header(info)
+        && info
+            .license
+            .as_ref()
+            .is_some_and(|license| license.license.is_none())
+}
+
+/// Looks for a license and copyright header in `text`. Only the first lines are searched
+/// unless `license_file` is set.
+pub fn detect(text: &str, license_file: bool) -> LicenseInfo {
+    let (sample_size, line_limit) = if license_file {
+        (LICENSE_FILE_SAMPLE_SIZE, usize::MAX)
+    } else {
+        (HEADER_SAMPLE_SIZE, HEADER_LINES)
+    };
+    let lines: Vec<&str> = encoding::text_prefix(text, sample_size)
+        .lines()
+        .take(line_limit)
+        .collect();
+
+    let mut info = LicenseInfo::default();
+    if let Some(expression) = lines
+        .iter()
+        .find_map(|line| SPDX_TAG.captures(line))
+        .and_then(|captures| captures.get(1))
+        // The capture runs into the dashes of `-->` and `-}` closers
+        .map(|m| clean_text(m.as_str().trim_end_matches([' ', '-'])))
+        .filter(|expression| !expression.is_empty())
+    {
+        info.license = Some(expression);
+        info.source = Some(LicenseSource::SpdxTag);
+    } else if let Some(license) = recognise_text(&lines, license_file) {
+        info.license = Some(license);
+        info.source = Some(LicenseSource::Text);
+    }
+    info.copyright = lines
+        .iter()
+        .find_map(|line| COPYRIGHT.find(line).map(|m| clean_text(&line[m.start()..])));
+    info
+}
+
+/// Recognises a known license in `lines`. License files are identified by their title, or
+/// failing that by the opening wording of an untitled permissive license, and their
+/// identifier is left unqualified: every GPL text says "any later version" in its
+/// appendix on how to apply it, whichever version a project chose. Source headers are
+/// searched in full and GPL identifiers qualified from their wording.
+fn recognise_text(lines: &[&str], license_file: bool) -> Option<String> {
+    if license_file {
+        let title: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| !line.trim().is_empty())
+            .take(TITLE_LINES)
+            .collect();
+        let opening = &lines[..lines.len().min(HEADER_LINES)];
+        return find_license(LICENSE_TITLES, &normalize(&title))
+            .or_else(|| find_license(UNTITLED_LICENSE_TEXTS, &normalize(opening)))
+            .map(str::to_string);
+    }
+    let normalized = normalize(lines);
+    find_license(LICENSE_TEXTS, &normalized).map(|spdx| qualify_gpl(spdx, &normalized))
+}
+
+/// Returns the identifier of the first entry in `known` whose phrases all appear in
+/// `normalized`.
+fn find_license(known: &[LicenseText], normalized: &str) -> Option<&'static str> {
+    known
+        .iter()
+        .find(|known| {
+            known
+                .phrases
+                .iter()
+                .all(|phrase| normalized.contains(phrase))
+        })
+        .map(|known| known.spdx)
+}
+
+/// Appends `-only` or `-or-later` to GPL family identifiers in a source header, as SPDX
+/// requires.
+fn qualify_gpl(spdx: &str, normalized: &str) -> String {
+    if !spdx.contains("GPL") {
+        return spdx.to_string();
+    }
+    if normalized.contains("any later version") {
+        format!("{spdx}-or-later")
+    } else {
+        format!("{spdx}-only")
+    }
+}
+
+/// Joins `lines` into lowercase text with comment leaders removed and whitespace collapsed,
+/// so phrases split across comment lines still match.
+fn normalize(lines: &[&str]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let line = line
+            .trim()
+            .trim_start_matches(['/', '*', '#', '-', ';', '%', '!', '(', '{', '<'])
+            .trim();
+        for word in line.split_whitespace() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&word.to_lowercase());
+        }
+    }
+    text
+}
+
+/// Strips comment closers and surrounding whitespace from header text and truncates it.
+fn clean_text(text: &str) -> String {
+    let mut text = text.trim();
+    for closer in ["*/", "-->", "*)", "-}", "#}"] {
+        text = text.strip_suffix(closer).unwrap_or(text).trim_end();
+    }
+    if text.chars().count() > MAX_TEXT_CHARS {
+        text.chars().take(MAX_TEXT_CHARS - 1).collect::<String>() + "…"
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn license_of(text: &str) -> Option<String> {
+        detect(text, false).license
+    }
+
+    #[test]
+    fn spdx_tags_stop_at_comment_closers() {
+        for (text, expected) in [
+            ("// SPDX-License-Identifier: MIT", "MIT"),
+            (
+                "/* SPDX-License-Identifier: Apache-2.0 OR MIT */",
+                "Apache-2.0 OR MIT",
+            ),
+            ("<!-- SPDX-License-Identifier: MIT -->", "MIT"),
+            (
+                "<!--SPDX-License-Identifier: GPL-2.0-only-->",
+                "GPL-2.0-only",
+            ),
+            ("{- SPDX-License-Identifier: MIT -}", "MIT"),
+            (
+                "{# SPDX-License-Identifier: BSD-3-Clause #}",
+                "BSD-3-Clause",
+            ),
+            (
+                "(* SPDX-License-Identifier: LGPL-2.1-or-later *)",
+                "LGPL-2.1-or-later",
+            ),
+            (
+                "# SPDX-License-Identifier: (MIT OR Apache-2.0) AND BSD-2-Clause",
+                "(MIT OR Apache-2.0) AND BSD-2-Clause",
+            ),
+        ] {
+            let info = detect(text, false);
+            assert_eq!(info.license.as_deref(), Some(expected), "{text}");
+            assert_eq!(info.source, Some(LicenseSource::SpdxTag));
+        }
+    }
+
+    #[test]
+    fn recognises_license_texts() {
+        let mit = "Permission is hereby granted, free of charge, to any person obtaining a copy";
+        assert_eq!(license_of(mit).as_deref(), Some("MIT"));
+
+        let apache = "// Licensed under the Apache License,\n// Version 2.0 (the \"License\");";
+        assert_eq!(license_of(apache).as_deref(), Some("Apache-2.0"));
+
+        let bsd3 = "Redistribution and use in source and binary forms ...\n\
+                    Neither the name of the copyright holder ...";
+        assert_eq!(license_of(bsd3).as_deref(), Some("BSD-3-Clause"));
+
+        let mpl = "/* This Source Code Form is subject to the terms of the Mozilla Public\n \
+                   * License, v. 2.0. If a copy of the MPL was not distributed with this\n \
+                   * file, You can obtain one at https://mozilla.org/MPL/2.0/. */";
+        assert_eq!(license_of(mpl).as_deref(), Some("MPL-2.0"));
+        assert_eq!(license_of("fn main() {}"), None);
+    }
+
+    #[test]
+    fn header_search_is_limited_to_the_first_lines() {
+        let text = format!(
+            "{}// SPDX-License-Identifier: MIT\n",
+            "code();\n".repeat(HEADER_LINES)
+        );
+        assert_eq!(detect(&text, false).license, None);
+        assert_eq!(detect(&text, true).license.as_deref(), Some("MIT"));
+    }
+
+    #[test]
+    fn qualifies_gpl_identifiers() {
+        let header =
+            "# This program is free software: you can redistribute it under the terms of\n\
+                      # the GNU General Public License as published by the Free Software\n\
+                      # Foundation, either version 3 of the License, or (at your option) any\n\
+                      # later version.";
+        assert_eq!(license_of(header).as_deref(), Some("GPL-3.0-or-later"));
+
+        let only = "GNU General Public License, version 2, as published by the FSF";
+        assert_eq!(license_of(only).as_deref(), Some("GPL-2.0-only"));
+
+        assert_eq!(qualify_gpl("MIT", "any later version"), "MIT");
+        assert_eq!(
+            qualify_gpl("LGPL-2.1", "or any later version"),
+            "LGPL-2.1-or-later"
+        );
+        assert_eq!(qualify_gpl("AGPL-3.0", "version 3"), "AGPL-3.0-only");
+    }
+
+    #[test]
+    fn identifies_license_files_by_title() {
+        for (text, expected) in [
+            (include_str!("testdata/GPL-3.0.txt"), "GPL-3.0"),
+            (include_str!("testdata/MPL-2.0.txt"), "MPL-2.0"),
+            (include_str!("testdata/GPL-2.0.txt"), "GPL-2.0"),
+            (include_str!("testdata/LGPL-2.1.txt"), "LGPL-2.1"),
+            (include_str!("testdata/LGPL-3.0.txt"), "LGPL-3.0"),
+            (
+                "MIT License\n\nCopyright (c) 2024 Jane Doe\n\nPermission is hereby granted, \
+                 free of charge, to any person obtaining a copy",
+                "MIT",
+            ),
+        ] {
+            let info = detect(text, true);
+            assert_eq!(info.license.as_deref(), Some(expected));
+            assert_eq!(info.source, Some(LicenseSource::Text));
+        }
+    }
+
+    #[test]
+    fn recognises_untitled_permissive_license_files() {
+        let bsd3 = "Copyright (c) 2024 Jane Doe\n\n\
+                    Redistribution and use in source and binary forms, with or without\n\
+                    modification, are permitted provided that the following conditions are met:\n\n\
+                    1. Redistributions of source code must retain the above copyright notice.\n\n\
+                    2. Redistributions in binary form must reproduce the above copyright notice.\n\n\
+                    3. Neither the name of the copyright holder nor the names of its contributors\n\
+                    may be used to endorse or promote products derived from this software.";
+        assert_eq!(detect(bsd3, true).license.as_deref(), Some("BSD-3-Clause"));
+        let info = detect(include_str!("../../LICENSE-MIT"), true);
+        assert_eq!(info.license.as_deref(), Some("MIT"));
+        assert_eq!(
+            info.copyright.as_deref(),
+            Some("Copyright 2025 Noah B. Clarkson")
+        );
+        assert_eq!(detect("See the README for details.", true).license, None);
+    }
+
+    #[test]
+    fn finds_copyright_lines() {
+        let info = detect("/*\n * Copyright (c) 2024 Jane Doe */\n", false);
+        assert_eq!(
+            info.copyright.as_deref(),
+            Some("Copyright (c) 2024 Jane Doe")
+        );
+        let info = detect("<!-- © 2021 Example Corp -->", false);
+        assert_eq!(info.copyright.as_deref(), Some("© 2021 Example Corp"));
+        assert_eq!(detect("no header here", false).copyright, None);
+    }
+}
@@ -0,0 +1,963 @@
+//! Parsing of dependency manifests into a normalized dependency list.
+//!
+//! Supported manifests are `Cargo.toml`, `package.json`, `pyproject.toml`,
+//! `requirements*.txt` and `go.mod`. Versions resolved by a lockfile next to the manifest
+//! (`Cargo.lock`, also looked up in parent directories for workspace members, and
+//! `package-lock.json`) are added to the declared requirements.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Manifests larger than this are skipped.
+const MAX_MANIFEST_SIZE: u64 = 4 * 1024 * 1024;
+/// Lockfiles larger than this are not read for resolved versions.
+const MAX_LOCKFILE_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Versions resolved by a lockfile, keyed by package name.
+type LockedVersions = HashMap<String, Vec<String>>;
+
+/// Lockfiles read so far, so workspace members sharing a `Cargo.lock` parse it only once.
+/// `None` marks a lockfile that couldn't be read.
+type LockfileCache = HashMap<PathBuf, Option<LockedVersions>>;
+
+/// The package ecosystem a manifest belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Python,
+    Go,
+}
+
+impl Ecosystem {
+    pub fn label(self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "Cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Python => "Python",
+            Ecosystem::Go => "Go",
+        }
+    }
+}
+
+/// How a dependency is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+    Peer,
+    Optional,
+    /// Required only by other dependencies (`// indirect` in `go.mod`).
+    Indirect,
+    /// A shared version declared in `[workspace.dependencies]` for members to inherit,
+    /// not a dependency of the manifest's own package.
+    Workspace,
+}
+
+impl DependencyKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "normal",
+            DependencyKind::Dev => "dev",
+            DependencyKind::Build => "build",
+            DependencyKind::Peer => "peer",
+            DependencyKind::Optional => "optional",
+            DependencyKind::Indirect => "indirect",
+            DependencyKind::Workspace => "workspace",
+        }
+    }
+}
+
+/// A dependency declared in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: DependencyKind,
+    /// The requirement as declared, e.g. `^1.2`, `>=3.8` or `path: ../core`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
+    /// The version a lockfile resolved it to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked: Option<String>,
+}
+
+/// The dependencies declared in one manifest file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Path of the manifest relative to the project.
+    pub path: String,
+    pub ecosystem: Ecosystem,
+    /// Name of the package the manifest describes, if it names one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Sorted by kind, then name.
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Manifest {
+    /// Describes the manifest's package, e.g. `Cargo package codebase_viewer 0.3.0`.
+    pub fn description(&self) -> String {
+        match (&self.package, &self.version) {
+            (Some(package), Some(version)) => {
+                format!("{} package {package} {version}", self.ecosystem.label())
+            }
+            (Some(package), None) => format!("{} package {package}", self.ecosystem.label()),
+            _ => format!("{} dependencies", self.ecosystem.label()),
+        }
+    }
+}
+
+/// Returns the ecosystem of the manifest at `path`, or `None` if it isn't a supported manifest.
+pub fn manifest_ecosystem(path: &Path) -> Option<Ecosystem> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        "Cargo.toml" => Some(Ecosystem::Cargo),
+        "package.json" => Some(Ecosystem::Npm),
+        "pyproject.toml" => Some(Ecosystem::Python),
+        "go.mod" => Some(Ecosystem::Go),
+        _ if is_requirements_file(name) => Some(Ecosystem::Python),
+        _ => None,
+    }
+}
+
+/// Matches `requirements.txt`, `requirements-dev.txt`, `dev-requirements.txt` and the like.
+fn is_requirements_file(name: &str) -> bool {
+    name.ends_with(".txt") && name.contains("requirements")
+}
+
+/// Parses the manifest at `path`, shown as `relative_path` in reports. Lockfiles are looked
+/// up in the manifest's directory and, for Cargo, its parents up to `root`.
+pub fn parse_manifest(path: &Path, relative_path: String, root: &Path) -> anyhow::Result<Manifest> {
+    parse_manifest_with_cache(path, relative_path, root, &mut LockfileCache::new())
+}
+
+fn parse_manifest_with_cache(
+    path: &Path,
+    relative_path: String,
+    root: &Path,
+    lockfiles: &mut LockfileCache,
+) -> anyhow::Result<Manifest> {
+    let ecosystem = manifest_ecosystem(path)
+        .with_context(|| format!("'{}' is not a supported manifest", path.display()))?;
+    let text = read_limited(path, MAX_MANIFEST_SIZE)?;
+    let mut manifest = Manifest {
+        path: relative_path,
+        ecosystem,
+        package: None,
+        version: None,
+        dependencies: Vec::new(),
+    };
+    let dir = path.parent().unwrap_or(root);
+    match ecosystem {
+        Ecosystem::Cargo => {
+            parse_cargo_toml(&text, &mut manifest)?;
+            let lock = dir
+                .ancestors()
+                .take_while(|ancestor| ancestor.starts_with(root))
+                .map(|ancestor| ancestor.join("Cargo.lock"))
+                .find(|lock| lock.is_file());
+            if let Some(lock) = lock {
+                let versions = cached_lockfile(lockfiles, &lock, read_cargo_lock);
+                apply_lockfile(&mut manifest, versions);
+            }
+        }
+        Ecosystem::Npm => {
+            parse_package_json(&text, &mut manifest)?;
+            let lock = dir.join("package-lock.json");
+            if lock.is_file() {
+                let versions = cached_lockfile(lockfiles, &lock, read_package_lock);
+                apply_lockfile(&mut manifest, versions);
+            }
+        }
+        Ecosystem::Python => {
+            if path
+                .file_name()
+                .is_some_and(|name| name == "pyproject.toml")
+            {
+                parse_pyproject(&text, &mut manifest)?;
+            } else {
+                parse_requirements(&text, path, &mut manifest);
+            }
+        }
+        Ecosystem::Go => parse_go_mod(&text, &mut manifest),
+    }
+    manifest
+        .dependencies
+        .sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+    manifest
+        .dependencies
+        .dedup_by(|a, b| a.kind == b.kind && a.name == b.name);
+    Ok(manifest)
+}
+
+fn read_limited(path: &Path, limit: u64) -> anyhow::Result<String> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("Failed to read metadata of '{}'", path.display()))?
+        .len();
+    if size > limit {
+        anyhow::bail!("'{}' is too large to parse ({size} bytes)", path.display());
+    }
+    fs::read_to_string(path).with_context(|| format!("Failed to read '{}'", path.display()))
+}
+
+fn parse_cargo_toml(text: &str, manifest: &mut Manifest) -> anyhow::Result<()> {
+    let value: toml::Table = text.parse().context("Invalid Cargo.toml")?;
+    if let Some(package) = value.get("package").and_then(toml::Value::as_table) {
+        manifest.package = package
+            .get("name")
+            .and_then(toml::Value::as_str)
+            .map(str::to_string);
+        manifest.version = package
+            .get("version")
+            .and_then(toml::Value::as_str)
+            .map(str::to_string);
+    }
+    let mut tables = vec![&value];
+    // Platform-specific dependencies live under `[target.'cfg(...)'.dependencies]`.
+    if let Some(targets) = value.get("target").and_then(toml::Value::as_table) {
+        tables.extend(targets.values().filter_map(toml::Value::as_table));
+    }
+    for table in tables {
+        for (section, kind) in [
+            ("dependencies", DependencyKind::Normal),
+            ("dev-dependencies", DependencyKind::Dev),
+            ("build-dependencies", DependencyKind::Build),
+        ] {
+            add_cargo_dependencies(table.get(section), kind, manifest);
+        }
+    }
+    if let Some(workspace) = value.get("workspace").and_then(toml::Value::as_table) {
+        add_cargo_dependencies(
+            workspace.get("dependencies"),
+            DependencyKind::Workspace,
+            manifest,
+        );
+    }
+    Ok(())
+}
+
+fn add_cargo_dependencies(
+    section: Option<&toml::Value>,
+    kind: DependencyKind,
+    manifest: &mut Manifest,
+) {
+    let Some(section) = section.and_then(toml::Value::as_table) else {
+        return;
+    };
+    for (key, spec) in section {
+        let (name, requirement, optional) = match spec {
+            toml::Value::String(version) => (key.clone(), Some(version.clone()), false),
+            toml::Value::Table(table) => {
+                let get = |field: &str| table.get(field).and_then(toml::Value::as_str);
+                let requirement = get("version")
+                    .map(str::to_string)
+                    .or_else(|| get("path").map(|path| format!("path: {path}")))
+                    .or_else(|| get("git").map(|git| format!("git: {git}")))
+                    .or_else(|| {
+                        table
+                            .get("workspace")
+                            .and_then(toml::Value::as_bool)
+                            .filter(|&inherited| inherited)
+                            .map(|_| "workspace".to_string())
+                    });
+                let optional = table
+                    .get("optional")
+                    .and_then(toml::Value::as_bool)
+                    .unwrap_or(false);
+                // `package` renames a dependency; the real crate name is what gets locked.
+                let name = get("package").unwrap_or(key).to_string();
+                (name, requirement, optional)
+            }
+            _ => (key.clone(), None, false),
+        };
+        manifest.dependencies.push(Dependency {
+            name,
+            kind: if optional && kind == DependencyKind::Normal {
+                DependencyKind::Optional
+            } else {
+                kind
+            },
+            requirement,
+            locked: None,
+        });
+    }
+}
+
+/// Reads the versions of all packages in a `Cargo.lock`, keyed by crate name.
+fn read_cargo_lock(path: &Path) -> anyhow::Result<LockedVersions> {
+    let text = read_limited(path, MAX_LOCKFILE_SIZE)?;
+    let value: toml::Table = text
+        .parse()
+        .with_context(|| format!("Invalid lockfile '{}'", path.display()))?;
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    for package in value
+        .get("package")
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_table)
+    {
+        let name = package.get("name").and_then(toml::Value::as_str);
+        let version = package.get("version").and_then(toml::Value::as_str);
+        if let (Some(name), Some(version)) = (name, version) {
+            versions
+                .entry(name.to_string())
+                .or_default()
+                .push(version.to_string());
+        }
+    }
+    Ok(versions)
+}
+
+fn parse_package_json(text: &str, manifest: &mut Manifest) -> anyhow::Result<()> {
+    let value: serde_json::Value = serde_json::from_str(text).context("Invalid package.json")?;
+    manifest.package = value
+        .get("name")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string);
+    manifest.version = value
+        .get("version")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string);
+    for (section, kind) in [
+        ("dependencies", DependencyKind::Normal),
+        ("devDependencies", DependencyKind::Dev),
+        ("peerDependencies", DependencyKind::Peer),
+        ("optionalDependencies", DependencyKind::Optional),
+    ] {
+        let Some(dependencies) = value.get(section).and_then(serde_json::Value::as_object) else {
+            continue;
+        };
+        for (name, requirement) in dependencies {
+            manifest.dependencies.push(Dependency {
+                name: name.clone(),
+                kind,
+                requirement: requirement.as_str().map(str::to_string),
+                locked: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Reads the installed versions from a `package-lock.json` (lockfile versions 1 to 3).
+fn read_package_lock(path: &Path) -> anyhow::Result<LockedVersions> {
+    let text = read_limited(path, MAX_LOCKFILE_SIZE)?;
+    let value: serde_json::Value = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid lockfile '{}'", path.display()))?;
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    let version_of = |entry: &serde_json::Value| {
+        entry
+            .get("version")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    };
+    if let Some(packages) = value.get("packages").and_then(serde_json::Value::as_object) {
+        // Only top-level installs; nested `node_modules` hold other packages' dependencies.
+        for (key, entry) in packages {
+            let Some(name) = key.strip_prefix("node_modules/") else {
+                continue;
+            };
+            if name.contains("/node_modules/") {
+                continue;
+            }
+            if let Some(version) = version_of(entry) {
+                versions.entry(name.to_string()).or_default().push(version);
+            }
+        }
+    } else if let Some(dependencies) = value
+        .get("dependencies")
+        .and_then(serde_json::Value::as_object)
+    {
+        for (name, entry) in dependencies {
+            if let Some(version) = version_of(entry) {
+                versions.entry(name.clone()).or_default().push(version);
+            }
+        }
+    }
+    Ok(versions)
+}
+
+fn parse_pyproject(text: &str, manifest: &mut Manifest) -> anyhow::Result<()> {
+    let value: toml::Table = text.parse().context("Invalid pyproject.toml")?;
+    if let Some(project) = value.get("project").and_then(toml::Value::as_table) {
+        manifest.package = project
+            .get("name")
+            .and_then(toml::Value::as_str)
+            .map(str::to_string);
+        manifest.version = project
+            .get("version")
+            .and_then(toml::Value::as_str)
+            .map(str::to_string);
+        add_pep508_list(
+            project.get("dependencies"),
+            DependencyKind::Normal,
+            manifest,
+        );
+        if let Some(extras) = project
+            .get("optional-dependencies")
+            .and_then(toml::Value::as_table)
+        {
+            for group in extras.values() {
+                add_pep508_list(Some(group), DependencyKind::Optional, manifest);
+            }
+        }
+    }
+    if let Some(groups) = value
+        .get("dependency-groups")
+        .and_then(toml::Value::as_table)
+    {
+        for group in groups.values() {
+            add_pep508_list(Some(group), DependencyKind::Dev, manifest);
+        }
+    }
+
+    let poetry = value
+        .get("tool")
+        .and_then(|tool| tool.get("poetry"))
+        .and_then(toml::Value::as_table);
+    if let Some(poetry) = poetry {
+        if manifest.package.is_none() {
+            manifest.package = poetry
+                .get("name")
+                .and_then(toml::Value::as_str)
+                .map(str::to_string);
+            manifest.version = poetry
+                .get("version")
+                .and_then(toml::Value::as_str)
+                .map(str::to_string);
+        }
+        add_poetry_dependencies(poetry.get("dependencies"), DependencyKind::Normal, manifest);
+        add_poetry_dependencies(
+            poetry.get("dev-dependencies"),
+            DependencyKind::Dev,
+            manifest,
+        );
+        if let Some(groups) = poetry.get("group").and_then(toml::Value::as_table) {
+            for group in groups.values() {
+                add_poetry_dependencies(group.get("dependencies"), DependencyKind::Dev, manifest);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn add_pep508_list(list: Option<&toml::Value>, kind: DependencyKind, manifest: &mut Manifest) {
+    for requirement in list
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_str)
+    {
+        if let Some(dependency) = parse_pep508(requirement, kind) {
+            manifest.dependencies.push(dependency);
+        }
+    }
+}
+
+fn add_poetry_dependencies(
+    section: Option<&toml::Value>,
+    kind: DependencyKind,
+    manifest: &mut Manifest,
+) {
+    let Some(section) = section.and_then(toml::Value::as_table) else {
+        return;
+    };
+    for (name, spec) in section {
+        // Poetry lists the supported Python versions among the dependencies.
+        if name == "python" {
+            continue;
+        }
+        let requirement = match spec {
+            toml::Value::String(version) => Some(version.clone()),
+            toml::Value::Table(table) => table
+                .get("version")
+                .and_then(toml::Value::as_str)
+                .map(str::to_string)
+                .or_else(|| {
+                    table
+                        .get("path")
+                        .and_then(toml::Value::as_str)
+                        .map(|path| format!("path: {path}"))
+                }),
+            _ => None,
+        };
+        manifest.dependencies.push(Dependency {
+            name: name.clone(),
+            kind,
+            requirement,
+            locked: None,
+        });
+    }
+}
+
+fn parse_requirements(text: &str, path: &Path, manifest: &mut Manifest) {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let kind = if file_name.contains("dev") || file_name.contains("test") {
+        DependencyKind::Dev
+    } else {
+        DependencyKind::Normal
+    };
+    for line in join_continuations(text) {
+        let line = line.split(" #").next().unwrap_or(&line).trim();
+        // Options such as `-r other.txt` or `-e .` and comments aren't dependencies, and
+        // neither are bare URLs or paths to archives and checkouts.
+        if line.is_empty() || line.starts_with('#') || line.starts_with('-') || is_url_or_path(line)
+        {
+            continue;
+        }
+        if let Some(dependency) = parse_pep508(line, kind) {
+            manifest.dependencies.push(dependency);
+        }
+    }
+}
+
+/// Joins lines ending in a `\` continuation with the line that follows.
+fn join_continuations(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        match line.trim_end().strip_suffix('\\') {
+            Some(start) => {
+                current.push_str(start);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Whether a requirements line is a URL (`https://…/pkg.whl`, `git+https://…`) or a local
+/// path rather than a named requirement. `name @ https://…` is a named requirement.
+fn is_url_or_path(line: &str) -> bool {
+    let head = line.split([' ', '@']).next().unwrap_or(line);
+    head.contains("://") || head.starts_with("file:") || head.starts_with(['.', '/', '\\', '~'])
+}
+
+/// Parses a PEP 508 requirement such as `requests[socks]>=2.31; python_version >= "3.8"`
+/// into its name and version specifier.
+fn parse_pep508(requirement: &str, kind: DependencyKind) -> Option<Dependency> {
+    let requirement = requirement.split(';').next()?.trim();
+    let name_end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+    let mut rest = requirement[name_end..].trim();
+    if rest.starts_with('[') {
+        rest = rest.find(']').map_or("", |end| rest[end + 1..].trim());
+    }
+    let rest = rest.trim_start_matches('(').trim_end_matches(')').trim();
+    Some(Dependency {
+        name: name.to_string(),
+        kind,
+        requirement: (!rest.is_empty()).then(|| rest.to_string()),
+        locked: None,
+    })
+}
+
+fn parse_go_mod(text: &str, manifest: &mut Manifest) {
+    let mut in_require_block = false;
+    for line in text.lines() {
+        let (code, comment) = match line.split_once("//") {
+            Some((code, comment)) => (code.trim(), comment.trim()),
+            None => (line.trim(), ""),
+        };
+        let requirement = if in_require_block {
+            if code == ")" {
+                in_require_block = false;
+                continue;
+            }
+            code
+        } else if let Some(module) = code.strip_prefix("module ") {
+            manifest.package = Some(module.trim().to_string());
+            continue;
+        } else if code == "require (" {
+            in_require_block = true;
+            continue;
+        } else if let Some(requirement) = code.strip_prefix("require ") {
+            requirement
+        } else {
+            continue;
+        };
+        let mut parts = requirement.split_whitespace();
+        let (Some(name), Some(version)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        manifest.dependencies.push(Dependency {
+            name: name.to_string(),
+            kind: if comment == "indirect" {
+                DependencyKind::Indirect
+            } else {
+                DependencyKind::Normal
+            },
+            // `go.mod` pins exact minimum versions, which is what gets built.
+            requirement: Some(version.to_string()),
+            locked: Some(version.to_string()),
+        });
+    }
+}
+
+/// Returns the versions in the lockfile at `path`, reading it with `read` the first time.
+/// A lockfile that can't be read is logged once and yields `None`.
+fn cached_lockfile<'a>(
+    lockfiles: &'a mut LockfileCache,
+    path: &Path,
+    read: fn(&Path) -> anyhow::Result<LockedVersions>,
+) -> Option<&'a LockedVersions> {
+    lockfiles
+        .entry(path.to_path_buf())
+        .or_insert_with(|| match read(path) {
+            Ok(versions) => Some(versions),
+            Err(e) => {
+                log::warn!("Ignoring lockfile '{}': {:#}", path.display(), e);
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// Fills in the locked versions of the manifest's dependencies. Without a readable
+/// lockfile the dependencies are listed without locked versions.
+fn apply_lockfile(manifest: &mut Manifest, versions: Option<&LockedVersions>) {
+    let Some(versions) = versions else {
+        return;
+    };
+    for dependency in &mut manifest.dependencies {
+        if let Some(locked) = versions.get(&dependency.name) {
+            dependency.locked = Some(locked.join(", "));
+        }
+    }
+}
+
+/// Parses every manifest in `manifests` (absolute path, project-relative path and scan root),
+/// logging and skipping the ones that can't be read.
+pub fn parse_manifests(manifests: &[(PathBuf, String, PathBuf)]) -> Vec<Manifest> {
+    let mut parsed: BTreeMap<String, Manifest> = BTreeMap::new();
+    let mut lockfiles = LockfileCache::new();
+    for (path, relative_path, root) in manifests {
+        match parse_manifest_with_cache(path, relative_path.clone(), root, &mut lockfiles) {
+            Ok(manifest) => {
+                parsed.insert(relative_path.clone(), manifest);
+            }
+            Err(e) => log::warn!("Skipping manifest '{}': {:#}", path.display(), e),
+        }
+    }
+    parsed.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty(ecosystem: Ecosystem) -> Manifest {
+        Manifest {
+            path: "manifest".to_string(),
+            ecosystem,
+            package: None,
+            version: None,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn find<'a>(manifest: &'a Manifest, name: &str) -> &'a Dependency {
+        manifest
+            .dependencies
+            .iter()
+            .find(|dependency| dependency.name == name)
+            .unwrap_or_else(|| panic!("no dependency '{name}' in {:?}", manifest.dependencies))
+    }
+
+    fn names(manifest: &Manifest) -> Vec<&str> {
+        manifest
+            .dependencies
+            .iter()
+            .map(|dependency| dependency.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn parses_cargo_toml() {
+        let mut manifest = empty(Ecosystem::Cargo);
+        parse_cargo_toml(
+            r#"
+[package]
+name = "demo"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+log = { version = "0.4", optional = true }
+core = { path = "../core" }
+json = { package = "serde_json", version = "1" }
+shared = { workspace = true }
+
+[dev-dependencies]
+tempfile = { git = "https://github.com/example/tempfile" }
+
+[build-dependencies]
+cc = "1"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[workspace.dependencies]
+anyhow = "1.0"
+"#,
+            &mut manifest,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.as_deref(), Some("demo"));
+        assert_eq!(manifest.version.as_deref(), Some("0.1.0"));
+        assert_eq!(find(&manifest, "serde").requirement.as_deref(), Some("1.0"));
+        assert_eq!(find(&manifest, "log").kind, DependencyKind::Optional);
+        assert_eq!(
+            find(&manifest, "core").requirement.as_deref(),
+            Some("path: ../core")
+        );
+        assert_eq!(
+            find(&manifest, "serde_json").requirement.as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            find(&manifest, "shared").requirement.as_deref(),
+            Some("workspace")
+        );
+        let tempfile = find(&manifest, "tempfile");
+        assert_eq!(tempfile.kind, DependencyKind::Dev);
+        assert_eq!(
+            tempfile.requirement.as_deref(),
+            Some("git: https://github.com/example/tempfile")
+        );
+        assert_eq!(find(&manifest, "cc").kind, DependencyKind::Build);
+        assert_eq!(find(&manifest, "winapi").kind, DependencyKind::Normal);
+        assert_eq!(find(&manifest, "anyhow").kind, DependencyKind::Workspace);
+    }
+
+    #[test]
+    fn parses_package_json_and_lockfile() {
+        let mut manifest = empty(Ecosystem::Npm);
+        parse_package_json(
+            r#"{
+                "name": "web",
+                "version": "2.0.0",
+                "dependencies": { "react": "^16.8.0 || ^17.0.0" },
+                "devDependencies": { "jest": "^29.0.0" },
+                "peerDependencies": { "react-dom": ">=16" },
+                "optionalDependencies": { "fsevents": "*" }
+            }"#,
+            &mut manifest,
+        )
+        .unwrap();
+        assert_eq!(manifest.package.as_deref(), Some("web"));
+        assert_eq!(
+            find(&manifest, "react").requirement.as_deref(),
+            Some("^16.8.0 || ^17.0.0")
+        );
+        assert_eq!(find(&manifest, "jest").kind, DependencyKind::Dev);
+        assert_eq!(find(&manifest, "react-dom").kind, DependencyKind::Peer);
+        assert_eq!(find(&manifest, "fsevents").kind, DependencyKind::Optional);
+
+        let dir = tempfile::tempdir().unwrap();
+        let lock = dir.path().join("package-lock.json");
+        fs::write(
+            &lock,
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "name": "web" },
+                    "node_modules/react": { "version": "17.0.2" },
+                    "node_modules/jest/node_modules/react": { "version": "16.0.0" }
+                }
+            }"#,
+        )
+        .unwrap();
+        let versions = read_package_lock(&lock).unwrap();
+        assert_eq!(versions["react"], ["17.0.2"]);
+        apply_lockfile(&mut manifest, Some(&versions));
+        assert_eq!(find(&manifest, "react").locked.as_deref(), Some("17.0.2"));
+        assert_eq!(find(&manifest, "jest").locked, None);
+    }
+
+    #[test]
+    fn parses_requirements_files() {
+        let mut manifest = empty(Ecosystem::Python);
+        parse_requirements(
+            "# Pinned dependencies\n\
+             requests[socks]>=2.31 ; python_version >= \"3.8\"\n\
+             numpy==1.26.4  # for arrays\n\
+             flask \\\n    >=3.0 \\\n    <4\n\
+             -r base.txt\n\
+             -e .\n\
+             git+https://github.com/example/tool.git#egg=tool\n\
+             https://example.com/wheels/pkg-1.0-py3-none-any.whl\n\
+             ./vendor/local_pkg\n\
+             mylib @ https://example.com/mylib-1.0.tar.gz\n",
+            Path::new("requirements-dev.txt"),
+            &mut manifest,
+        );
+
+        assert_eq!(names(&manifest), ["requests", "numpy", "flask", "mylib"]);
+        assert!(manifest
+            .dependencies
+            .iter()
+            .all(|dependency| dependency.kind == DependencyKind::Dev));
+        assert_eq!(
+            find(&manifest, "requests").requirement.as_deref(),
+            Some(">=2.31")
+        );
+        assert_eq!(
+            find(&manifest, "numpy").requirement.as_deref(),
+            Some("==1.26.4")
+        );
+        let flask = find(&manifest, "flask").requirement.as_deref().unwrap();
+        assert!(!flask.contains('\\'), "{flask}");
+        assert_eq!(
+            flask.split_whitespace().collect::<Vec<_>>(),
+            [">=3.0", "<4"]
+        );
+        assert_eq!(
+            find(&manifest, "mylib").requirement.as_deref(),
+            Some("@ https://example.com/mylib-1.0.tar.gz")
+        );
+    }
+
+    #[test]
+    fn parses_pyproject() {
+        let mut manifest = empty(Ecosystem::Python);
+        parse_pyproject(
+            r#"
+[project]
+name = "tool"
+version = "1.2.3"
+dependencies = ["httpx>=0.27", "rich"]
+
+[project.optional-dependencies]
+docs = ["sphinx (>=7)"]
+
+[dependency-groups]
+test = ["pytest>=8"]
+
+[tool.poetry.dependencies]
+python = "^3.10"
+click = "^8.1"
+
+[tool.poetry.group.lint.dependencies]
+ruff = { version = "^0.4" }
+"#,
+            &mut manifest,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.as_deref(), Some("tool"));
+        assert_eq!(manifest.version.as_deref(), Some("1.2.3"));
+        assert_eq!(
+            find(&manifest, "httpx").requirement.as_deref(),
+            Some(">=0.27")
+        );
+        assert_eq!(find(&manifest, "rich").requirement, None);
+        let sphinx = find(&manifest, "sphinx");
+        assert_eq!(sphinx.kind, DependencyKind::Optional);
+        assert_eq!(sphinx.requirement.as_deref(), Some(">=7"));
+        assert_eq!(find(&manifest, "pytest").kind, DependencyKind::Dev);
+        assert_eq!(
+            find(&manifest, "click").requirement.as_deref(),
+            Some("^8.1")
+        );
+        assert_eq!(find(&manifest, "ruff").kind, DependencyKind::Dev);
+        assert!(!names(&manifest).contains(&"python"));
+    }
+
+    #[test]
+    fn parses_go_mod() {
+        let mut manifest = empty(Ecosystem::Go);
+        parse_go_mod(
+            "module example.com/service\n\n\
+             go 1.22\n\n\
+             require github.com/google/uuid v1.6.0\n\n\
+             require (\n\
+             \tgolang.org/x/sync v0.7.0\n\
+             \tgithub.com/pkg/errors v0.9.1 // indirect\n\
+             )\n",
+            &mut manifest,
+        );
+
+        assert_eq!(manifest.package.as_deref(), Some("example.com/service"));
+        let uuid = find(&manifest, "github.com/google/uuid");
+        assert_eq!(uuid.requirement.as_deref(), Some("v1.6.0"));
+        assert_eq!(uuid.locked.as_deref(), Some("v1.6.0"));
+        assert_eq!(
+            find(&manifest, "golang.org/x/sync").kind,
+            DependencyKind::Normal
+        );
+        assert_eq!(
+            find(&manifest, "github.com/pkg/errors").kind,
+            DependencyKind::Indirect
+        );
+    }
+
+    #[test]
+    fn workspace_members_share_one_cargo_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.lock"),
+            "version = 3\n\n\
+             [[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n\n\
+             [[package]]\nname = \"log\"\nversion = \"0.4.21\"\n",
+        )
+        .unwrap();
+        let mut manifests = Vec::new();
+        for member in ["a", "b"] {
+            fs::create_dir(root.join(member)).unwrap();
+            let path = root.join(member).join("Cargo.toml");
+            fs::write(
+                &path,
+                format!(
+                    "[package]\nname = \"{member}\"\n\n[dependencies]\nserde = \"1\"\nlog = \"0.4\"\n"
+                ),
+            )
+            .unwrap();
+            manifests.push((path, format!("{member}/Cargo.toml"), root.to_path_buf()));
+        }
+
+        let mut lockfiles = LockfileCache::new();
+        for (path, relative_path, root) in &manifests {
+            let manifest =
+                parse_manifest_with_cache(path, relative_path.clone(), root, &mut lockfiles)
+                    .unwrap();
+            assert_eq!(find(&manifest, "serde").locked.as_deref(), Some("1.0.200"));
+            assert_eq!(find(&manifest, "log").locked.as_deref(), Some("0.4.21"));
+        }
+        assert_eq!(lockfiles.len(), 1);
+
+        let parsed = parse_manifests(&manifests);
+        assert_eq!(
+            parsed.iter().map(|m| m.path.as_str()).collect::<Vec<_>>(),
+            ["a/Cargo.toml", "b/Cargo.toml"]
+        );
+    }
+}
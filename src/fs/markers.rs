@@ -0,0 +1,151 @@
+//! Extraction of comment markers such as `TODO`, `FIXME` and `HACK`.
+//!
+//! A marker is a configured keyword that appears in a comment, optionally followed by an
+//! author in parentheses and a colon, e.g. `// TODO(alice): handle errors`. Keywords are
+//! matched case-sensitively as whole words. Whether a match is inside a comment is decided
+//! by looking for a comment leader (`//`, `#`, `/*`, `--`, ...) earlier on the same line,
+//! which keeps keywords in string literals and identifiers out of the list without
+//! needing a parser for every language.
+
+use crate::fs::encoding::{self, TextEncoding};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// Keywords collected when the configuration doesn't name any.
+pub const DEFAULT_KEYWORDS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// Files larger than this are not searched for markers.
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+/// At most this many markers are kept per file, so a file full of them can't flood the list.
+const MAX_MARKERS_PER_FILE: usize = 200;
+/// Marker text longer than this many characters is truncated.
+const MAX_TEXT_CHARS: usize = 200;
+
+/// Comment leaders that may appear anywhere before a marker keyword.
+const COMMENT_LEADERS: &[&str] = &["//", "#", "/*", "--", "<!--", "(*", "{-", "\"\"\"", "'''"];
+/// Comment leaders that only count at the start of a line (block comment continuations,
+/// Lisp/assembly comments, LaTeX/Erlang comments).
+const LINE_START_LEADERS: &[&str] = &["*", ";", "%"];
+
+/// A marker keyword found in a comment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentMarker {
+    /// The keyword that matched, e.g. `TODO`.
+    pub keyword: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// The comment text following the keyword.
+    pub text: String,
+}
+
+/// Finds marker keywords in comments.
+#[derive(Debug, Clone)]
+pub struct MarkerMatcher {
+    regex: Regex,
+}
+
+impl MarkerMatcher {
+    /// Creates a matcher for `keywords`. Returns `None` if no non-empty keyword is given.
+    pub fn new(keywords: &[String]) -> Option<Self> {
+        let alternatives: Vec<String> = keywords
+            .iter()
+            .map(|keyword| keyword.trim())
+            .filter(|keyword| !keyword.is_empty())
+            .map(regex::escape)
+            .collect();
+        if alternatives.is_empty() {
+            return None;
+        }
+        // The keyword, an optional `(author)`, then a colon, whitespace or the end of the line.
+        let pattern = format!(
+            r"(?:^|[^\w])({})(?:\([^)]*\))?(?::|\s|$)",
+            alternatives.join("|")
+        );
+        match Regex::new(&pattern) {
+            Ok(regex) => Some(Self { regex }),
+            Err(e) => {
+                log::error!("Failed to build comment marker pattern: {e}");
+                None
+            }
+        }
+    }
+
+    /// Returns `true` if files of `size` bytes are searched for markers.
+    pub fn searches_size(size: u64) -> bool {
+        size <= MAX_FILE_SIZE
+    }
+
+    /// Returns the markers in the text file at `path`, stored in `encoding`.
+    /// Unreadable and very large files are reported as having none.
+    pub fn find_in_file(
+        &self,
+        path: &Path,
+        size: u64,
+        encoding: TextEncoding,
+    ) -> Vec<CommentMarker> {
+        if !Self::searches_size(size) {
+            return Vec::new();
+        }
+        match fs::read(path) {
+            Ok(bytes) => self.find(&encoding::decode(&bytes, encoding)),
+            Err(e) => {
+                // Files deleted since they were walked are skipped silently.
+                if e.kind() != ErrorKind::NotFound {
+                    log::warn!(
+                        "Failed to read '{}' for comment markers: {}",
+                        path.display(),
+                        e
+                    );
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Returns the markers in `text`, at most one per line.
+    pub fn find(&self, text: &str) -> Vec<CommentMarker> {
+        let mut markers = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if markers.len() >= MAX_MARKERS_PER_FILE {
+                break;
+            }
+            let found = self.regex.captures_iter(line).find_map(|captures| {
+                let keyword = captures.get(1)?;
+                let end = captures.get(0)?.end();
+                is_comment_prefix(&line[..keyword.start()]).then(|| (keyword.as_str(), end))
+            });
+            if let Some((keyword, end)) = found {
+                markers.push(CommentMarker {
+                    keyword: keyword.to_string(),
+                    line: index + 1,
+                    text: clean_text(&line[end..]),
+                });
+            }
+        }
+        markers
+    }
+}
+
+/// Returns `true` if `prefix`, the part of a line before a keyword, opens a comment.
+fn is_comment_prefix(prefix: &str) -> bool {
+    let trimmed = prefix.trim_start();
+    COMMENT_LEADERS.iter().any(|leader| prefix.contains(leader))
+        || LINE_START_LEADERS
+            .iter()
+            .any(|leader| trimmed.starts_with(leader))
+}
+
+/// Strips comment closers and surrounding punctuation from marker text and truncates it.
+fn clean_text(text: &str) -> String {
+    let mut text = text.trim();
+    for closer in ["*/", "-->", "*)", "-}", "\"\"\"", "'''"] {
+        text = text.strip_suffix(closer).unwrap_or(text).trim_end();
+    }
+    let text = text.trim_start_matches([':', '-', ' ', '\t']).trim();
+    if text.chars().count() > MAX_TEXT_CHARS {
+        text.chars().take(MAX_TEXT_CHARS - 1).collect::<String>() + "…"
+    } else {
+        text.to_string()
+    }
+}
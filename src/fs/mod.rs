@@ -3,12 +3,26 @@
 //! This module contains components related to file system scanning,
 //! metadata extraction, and statistics gathering.
 
+pub mod archive;
+pub mod classify;
+pub mod encoding;
 pub mod file_info;
+pub mod git;
+pub mod license;
+pub mod manifest;
+pub mod markers;
+pub mod scan_error;
+pub mod scan_index;
 pub mod scanner;
 pub mod stats;
 pub mod token_worker;
+pub mod watcher;
 
 // Re-export key types for easier access from other modules (like app.rs)
+pub use classify::FileClass;
+pub use encoding::TextEncoding;
 pub use file_info::FileInfo;
+pub use markers::CommentMarker;
+pub use scan_error::{ScanError, ScanErrorKind};
 pub use scanner::scan; // Re-export the main scan function
-pub use stats::ScanStats;
+pub use stats::{FileLocStats, LanguageStats, LicenseStats, ScanProgress, ScanStats};
@@ -0,0 +1,175 @@
+//! Typed errors reported while scanning or watching a directory tree.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// What went wrong for a scan error, used for filtering and machine-readable output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    /// The entry exists but may not be read.
+    PermissionDenied,
+    /// The entry disappeared while the scan was running.
+    NotFound,
+    /// The entry's metadata couldn't be read for another reason.
+    Metadata,
+    /// The directory walker failed to list a directory.
+    Walk,
+    /// A followed symbolic link points back at one of its ancestors.
+    SymlinkLoop,
+    /// An ignore file (`.gitignore`, `.cbvignore`, ...) couldn't be parsed.
+    IgnoreFile,
+    /// An archive couldn't be listed when scanning archives as directories.
+    Archive,
+    /// A text file couldn't be read for analysis, or holds sequences that are invalid in
+    /// its detected encoding.
+    Decode,
+    /// The filesystem watcher reported an error.
+    Watch,
+    /// The scan settings are invalid (e.g. a bad include/exclude glob).
+    Config,
+    /// Anything else, such as a crashed worker thread.
+    Other,
+}
+
+impl ScanErrorKind {
+    /// All kinds, in display order.
+    pub const ALL: [ScanErrorKind; 11] = [
+        ScanErrorKind::PermissionDenied,
+        ScanErrorKind::NotFound,
+        ScanErrorKind::Metadata,
+        ScanErrorKind::Walk,
+        ScanErrorKind::SymlinkLoop,
+        ScanErrorKind::IgnoreFile,
+        ScanErrorKind::Archive,
+        ScanErrorKind::Decode,
+        ScanErrorKind::Watch,
+        ScanErrorKind::Config,
+        ScanErrorKind::Other,
+    ];
+
+    /// Human-readable name of the kind.
+    pub fn label(self) -> &'static str {
+        match self {
+            ScanErrorKind::PermissionDenied => "Permission denied",
+            ScanErrorKind::NotFound => "Not found",
+            ScanErrorKind::Metadata => "Metadata",
+            ScanErrorKind::Walk => "Walk",
+            ScanErrorKind::SymlinkLoop => "Symlink loop",
+            ScanErrorKind::IgnoreFile => "Ignore file",
+            ScanErrorKind::Archive => "Archive",
+            ScanErrorKind::Decode => "Decode",
+            ScanErrorKind::Watch => "Watch",
+            ScanErrorKind::Config => "Configuration",
+            ScanErrorKind::Other => "Other",
+        }
+    }
+
+    /// Classifies an I/O error, using `fallback` for anything not more specific.
+    fn from_io(err: &io::Error, fallback: ScanErrorKind) -> Self {
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            io::ErrorKind::NotFound => ScanErrorKind::NotFound,
+            _ => fallback,
+        }
+    }
+}
+
+/// An error encountered for one entry (or the whole scan) while scanning.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    /// The entry the error concerns, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    pub message: String,
+    /// Raw OS error code (`errno` on Unix), if the error came from the operating system.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_error: Option<i32>,
+}
+
+impl ScanError {
+    pub fn new(kind: ScanErrorKind, path: Option<PathBuf>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            path,
+            message: message.into(),
+            os_error: None,
+        }
+    }
+
+    /// Creates an error for `path` from an I/O error, classified by its `ErrorKind`.
+    pub fn from_io(path: &Path, err: &io::Error, fallback: ScanErrorKind) -> Self {
+        Self {
+            kind: ScanErrorKind::from_io(err, fallback),
+            path: Some(path.to_path_buf()),
+            message: err.to_string(),
+            os_error: err.raw_os_error(),
+        }
+    }
+
+    /// Creates an error for `path` from an `anyhow` error, classifying it by the
+    /// underlying I/O error if there is one.
+    pub fn from_anyhow(path: &Path, err: &anyhow::Error, fallback: ScanErrorKind) -> Self {
+        match err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<io::Error>())
+        {
+            Some(io_err) => Self::from_io(path, io_err, fallback),
+            None => Self::new(fallback, Some(path.to_path_buf()), format!("{err:#}")),
+        }
+    }
+
+    /// Creates an error from a directory walker error, unwrapping the path and
+    /// line-number context `ignore` adds around the actual cause.
+    pub fn from_walk(err: &ignore::Error) -> Self {
+        let mut path = None;
+        let mut current = err;
+        loop {
+            match current {
+                ignore::Error::WithPath { path: p, err } => {
+                    path.get_or_insert_with(|| p.clone());
+                    current = err;
+                }
+                ignore::Error::WithDepth { err, .. }
+                | ignore::Error::WithLineNumber { err, .. } => {
+                    current = err;
+                }
+                ignore::Error::Loop { child, .. } => {
+                    let path = path.unwrap_or_else(|| child.clone());
+                    return Self::new(ScanErrorKind::SymlinkLoop, Some(path), current.to_string());
+                }
+                ignore::Error::Io(io_err) => {
+                    return Self {
+                        kind: ScanErrorKind::from_io(io_err, ScanErrorKind::Walk),
+                        path,
+                        message: io_err.to_string(),
+                        os_error: io_err.raw_os_error(),
+                    };
+                }
+                _ => {
+                    // Glob, file type and partial errors all come from parsing ignore files.
+                    return Self::new(ScanErrorKind::IgnoreFile, path, err.to_string());
+                }
+            }
+        }
+    }
+
+    /// Key used to list errors in a stable order: by path, then kind, then message.
+    pub fn sort_key(&self) -> (Option<&Path>, ScanErrorKind, &str) {
+        (self.path.as_deref(), self.kind, &self.message)
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind.label())?;
+        if let Some(path) = &self.path {
+            write!(f, " '{}'", path.display())?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
@@ -0,0 +1,352 @@
+//! Persistent per-project index of expensive per-file analysis results.
+//!
+//! Scanning a file involves a binary sniff and a full `tokei` pass, and token
+//! counting reads the whole file again. The index stores those results keyed by
+//! the file's path relative to the project root, together with the size and
+//! modification time they were computed for. An entry is only reused when both
+//! still match, so edited files are always re-analysed.
+//!
+//! Each project gets its own JSON file in the `scan_index` directory next to
+//! `config.json`. Writes hold a process-wide lock, so a scan saving its index and token
+//! counts written back from the UI (`update`) can't overwrite each other's entries.
+
+use crate::{
+    config,
+    fs::{license::LicenseInfo, CommentMarker, FileInfo, TextEncoding},
+    model::FileNode,
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Bumped whenever the on-disk layout or the meaning of stored data changes.
+const INDEX_VERSION: u32 = 4;
+
+/// Held while an index file is read and rewritten or removed.
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Cached analysis results for a single file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub size: u64,
+    pub modified: SystemTime,
+    pub is_binary: bool,
+    #[serde(default)]
+    pub encoding: Option<TextEncoding>,
+    pub loc_stats: Option<tokei::Language>,
+    pub token_count: Option<usize>,
+    #[serde(default)]
+    pub generated_marker: bool,
+    /// Comment markers, if the file was searched with the index's marker keywords.
+    #[serde(default)]
+    pub markers: Option<Vec<CommentMarker>>,
+    /// License and copyright header; `None` for binary files.
+    #[serde(default)]
+    pub license: Option<LicenseInfo>,
+}
+
+impl IndexEntry {
+    /// Returns `true` if this entry was computed for a file with the given size and mtime.
+    fn matches(&self, size: u64, modified: Option<SystemTime>) -> bool {
+        self.size == size && Some(self.modified) == modified
+    }
+}
+
+/// The persistent scan index for one project root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanIndex {
+    version: u32,
+    root: PathBuf,
+    /// Keywords the stored comment markers were collected with.
+    #[serde(default)]
+    marker_keywords: Vec<String>,
+    entries: HashMap<String, IndexEntry>,
+}
+
+impl ScanIndex {
+    /// Creates an empty index for `root`.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            version: INDEX_VERSION,
+            root: root.to_path_buf(),
+            marker_keywords: Vec::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the index for `root`, returning an empty index if none exists or it can't be used.
+    pub fn load(root: &Path) -> Self {
+        let path = match index_file(root) {
+            Ok(path) => path,
+            Err(e) => {
+                log::warn!("Scan index unavailable: {e}");
+                return Self::new(root);
+            }
+        };
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(_) => {
+                log::debug!("No scan index found at {}", path.display());
+                return Self::new(root);
+            }
+        };
+        match serde_json::from_reader::<_, Self>(BufReader::new(file)) {
+            Ok(index) if index.version == INDEX_VERSION && index.root == root => {
+                log::info!(
+                    "Loaded scan index with {} entries from {}",
+                    index.entries.len(),
+                    path.display()
+                );
+                index
+            }
+            Ok(_) => {
+                log::info!("Discarding outdated scan index at {}", path.display());
+                Self::new(root)
+            }
+            Err(e) => {
+                log::warn!("Failed to parse scan index {}: {e}", path.display());
+                Self::new(root)
+            }
+        }
+    }
+
+    /// Writes the index to its file in the config directory. Token counts stored in the
+    /// file since this index was built are kept for files that haven't changed.
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        let _guard = WRITE_LOCK.lock();
+        let stored = Self::load(&self.root);
+        for (key, entry) in &mut self.entries {
+            if entry.token_count.is_none() {
+                entry.token_count = stored
+                    .entries
+                    .get(key)
+                    .filter(|stored| stored.matches(entry.size, Some(entry.modified)))
+                    .and_then(|stored| stored.token_count);
+            }
+        }
+        self.write()
+    }
+
+    /// Writes the index to its file; the caller holds `WRITE_LOCK`.
+    fn write(&self) -> anyhow::Result<()> {
+        let path = index_file(&self.root)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to create scan index directory {}: {}",
+                    parent.display(),
+                    e
+                )
+            })?;
+        }
+        let file = BufWriter::new(fs::File::create(&path).map_err(|e| {
+            anyhow::anyhow!("Failed to create scan index {}: {}", path.display(), e)
+        })?);
+        serde_json::to_writer(file, self)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize or write scan index: {}", e))?;
+        log::info!(
+            "Saved scan index with {} entries to {}",
+            self.entries.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Number of files recorded in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the index has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sets the keywords comment markers are collected with. Markers stored for different
+    /// keywords are dropped, so those files are searched again.
+    pub fn use_marker_keywords(&mut self, keywords: &[String]) {
+        if self.marker_keywords == keywords {
+            return;
+        }
+        for entry in self.entries.values_mut() {
+            entry.markers = None;
+        }
+        self.marker_keywords = keywords.to_vec();
+    }
+
+    /// Builds a file node from an up-to-date index entry, skipping the binary check, LOC pass
+    /// and marker search.
+    /// Returns `None` if the file isn't indexed or has changed since it was indexed.
+    pub fn lookup(&self, path: &Path, metadata: &Metadata) -> Option<FileNode> {
+        if metadata.is_dir() {
+            return None;
+        }
+        let entry = self.entries.get(&self.key_for(path)?)?;
+        if !entry.matches(metadata.len(), metadata.modified().ok()) {
+            return None;
+        }
+        let mut info = FileInfo::from_metadata_unanalyzed(path.to_path_buf(), metadata);
+        info.is_binary = entry.is_binary;
+        info.encoding = entry.encoding;
+        info.loc_stats = entry.loc_stats.clone();
+        info.generated_marker = entry.generated_marker;
+        info.markers = entry.markers.clone();
+        info.license = entry.license.clone();
+        let mut node = FileNode::new(info);
+        node.token_count = entry.token_count;
+        Some(node)
+    }
+
+    /// Records the analysis results of a scanned file node. Directories and archive
+    /// members are ignored.
+    pub fn record(&mut self, node: &FileNode) {
+        if node.is_dir() || node.info.archive.is_some() {
+            return;
+        }
+        let (Some(key), Some(modified)) = (self.key_for(node.path()), node.info.modified) else {
+            return;
+        };
+        self.entries.insert(
+            key,
+            IndexEntry {
+                size: node.info.size,
+                modified,
+                is_binary: node.info.is_binary,
+                encoding: node.info.encoding,
+                loc_stats: node.info.loc_stats.clone(),
+                token_count: node.token_count,
+                generated_marker: node.info.generated_marker,
+                markers: node.info.markers.clone(),
+                license: node.info.license.clone(),
+            },
+        );
+    }
+
+    /// Stores a token count for the file at `path` if the index has a matching entry for it.
+    pub fn set_token_count(
+        &mut self,
+        path: &Path,
+        size: u64,
+        modified: Option<SystemTime>,
+        count: usize,
+    ) {
+        let Some(key) = self.key_for(path) else {
+            return;
+        };
+        if let Some(entry) = self.entries.get_mut(&key) {
+            if entry.matches(size, modified) {
+                entry.token_count = Some(count);
+            }
+        }
+    }
+
+    /// Returns the platform-independent key for `path`, or `None` if it lies outside the root.
+    fn key_for(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// Loads the index for `root`, applies `update` to it and saves it, holding the write lock
+/// throughout so no other write is lost in between.
+pub fn update(root: &Path, update: impl FnOnce(&mut ScanIndex)) -> anyhow::Result<()> {
+    let _guard = WRITE_LOCK.lock();
+    let mut index = ScanIndex::load(root);
+    update(&mut index);
+    index.write()
+}
+
+/// Returns the directory holding all project scan indexes.
+pub fn index_dir() -> anyhow::Result<PathBuf> {
+    Ok(config::config_dir()?.join("scan_index"))
+}
+
+/// Returns the index file path for the project rooted at `root`.
+///
+/// The file name combines the root's directory name with a stable hash of the
+/// full path, so different projects with the same name don't collide.
+pub fn index_file(root: &Path) -> anyhow::Result<PathBuf> {
+    let name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "root".to_string());
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let hash = fnv1a_64(root.to_string_lossy().as_bytes());
+    Ok(index_dir()?.join(format!("{name}-{hash:016x}.json")))
+}
+
+/// Deletes the index for the project rooted at `root`. Returns `true` if a file was removed.
+pub fn clear(root: &Path) -> anyhow::Result<bool> {
+    let path = index_file(root)?;
+    let _guard = WRITE_LOCK.lock();
+    match fs::remove_file(&path) {
+        Ok(()) => {
+            log::info!("Removed scan index {}", path.display());
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(anyhow::anyhow!(
+            "Failed to remove scan index {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Deletes the indexes of all projects. Returns the number of files removed.
+pub fn clear_all() -> anyhow::Result<usize> {
+    let dir = index_dir()?;
+    let _guard = WRITE_LOCK.lock();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => {
+            return Err(anyhow::anyhow!(
+                "Failed to read scan index directory {}: {}",
+                dir.display(),
+                e
+            ))
+        }
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            fs::remove_file(&path).map_err(|e| {
+                anyhow::anyhow!("Failed to remove scan index {}: {}", path.display(), e)
+            })?;
+            removed += 1;
+        }
+    }
+    log::info!(
+        "Removed {removed} scan index file(s) from {}",
+        dir.display()
+    );
+    Ok(removed)
+}
+
+/// 64-bit FNV-1a hash. Used instead of `DefaultHasher`, whose output may change between
+/// Rust releases, so index file names stay stable across builds.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
@@ -1,11 +1,24 @@
 //! Background directory scanning implementation using the `ignore` crate.
 
-use crate::{fs::FileInfo, model::FileNode, task::ScanMessage};
+use crate::{
+    config::AppConfig,
+    fs::{
+        archive, classify::FileClassifier, markers::MarkerMatcher, scan_index::ScanIndex, FileInfo,
+        ScanError, ScanErrorKind, ScanProgress,
+    },
+    model::FileNode,
+    task::{LinkedNode, ScanMessage},
+};
 use crossbeam_channel::select;
 use crossbeam_channel::Sender;
-use ignore::{WalkBuilder, WalkState};
+use humansize::{format_size, DECIMAL};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    WalkBuilder, WalkState,
+};
 use std::{
-    path::PathBuf,
+    collections::HashMap,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -17,11 +30,99 @@ use std::{
 
 const BATCH_SIZE: usize = 100;
 const BATCH_TIMEOUT: Duration = Duration::from_millis(50);
+/// How often the scanner reports progress while a scan is running.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Options controlling which entries the scanner visits and how they are analysed.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Include hidden files and directories.
+    pub show_hidden: bool,
+    /// Honour `.cbvignore` files in addition to the standard ignore files.
+    pub respect_cbvignore: bool,
+    /// Follow symbolic links instead of skipping them. Link loops are reported and skipped.
+    pub follow_symlinks: bool,
+    /// Reuse unchanged file analysis from the persistent scan index and refresh it afterwards.
+    pub use_index: bool,
+    /// Globs a file must match to be scanned (relative to the scan root). Empty means all files.
+    pub include_globs: Vec<String>,
+    /// Globs for files and directories to skip, applied on top of the ignore files.
+    pub exclude_globs: Vec<String>,
+    /// List the contents of zip and tar archives as virtual directories.
+    pub scan_archives: bool,
+    /// Flag generated, vendored and documentation files (see `fs::classify`).
+    pub classify_files: bool,
+    /// Keywords collected from comments as markers (see `fs::markers`). Empty disables markers.
+    pub marker_keywords: Vec<String>,
+}
+
+impl ScanOptions {
+    /// Creates scan options from the application configuration.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            show_hidden: config.show_hidden_files,
+            respect_cbvignore: config.respect_cbvignore,
+            follow_symlinks: config.follow_symlinks,
+            use_index: config.use_scan_index,
+            include_globs: config.include_globs.clone(),
+            exclude_globs: config.exclude_globs.clone(),
+            scan_archives: config.scan_archives,
+            classify_files: config.classify_files,
+            marker_keywords: config.marker_keywords.clone(),
+        }
+    }
+
+    /// Compiles the include/exclude globs into an `ignore` override matcher rooted at `root`.
+    ///
+    /// Include globs may themselves be negated with a leading `!`; exclude globs are
+    /// negated automatically. Returns an error naming the offending glob if one is invalid.
+    pub fn build_overrides(&self, root: &Path) -> anyhow::Result<Override> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in self.include_globs.iter().map(|g| g.trim()) {
+            if glob.is_empty() {
+                continue;
+            }
+            builder
+                .add(glob)
+                .map_err(|e| anyhow::anyhow!("Invalid include glob '{glob}': {e}"))?;
+        }
+        for glob in self.exclude_globs.iter().map(|g| g.trim()) {
+            let glob = glob.strip_prefix('!').unwrap_or(glob);
+            if glob.is_empty() {
+                continue;
+            }
+            builder
+                .add(&format!("!{glob}"))
+                .map_err(|e| anyhow::anyhow!("Invalid exclude glob '{glob}': {e}"))?;
+        }
+        builder
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build scan globs: {e}"))
+    }
+
+    /// Creates the file classifier for `root`, or `None` if classification is disabled.
+    pub fn classifier(&self, root: &Path) -> Option<Arc<FileClassifier>> {
+        self.classify_files
+            .then(|| Arc::new(FileClassifier::new(root)))
+    }
+
+    /// Creates the comment marker matcher, or `None` if no marker keywords are configured.
+    pub fn marker_matcher(&self) -> Option<Arc<MarkerMatcher>> {
+        MarkerMatcher::new(&self.marker_keywords).map(Arc::new)
+    }
+
+    /// Loads the scan index for `root`, keeping stored markers only if they were collected
+    /// with the configured keywords.
+    fn load_index(&self, root: &Path) -> ScanIndex {
+        let mut index = ScanIndex::load(root);
+        index.use_marker_keywords(&self.marker_keywords);
+        index
+    }
+}
 
 pub fn scan(
     root: PathBuf,
-    show_hidden: bool,
-    respect_cbvignore: bool,
+    options: ScanOptions,
     sender: Sender<ScanMessage>,
 ) -> (JoinHandle<()>, Arc<AtomicBool>) {
     let cancel_signal = Arc::new(AtomicBool::new(false));
@@ -29,55 +130,326 @@ pub fn scan(
     let root_clone = root.clone();
 
     log::info!(
-        "Spawning scan worker thread for path: '{}', show_hidden: {}, respect_cbvignore: {}",
+        "Spawning scan worker thread for path: '{}', options: {:?}",
         root_clone.display(),
-        show_hidden,
-        respect_cbvignore
+        options
     );
 
     let handle = thread::Builder::new() // MODIFIED
         .name("scan_worker".to_string())
         .spawn(move || {
-            scan_worker(
-                root_clone,
-                show_hidden,
-                respect_cbvignore,
-                sender,
-                cancel_signal_clone,
-            );
+            scan_worker(root_clone, options, sender, cancel_signal_clone);
         })
         .expect("Failed to spawn scan worker thread");
 
     (handle, cancel_signal)
 }
 
+/// Creates a `WalkBuilder` configured with the ignore rules shared by the scanner
+/// and the filesystem watcher, so both see the same set of entries.
+///
+/// `overrides` must come from `ScanOptions::build_overrides` for the scan root, which
+/// may be an ancestor of `root` when only part of the tree is re-walked.
+pub(crate) fn walk_builder(
+    root: &Path,
+    options: &ScanOptions,
+    overrides: &Override,
+) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .overrides(overrides.clone())
+        .follow_links(options.follow_symlinks)
+        .hidden(!options.show_hidden)
+        .parents(true)
+        .ignore(true)
+        .git_global(true)
+        .git_ignore(true)
+        .git_exclude(true);
+
+    if options.respect_cbvignore {
+        builder.add_custom_ignore_filename(".cbvignore");
+    }
+    builder
+}
+
+/// Builds the node for a walked entry, reusing the scan index when it is up to date.
+/// Files analyzed afresh are searched for comment markers with `markers`, if given.
+fn build_node(
+    entry: &ignore::DirEntry,
+    index: Option<&ScanIndex>,
+    markers: Option<&MarkerMatcher>,
+) -> anyhow::Result<FileNode> {
+    let metadata = FileInfo::entry_metadata(entry)?;
+    let mut node = match index.and_then(|index| index.lookup(entry.path(), &metadata)) {
+        Some(node) => node,
+        None => FileNode::new(FileInfo::from_metadata(
+            entry.path().to_path_buf(),
+            metadata,
+            markers,
+        )?),
+    };
+    node.info.symlink_target = FileInfo::entry_link_target(entry);
+    Ok(node)
+}
+
+/// Builds the nodes for a walked entry: the entry itself, followed by the members of the
+/// archive it names when archive scanning is enabled. An archive that can't be read is
+/// kept as a plain file, and its error is returned alongside the nodes together with the
+/// files whose text couldn't be decoded. Each node is classified
+/// with `classifier` and files not restored from the index are searched for comment
+/// markers with `markers`, if given. Files on disk are searched while they are read for
+/// their line counts; archive members and index entries without markers afterwards.
+pub(crate) fn build_nodes(
+    entry: &ignore::DirEntry,
+    index: Option<&ScanIndex>,
+    scan_archives: bool,
+    classifier: Option<&FileClassifier>,
+    markers: Option<&MarkerMatcher>,
+) -> Result<(Vec<FileNode>, Vec<ScanError>), ScanError> {
+    let mut node = build_node(entry, index, markers)
+        .map_err(|e| ScanError::from_anyhow(entry.path(), &e, ScanErrorKind::Metadata))?;
+    let (mut nodes, archive_error) = if !scan_archives
+        || node.is_dir()
+        || archive::ArchiveKind::from_path(node.path()).is_none()
+    {
+        (vec![node], None)
+    } else {
+        match archive::expand_node(&mut node) {
+            Ok(members) => {
+                log::debug!(
+                    "Listed {} member(s) of archive '{}'",
+                    members.len(),
+                    node.path().display()
+                );
+                let mut nodes = Vec::with_capacity(members.len() + 1);
+                nodes.push(node);
+                nodes.extend(members);
+                (nodes, None)
+            }
+            Err(e) => {
+                let error = ScanError::from_anyhow(entry.path(), &e, ScanErrorKind::Archive);
+                (vec![node], Some(error))
+            }
+        }
+    };
+    if let Some(classifier) = classifier {
+        for node in &mut nodes {
+            node.info.class = classifier.classify(node.path(), node.info.generated_marker);
+        }
+    }
+    if let Some(matcher) = markers {
+        for node in &mut nodes {
+            if node.is_dir() || node.info.markers.is_some() {
+                continue;
+            }
+            node.info.markers = Some(match node.info.encoding {
+                Some(encoding) => matcher.find_in_file(node.path(), node.info.size, encoding),
+                None => Vec::new(),
+            });
+        }
+    }
+    let errors = archive_error
+        .into_iter()
+        .chain(
+            nodes
+                .iter_mut()
+                .filter_map(|node| node.info.decode_error.take()),
+        )
+        .collect();
+    Ok((nodes, errors))
+}
+
+/// Links walked nodes into a tree before they are sent to the UI.
+///
+/// The parallel walker may deliver a child before its parent directory. Such nodes wait
+/// here until the parent has been sent, so every `LinkedNode` refers to a parent the
+/// receiver already knows.
+struct TreeLinker {
+    root: PathBuf,
+    /// Position in the output of every directory sent so far.
+    sent_dirs: HashMap<PathBuf, usize>,
+    /// Nodes waiting for their parent directory, keyed by the parent's path.
+    waiting: HashMap<PathBuf, Vec<FileNode>>,
+    sent: usize,
+}
+
+impl TreeLinker {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            sent_dirs: HashMap::new(),
+            waiting: HashMap::new(),
+            sent: 0,
+        }
+    }
+
+    /// Appends `node` to `out` if its parent has been sent, together with any nodes
+    /// that were waiting for it. Otherwise the node waits for its parent.
+    fn link(&mut self, node: FileNode, out: &mut Vec<LinkedNode>) {
+        if node.path() == self.root {
+            self.send(node, None, out);
+            return;
+        }
+        let parent_path = node.path().parent().unwrap_or(Path::new(""));
+        match self.sent_dirs.get(parent_path) {
+            Some(&parent) => self.send(node, Some(parent), out),
+            None => {
+                log::trace!(
+                    "Node {} arrived before its parent; holding it back.",
+                    node.path().display()
+                );
+                self.waiting
+                    .entry(parent_path.to_path_buf())
+                    .or_default()
+                    .push(node);
+            }
+        }
+    }
+
+    fn send(&mut self, node: FileNode, parent: Option<usize>, out: &mut Vec<LinkedNode>) {
+        let mut ready = vec![(node, parent)];
+        while let Some((node, parent)) = ready.pop() {
+            let position = self.sent;
+            self.sent += 1;
+            if node.is_dir() {
+                if let Some(children) = self.waiting.remove(node.path()) {
+                    ready.extend(children.into_iter().map(|child| (child, Some(position))));
+                }
+                self.sent_dirs.insert(node.path().to_path_buf(), position);
+            }
+            out.push(LinkedNode { node, parent });
+        }
+    }
+
+    /// Sends the nodes still waiting once the walk is over. Their parent directories were
+    /// walked but couldn't be read, so placeholder directories are created for them.
+    /// Returns an error for each group of nodes that lies outside the root.
+    fn finish(&mut self, out: &mut Vec<LinkedNode>) -> Vec<ScanError> {
+        let mut errors = Vec::new();
+        // Resolve the shallowest missing directory first so no placeholder is created twice.
+        while let Some(parent_path) = self
+            .waiting
+            .keys()
+            .min_by_key(|path| path.components().count())
+            .cloned()
+        {
+            if !parent_path.starts_with(&self.root) {
+                let nodes = self.waiting.remove(&parent_path).unwrap_or_default();
+                errors.push(ScanError::new(
+                    ScanErrorKind::Walk,
+                    Some(parent_path),
+                    format!(
+                        "Dropped {} scanned entr{} outside the scan root",
+                        nodes.len(),
+                        if nodes.len() == 1 { "y" } else { "ies" }
+                    ),
+                ));
+                continue;
+            }
+            log::warn!(
+                "Directory '{}' was not scanned; adding a placeholder for its entries.",
+                parent_path.display()
+            );
+            self.link(placeholder_dir(parent_path), out);
+        }
+        errors
+    }
+}
+
+/// A directory node for a path whose metadata couldn't be read.
+fn placeholder_dir(path: PathBuf) -> FileNode {
+    FileNode::new(FileInfo {
+        path,
+        is_dir: true,
+        size: 0,
+        human_size: format_size(0u64, DECIMAL),
+        is_binary: false,
+        encoding: None,
+        modified: None,
+        extension: None,
+        loc_stats: None,
+        symlink_target: None,
+        archive: None,
+        class: None,
+        generated_marker: false,
+        markers: None,
+        license: None,
+        decode_error: None,
+    })
+}
+
+/// Counts a scanned node towards the running progress totals.
+fn record_progress(progress: &mut ScanProgress, node: &FileNode, root: &Path) {
+    let dir = if node.is_dir() {
+        progress.dirs_seen += 1;
+        node.path()
+    } else {
+        progress.files_seen += 1;
+        progress.bytes_seen += node.info.size;
+        node.path().parent().unwrap_or(root)
+    };
+    progress.current_dir = Some(dir.strip_prefix(root).unwrap_or(dir).to_path_buf());
+}
+
+/// Wraps the pending batch in a message, leaving `batch` empty.
+fn add_nodes(root: &Path, batch: &mut Vec<LinkedNode>) -> ScanMessage {
+    ScanMessage::AddNodes {
+        root: root.to_path_buf(),
+        nodes: std::mem::take(batch),
+    }
+}
+
 fn scan_worker(
     root: PathBuf,
-    show_hidden: bool,
-    respect_cbvignore: bool,
+    options: ScanOptions,
     ui_sender: Sender<ScanMessage>,
     cancel_signal: Arc<AtomicBool>,
 ) {
     log::info!("Scan worker started for path: {}", root.display());
 
-    let (local_node_tx, local_node_rx) = crossbeam_channel::unbounded::<Result<FileNode, String>>();
+    let overrides = match options.build_overrides(&root) {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            log::error!("Aborting scan: {e}");
+            let error = ScanError::new(ScanErrorKind::Config, Some(root.clone()), e.to_string());
+            let _ = ui_sender.send(ScanMessage::Error(error));
+            let _ = ui_sender.send(ScanMessage::Finished);
+            return;
+        }
+    };
+
+    let previous_index = options
+        .use_index
+        .then(|| Arc::new(options.load_index(&root)));
+    let classifier = options.classifier(&root);
+    let marker_matcher = options.marker_matcher();
+    let mut fresh_index = options.use_index.then(|| {
+        let mut index = ScanIndex::new(&root);
+        index.use_marker_keywords(&options.marker_keywords);
+        index
+    });
+    let started = Instant::now();
+    let mut progress = ScanProgress {
+        root: root.clone(),
+        expected_files: previous_index
+            .as_deref()
+            .filter(|index| !index.is_empty())
+            .map(ScanIndex::len),
+        ..Default::default()
+    };
+    let mut last_progress_time = started;
+
+    let (local_node_tx, local_node_rx) =
+        crossbeam_channel::unbounded::<Result<FileNode, ScanError>>();
 
     let walker_cancel_signal = cancel_signal.clone();
+    let walker_root = root.clone();
     let walker_thread = thread::Builder::new() // MODIFIED
         .name("ignore_walker".to_string())
         .spawn(move || {
-            let mut builder = WalkBuilder::new(&root);
-            builder
-                .hidden(!show_hidden)
-                .parents(true)
-                .ignore(true)
-                .git_global(true)
-                .git_ignore(true)
-                .git_exclude(true)
-                .threads(num_cpus::get().min(8));
-
-            if respect_cbvignore {
-                builder.add_custom_ignore_filename(".cbvignore");
+            let mut builder = walk_builder(&walker_root, &options, &overrides);
+            builder.threads(num_cpus::get().min(8));
+            if options.respect_cbvignore {
                 log::info!("Scanner will respect .cbvignore files.");
             }
 
@@ -86,6 +458,11 @@ fn scan_worker(
             walker.run(|| {
                 let node_tx = local_node_tx.clone();
                 let cancel = walker_cancel_signal.clone();
+                let index = previous_index.clone();
+                let classifier = classifier.clone();
+                let marker_matcher = marker_matcher.clone();
+                let follow_symlinks = options.follow_symlinks;
+                let scan_archives = options.scan_archives;
 
                 Box::new(move |result| {
                     if cancel.load(Ordering::Relaxed) {
@@ -94,24 +471,27 @@ fn scan_worker(
 
                     match result {
                         Ok(entry) => {
-                            if entry.file_type().is_some_and(|ft| ft.is_symlink()) {
+                            if !follow_symlinks && entry.file_type().is_some_and(|ft| ft.is_symlink()) {
                                 log::trace!("Skipping symlink: {}", entry.path().display());
                                 return WalkState::Continue;
                             }
 
-                            let path = entry.path();
-                            match FileInfo::from_entry(&entry) {
-                                Ok(file_info) => {
-                                    let node = FileNode::new(file_info);
-                                    if node_tx.send(Ok(node)).is_err() {
-                                        log::warn!("Local node send failed: Channel closed. Quitting walk.");
-                                        return WalkState::Quit;
+                            match build_nodes(&entry, index.as_deref(), scan_archives, classifier.as_deref(), marker_matcher.as_deref()) {
+                                Ok((nodes, errors)) => {
+                                    let results = nodes.into_iter().map(Ok).chain(errors.into_iter().map(Err));
+                                    for result in results {
+                                        if let Err(error) = &result {
+                                            log::warn!("{error}");
+                                        }
+                                        if node_tx.send(result).is_err() {
+                                            log::warn!("Local node send failed: Channel closed. Quitting walk.");
+                                            return WalkState::Quit;
+                                        }
                                     }
                                 }
-                                Err(e) => {
-                                    let error_msg = format!("Failed to process entry '{}': {}", path.display(), e);
-                                    log::warn!("{error_msg}");
-                                    if node_tx.send(Err(error_msg)).is_err() {
+                                Err(error) => {
+                                    log::warn!("Failed to process entry: {error}");
+                                    if node_tx.send(Err(error)).is_err() {
                                         log::warn!("Local error send failed: Channel closed. Quitting walk.");
                                         return WalkState::Quit;
                                     }
@@ -119,9 +499,9 @@ fn scan_worker(
                             }
                         }
                         Err(e) => {
-                            let error_msg = format!("Filesystem walk error: {e}");
-                            log::error!("{error_msg}");
-                            if node_tx.send(Err(error_msg)).is_err() {
+                            let error = ScanError::from_walk(&e);
+                            log::error!("Filesystem walk error: {error}");
+                            if node_tx.send(Err(error)).is_err() {
                                 log::warn!("Local walk error send failed: Channel closed. Quitting walk.");
                                 return WalkState::Quit;
                             }
@@ -134,8 +514,10 @@ fn scan_worker(
         })
         .expect("Failed to spawn walker thread");
 
-    let mut node_batch: Vec<FileNode> = Vec::with_capacity(BATCH_SIZE);
+    let mut linker = TreeLinker::new(root.clone());
+    let mut node_batch: Vec<LinkedNode> = Vec::with_capacity(BATCH_SIZE);
     let mut last_batch_send_time = Instant::now();
+    let mut ui_closed = false;
 
     loop {
         if cancel_signal.load(Ordering::Relaxed) {
@@ -148,20 +530,26 @@ fn scan_worker(
                     Ok(result) => {
                         match result {
                             Ok(node) => {
-                                node_batch.push(node);
-                                if node_batch.len() >= BATCH_SIZE && !node_batch.is_empty() {
+                                record_progress(&mut progress, &node, &root);
+                                if let Some(index) = fresh_index.as_mut() {
+                                    index.record(&node);
+                                }
+                                linker.link(node, &mut node_batch);
+                                if node_batch.len() >= BATCH_SIZE {
                                     log::trace!("Sending batch of {} nodes (size limit)", node_batch.len());
-                                    if ui_sender.send(ScanMessage::AddNodes(std::mem::take(&mut node_batch))).is_err() {
+                                    if ui_sender.send(add_nodes(&root, &mut node_batch)).is_err() {
                                         log::warn!("UI sender channel closed while sending full batch.");
+                                        ui_closed = true;
                                         break;
                                     }
                                     last_batch_send_time = Instant::now();
                                 }
                             }
-                            Err(error_msg) => {
-                                log::warn!("Received error from walker: {error_msg}");
-                                if ui_sender.send(ScanMessage::Error(error_msg)).is_err() {
+                            Err(error) => {
+                                progress.errors += 1;
+                                if ui_sender.send(ScanMessage::Error(error)).is_err() {
                                     log::warn!("UI sender channel closed while sending error.");
+                                    ui_closed = true;
                                     break;
                                 }
                             }
@@ -176,32 +564,60 @@ fn scan_worker(
             default(BATCH_TIMEOUT) => {
                 if !node_batch.is_empty() && last_batch_send_time.elapsed() >= BATCH_TIMEOUT {
                     log::trace!("Sending batch of {} nodes (timeout)", node_batch.len());
-                    if ui_sender.send(ScanMessage::AddNodes(std::mem::take(&mut node_batch))).is_err() {
+                    if ui_sender.send(add_nodes(&root, &mut node_batch)).is_err() {
                         log::warn!("UI sender channel closed while sending timed-out batch.");
+                        ui_closed = true;
                         break;
                     }
                     last_batch_send_time = Instant::now();
                 }
             }
         }
-    }
 
-    if !node_batch.is_empty() {
-        log::info!("Sending final batch of {} nodes.", node_batch.len());
-        let _ = ui_sender.send(ScanMessage::AddNodes(node_batch));
+        if last_progress_time.elapsed() >= PROGRESS_INTERVAL {
+            progress.elapsed = started.elapsed();
+            if ui_sender
+                .send(ScanMessage::Progress(progress.clone()))
+                .is_err()
+            {
+                log::warn!("UI sender channel closed while sending progress.");
+                ui_closed = true;
+                break;
+            }
+            last_progress_time = Instant::now();
+        }
     }
 
     if let Err(e) = walker_thread.join() {
         log::error!("Walker thread panicked: {e:?}");
-        let _ = ui_sender.send(ScanMessage::Error("Walker thread panicked.".to_string()));
+        let error = ScanError::new(ScanErrorKind::Other, None, "Walker thread panicked.");
+        let _ = ui_sender.send(ScanMessage::Error(error));
     } else {
         log::info!("Walker thread joined successfully.");
     }
 
-    if cancel_signal.load(Ordering::Relaxed) {
-        log::info!("Scan worker finished processing due to cancellation signal.");
+    if !ui_closed {
+        // Nodes whose parent never arrived are linked under placeholders, never dropped.
+        for error in linker.finish(&mut node_batch) {
+            log::error!("{error}");
+            let _ = ui_sender.send(ScanMessage::Error(error));
+        }
+        if !node_batch.is_empty() {
+            log::info!("Sending final batch of {} nodes.", node_batch.len());
+            let _ = ui_sender.send(add_nodes(&root, &mut node_batch));
+        }
+    }
+
+    if cancel_signal.load(Ordering::Relaxed) || ui_closed {
+        log::info!("Scan worker stopped early (cancelled or UI channel closed).");
     } else {
         log::info!("Scan worker finished processing path normally.");
+        // Only a complete scan may replace the index, otherwise unvisited files would be lost.
+        if let Some(mut index) = fresh_index {
+            if let Err(e) = index.save() {
+                log::warn!("Failed to save scan index: {e}");
+            }
+        }
     }
 
     let _ = ui_sender.send(ScanMessage::Finished);
@@ -1,37 +1,88 @@
 //! Defines `ScanStats` for collecting statistics during directory scanning.
 
-use crate::fs::FileInfo;
+use crate::{
+    fs::{license, FileClass, FileInfo, ScanError, ScanErrorKind},
+    workspace,
+};
 use humansize::{format_size, DECIMAL};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::Path};
+use std::{cmp::Ordering, collections::BTreeMap, path::PathBuf, time::Duration};
 
 /// Statistics collected during a directory scan.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanStats {
     pub total_files: usize,
     pub total_dirs: usize,
     pub total_size_bytes: u64,
-    pub file_types: HashMap<String, usize>,
+    /// File counts by extension, ordered by extension so reports list ties consistently.
+    pub file_types: BTreeMap<String, usize>,
     pub largest_files: Vec<FileStatInfo>,
-    pub errors: Vec<String>,
-    #[serde(skip)]
-    pub language_stats: tokei::Languages,
+    /// Counts of generated, vendored and documentation files. These files are left out of
+    /// `file_types`, `language_stats` and `largest_files`.
+    #[serde(default)]
+    pub file_classes: BTreeMap<FileClass, ClassStats>,
+    /// The first `MAX_STORED_ERRORS` errors reported by the scan, sorted by path once the
+    /// scan has finished. `error_counts` holds the totals.
+    pub errors: Vec<ScanError>,
+    /// Number of errors reported by the scan per kind, including those not kept in `errors`.
+    #[serde(default)]
+    pub error_counts: BTreeMap<ScanErrorKind, usize>,
+    /// Lines of code per language, keyed by the language's display name.
+    #[serde(default)]
+    pub language_stats: BTreeMap<String, LanguageStats>,
+    /// Files per detected license, keyed by SPDX expression. Unlike the other per-file
+    /// statistics this includes generated, vendored and documentation files.
+    #[serde(default)]
+    pub licenses: BTreeMap<String, LicenseStats>,
+    /// Source files without a license header (see `license::is_missing_header`).
+    #[serde(default)]
+    pub files_missing_license: usize,
 }
 
-impl Clone for ScanStats {
-    fn clone(&self) -> Self {
-        Self {
-            total_files: self.total_files,
-            total_dirs: self.total_dirs,
-            total_size_bytes: self.total_size_bytes,
-            file_types: self.file_types.clone(),
-            largest_files: self.largest_files.clone(),
-            errors: self.errors.clone(),
-            language_stats: Default::default(),
-        }
+/// Files carrying one license.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LicenseStats {
+    pub files: usize,
+    /// Files with a copyright line as well.
+    pub with_copyright: usize,
+    /// License files (`LICENSE`, `COPYING`, ...) declaring it, sorted once the scan has finished.
+    pub license_files: Vec<String>,
+}
+
+/// Line counts of all files of one language, with a breakdown per file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LanguageStats {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+    /// Per-file counts, sorted by path once the scan has finished.
+    pub files: Vec<FileLocStats>,
+}
+
+impl LanguageStats {
+    /// Total number of lines, including comments and blanks.
+    pub fn lines(&self) -> usize {
+        self.code + self.comments + self.blanks
+    }
+
+    fn add(&mut self, file: FileLocStats) {
+        self.code += file.code;
+        self.comments += file.comments;
+        self.blanks += file.blanks;
+        self.files.push(file);
     }
 }
 
+/// Line counts of a single file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileLocStats {
+    /// Path relative to the project (prefixed with the root name in a workspace).
+    pub path: String,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
 /// Simplified information about a file, used for tracking largest files.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct FileStatInfo {
@@ -40,11 +91,59 @@ pub struct FileStatInfo {
     pub human_size: String,
 }
 
+/// Number and total size of the files of one `FileClass`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClassStats {
+    pub files: usize,
+    pub size_bytes: u64,
+}
+
+impl ClassStats {
+    pub fn size_human(&self) -> String {
+        format_size(self.size_bytes, DECIMAL)
+    }
+}
+
 const MAX_LARGEST_FILES: usize = 10;
+/// Errors kept in `ScanStats::errors`; further errors are only counted.
+pub const MAX_STORED_ERRORS: usize = 100;
+
+/// Key of a file in `ScanStats::file_types`, e.g. `.rs` or `(no extension)`.
+pub fn extension_key(info: &FileInfo) -> String {
+    info.extension
+        .as_deref()
+        .map_or_else(|| "(no extension)".to_string(), |ext| format!(".{ext}"))
+}
+
+/// Name of the language a file with LOC statistics is counted under in `ScanStats::language_stats`.
+pub fn language_name(info: &FileInfo) -> Option<&'static str> {
+    info.loc_stats.as_ref()?;
+    tokei::LanguageType::from_path(&info.path, &tokei::Config::default()).map(|l| l.name())
+}
+
+/// Returns the language name and line counts of a file with LOC statistics.
+fn file_loc_stats(info: &FileInfo, roots: &[PathBuf]) -> Option<(String, FileLocStats)> {
+    let loc_stats = info.loc_stats.as_ref()?;
+    let language = language_name(info)?;
+    let file = FileLocStats {
+        path: workspace::relative_path(&info.path, roots)
+            .display()
+            .to_string(),
+        code: loc_stats.code,
+        comments: loc_stats.comments,
+        blanks: loc_stats.blanks,
+    };
+    Some((language.to_string(), file))
+}
+
+/// Orders files by descending size, breaking ties by path.
+fn largest_first(a: &FileStatInfo, b: &FileStatInfo) -> Ordering {
+    b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path))
+}
 
 impl ScanStats {
-    /// Updates the statistics based on a discovered `FileInfo`.
-    pub fn add_file(&mut self, info: &FileInfo, root_path: &Path) {
+    /// Updates the statistics based on a discovered `FileInfo` below one of `roots`.
+    pub fn add_file(&mut self, info: &FileInfo, roots: &[PathBuf]) {
         if info.is_dir {
             self.add_dir();
             return;
@@ -52,36 +151,26 @@ impl ScanStats {
 
         self.total_files += 1;
         self.total_size_bytes += info.size;
+        self.add_license(info, roots);
 
-        let extension_key = info
-            .extension
-            .as_deref()
-            .map_or_else(|| "(no extension)".to_string(), |ext| format!(".{ext}"));
-        *self.file_types.entry(extension_key).or_insert(0) += 1;
-
-        if let Some(loc_stats) = &info.loc_stats {
-            let language_type =
-                tokei::LanguageType::from_path(&info.path, &tokei::Config::default());
-            if let Some(lang_type) = language_type {
-                let entry = self.language_stats.entry(lang_type).or_default();
-
-                // MODIFIED: Manually add the stats.
-                entry.blanks += loc_stats.blanks;
-                entry.code += loc_stats.code;
-                entry.comments += loc_stats.comments;
-                // Also merge the reports, which contain individual file stats.
-                entry.reports.extend(loc_stats.reports.clone());
-            }
+        if let Some(class) = info.class {
+            let entry = self.file_classes.entry(class).or_default();
+            entry.files += 1;
+            entry.size_bytes += info.size;
+            return;
+        }
+
+        *self.file_types.entry(extension_key(info)).or_insert(0) += 1;
+
+        if let Some((language, file)) = file_loc_stats(info, roots) {
+            self.language_stats.entry(language).or_default().add(file);
         }
 
         if info.size > 0
             && (self.largest_files.len() < MAX_LARGEST_FILES
-                || info.size > self.largest_files.last().map_or(0, |f| f.size))
+                || info.size >= self.largest_files.last().map_or(0, |f| f.size))
         {
-            let relative_path = info
-                .path
-                .strip_prefix(root_path)
-                .unwrap_or(&info.path)
+            let relative_path = workspace::relative_path(&info.path, roots)
                 .display()
                 .to_string();
             let stat_info = FileStatInfo {
@@ -91,7 +180,7 @@ impl ScanStats {
             };
             let pos = self
                 .largest_files
-                .partition_point(|f| f.size >= stat_info.size);
+                .partition_point(|f| largest_first(f, &stat_info) == Ordering::Less);
             if pos < MAX_LARGEST_FILES {
                 self.largest_files.insert(pos, stat_info);
                 self.largest_files.truncate(MAX_LARGEST_FILES);
@@ -99,57 +188,356 @@ impl ScanStats {
         }
     }
 
+    /// Reverses the effect of a previous `add_file` call for the same `FileInfo`.
+    ///
+    /// Used when the filesystem watcher reports that a file was modified or removed.
+    /// The largest-files list may end up shorter than the limit afterwards; call
+    /// `refill_largest_files` to repopulate it.
+    pub fn remove_file(&mut self, info: &FileInfo, roots: &[PathBuf]) {
+        if info.is_dir {
+            self.total_dirs = self.total_dirs.saturating_sub(1);
+            return;
+        }
+
+        self.total_files = self.total_files.saturating_sub(1);
+        self.total_size_bytes = self.total_size_bytes.saturating_sub(info.size);
+        self.remove_license(info, roots);
+
+        if let Some(class) = info.class {
+            if let Some(entry) = self.file_classes.get_mut(&class) {
+                entry.files = entry.files.saturating_sub(1);
+                entry.size_bytes = entry.size_bytes.saturating_sub(info.size);
+                if entry.files == 0 {
+                    self.file_classes.remove(&class);
+                }
+            }
+            return;
+        }
+
+        let extension_key = extension_key(info);
+        if let Some(count) = self.file_types.get_mut(&extension_key) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.file_types.remove(&extension_key);
+            }
+        }
+
+        if let Some((language, file)) = file_loc_stats(info, roots) {
+            if let Some(entry) = self.language_stats.get_mut(&language) {
+                entry.blanks = entry.blanks.saturating_sub(file.blanks);
+                entry.code = entry.code.saturating_sub(file.code);
+                entry.comments = entry.comments.saturating_sub(file.comments);
+                entry.files.retain(|f| f.path != file.path);
+                if entry.files.is_empty() {
+                    self.language_stats.remove(&language);
+                }
+            }
+        }
+
+        let relative_path = workspace::relative_path(&info.path, roots)
+            .display()
+            .to_string();
+        self.largest_files.retain(|f| f.path != relative_path);
+    }
+
+    /// Counts the file's license, or counts it as missing one.
+    fn add_license(&mut self, info: &FileInfo, roots: &[PathBuf]) {
+        if license::is_missing_header(info) {
+            self.files_missing_license += 1;
+        }
+        let Some(found) = info.license.as_ref() else {
+            return;
+        };
+        let Some(name) = found.license.as_ref() else {
+            return;
+        };
+        let entry = self.licenses.entry(name.clone()).or_default();
+        entry.files += 1;
+        if found.copyright.is_some() {
+            entry.with_copyright += 1;
+        }
+        if license::is_license_file(&info.path) {
+            entry.license_files.push(
+                workspace::relative_path(&info.path, roots)
+                    .display()
+                    .to_string(),
+            );
+        }
+    }
+
+    /// Reverses the effect of `add_license`.
+    fn remove_license(&mut self, info: &FileInfo, roots: &[PathBuf]) {
+        if license::is_missing_header(info) {
+            self.files_missing_license = self.files_missing_license.saturating_sub(1);
+        }
+        let Some(found) = info.license.as_ref() else {
+            return;
+        };
+        let Some(name) = found.license.as_ref() else {
+            return;
+        };
+        let Some(entry) = self.licenses.get_mut(name) else {
+            return;
+        };
+        entry.files = entry.files.saturating_sub(1);
+        if found.copyright.is_some() {
+            entry.with_copyright = entry.with_copyright.saturating_sub(1);
+        }
+        if license::is_license_file(&info.path) {
+            let path = workspace::relative_path(&info.path, roots)
+                .display()
+                .to_string();
+            entry.license_files.retain(|f| *f != path);
+        }
+        if entry.files == 0 {
+            self.licenses.remove(name);
+        }
+    }
+
+    /// Rebuilds the largest-files list from the given file infos if it has fallen
+    /// below the limit (e.g. after `remove_file`).
+    pub fn refill_largest_files<'a>(
+        &mut self,
+        infos: impl Iterator<Item = &'a FileInfo>,
+        roots: &[PathBuf],
+    ) {
+        if self.largest_files.len() >= MAX_LARGEST_FILES
+            || self.largest_files.len() >= self.total_files
+        {
+            return;
+        }
+        let mut largest: Vec<FileStatInfo> = infos
+            .filter(|i| !i.is_dir && i.size > 0 && i.class.is_none())
+            .map(|info| FileStatInfo {
+                path: workspace::relative_path(&info.path, roots)
+                    .display()
+                    .to_string(),
+                size: info.size,
+                human_size: info.human_size.clone(),
+            })
+            .collect();
+        largest.sort_unstable_by(largest_first);
+        largest.truncate(MAX_LARGEST_FILES);
+        self.largest_files = largest;
+    }
+
     /// Increments the directory count.
     pub fn add_dir(&mut self) {
         self.total_dirs += 1;
     }
 
-    /// Adds an error message encountered during the scan.
-    pub fn add_error(&mut self, error: String) {
-        match self.errors.len() {
-            len if len < 100 => self.errors.push(error),
-            100 => self
-                .errors
-                .push("... more errors truncated ...".to_string()),
-            _ => {}
+    /// Records an error encountered during the scan. Errors beyond `MAX_STORED_ERRORS` are
+    /// only counted.
+    pub fn add_error(&mut self, error: ScanError) {
+        *self.error_counts.entry(error.kind).or_insert(0) += 1;
+        if self.errors.len() < MAX_STORED_ERRORS {
+            self.errors.push(error);
         }
     }
 
+    /// Total number of errors reported by the scan.
+    pub fn error_count(&self) -> usize {
+        self.error_counts.values().sum()
+    }
+
+    /// Number of errors reported by the scan that aren't kept in `errors`.
+    pub fn omitted_errors(&self) -> usize {
+        self.error_count().saturating_sub(self.errors.len())
+    }
+
     /// Merges statistics from another `ScanStats` instance.
     pub fn merge(&mut self, other: ScanStats) {
         self.total_files += other.total_files;
         self.total_dirs += other.total_dirs;
         self.total_size_bytes += other.total_size_bytes;
-        self.errors.extend(other.errors);
-        if self.errors.len() > 101 {
-            self.errors.truncate(101);
-            if !self.errors.last().is_some_and(|s| s.contains("truncated")) {
-                self.errors
-                    .push("... more errors truncated ...".to_string());
-            }
+        for (kind, count) in other.error_counts {
+            *self.error_counts.entry(kind).or_insert(0) += count;
         }
+        self.errors.extend(other.errors);
+        self.errors.truncate(MAX_STORED_ERRORS);
 
         for (ext, count) in other.file_types {
             *self.file_types.entry(ext).or_insert(0) += count;
         }
 
-        for (language_type, language) in other.language_stats {
-            self.language_stats.insert(language_type, language);
+        for (class, counts) in other.file_classes {
+            let entry = self.file_classes.entry(class).or_default();
+            entry.files += counts.files;
+            entry.size_bytes += counts.size_bytes;
+        }
+
+        for (language, stats) in other.language_stats {
+            let entry = self.language_stats.entry(language).or_default();
+            for file in stats.files {
+                entry.add(file);
+            }
+        }
+
+        for (name, counts) in other.licenses {
+            let entry = self.licenses.entry(name).or_default();
+            entry.files += counts.files;
+            entry.with_copyright += counts.with_copyright;
+            entry.license_files.extend(counts.license_files);
         }
+        self.files_missing_license += other.files_missing_license;
 
         self.largest_files.extend(other.largest_files);
-        self.largest_files
-            .sort_unstable_by(|a, b| b.size.cmp(&a.size));
+        self.largest_files.sort_unstable_by(largest_first);
         self.largest_files
             .dedup_by(|a, b| a.path == b.path && a.size == b.size);
         self.largest_files.truncate(MAX_LARGEST_FILES);
     }
 
+    /// Puts order-dependent statistics into a canonical order once a scan is complete,
+    /// since the parallel scanner reports entries in a different order on every run.
     pub fn finalize(&mut self) {
         log::debug!("Finalizing scan statistics.");
+        self.errors
+            .sort_unstable_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        for language in self.language_stats.values_mut() {
+            language.files.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        }
+        for license in self.licenses.values_mut() {
+            license.license_files.sort_unstable();
+        }
+    }
+
+    /// Detected licenses, most common first.
+    pub fn licenses_by_files(&self) -> Vec<(&String, &LicenseStats)> {
+        let mut licenses: Vec<_> = self.licenses.iter().collect();
+        licenses.sort_by(|a, b| b.1.files.cmp(&a.1.files).then_with(|| a.0.cmp(b.0)));
+        licenses
     }
 
     pub fn total_size_human(&self) -> String {
         format_size(self.total_size_bytes, DECIMAL)
     }
 }
+
+/// A snapshot of a running scan's progress, sent periodically by the scanner.
+#[derive(Debug, Clone, Default)]
+pub struct ScanProgress {
+    /// Root directory of the scan this progress belongs to.
+    pub root: PathBuf,
+    pub files_seen: usize,
+    pub dirs_seen: usize,
+    pub bytes_seen: u64,
+    pub errors: usize,
+    /// Directory (relative to the scan root) of the most recently scanned entry.
+    pub current_dir: Option<PathBuf>,
+    pub elapsed: Duration,
+    /// Number of files the previous scan of this root found, if known from the scan index.
+    pub expected_files: Option<usize>,
+}
+
+impl ScanProgress {
+    /// Adds up the progress of scans running side by side (one per workspace root).
+    /// The current directory is taken from the last entry, which should be the most recent.
+    pub fn combine(parts: &[ScanProgress]) -> Option<Self> {
+        let last = parts.last()?;
+        if parts.len() == 1 {
+            return Some(last.clone());
+        }
+        let mut total = ScanProgress {
+            root: last.root.clone(),
+            current_dir: last
+                .current_dir
+                .as_ref()
+                .map(|dir| PathBuf::from(workspace::root_label(&last.root)).join(dir)),
+            expected_files: Some(0),
+            ..Default::default()
+        };
+        for part in parts {
+            total.files_seen += part.files_seen;
+            total.dirs_seen += part.dirs_seen;
+            total.bytes_seen += part.bytes_seen;
+            total.errors += part.errors;
+            total.elapsed = total.elapsed.max(part.elapsed);
+            total.expected_files = total
+                .expected_files
+                .zip(part.expected_files)
+                .map(|(a, b)| a + b);
+        }
+        Some(total)
+    }
+
+    /// Average scan rate so far.
+    pub fn files_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.files_seen as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated completed fraction, if the expected file count is known.
+    /// Capped below 1.0 since the estimate comes from the previous scan.
+    pub fn fraction(&self) -> Option<f32> {
+        let expected = self.expected_files.filter(|&n| n > 0)?;
+        Some((self.files_seen as f32 / expected as f32).min(0.99))
+    }
+
+    /// Estimated time remaining, if the expected file count is known.
+    pub fn eta(&self) -> Option<Duration> {
+        let expected = self.expected_files?;
+        let rate = self.files_per_sec();
+        if rate <= 0.0 {
+            return None;
+        }
+        let remaining = expected.saturating_sub(self.files_seen) as f64;
+        Some(Duration::from_secs_f64(remaining / rate))
+    }
+
+    /// One-line summary, e.g. `1204 files, 87 dirs, 12.30 MB · 850 files/s · ETA 4s`.
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "{} files, {} dirs, {}",
+            self.files_seen,
+            self.dirs_seen,
+            format_size(self.bytes_seen, DECIMAL)
+        );
+        if self.errors > 0 {
+            text.push_str(&format!(", {} errors", self.errors));
+        }
+        text.push_str(&format!(" · {:.0} files/s", self.files_per_sec()));
+        if let Some(eta) = self.eta() {
+            let secs = eta.as_secs();
+            if secs >= 60 {
+                text.push_str(&format!(" · ETA {}m {:02}s", secs / 60, secs % 60));
+            } else {
+                text.push_str(&format!(" · ETA {secs}s"));
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(kind: ScanErrorKind, index: usize) -> ScanError {
+        ScanError::new(kind, Some(PathBuf::from(format!("f{index}"))), "failed")
+    }
+
+    #[test]
+    fn caps_stored_errors_but_counts_them_all() {
+        let mut stats = ScanStats::default();
+        for index in 0..MAX_STORED_ERRORS + 5 {
+            stats.add_error(error(ScanErrorKind::PermissionDenied, index));
+        }
+        let mut other = ScanStats::default();
+        other.add_error(error(ScanErrorKind::Decode, 0));
+        stats.merge(other);
+
+        assert_eq!(stats.errors.len(), MAX_STORED_ERRORS);
+        assert_eq!(stats.error_count(), MAX_STORED_ERRORS + 6);
+        assert_eq!(stats.omitted_errors(), 6);
+        assert_eq!(
+            stats.error_counts[&ScanErrorKind::PermissionDenied],
+            MAX_STORED_ERRORS + 5
+        );
+        assert_eq!(stats.error_counts[&ScanErrorKind::Decode], 1);
+    }
+}
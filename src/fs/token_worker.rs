@@ -1,6 +1,10 @@
 //! Background worker for token counting and content caching.
 
-use crate::{llm::token_counter::count_tokens_local, task::TaskMessage};
+use crate::{
+    fs::{archive::ArchiveMember, encoding, TextEncoding},
+    llm::token_counter::count_tokens_local,
+    task::TaskMessage,
+};
 use crossbeam_channel::Sender;
 use std::{
     fs,
@@ -16,6 +20,7 @@ pub struct TokenWorkItem {
     pub id: usize,
     pub path: PathBuf,
     pub is_binary: bool,
+    pub encoding: Option<TextEncoding>,
 }
 
 pub fn start_token_calculation(
@@ -39,23 +44,19 @@ pub fn start_token_calculation(
                 continue;
             }
 
-            let file_size = match fs::metadata(&file.path) {
-                Ok(metadata) => metadata.len(),
-                Err(_) => continue,
+            let bytes = match fs::metadata(&file.path) {
+                Ok(metadata) if metadata.len() > max_count_size => continue,
+                Ok(_) => fs::read(&file.path).ok(),
+                // Archive members are read straight from their archive.
+                Err(_) => ArchiveMember::from_virtual_path(&file.path)
+                    .and_then(|member| member.read(max_count_size).ok()),
             };
-            if file_size > max_count_size {
+            let Some(bytes) = bytes else {
                 continue;
-            }
-
-            let bytes = match fs::read(&file.path) {
-                Ok(bytes) => bytes,
-                Err(_) => continue,
             };
+            let file_size = bytes.len() as u64;
             let cache_allowed = max_cache_size > 0 && file_size <= max_cache_size;
-            let content = match String::from_utf8(bytes) {
-                Ok(text) => text,
-                Err(err) => String::from_utf8_lossy(&err.into_bytes()).to_string(),
-            };
+            let content = encoding::decode_text(bytes, file.encoding);
 
             let count = count_tokens_local(&content);
             let cached_content = if cache_allowed { Some(content) } else { None };
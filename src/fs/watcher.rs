@@ -0,0 +1,220 @@
+//! Watches a scanned directory for changes and streams incremental tree updates.
+//!
+//! Raw `notify` events are coalesced over a short debounce window. Each affected
+//! path is then re-examined: paths that no longer exist are reported as removals,
+//! while existing paths are re-walked with the same ignore rules as the scanner so
+//! that ignored files never enter the tree.
+
+use crate::{
+    fs::{
+        classify::FileClassifier,
+        markers::MarkerMatcher,
+        scanner::{self, ScanOptions},
+        ScanError, ScanErrorKind,
+    },
+    task::ScanMessage,
+};
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use ignore::overrides::Override;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long the watcher waits for the event stream to go quiet before applying changes.
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(300);
+/// How often the watcher thread checks its cancellation flag while idle.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Starts watching `root` recursively in a background thread.
+///
+/// Changes are sent to `sender` as `ScanMessage::AddNode` (new or modified entries)
+/// and `ScanMessage::RemovePath` (deleted or renamed-away entries). The returned
+/// flag stops the watcher when set to `true`.
+pub fn watch(
+    root: PathBuf,
+    options: ScanOptions,
+    sender: Sender<ScanMessage>,
+) -> anyhow::Result<(JoinHandle<()>, Arc<AtomicBool>)> {
+    let overrides = options.build_overrides(&root)?;
+    let classifier = options.classifier(&root);
+    let marker_matcher = options.marker_matcher();
+    let (event_tx, event_rx) = crossbeam_channel::unbounded();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = event_tx.send(res);
+    })
+    .map_err(|e| anyhow::anyhow!("Failed to create filesystem watcher: {e}"))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| anyhow::anyhow!("Failed to watch '{}': {e}", root.display()))?;
+
+    let cancel_signal = Arc::new(AtomicBool::new(false));
+    let cancel_clone = cancel_signal.clone();
+
+    log::info!("Starting filesystem watcher for: {}", root.display());
+    let handle = thread::Builder::new()
+        .name("fs_watcher".to_string())
+        .spawn(move || {
+            // Keep the watcher alive for as long as this thread runs.
+            let _watcher = watcher;
+            let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+            let mut last_event = Instant::now();
+
+            while !cancel_clone.load(Ordering::Relaxed) {
+                match event_rx.recv_timeout(POLL_INTERVAL) {
+                    Ok(Ok(event)) => {
+                        if matches!(event.kind, EventKind::Access(_)) {
+                            continue;
+                        }
+                        pending.extend(event.paths.into_iter().filter(|p| p != &root));
+                        last_event = Instant::now();
+                    }
+                    Ok(Err(e)) => {
+                        log::warn!("Filesystem watch error: {e}");
+                        let error = ScanError::new(
+                            ScanErrorKind::Watch,
+                            e.paths.first().cloned(),
+                            e.to_string(),
+                        );
+                        if sender.send(ScanMessage::Error(error)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                if !pending.is_empty() && last_event.elapsed() >= DEBOUNCE_WINDOW {
+                    let mut paths = std::mem::take(&mut pending);
+                    log::debug!("Applying {} debounced filesystem change(s).", paths.len());
+                    if let Some(classifier) = &classifier {
+                        let attribute_dirs: Vec<PathBuf> = paths
+                            .iter()
+                            .filter(|p| p.ends_with(".gitattributes"))
+                            .map(|p| p.parent().unwrap_or(&root).to_path_buf())
+                            .collect();
+                        for dir in attribute_dirs {
+                            classifier.invalidate(&dir);
+                            // Re-walk the directory so the files already in the tree pick
+                            // up their new class.
+                            paths.insert(dir);
+                        }
+                    }
+                    if !apply_changes(
+                        &paths,
+                        &options,
+                        &overrides,
+                        classifier.as_deref(),
+                        marker_matcher.as_deref(),
+                        &sender,
+                    ) {
+                        log::warn!("UI channel closed; stopping filesystem watcher.");
+                        break;
+                    }
+                }
+            }
+            log::info!("Filesystem watcher thread exiting.");
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to spawn watcher thread: {e}"))?;
+
+    Ok((handle, cancel_signal))
+}
+
+/// Re-examines each changed path and sends the resulting updates.
+/// Returns `false` if the UI channel has been closed.
+fn apply_changes(
+    paths: &BTreeSet<PathBuf>,
+    options: &ScanOptions,
+    overrides: &Override,
+    classifier: Option<&FileClassifier>,
+    marker_matcher: Option<&MarkerMatcher>,
+    sender: &Sender<ScanMessage>,
+) -> bool {
+    // Paths are sorted, so a re-walked directory always comes before its descendants.
+    let mut walked_dirs: Vec<&Path> = Vec::new();
+
+    for path in paths {
+        if walked_dirs.iter().any(|dir| path.starts_with(dir)) {
+            continue;
+        }
+
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(m) => m,
+            Err(_) => {
+                if sender.send(ScanMessage::RemovePath(path.clone())).is_err() {
+                    return false;
+                }
+                continue;
+            }
+        };
+        let is_dir = if metadata.file_type().is_symlink() {
+            if !options.follow_symlinks {
+                continue;
+            }
+            path.is_dir()
+        } else {
+            metadata.is_dir()
+        };
+        let Some(parent) = path.parent() else {
+            continue;
+        };
+        if is_dir {
+            walked_dirs.push(path);
+        }
+
+        // Walk from the parent so the entry itself is subject to the ignore rules.
+        let mut builder = scanner::walk_builder(parent, options, overrides);
+        builder.max_depth(if is_dir { None } else { Some(1) });
+        let target = path.clone();
+        builder.filter_entry(move |entry| entry.path().starts_with(&target));
+
+        for entry in builder.build().flatten() {
+            if entry.depth() == 0
+                || (!options.follow_symlinks && entry.file_type().is_some_and(|ft| ft.is_symlink()))
+            {
+                continue;
+            }
+            match scanner::build_nodes(
+                &entry,
+                None,
+                options.scan_archives,
+                classifier,
+                marker_matcher,
+            ) {
+                Ok((nodes, errors)) => {
+                    // A rewritten archive may have lost members, so drop its old subtree first.
+                    if nodes.len() > 1 || nodes[0].info.archive.is_some() {
+                        let archive_path = entry.path().to_path_buf();
+                        if sender.send(ScanMessage::RemovePath(archive_path)).is_err() {
+                            return false;
+                        }
+                    }
+                    for node in nodes {
+                        if sender.send(ScanMessage::AddNode(Box::new(node))).is_err() {
+                            return false;
+                        }
+                    }
+                    for error in errors {
+                        if sender.send(ScanMessage::Error(error)).is_err() {
+                            return false;
+                        }
+                    }
+                }
+                Err(error) => {
+                    log::warn!("Failed to process changed entry: {error}");
+                    if sender.send(ScanMessage::Error(error)).is_err() {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+    true
+}
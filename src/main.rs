@@ -19,8 +19,10 @@ pub mod model;
 pub mod preview;
 pub mod report;
 pub mod selection;
+pub mod snapshot;
 pub mod task;
 pub mod ui;
+pub mod workspace;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
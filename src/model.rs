@@ -1,6 +1,6 @@
 //! Core data model definitions for the application.
 
-use crate::fs::FileInfo; // Use FileInfo from the fs module
+use crate::fs::{git::GitStatus, FileInfo}; // Use FileInfo from the fs module
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -35,6 +35,12 @@ pub struct FileNode {
     /// Token count for file contents, or summed directory totals when available.
     #[serde(skip)]
     pub token_count: Option<usize>,
+    /// Git working tree status of the file, if it is changed relative to `HEAD`.
+    #[serde(skip)]
+    pub git_status: Option<GitStatus>,
+    /// The file's own metrics, or the totals over every file below a directory.
+    #[serde(skip)]
+    pub metrics: NodeMetrics,
 }
 
 /// Type alias for the index into the `CodebaseApp::nodes` vector, uniquely identifying a node.
@@ -50,6 +56,8 @@ impl FileNode {
             state: Check::default(), // Default is Checked
             is_expanded: false,      // Default is collapsed
             token_count: None,
+            git_status: None,
+            metrics: NodeMetrics::default(),
         }
     }
 
@@ -73,3 +81,119 @@ impl FileNode {
             .unwrap_or_else(|| self.path().to_str().unwrap_or("[Invalid Path]"))
     }
 }
+
+/// File count, size, line counts and tokens of a file, or summed over a directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeMetrics {
+    pub files: usize,
+    pub size: u64,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+    /// Sum of the known token counts, or `None` if no file has been counted yet.
+    pub tokens: Option<usize>,
+}
+
+impl NodeMetrics {
+    /// Metrics of a single file, using the line counts already collected by the scanner.
+    pub fn of_file(info: &FileInfo, tokens: Option<usize>) -> Self {
+        let (code, comments, blanks) = info
+            .loc_stats
+            .as_ref()
+            .map_or((0, 0, 0), |loc| (loc.code, loc.comments, loc.blanks));
+        Self {
+            files: 1,
+            size: info.size,
+            code,
+            comments,
+            blanks,
+            tokens,
+        }
+    }
+
+    /// Adds the metrics of a file or subdirectory to these totals.
+    pub fn add(&mut self, other: &NodeMetrics) {
+        self.files += other.files;
+        self.size += other.size;
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+        self.tokens = match (self.tokens, other.tokens) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+    }
+
+    /// Total number of lines, including comments and blanks.
+    pub fn lines(&self) -> usize {
+        self.code + self.comments + self.blanks
+    }
+}
+
+/// An optional metric column in the file tree, which the tree can also be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeColumn {
+    Files,
+    Size,
+    Lines,
+    Code,
+    Comments,
+    Tokens,
+}
+
+impl TreeColumn {
+    /// All columns, in the order they are drawn.
+    pub const ALL: [TreeColumn; 6] = [
+        TreeColumn::Files,
+        TreeColumn::Size,
+        TreeColumn::Lines,
+        TreeColumn::Code,
+        TreeColumn::Comments,
+        TreeColumn::Tokens,
+    ];
+
+    /// Column header text.
+    pub fn label(self) -> &'static str {
+        match self {
+            TreeColumn::Files => "Files",
+            TreeColumn::Size => "Size",
+            TreeColumn::Lines => "Lines",
+            TreeColumn::Code => "Code",
+            TreeColumn::Comments => "Comments",
+            TreeColumn::Tokens => "Tokens",
+        }
+    }
+
+    /// The column's value, used for sorting.
+    pub fn value(self, metrics: &NodeMetrics) -> u64 {
+        match self {
+            TreeColumn::Files => metrics.files as u64,
+            TreeColumn::Size => metrics.size,
+            TreeColumn::Lines => metrics.lines() as u64,
+            TreeColumn::Code => metrics.code as u64,
+            TreeColumn::Comments => metrics.comments as u64,
+            TreeColumn::Tokens => metrics.tokens.unwrap_or(0) as u64,
+        }
+    }
+
+    /// The column's value formatted compactly for display, e.g. `12.3k` or `4.5 MB`.
+    pub fn format(self, metrics: &NodeMetrics) -> String {
+        match self {
+            TreeColumn::Size => humansize::format_size(metrics.size, humansize::DECIMAL),
+            TreeColumn::Tokens if metrics.tokens.is_none() => "-".to_string(),
+            _ => format_count(self.value(metrics)),
+        }
+    }
+}
+
+/// Formats a count compactly: `950`, `12.3k`, `4.5M`.
+pub fn format_count(count: u64) -> String {
+    if count >= 1_000_000 {
+        format!("{:.1}M", count as f64 / 1_000_000.0)
+    } else if count >= 1000 {
+        format!("{:.1}k", count as f64 / 1000.0)
+    } else {
+        count.to_string()
+    }
+}
@@ -1,7 +1,10 @@
 //! Handles generation of file content previews (text highlighting, images).
 
-use crate::config::AppConfig;
-use egui::{text::LayoutJob, Color32, Context, FontId, TextureHandle, Vec2};
+use crate::{
+    config::AppConfig,
+    fs::{archive::ArchiveMember, encoding, TextEncoding},
+};
+use egui::{text::LayoutJob, Color32, Context, FontId, Rect, Shape, TextureHandle, Vec2};
 use egui_phosphor::regular::*;
 use log;
 use once_cell::sync::Lazy;
@@ -103,6 +106,7 @@ pub fn generate_preview(
     syntax_set: &'static SyntaxSet,
     theme_set: &'static ThemeSet,
     path: &Path,
+    encoding: Option<TextEncoding>,
     node_id: crate::model::FileId,
     ctx: &Context,
 ) -> PreviewCache {
@@ -132,7 +136,7 @@ pub fn generate_preview(
             PreviewContent::Unsupported("PDF preview not yet implemented".to_string()),
             None,
         ),
-        _ => match highlight_text_content(config, syntax_set, theme_set, path) {
+        _ => match highlight_text_content(config, syntax_set, theme_set, path, encoding) {
             Ok((lines, theme_name)) => (PreviewContent::Text(lines), Some(theme_name)),
             Err(e) => {
                 let fallback_theme = get_fallback_theme_name(config);
@@ -177,8 +181,9 @@ fn highlight_text_content(
     syntax_set: &'static SyntaxSet,
     theme_set: &'static ThemeSet,
     path: &Path,
+    encoding: Option<TextEncoding>,
 ) -> Result<(Vec<HighlightedLine>, String), String> {
-    let content = read_file_content(path, config.max_file_size_preview)?;
+    let content = read_file_content(path, config.max_file_size_preview, encoding)?;
     highlight_text_content_from_str(config, syntax_set, theme_set, path, &content)
 }
 
@@ -300,35 +305,34 @@ fn highlight_text_content_from_str(
     Ok((highlighted_lines, theme_name_used))
 }
 
-pub(crate) fn read_file_content(path: &Path, max_size: i64) -> Result<String, String> {
+/// Reads a text file for display, transcoding it from `encoding` (or the encoding detected
+/// from its content, if unknown) to UTF-8.
+pub(crate) fn read_file_content(
+    path: &Path,
+    max_size: i64,
+    encoding: Option<TextEncoding>,
+) -> Result<String, String> {
     let bytes = read_file_bytes(path, max_size)?;
-    match String::from_utf8(bytes) {
-        Ok(content) => Ok(content),
-        Err(e) => {
-            log::warn!(
-                "UTF-8 decoding failed for '{}', trying lossy decoding. Error: {}",
-                path.display(),
-                e
-            );
-            let bytes_lossy = e.into_bytes();
-            Ok(String::from_utf8_lossy(&bytes_lossy).to_string())
-        }
-    }
+    Ok(encoding::decode_text(bytes, encoding))
 }
 
 fn read_file_bytes(path: &Path, max_size: i64) -> Result<Vec<u8>, String> {
+    let use_limit = max_size >= 0;
+    let max_size_u64 = if use_limit { max_size as u64 } else { u64::MAX };
     let metadata = match fs::metadata(path) {
         Ok(m) => m,
         Err(e) => {
+            // Archive members only exist inside their archive file.
+            if let Some(member) = ArchiveMember::from_virtual_path(path) {
+                return member.read(max_size_u64).map_err(|e| e.to_string());
+            }
             return Err(format!(
                 "Failed to get metadata for '{}': {}",
                 path.display(),
                 e
-            ))
+            ));
         }
     };
-    let use_limit = max_size >= 0;
-    let max_size_u64 = if use_limit { max_size as u64 } else { u64::MAX };
     if use_limit && metadata.len() > max_size_u64 {
         let limit_str = humansize::format_size(max_size_u64, humansize::DECIMAL);
         let file_size_str = humansize::format_size(metadata.len(), humansize::DECIMAL);
@@ -421,12 +425,18 @@ fn get_fallback_theme_name(config: &AppConfig) -> String {
 }
 
 /// Helper function to render a `PreviewContent` enum variant into the UI.
+///
+/// Text line `target_line` (1-based) is highlighted, and scrolled into view if
+/// `scroll_to_target` is set. Returns `true` if it was scrolled to.
 pub(crate) fn render_preview_content(
     ui: &mut egui::Ui,
     content: &PreviewContent,
     word_wrap: bool,
     selectable_line_numbers: bool,
-) {
+    target_line: Option<usize>,
+    scroll_to_target: bool,
+) -> bool {
+    let mut scrolled = false;
     match content {
         PreviewContent::Text(lines) => {
             {
@@ -436,7 +446,10 @@ pub(crate) fn render_preview_content(
             }
 
             for line in lines {
-                ui.horizontal_top(|ui| {
+                let is_target = target_line == Some(line.line_number);
+                // Reserve a shape slot so the highlight is painted behind the line.
+                let highlight = is_target.then(|| ui.painter().add(Shape::Noop));
+                let response = ui.horizontal_top(|ui| {
                     let mut line_num_job = line.line_number_job.clone();
                     line_num_job.wrap.max_width = f32::INFINITY;
                     let line_num_label =
@@ -460,13 +473,26 @@ pub(crate) fn render_preview_content(
 
                     ui.add(content_label);
                 });
+                if let Some(highlight) = highlight {
+                    let rect = Rect::from_x_y_ranges(
+                        ui.max_rect().x_range(),
+                        response.response.rect.y_range(),
+                    );
+                    let color = ui.visuals().selection.bg_fill.gamma_multiply(0.5);
+                    ui.painter()
+                        .set(highlight, Shape::rect_filled(rect, 0.0, color));
+                    if scroll_to_target {
+                        response.response.scroll_to_me(Some(egui::Align::Center));
+                        scrolled = true;
+                    }
+                }
             }
         }
         PreviewContent::Image(texture_handle_arc) => {
             let max_size = ui.available_size() - Vec2::splat(10.0);
             if max_size.x <= 0.0 || max_size.y <= 0.0 {
                 ui.label("[Panel too small to display image]");
-                return;
+                return false;
             }
             let img_size = texture_handle_arc.size_vec2();
             let aspect_ratio = img_size.x / img_size.y;
@@ -512,4 +538,5 @@ pub(crate) fn render_preview_content(
             });
         }
     }
+    scrolled
 }
@@ -0,0 +1,580 @@
+//! Fits a report under a token budget (`ReportOptions::max_tokens`).
+//!
+//! The report is formatted and counted with `llm::token_counter`. While it's over budget,
+//! files are dropped or truncated according to the `FitStrategy` and the report is counted
+//! again, since the tree, statistics and the list of dropped files take up tokens too.
+//! What was left out is recorded in `ReportData::token_budget`, which every format lists.
+
+use super::{
+    DroppedFile, FileDetail, FitStrategy, ReportData, ReportOptions, TokenBudget, TruncatedFile,
+};
+use crate::llm::token_counter::count_tokens_local;
+use globset::{Glob, GlobMatcher};
+use std::{cmp::Reverse, collections::HashMap};
+
+/// Rounds of cutting before giving up. Each round frees an estimate of the overflow, so a
+/// further round is only needed when the estimate was off or the list of cut files grew.
+const MAX_FIT_ROUNDS: usize = 8;
+
+/// Rough number of tokens a file's heading and metadata take up in a report.
+const FILE_ENTRY_OVERHEAD: usize = 16;
+
+/// Rough number of tokens the elision marker and the listing of a truncated file add.
+const TRUNCATION_OVERHEAD: usize = 32;
+
+/// Cuts `data` down until the report formatted with `options` has at most `max_tokens`
+/// tokens, and records the outcome in `data.token_budget`. If even the report without
+/// any files is too long, it's left as small as the strategy can make it.
+pub fn fit_to_budget(
+    data: &mut ReportData,
+    options: &ReportOptions,
+    max_tokens: usize,
+    low_priority_dirs: &[String],
+) -> anyhow::Result<()> {
+    let low_priority = compile_dir_globs(low_priority_dirs);
+    let mut budget = TokenBudget {
+        max_tokens,
+        strategy: options.fit_strategy,
+        report_tokens: 0,
+        fits: false,
+        dropped: Vec::new(),
+        truncated: Vec::new(),
+    };
+    // Original contents of truncated files, so later rounds cut from the full text.
+    let mut originals = HashMap::new();
+    let mut round = 0;
+
+    // Tokens of everything but the files (header, trees, statistics, ...), which cutting
+    // files doesn't change.
+    let mut skeleton = data.clone();
+    skeleton.file_details = Vec::new();
+    skeleton.token_budget = Some(budget.clone());
+    let skeleton_tokens = count_tokens_local(&super::format_report_content(&skeleton, options)?);
+
+    loop {
+        data.token_budget = Some(budget.clone());
+        let report = super::format_report_content(data, options)?;
+        budget.report_tokens = count_tokens_local(&report);
+        budget.fits = budget.report_tokens <= max_tokens;
+        if budget.fits {
+            break;
+        }
+
+        // Formats escape and decorate file contents, so a file takes up more tokens in the
+        // report than on its own. Scale the overflow down to raw content tokens, counting
+        // only the files' share of the report; cutting too little only costs another round,
+        // cutting too much loses content for nothing.
+        let content_tokens: usize = data.file_details.iter().map(entry_tokens).sum();
+        let inflation = (budget.report_tokens.saturating_sub(skeleton_tokens) as f64
+            / content_tokens.max(1) as f64)
+            .max(1.0);
+        let overflow = ((budget.report_tokens - max_tokens) as f64 / inflation).ceil() as usize;
+        let freed = if round < MAX_FIT_ROUNDS {
+            match options.fit_strategy {
+                FitStrategy::DropLargest => {
+                    drop_largest(&mut data.file_details, overflow, &mut budget.dropped)
+                }
+                FitStrategy::DropLowPriorityDirs => drop_directories(
+                    &mut data.file_details,
+                    overflow,
+                    &low_priority,
+                    &mut budget.dropped,
+                ),
+                FitStrategy::TruncateFiles => truncate_files(
+                    &mut data.file_details,
+                    overflow,
+                    &mut originals,
+                    &mut budget.truncated,
+                ),
+            }
+        } else {
+            0
+        };
+        if freed == 0 {
+            log::warn!(
+                "Could not fit the report under {} tokens; it has {} tokens",
+                max_tokens,
+                budget.report_tokens
+            );
+            break;
+        }
+        round += 1;
+    }
+
+    log::info!(
+        "Report has {} of {} budgeted tokens ({} file(s) dropped, {} truncated)",
+        budget.report_tokens,
+        max_tokens,
+        budget.dropped.len(),
+        budget.truncated.len()
+    );
+    data.token_budget = Some(budget);
+    Ok(())
+}
+
+/// Drops the files with the most tokens until `overflow` tokens are freed. Returns the
+/// number of tokens freed.
+fn drop_largest(
+    files: &mut Vec<FileDetail>,
+    overflow: usize,
+    dropped: &mut Vec<DroppedFile>,
+) -> usize {
+    let mut by_size: Vec<(usize, usize)> = files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (index, entry_tokens(file)))
+        .collect();
+    by_size.sort_by_key(|&(_, tokens)| Reverse(tokens));
+
+    let mut freed = 0;
+    let mut drop = vec![false; files.len()];
+    for (index, tokens) in by_size {
+        if freed >= overflow {
+            break;
+        }
+        drop[index] = true;
+        freed += tokens;
+        dropped.push(DroppedFile {
+            relative_path: files[index].relative_path.clone(),
+            tokens,
+        });
+    }
+    let mut drop = drop.into_iter();
+    files.retain(|_| !drop.next().unwrap_or(false));
+    freed
+}
+
+/// Drops whole directories until `overflow` tokens are freed: those matching a
+/// `low_priority` glob first (in the globs' order), then the remaining ones deepest first.
+/// Files at the top level go last. Returns the number of tokens freed.
+fn drop_directories(
+    files: &mut Vec<FileDetail>,
+    overflow: usize,
+    low_priority: &[GlobMatcher],
+    dropped: &mut Vec<DroppedFile>,
+) -> usize {
+    let mut directories: HashMap<String, (usize, Vec<usize>)> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        let path = file.relative_path.replace('\\', "/");
+        let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir).to_string();
+        let entry = directories.entry(dir).or_default();
+        entry.0 += entry_tokens(file);
+        entry.1.push(index);
+    }
+    let mut order: Vec<(String, usize, Vec<usize>)> = directories
+        .into_iter()
+        .map(|(dir, (tokens, indices))| (dir, tokens, indices))
+        .collect();
+    order.sort_by_cached_key(|(dir, tokens, _)| {
+        (
+            directory_rank(dir, low_priority),
+            Reverse(*tokens),
+            dir.clone(),
+        )
+    });
+
+    let mut freed = 0;
+    let mut drop = vec![false; files.len()];
+    for (_, tokens, indices) in order {
+        if freed >= overflow {
+            break;
+        }
+        freed += tokens;
+        for index in indices {
+            drop[index] = true;
+            dropped.push(DroppedFile {
+                relative_path: files[index].relative_path.clone(),
+                tokens: entry_tokens(&files[index]),
+            });
+        }
+    }
+    let mut drop = drop.into_iter();
+    files.retain(|_| !drop.next().unwrap_or(false));
+    freed
+}
+
+/// Sort key putting the directories to drop first: low-priority matches by glob order,
+/// then the rest deepest first, then the top level.
+fn directory_rank(dir: &str, low_priority: &[GlobMatcher]) -> (u8, usize) {
+    if dir.is_empty() {
+        return (2, 0);
+    }
+    let ancestors: Vec<&str> = dir
+        .match_indices('/')
+        .map(|(end, _)| &dir[..end])
+        .chain([dir])
+        .collect();
+    match low_priority
+        .iter()
+        .position(|glob| ancestors.iter().any(|ancestor| glob.is_match(ancestor)))
+    {
+        Some(position) => (0, position),
+        None => (1, usize::MAX - ancestors.len()),
+    }
+}
+
+/// Truncates the longest file bodies to a common number of tokens, chosen so at least
+/// `overflow` tokens are freed. Returns the number of tokens freed.
+fn truncate_files(
+    files: &mut [FileDetail],
+    overflow: usize,
+    originals: &mut HashMap<usize, String>,
+    truncated: &mut Vec<TruncatedFile>,
+) -> usize {
+    let tokens: Vec<usize> = files
+        .iter()
+        .map(|file| file.content.as_deref().map_or(0, count_tokens_local))
+        .collect();
+    let previously_kept: Vec<Option<usize>> = files
+        .iter()
+        .map(|file| {
+            truncated
+                .iter()
+                .find(|entry| entry.relative_path == file.relative_path)
+                .map(|entry| entry.kept_tokens)
+        })
+        .collect();
+    // Tokens freed by cutting every file down to `cap`, less the marker and listing entry
+    // each newly truncated file adds.
+    let excess = |cap: usize| -> usize {
+        tokens
+            .iter()
+            .zip(&previously_kept)
+            .map(|(&t, kept)| {
+                let cut = t.saturating_sub(cap);
+                if kept.is_some() {
+                    cut
+                } else {
+                    cut.saturating_sub(TRUNCATION_OVERHEAD)
+                }
+            })
+            .sum()
+    };
+
+    // The largest cap whose excess still covers the overflow.
+    let (mut low, mut high) = (0, tokens.iter().copied().max().unwrap_or(0));
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if excess(mid) >= overflow {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let cap = low;
+
+    let mut freed = 0;
+    for (index, file) in files.iter_mut().enumerate() {
+        if tokens[index] <= cap {
+            continue;
+        }
+        let Ok(content) = &mut file.content else {
+            continue;
+        };
+        // Files are cut at whole lines, so a file cut before must lose at least one more
+        // line for the round to make progress.
+        let target = previously_kept[index].map_or(cap, |kept| cap.min(kept.saturating_sub(1)));
+        let original = originals.entry(index).or_insert_with(|| content.clone());
+        let (shortened, kept_tokens) = truncate_content(original, target);
+        let new_tokens = count_tokens_local(&shortened);
+        if new_tokens >= tokens[index] {
+            continue;
+        }
+        freed += tokens[index] - new_tokens;
+        *content = shortened;
+
+        match truncated
+            .iter_mut()
+            .find(|entry| entry.relative_path == file.relative_path)
+        {
+            Some(entry) => entry.kept_tokens = kept_tokens,
+            None => truncated.push(TruncatedFile {
+                relative_path: file.relative_path.clone(),
+                original_tokens: tokens[index],
+                kept_tokens,
+            }),
+        }
+    }
+    freed
+}
+
+/// Keeps the whole lines of `text` that fit in `cap` tokens and appends an elision marker.
+/// Returns the shortened text and the number of tokens kept.
+fn truncate_content(text: &str, cap: usize) -> (String, usize) {
+    let total_lines = text.lines().count();
+    let mut kept_tokens = 0;
+    let mut kept_lines = 0;
+    let mut end = 0;
+    for line in text.split_inclusive('\n') {
+        let tokens = count_tokens_local(line);
+        if kept_tokens + tokens > cap {
+            break;
+        }
+        kept_tokens += tokens;
+        kept_lines += 1;
+        end += line.len();
+    }
+
+    let mut shortened = text[..end].to_string();
+    if !shortened.is_empty() && !shortened.ends_with('\n') {
+        shortened.push('\n');
+    }
+    shortened.push_str(&format!(
+        "[... truncated to fit the token budget: {kept_lines} of {total_lines} lines kept ...]\n"
+    ));
+    (shortened, kept_tokens)
+}
+
+/// Approximate tokens a file adds to the report: its content (or error) plus its heading.
+fn entry_tokens(file: &FileDetail) -> usize {
+    let body = match &file.content {
+        Ok(content) => count_tokens_local(content),
+        Err(reason) => count_tokens_local(reason),
+    };
+    body + count_tokens_local(&file.relative_path) + FILE_ENTRY_OVERHEAD
+}
+
+/// Compiles the low-priority directory globs, skipping (and logging) invalid ones.
+fn compile_dir_globs(globs: &[String]) -> Vec<GlobMatcher> {
+    globs
+        .iter()
+        .map(|glob| glob.trim())
+        .filter(|glob| !glob.is_empty())
+        .filter_map(|glob| match Glob::new(glob) {
+            Ok(glob) => Some(glob.compile_matcher()),
+            Err(e) => {
+                log::warn!("Ignoring invalid low-priority directory glob '{glob}': {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{
+        format_report_content,
+        test_support::{file, lines, options, report_data},
+    };
+
+    fn paths(files: &[FileDetail]) -> Vec<&str> {
+        files.iter().map(|f| f.relative_path.as_str()).collect()
+    }
+
+    #[test]
+    fn drop_largest_drops_biggest_files_first() {
+        let mut files = vec![
+            file("small.rs", &lines("s", 5)),
+            file("large.rs", &lines("l", 200)),
+            file("medium.rs", &lines("m", 50)),
+        ];
+        let mut dropped = Vec::new();
+        let freed = drop_largest(&mut files, 10, &mut dropped);
+
+        assert_eq!(paths(&files), ["small.rs", "medium.rs"]);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].relative_path, "large.rs");
+        assert_eq!(freed, dropped[0].tokens);
+
+        // An overflow beyond the medium file takes the small one as well
+        let overflow = entry_tokens(&files[1]) + 1;
+        drop_largest(&mut files, overflow, &mut dropped);
+        assert!(files.is_empty());
+        assert_eq!(
+            dropped
+                .iter()
+                .map(|d| d.relative_path.as_str())
+                .collect::<Vec<_>>(),
+            ["large.rs", "medium.rs", "small.rs"]
+        );
+    }
+
+    #[test]
+    fn directory_rank_orders_low_priority_then_deepest_then_top_level() {
+        let globs = compile_dir_globs(&["**/tests".to_string(), "**/docs".to_string()]);
+        let tests = directory_rank("src/tests", &globs);
+        let nested_tests = directory_rank("src/tests/fixtures", &globs);
+        let docs = directory_rank("docs", &globs);
+        let deep = directory_rank("src/a/b", &globs);
+        let shallow = directory_rank("src", &globs);
+        let top = directory_rank("", &globs);
+
+        assert_eq!(
+            tests, nested_tests,
+            "subdirectories share their ancestor's rank"
+        );
+        assert!(tests < docs, "earlier globs are dropped first");
+        assert!(docs < deep);
+        assert!(deep < shallow);
+        assert!(shallow < top);
+    }
+
+    #[test]
+    fn drop_directories_drops_whole_low_priority_directories_first() {
+        let globs = compile_dir_globs(&["**/tests".to_string()]);
+        let mut files = vec![
+            file("main.rs", &lines("main", 100)),
+            file("src/lib.rs", &lines("lib", 100)),
+            file("src/deep/mod.rs", &lines("deep", 100)),
+            file("tests/a.rs", &lines("a", 5)),
+            file("tests/b.rs", &lines("b", 5)),
+        ];
+        let mut dropped = Vec::new();
+        drop_directories(&mut files, 1, &globs, &mut dropped);
+        assert_eq!(paths(&files), ["main.rs", "src/lib.rs", "src/deep/mod.rs"]);
+        assert_eq!(dropped.len(), 2, "the whole directory goes at once");
+
+        drop_directories(&mut files, 1, &globs, &mut dropped);
+        assert_eq!(paths(&files), ["main.rs", "src/lib.rs"]);
+
+        drop_directories(&mut files, 1, &globs, &mut dropped);
+        assert_eq!(paths(&files), ["main.rs"]);
+    }
+
+    #[test]
+    fn truncate_content_keeps_whole_lines_and_appends_marker() {
+        let text = lines("x", 10);
+        let per_line = count_tokens_local(text.lines().next().unwrap());
+        let (shortened, kept) = truncate_content(&text, per_line * 3 + 1);
+
+        let result: Vec<&str> = shortened.lines().collect();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[..3], text.lines().take(3).collect::<Vec<_>>()[..]);
+        assert_eq!(
+            result[3],
+            "[... truncated to fit the token budget: 3 of 10 lines kept ...]"
+        );
+        assert!(kept <= per_line * 3 + 1);
+    }
+
+    #[test]
+    fn truncate_content_marks_file_cut_to_nothing() {
+        let (shortened, kept) = truncate_content("one line without newline", 0);
+        assert_eq!(
+            shortened,
+            "[... truncated to fit the token budget: 0 of 1 lines kept ...]\n"
+        );
+        assert_eq!(kept, 0);
+    }
+
+    #[test]
+    fn truncate_files_cuts_longest_files_to_common_length() {
+        let short = lines("s", 5);
+        let mut files = vec![
+            file("short.rs", &short),
+            file("long_a.rs", &lines("a", 200)),
+            file("long_b.rs", &lines("b", 150)),
+        ];
+        let mut originals = HashMap::new();
+        let mut truncated = Vec::new();
+        let freed = truncate_files(&mut files, 500, &mut originals, &mut truncated);
+
+        assert!(freed >= 500);
+        assert_eq!(files[0].content.as_deref().unwrap(), short);
+        assert_eq!(
+            truncated
+                .iter()
+                .map(|t| t.relative_path.as_str())
+                .collect::<Vec<_>>(),
+            ["long_a.rs", "long_b.rs"]
+        );
+        let (a, b) = (&truncated[0], &truncated[1]);
+        assert!(a.kept_tokens < a.original_tokens && b.kept_tokens < b.original_tokens);
+        assert!(a.kept_tokens.abs_diff(b.kept_tokens) <= count_tokens_local(&lines("a", 1)));
+        assert!(files[1]
+            .content
+            .as_deref()
+            .unwrap()
+            .contains("[... truncated"));
+    }
+
+    #[test]
+    fn fits_despite_a_large_tree() {
+        // The tree alone takes most of the budget; the content still has to be cut to fit.
+        let tree = lines("tree_entry", 2_000);
+        let files = (0..20)
+            .map(|i| file(&format!("src/file_{i}.rs"), &lines(&format!("f{i}"), 100)))
+            .collect();
+        let mut data = report_data(files, &tree);
+        let tree_tokens = count_tokens_local(
+            &format_report_content(&report_data(Vec::new(), &tree), &options()).unwrap(),
+        );
+        let max_tokens = tree_tokens + 4_000;
+
+        for strategy in FitStrategy::ALL {
+            let mut data = data.clone();
+            let options = ReportOptions {
+                fit_strategy: strategy,
+                ..options()
+            };
+            fit_to_budget(&mut data, &options, max_tokens, &[]).unwrap();
+            let budget = data.token_budget.as_ref().unwrap();
+            assert!(budget.fits, "{strategy:?}: {}", budget.summary());
+            assert!(budget.report_tokens <= max_tokens);
+            let report = format_report_content(&data, &options).unwrap();
+            // The recorded count itself appears in the report, give or take a digit
+            assert!(count_tokens_local(&report).abs_diff(budget.report_tokens) <= 4);
+        }
+
+        // Under a generous budget nothing is cut
+        fit_to_budget(&mut data, &options(), usize::MAX / 2, &[]).unwrap();
+        let budget = data.token_budget.unwrap();
+        assert!(budget.fits && budget.dropped.is_empty() && budget.truncated.is_empty());
+    }
+
+    #[test]
+    fn reports_when_no_fit_is_possible() {
+        let tree = lines("tree_entry", 500);
+        let mut data = report_data(
+            vec![file("a.rs", &lines("a", 50)), file("b.rs", &lines("b", 50))],
+            &tree,
+        );
+        fit_to_budget(&mut data, &options(), 100, &[]).unwrap();
+
+        let budget = data.token_budget.unwrap();
+        assert!(!budget.fits);
+        assert!(budget.report_tokens > 100);
+        assert!(
+            data.file_details.is_empty(),
+            "every file was dropped trying"
+        );
+        assert_eq!(budget.dropped.len(), 2);
+        assert!(budget
+            .summary()
+            .contains("could not be cut under the budget of 100"));
+    }
+
+    #[test]
+    fn summary_describes_each_outcome() {
+        let mut budget = TokenBudget {
+            max_tokens: 1000,
+            strategy: FitStrategy::TruncateFiles,
+            report_tokens: 800,
+            fits: true,
+            dropped: Vec::new(),
+            truncated: Vec::new(),
+        };
+        assert_eq!(
+            budget.summary(),
+            "The report has 800 tokens and fits the budget of 1000 tokens."
+        );
+
+        budget.truncated.push(TruncatedFile {
+            relative_path: "a.rs".to_string(),
+            original_tokens: 500,
+            kept_tokens: 100,
+        });
+        assert_eq!(
+            budget.summary(),
+            "The report was cut to 800 tokens to fit the budget of 1000 tokens (truncate file bodies)."
+        );
+
+        budget.fits = false;
+        budget.report_tokens = 1200;
+        assert_eq!(
+            budget.summary(),
+            "The report has 1200 tokens and could not be cut under the budget of 1000 tokens (truncate file bodies)."
+        );
+    }
+}
@@ -1,12 +1,17 @@
 //! Core logic for report generation: data collection and formatting dispatch.
 
-use super::{FileDetail, ReportData, ReportFormat, ReportOptions};
+use super::{
+    DirectorySummary, FileDetail, FileMarkers, ReportData, ReportFormat, ReportOptions,
+    ReportTreeNode,
+};
 use crate::{
     app::CodebaseApp,
+    fs::{manifest, FileClass},
     model::{self, Check}, // Use model types
     preview,              // Use preview module for reading file content
 };
-use std::path::Path;
+use chrono::{DateTime, Local};
+use std::path::PathBuf;
 
 /// Prepends line numbers to a block of text.
 fn prepend_line_numbers(content: &str) -> String {
@@ -33,14 +38,20 @@ fn prepend_line_numbers(content: &str) -> String {
 ///
 /// This function orchestrates the process:
 /// 1. Collects necessary data from the `CodebaseApp` state (`collect_report_data`).
-/// 2. Formats the collected data into the desired output string (`format_report_content`).
+/// 2. Parses the dependency manifests, if included (`parse_report_dependencies`).
+/// 3. Fits the data under the token budget, if any (`fit_report_data`).
+/// 4. Formats the collected data into the desired output string (`format_report_content`).
 ///
 /// This function is suitable for simple, synchronous report generation. For background
-/// generation, `collect_report_data` and `format_report_content` should be used separately.
+/// generation, `collect_report_data` should be called on the UI thread and the remaining
+/// steps in the background.
+/// The report is returned in one piece; `generate_report_parts` honours `ReportOptions::split`.
 pub fn generate_report(app: &CodebaseApp, options: &ReportOptions) -> anyhow::Result<String> {
     log::info!("Starting report generation with options: {options:?}");
     // 1. Collect data
-    let data = collect_report_data(app, options)?;
+    let mut data = collect_report_data(app, options)?;
+    parse_report_dependencies(&mut data);
+    fit_report_data(&mut data, options, &app.config.low_priority_dirs)?;
     log::info!("Report data collected successfully.");
     // 2. Format data
     let report_string = format_report_content(&data, options)?;
@@ -48,11 +59,25 @@ pub fn generate_report(app: &CodebaseApp, options: &ReportOptions) -> anyhow::Re
     Ok(report_string)
 }
 
+/// Like `generate_report`, but returns the numbered parts of the report when
+/// `ReportOptions::split` is set (a single part otherwise).
+pub fn generate_report_parts(
+    app: &CodebaseApp,
+    options: &ReportOptions,
+) -> anyhow::Result<Vec<String>> {
+    let mut data = collect_report_data(app, options)?;
+    parse_report_dependencies(&mut data);
+    fit_report_data(&mut data, options, &app.config.low_priority_dirs)?;
+    format_report_parts(&data, options)
+}
+
 /// Collects all necessary data from the application state for report generation.
 ///
 /// This function gathers information like project name, paths, tree structures,
 /// selected file details (potentially reading file content), and scan statistics.
 /// It returns an owned `ReportData` struct, suitable for passing to background threads.
+/// Dependency manifests are only located; they are parsed separately by
+/// `parse_report_dependencies`, and the token budget is applied by `fit_report_data`.
 ///
 /// # Arguments
 /// * `app` - A reference to the main `CodebaseApp` state.
@@ -60,28 +85,37 @@ pub fn generate_report(app: &CodebaseApp, options: &ReportOptions) -> anyhow::Re
 ///
 /// # Returns
 /// * `Ok(ReportData)` containing the collected information.
-/// * `Err(anyhow::Error)` if essential data (like `root_path`) is missing.
+/// * `Err(anyhow::Error)` if essential data (like the root paths) is missing.
 pub fn collect_report_data(
     app: &CodebaseApp,
     options: &ReportOptions,
 ) -> anyhow::Result<ReportData> {
     log::debug!("Collecting report data...");
     // Ensure a directory is open
-    let root_path = app.root_path.as_ref().ok_or_else(|| {
-        anyhow::anyhow!("Cannot generate report: No directory is currently open.")
-    })?;
+    if app.roots.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Cannot generate report: No directory is currently open."
+        ));
+    }
 
-    let project_name = root_path
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_else(|| "Unknown Project".to_string());
-    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let project_name = app.project_name();
+    let generated_at = Local::now();
+    let timestamp = generated_at.format("%Y-%m-%d %H:%M:%S").to_string();
 
     // Generate tree structure strings
     log::debug!("Generating full tree structure string...");
     let full_tree_structure = generate_tree_string(app, false); // Include all nodes
     log::debug!("Generating selected tree structure string...");
     let selected_tree_structure = generate_tree_string(app, true); // Include only selected nodes/ancestors
+    let (full_tree, selected_tree) =
+        if options.format == ReportFormat::Json || options.template.is_some() {
+            (
+                generate_tree_nodes(app, false),
+                generate_tree_nodes(app, true),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
 
     // Collect details for selected files (including content if requested)
     let file_details = if options.include_contents {
@@ -98,41 +132,102 @@ pub fn collect_report_data(
     );
 
     // Include scan statistics if requested
-    let stats = if options.include_stats {
+    let (stats, directory_summary) = if options.include_stats {
         log::debug!("Including scan statistics.");
-        app.scan_stats.clone() // Clone the Option<ScanStats>
+        (app.scan_stats.clone(), collect_directory_summary(app))
     } else {
         log::debug!("Excluding scan statistics.");
-        None
+        (None, Vec::new())
     };
 
+    let markers = options.include_markers.then(|| collect_markers(app));
+    let pending_manifests = options
+        .include_dependencies
+        .then(|| collect_manifest_paths(app));
+
     // Construct the owned ReportData struct
     Ok(ReportData {
         project_name,
         timestamp,
-        root_path: root_path.display().to_string(),
+        generated_at,
+        root_paths: app
+            .roots
+            .iter()
+            .map(|root| root.display().to_string())
+            .collect(),
         full_tree_structure,
         selected_tree_structure,
+        full_tree,
+        selected_tree,
         file_details,
         stats,
+        directory_summary,
+        markers,
+        dependencies: None,
+        pending_manifests,
+        token_budget: None,
+        part: None,
     })
 }
 
+/// Parses the manifests located by `collect_report_data` into `ReportData::dependencies`.
+/// Lockfiles can be tens of megabytes, so this belongs in the background alongside
+/// `format_report_content`.
+pub fn parse_report_dependencies(data: &mut ReportData) {
+    if let Some(manifests) = data.pending_manifests.take() {
+        log::debug!("Parsing {} dependency manifest(s).", manifests.len());
+        data.dependencies = Some(manifest::parse_manifests(&manifests));
+    }
+}
+
+/// Cuts `data` down to `ReportOptions::max_tokens`, if set, by dropping or truncating files
+/// (see `report::budget`). This formats and counts the report several times, so it belongs
+/// in the background alongside `format_report_content`.
+pub fn fit_report_data(
+    data: &mut ReportData,
+    options: &ReportOptions,
+    low_priority_dirs: &[String],
+) -> anyhow::Result<()> {
+    if let Some(max_tokens) = options.max_tokens {
+        super::budget::fit_to_budget(data, options, max_tokens, low_priority_dirs)?;
+    }
+    Ok(())
+}
+
 /// Formats the collected `ReportData` into the final report string based on `ReportOptions`.
 ///
-/// This function dispatches to the appropriate formatting function (Markdown, HTML, Text).
+/// This function dispatches to the appropriate formatting function (Markdown, HTML, Text),
+/// or renders the user template from `ReportOptions::template` if one is set.
 /// It's designed to be called after `collect_report_data`, potentially in a background thread.
 pub fn format_report_content(data: &ReportData, options: &ReportOptions) -> anyhow::Result<String> {
+    if let Some(template_path) = &options.template {
+        log::debug!("Rendering report template '{}'", template_path.display());
+        return super::template::render_template(data, options, template_path);
+    }
     log::debug!("Formatting report content as {:?}", options.format);
     let report_content = match options.format {
         ReportFormat::Markdown => super::markdown::format_markdown(data),
         ReportFormat::Html => super::html::format_html(data),
         ReportFormat::Text => super::text::format_text(data),
+        ReportFormat::Json => super::json::format_json(data, options.include_contents)?,
+        ReportFormat::Llm => super::llm::format_llm(data, options.include_contents),
     };
     // Basic validation or post-processing could happen here if needed
     Ok(report_content)
 }
 
+/// Formats the collected `ReportData` as numbered parts under `ReportOptions::split`, or as
+/// a single part when the report isn't split.
+pub fn format_report_parts(
+    data: &ReportData,
+    options: &ReportOptions,
+) -> anyhow::Result<Vec<String>> {
+    match options.split {
+        Some(limit) => super::split::split_report(data, options, limit),
+        None => Ok(vec![format_report_content(data, options)?]),
+    }
+}
+
 // --- Helper Functions ---
 
 /// Collects file details for previewing report content in the UI.
@@ -152,46 +247,68 @@ pub fn preview_file_details(app: &CodebaseApp, options: &ReportOptions) -> Vec<F
 ///   and their ancestors. If false, includes all nodes.
 fn generate_tree_string(app: &CodebaseApp, selected_only: bool) -> String {
     let mut output = String::new();
-    if let Some(root_id) = app.root_id {
-        if let Some(root_node) = app.nodes.get(root_id) {
-            // Check if the root itself should be included based on selection status
-            if !selected_only || root_node.state != Check::Unchecked {
-                output.push_str(root_node.name()); // Add root node name
-                output.push('\n');
-
-                // Filter children based on selection status if needed
-                let children_to_render: Vec<_> = root_node
-                    .children
-                    .iter()
-                    .filter(|&&child_id| {
-                        !selected_only
-                            || app
-                                .nodes
-                                .get(child_id)
-                                .is_some_and(|n| n.state != Check::Unchecked)
-                    })
-                    .cloned()
-                    .collect();
-
-                let num_children = children_to_render.len();
-                for (i, &child_id) in children_to_render.iter().enumerate() {
-                    // Start recursion for children
-                    build_tree_string_recursive(
-                        app,
-                        &mut output,
-                        child_id,
-                        "",                    // Initial prefix
-                        i == num_children - 1, // is_last flag
-                        selected_only,
-                    );
-                }
-            } else if selected_only {
-                // If root is unchecked and we only want selected, the tree is empty
-                return "(Root directory not selected)".to_string();
+    if app.root_ids.is_empty() {
+        return "(No directory loaded)".to_string();
+    }
+    // Filter nodes based on selection status if needed
+    let visible = |ids: &[model::FileId]| -> Vec<model::FileId> {
+        ids.iter()
+            .filter(|&&id| {
+                !selected_only
+                    || app
+                        .nodes
+                        .get(id)
+                        .is_some_and(|n| n.state != Check::Unchecked)
+            })
+            .cloned()
+            .collect()
+    };
+
+    if app.root_ids.len() > 1 {
+        // Workspace: the project name heads the tree and each root is a top-level entry.
+        let roots_to_render = visible(&app.root_ids);
+        if roots_to_render.is_empty() && selected_only {
+            return "(No root directory selected)".to_string();
+        }
+        output.push_str(&app.project_name());
+        output.push('\n');
+        let num_roots = roots_to_render.len();
+        for (i, &root_id) in roots_to_render.iter().enumerate() {
+            build_tree_string_recursive(
+                app,
+                &mut output,
+                root_id,
+                "",
+                i == num_roots - 1,
+                selected_only,
+            );
+        }
+        return output.trim_end().to_string();
+    }
+
+    if let Some(root_node) = app.nodes.get(app.root_ids[0]) {
+        // Check if the root itself should be included based on selection status
+        if !selected_only || root_node.state != Check::Unchecked {
+            output.push_str(root_node.name()); // Add root node name
+            output.push('\n');
+
+            let children_to_render = visible(&root_node.children);
+            let num_children = children_to_render.len();
+            for (i, &child_id) in children_to_render.iter().enumerate() {
+                // Start recursion for children
+                build_tree_string_recursive(
+                    app,
+                    &mut output,
+                    child_id,
+                    "",                    // Initial prefix
+                    i == num_children - 1, // is_last flag
+                    selected_only,
+                );
             }
+        } else if selected_only {
+            // If root is unchecked and we only want selected, the tree is empty
+            return "(Root directory not selected)".to_string();
         }
-    } else {
-        return "(No directory loaded)".to_string();
     }
     output.trim_end().to_string() // Trim trailing newline
 }
@@ -215,7 +332,10 @@ fn build_tree_string_recursive(
 
     // Determine the connector based on whether it's the last sibling
     let connector = if is_last { "└── " } else { "├── " };
-    let line = format!("{}{}{}\n", prefix, connector, node.name());
+    let line = match link_target_string(node) {
+        Some(target) => format!("{}{}{} -> {}\n", prefix, connector, node.name(), target),
+        None => format!("{}{}{}\n", prefix, connector, node.name()),
+    };
     output.push_str(&line);
 
     // If it's a directory, recurse into its children
@@ -251,6 +371,104 @@ fn build_tree_string_recursive(
     }
 }
 
+/// Builds the tree as nested nodes, one top-level entry per root. With `selected_only`,
+/// only Checked or Partial nodes are included, as in `generate_tree_string`.
+fn generate_tree_nodes(app: &CodebaseApp, selected_only: bool) -> Vec<ReportTreeNode> {
+    app.root_ids
+        .iter()
+        .filter_map(|&root_id| build_tree_node(app, root_id, selected_only))
+        .collect()
+}
+
+fn build_tree_node(
+    app: &CodebaseApp,
+    node_id: model::FileId,
+    selected_only: bool,
+) -> Option<ReportTreeNode> {
+    let node = app.nodes.get(node_id)?;
+    if selected_only && node.state == Check::Unchecked {
+        return None;
+    }
+    let path = app.relative_path(node.path()).display().to_string();
+    Some(ReportTreeNode {
+        name: node.name().to_string(),
+        path: if path.is_empty() {
+            ".".to_string()
+        } else {
+            path
+        },
+        is_dir: node.is_dir(),
+        size: (!node.is_dir()).then_some(node.info.size),
+        link_target: link_target_string(node),
+        children: node
+            .children
+            .iter()
+            .filter_map(|&child_id| build_tree_node(app, child_id, selected_only))
+            .collect(),
+    })
+}
+
+/// Maximum number of rows in the directory summary table.
+const MAX_SUMMARY_DIRS: usize = 50;
+
+/// Collects the rolled-up metrics of each root and the directories up to two levels below it,
+/// in tree order. Only the first level is listed if the second would make the table too long.
+fn collect_directory_summary(app: &CodebaseApp) -> Vec<DirectorySummary> {
+    let mut dirs = Vec::new();
+    for &root_id in &app.root_ids {
+        collect_summary_dirs(app, root_id, 0, 2, &mut dirs);
+    }
+    if dirs.len() > MAX_SUMMARY_DIRS {
+        dirs.retain(|&(_, depth)| depth <= 1);
+    }
+    dirs.truncate(MAX_SUMMARY_DIRS);
+
+    dirs.into_iter()
+        .map(|(id, depth)| {
+            let node = &app.nodes[id];
+            let path = if depth == 0 && app.root_ids.len() == 1 {
+                ".".to_string()
+            } else {
+                format!("{}/", app.relative_path(node.path()).display())
+            };
+            DirectorySummary {
+                path,
+                metrics: node.metrics,
+            }
+        })
+        .collect()
+}
+
+/// Pushes `node_id` and its subdirectories down to `max_depth`, with their depths.
+fn collect_summary_dirs(
+    app: &CodebaseApp,
+    node_id: model::FileId,
+    depth: usize,
+    max_depth: usize,
+    dirs: &mut Vec<(model::FileId, usize)>,
+) {
+    let Some(node) = app.nodes.get(node_id) else {
+        return;
+    };
+    if !node.is_dir() {
+        return;
+    }
+    dirs.push((node_id, depth));
+    if depth < max_depth {
+        for &child_id in &node.children {
+            collect_summary_dirs(app, child_id, depth + 1, max_depth, dirs);
+        }
+    }
+}
+
+/// Returns the symlink target of a node as a display string, if it was reached through a link.
+fn link_target_string(node: &model::FileNode) -> Option<String> {
+    node.info
+        .symlink_target
+        .as_ref()
+        .map(|target| target.display().to_string())
+}
+
 /// Collects content and metadata for all *selected* files.
 /// Reads file content based on `max_size` limit.
 fn collect_file_details(
@@ -259,27 +477,18 @@ fn collect_file_details(
     options: &ReportOptions,
 ) -> Vec<FileDetail> {
     let mut details = Vec::new();
-    let root_path = app.root_path.as_deref().unwrap_or_else(|| Path::new(""));
 
     for (node_id, node) in app.nodes.iter().enumerate() {
         // Only include files that are explicitly checked
         if !node.is_dir() && node.state == Check::Checked {
             let path = node.path();
-            let relative_path = path
-                .strip_prefix(root_path)
-                .unwrap_or(path) // Fallback to absolute if strip fails
-                .display()
-                .to_string();
-
-            // Format modification time
-            let modified_str = node
-                .info
-                .modified
-                .map(|st| {
-                    let datetime: chrono::DateTime<chrono::Local> = st.into();
-                    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-                })
-                .unwrap_or_else(|| "N/A".to_string());
+            let relative_path = app.relative_path(path).display().to_string();
+
+            let modified_at: Option<DateTime<Local>> = node.info.modified.map(Into::into);
+            let modified_str = modified_at.map_or_else(
+                || "N/A".to_string(),
+                |datetime| datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+            );
 
             // Attempt to read content, handling binary files and size limits
             let content_result = if node.info.is_binary {
@@ -287,13 +496,13 @@ fn collect_file_details(
             } else if let Some(cached) = app.content_cache.get(&node_id) {
                 let size_limit = max_size >= 0;
                 if size_limit && node.info.size > max_size as u64 {
-                    preview::read_file_content(path, max_size)
+                    preview::read_file_content(path, max_size, node.info.encoding)
                 } else {
                     Ok(cached.as_str().to_string())
                 }
             } else {
                 // Use the preview module's reader which handles size limits and encoding
-                preview::read_file_content(path, max_size)
+                preview::read_file_content(path, max_size, node.info.encoding)
             };
 
             // Conditionally prepend line numbers
@@ -306,7 +515,10 @@ fn collect_file_details(
             details.push(FileDetail {
                 relative_path,
                 size: node.info.human_size.clone(),
+                size_bytes: node.info.size,
                 modified: modified_str,
+                modified_at,
+                link_target: link_target_string(node),
                 content: final_content,
             });
         }
@@ -316,36 +528,73 @@ fn collect_file_details(
     details
 }
 
+/// Collects the comment markers of the selected files, sorted by path.
+fn collect_markers(app: &CodebaseApp) -> Vec<FileMarkers> {
+    let mut files: Vec<FileMarkers> = app
+        .nodes
+        .iter()
+        .filter(|node| {
+            !node.is_dir() && node.state == Check::Checked && !node.info.markers().is_empty()
+        })
+        .map(|node| FileMarkers {
+            relative_path: app.relative_path(node.path()).display().to_string(),
+            markers: node.info.markers().to_vec(),
+        })
+        .collect();
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    files
+}
+
+/// Locates the dependency manifests found by the scan, selected or not, for
+/// `parse_report_dependencies`. Manifests inside archives and vendored directories are
+/// skipped.
+fn collect_manifest_paths(app: &CodebaseApp) -> Vec<(PathBuf, String, PathBuf)> {
+    app.nodes
+        .iter()
+        .filter(|node| {
+            !node.is_dir()
+                && node.info.archive.is_none()
+                && node.info.class != Some(FileClass::Vendored)
+                && manifest::manifest_ecosystem(node.path()).is_some()
+        })
+        .filter_map(|node| {
+            let root = app
+                .roots
+                .iter()
+                .find(|root| node.path().starts_with(root))?;
+            Some((
+                node.path().to_path_buf(),
+                app.relative_path(node.path()).display().to_string(),
+                root.clone(),
+            ))
+        })
+        .collect()
+}
+
 /// Collects metadata (but not content) for all *selected* files.
 /// Used when `ReportOptions::include_contents` is false.
 fn collect_file_details_metadata_only(app: &CodebaseApp) -> Vec<FileDetail> {
     let mut details = Vec::new();
-    let root_path = app.root_path.as_deref().unwrap_or_else(|| Path::new(""));
 
     for node in &app.nodes {
         // Only include files that are explicitly checked
         if !node.is_dir() && node.state == Check::Checked {
             let path = node.path();
-            let relative_path = path
-                .strip_prefix(root_path)
-                .unwrap_or(path) // Fallback to absolute if strip fails
-                .display()
-                .to_string();
-
-            // Format modification time
-            let modified_str = node
-                .info
-                .modified
-                .map(|st| {
-                    let datetime: chrono::DateTime<chrono::Local> = st.into();
-                    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-                })
-                .unwrap_or_else(|| "N/A".to_string());
+            let relative_path = app.relative_path(path).display().to_string();
+
+            let modified_at: Option<DateTime<Local>> = node.info.modified.map(Into::into);
+            let modified_str = modified_at.map_or_else(
+                || "N/A".to_string(),
+                |datetime| datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+            );
 
             details.push(FileDetail {
                 relative_path,
                 size: node.info.human_size.clone(),
+                size_bytes: node.info.size,
                 modified: modified_str,
+                modified_at,
+                link_target: link_target_string(node),
                 // Indicate content was explicitly excluded
                 content: Err("[File content excluded by report options]".to_string()),
             });
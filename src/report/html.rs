@@ -46,10 +46,27 @@ pub fn format_html(data: &ReportData) -> String {
         "  <p>Generated on: {}</p>\n",
         html_escape(&data.timestamp)
     ));
-    html.push_str(&format!(
-        "  <p>Root Path: <code>{}</code></p>\n",
-        html_escape(&data.root_path)
-    ));
+    if let [root_path] = data.root_paths.as_slice() {
+        html.push_str(&format!(
+            "  <p>Root Path: <code>{}</code></p>\n",
+            html_escape(root_path)
+        ));
+    } else {
+        html.push_str("  <p>Root Paths:</p>\n  <ul>\n");
+        for root_path in &data.root_paths {
+            html.push_str(&format!(
+                "    <li><code>{}</code></li>\n",
+                html_escape(root_path)
+            ));
+        }
+        html.push_str("  </ul>\n");
+    }
+    if let Some(part) = &data.part {
+        html.push_str(&format!(
+            "  <p class=\"part-note\"><strong>{}</strong></p>\n",
+            html_escape(&part.note())
+        ));
+    }
     html.push_str("</header>\n");
     html.push_str("<hr>\n");
 
@@ -69,6 +86,14 @@ pub fn format_html(data: &ReportData) -> String {
             "    <li><strong>Total Size:</strong> {}</li>\n",
             html_escape(&stats.total_size_human())
         ));
+        for (class, counts) in &stats.file_classes {
+            html.push_str(&format!(
+                "    <li><strong>{} Files:</strong> {} ({}, not counted below)</li>\n",
+                class.label(),
+                counts.files,
+                html_escape(&counts.size_human())
+            ));
+        }
         html.push_str("  </ul>\n");
 
         // MODIFIED: Language Statistics Table
@@ -77,12 +102,12 @@ pub fn format_html(data: &ReportData) -> String {
             html.push_str("  <table>\n");
             html.push_str("    <tr><th>Language</th><th>Files</th><th>Lines</th><th>Code</th><th>Comments</th><th>Blanks</th></tr>\n");
             let mut sorted_langs: Vec<_> = stats.language_stats.iter().collect();
-            sorted_langs.sort_by(|a, b| b.1.code.cmp(&a.1.code));
-            for (lang_type, lang) in sorted_langs {
+            sorted_langs.sort_by_key(|(_, lang)| std::cmp::Reverse(lang.code));
+            for (language, lang) in sorted_langs {
                 html.push_str(&format!(
                     "    <tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
-                    lang_type,
-                    lang.reports.len(),
+                    language,
+                    lang.files.len(),
                     lang.lines(),
                     lang.code,
                     lang.comments,
@@ -121,13 +146,66 @@ pub fn format_html(data: &ReportData) -> String {
             html.push_str("  </ul>\n");
         }
 
+        if !stats.licenses.is_empty() || stats.files_missing_license > 0 {
+            html.push_str("  <h3>License Summary:</h3>\n");
+            if !stats.licenses.is_empty() {
+                html.push_str("  <table>\n");
+                html.push_str("    <tr><th>License</th><th>Files</th><th>With Copyright</th><th>License Files</th></tr>\n");
+                for (license, counts) in stats.licenses_by_files() {
+                    let license_files = counts
+                        .license_files
+                        .iter()
+                        .map(|path| format!("<code>{}</code>", html_escape(path)))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    html.push_str(&format!(
+                        "    <tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                        html_escape(license),
+                        counts.files,
+                        counts.with_copyright,
+                        license_files
+                    ));
+                }
+                html.push_str("  </table>\n");
+            }
+            html.push_str(&format!(
+                "  <p><strong>Source files without a license header:</strong> {}</p>\n",
+                stats.files_missing_license
+            ));
+        }
+
+        if !data.directory_summary.is_empty() {
+            html.push_str("  <h3>Directory Summary:</h3>\n");
+            html.push_str("  <table>\n");
+            html.push_str("    <tr><th>Directory</th><th>Files</th><th>Size</th><th>Lines</th><th>Code</th><th>Comments</th><th>Blanks</th><th>Tokens</th></tr>\n");
+            for dir in &data.directory_summary {
+                let m = &dir.metrics;
+                html.push_str(&format!(
+                    "    <tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    html_escape(&dir.path),
+                    m.files,
+                    humansize::format_size(m.size, humansize::DECIMAL),
+                    m.lines(),
+                    m.code,
+                    m.comments,
+                    m.blanks,
+                    m.tokens.map_or("-".to_string(), |t| t.to_string())
+                ));
+            }
+            html.push_str("  </table>\n");
+        }
+
         if !stats.errors.is_empty() {
             html.push_str("  <h3>Scan Errors:</h3>\n  <pre><code class=\"error-text\">");
             for error in stats.errors.iter().take(10) {
-                html.push_str(&format!("- {}\n", html_escape(error)));
+                html.push_str(&format!("- {}\n", html_escape(&error.to_string())));
             }
-            if stats.errors.len() > 10 {
-                html.push_str("- ... and more errors truncated\n");
+            let listed = stats.errors.len().min(10);
+            if stats.error_count() > listed {
+                html.push_str(&format!(
+                    "- ... and {} more\n",
+                    stats.error_count() - listed
+                ));
             }
             html.push_str("</code></pre>\n");
         }
@@ -155,6 +233,99 @@ pub fn format_html(data: &ReportData) -> String {
     html.push_str("</section>\n");
     html.push_str("<hr>\n");
 
+    if let Some(files) = &data.markers {
+        html.push_str("<section id=\"markers\">\n");
+        html.push_str("  <h2>Open Markers</h2>\n");
+        if files.is_empty() {
+            html.push_str("  <p><em>(No markers in the selected files)</em></p>\n");
+        }
+        for file in files {
+            html.push_str(&format!(
+                "  <h3><code>{}</code></h3>\n  <ul>\n",
+                html_escape(&file.relative_path)
+            ));
+            for marker in &file.markers {
+                html.push_str(&format!(
+                    "    <li>Line {}: <strong>{}</strong> {}</li>\n",
+                    marker.line,
+                    html_escape(&marker.keyword),
+                    html_escape(&marker.text)
+                ));
+            }
+            html.push_str("  </ul>\n");
+        }
+        html.push_str("</section>\n");
+        html.push_str("<hr>\n");
+    }
+
+    if let Some(manifests) = &data.dependencies {
+        html.push_str("<section id=\"dependencies\">\n");
+        html.push_str("  <h2>Dependencies</h2>\n");
+        if manifests.is_empty() {
+            html.push_str("  <p><em>(No dependency manifests found)</em></p>\n");
+        }
+        for manifest in manifests {
+            html.push_str(&format!(
+                "  <h3><code>{}</code> ({})</h3>\n",
+                html_escape(&manifest.path),
+                html_escape(&manifest.description())
+            ));
+            if manifest.dependencies.is_empty() {
+                html.push_str("  <p><em>(No dependencies declared)</em></p>\n");
+                continue;
+            }
+            html.push_str("  <table>\n");
+            html.push_str(
+                "    <tr><th>Dependency</th><th>Kind</th><th>Requirement</th><th>Locked</th></tr>\n",
+            );
+            for dependency in &manifest.dependencies {
+                html.push_str(&format!(
+                    "    <tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    html_escape(&dependency.name),
+                    dependency.kind.label(),
+                    html_escape(dependency.requirement.as_deref().unwrap_or("-")),
+                    html_escape(dependency.locked.as_deref().unwrap_or("-"))
+                ));
+            }
+            html.push_str("  </table>\n");
+        }
+        html.push_str("</section>\n");
+        html.push_str("<hr>\n");
+    }
+
+    if let Some(budget) = &data.token_budget {
+        html.push_str("<section id=\"token-budget\">\n");
+        html.push_str("  <h2>Token Budget</h2>\n");
+        html.push_str(&format!("  <p>{}</p>\n", html_escape(&budget.summary())));
+        if !budget.dropped.is_empty() {
+            html.push_str("  <table>\n");
+            html.push_str("    <tr><th>Dropped File</th><th>Tokens</th></tr>\n");
+            for file in &budget.dropped {
+                html.push_str(&format!(
+                    "    <tr><td><code>{}</code></td><td>{}</td></tr>\n",
+                    html_escape(&file.relative_path),
+                    file.tokens
+                ));
+            }
+            html.push_str("  </table>\n");
+        }
+        if !budget.truncated.is_empty() {
+            html.push_str("  <table>\n");
+            html.push_str("    <tr><th>Truncated File</th><th>Tokens</th><th>Kept</th></tr>\n");
+            for file in &budget.truncated {
+                html.push_str(&format!(
+                    "    <tr><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+                    html_escape(&file.relative_path),
+                    file.original_tokens,
+                    file.kept_tokens
+                ));
+            }
+            html.push_str("  </table>\n");
+        }
+        html.push_str("</section>\n");
+        html.push_str("<hr>\n");
+    }
+
     html.push_str("<section id=\"file-contents\">\n");
     html.push_str("  <h2>Selected File Contents</h2>\n");
     if !data.file_details.is_empty() {
@@ -164,10 +335,16 @@ pub fn format_html(data: &ReportData) -> String {
                 "    <h3><code>{}</code></h3>\n",
                 html_escape(&detail.relative_path)
             ));
+            let link_meta = detail
+                .link_target
+                .as_ref()
+                .map(|target| format!(" | Symlink to: <code>{}</code>", html_escape(target)))
+                .unwrap_or_default();
             html.push_str(&format!(
-                "    <div class=\"file-meta\">Size: {} | Modified: {}</div>\n",
+                "    <div class=\"file-meta\">Size: {} | Modified: {}{}</div>\n",
                 html_escape(&detail.size),
-                html_escape(&detail.modified)
+                html_escape(&detail.modified),
+                link_meta
             ));
             html.push_str("  </div>\n");
             html.push_str("  <pre><code>");
@@ -190,6 +367,13 @@ pub fn format_html(data: &ReportData) -> String {
     }
     html.push_str("</section>\n");
 
+    if let Some(part) = &data.part {
+        html.push_str(&format!(
+            "<p class=\"part-note\"><em>{}</em></p>\n",
+            html_escape(&part.continuation())
+        ));
+    }
+
     html.push_str("\n</body>\n</html>\n");
     html
 }
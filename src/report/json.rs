@@ -0,0 +1,857 @@
+//! Formats report data as JSON for programmatic consumers.
+//!
+//! The output is a single object whose layout is versioned by `schema_version`. Fields are
+//! only ever added within a version; renaming or removing one, or changing its meaning,
+//! bumps `JSON_SCHEMA_VERSION`. Every section is copied into structs of this module, and
+//! enum values are spelled out here, rather than serialized from the application's types,
+//! so the schema doesn't follow internal renames; `tests::pins_schema_fields` guards the
+//! field names. Report templates get the same structs for the sections listed as such in
+//! `super::template`.
+//!
+//! Schema version 1:
+//!
+//! - `schema`: always `"codebase_viewer.report"`.
+//! - `schema_version`: `1`.
+//! - `generator`: `{ name, version }` of the application that wrote the report.
+//! - `project`: `{ name, generated_at, roots }`. `generated_at` is an RFC 3339 timestamp
+//!   with UTC offset and `roots` are absolute paths.
+//! - `full_tree`, `selected_tree`: arrays of tree nodes `{ name, path, is_dir, size?,
+//!   link_target?, children? }`. `path` is relative to the project (prefixed with the root
+//!   name in a workspace); `size` is given for files only.
+//! - `files`: the selected files, sorted by path, as `{ path, size, modified,
+//!   link_target?, content?, error? }`. `size` is in bytes and `modified` is an RFC 3339
+//!   timestamp with UTC offset, or `null` if unknown. `content` is absent when contents are
+//!   excluded, and starts every line with a right-aligned line number and ` | ` when line
+//!   numbers are included. `error` explains why a file's content couldn't be included.
+//! - `stats`: `{ total_files, total_dirs, total_size_bytes, file_types, largest_files,
+//!   file_classes, errors, error_counts, language_stats, licenses, files_missing_license }`,
+//!   or `null` when excluded.
+//!   - `file_types`: file counts keyed by extension (`.rs`, `(no extension)`).
+//!   - `largest_files`: `[{ path, size }]`, largest first.
+//!   - `file_classes`: `{ files, size_bytes }` keyed by `generated`, `vendored` or
+//!     `documentation`.
+//!   - `errors`: `[{ kind, path?, message, os_error? }]`, where `kind` is a snake_case
+//!     error kind such as `permission_denied`. At most 100 errors are listed.
+//!   - `error_counts`: the number of errors keyed by kind, including those not listed.
+//!   - `language_stats`: `{ code, comments, blanks, files: [{ path, code, comments,
+//!     blanks }] }` keyed by language name.
+//!   - `licenses`: `{ files, with_copyright, license_files }` keyed by SPDX expression.
+//! - `directory_summary`: `[{ path, metrics: { files, size, code, comments, blanks,
+//!   tokens } }]`, empty when statistics are excluded. `tokens` is `null` until counted.
+//! - `markers`: `[{ path, markers: [{ keyword, line, text }] }]`, or `null`.
+//! - `dependencies`: parsed manifests `[{ path, ecosystem, package?, version?,
+//!   dependencies: [{ name, kind, requirement?, locked? }] }]`, or `null`. `ecosystem` is
+//!   one of `cargo`, `npm`, `python` and `go`; `kind` one of `normal`, `dev`, `build`,
+//!   `peer`, `optional`, `indirect` and `workspace` (a shared version declared in
+//!   `[workspace.dependencies]`, not a dependency of the package).
+//! - `token_budget`: `{ max_tokens, strategy, report_tokens, fits, dropped: [{ path,
+//!   tokens }], truncated: [{ path, original_tokens, kept_tokens }] }` when the report was
+//!   fitted to a token budget, or `null`. `strategy` is one of `drop_largest`,
+//!   `drop_low_priority_dirs` and `truncate_files`.
+//! - `part`: `{ number, total }` when the report was split into parts, or `null`. Every
+//!   part is a complete document; `stats`, `directory_summary`, `markers`, `dependencies`
+//!   and `token_budget` are only filled in the first.
+
+use super::{DirectorySummary, FileMarkers, FitStrategy, ReportData, ReportTreeNode, TokenBudget};
+use crate::{
+    fs::{
+        manifest::{Dependency, DependencyKind, Ecosystem, Manifest},
+        FileClass, ScanErrorKind, ScanStats,
+    },
+    model::NodeMetrics,
+};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Version of the JSON report layout described in the module documentation.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Identifies the document type, so consumers can reject other JSON files.
+const SCHEMA_NAME: &str = "codebase_viewer.report";
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema: &'static str,
+    schema_version: u32,
+    generator: JsonGenerator,
+    project: JsonProject<'a>,
+    full_tree: Vec<JsonTreeNode<'a>>,
+    selected_tree: Vec<JsonTreeNode<'a>>,
+    files: Vec<JsonFile<'a>>,
+    stats: Option<JsonStats<'a>>,
+    directory_summary: Vec<JsonDirectory<'a>>,
+    markers: Option<Vec<JsonFileMarkers<'a>>>,
+    dependencies: Option<Vec<JsonManifest<'a>>>,
+    token_budget: Option<JsonTokenBudget<'a>>,
+    part: Option<JsonPart>,
+}
+
+#[derive(Serialize)]
+struct JsonGenerator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct JsonProject<'a> {
+    name: &'a str,
+    generated_at: String,
+    roots: &'a [String],
+}
+
+#[derive(Serialize)]
+struct JsonTreeNode<'a> {
+    name: &'a str,
+    path: &'a str,
+    is_dir: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_target: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<JsonTreeNode<'a>>,
+}
+
+impl<'a> JsonTreeNode<'a> {
+    fn new(node: &'a ReportTreeNode) -> Self {
+        Self {
+            name: &node.name,
+            path: &node.path,
+            is_dir: node.is_dir,
+            size: node.size,
+            link_target: node.link_target.as_deref(),
+            children: node.children.iter().map(JsonTreeNode::new).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    path: &'a str,
+    size: u64,
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_target: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+pub(super) struct JsonStats<'a> {
+    total_files: usize,
+    total_dirs: usize,
+    total_size_bytes: u64,
+    file_types: &'a BTreeMap<String, usize>,
+    largest_files: Vec<JsonSizedFile<'a>>,
+    file_classes: BTreeMap<&'static str, JsonClassStats>,
+    errors: Vec<JsonScanError<'a>>,
+    error_counts: BTreeMap<&'static str, usize>,
+    language_stats: BTreeMap<&'a str, JsonLanguage<'a>>,
+    licenses: BTreeMap<&'a str, JsonLicense<'a>>,
+    files_missing_license: usize,
+}
+
+impl<'a> JsonStats<'a> {
+    pub(super) fn new(stats: &'a ScanStats) -> Self {
+        Self {
+            total_files: stats.total_files,
+            total_dirs: stats.total_dirs,
+            total_size_bytes: stats.total_size_bytes,
+            file_types: &stats.file_types,
+            largest_files: stats
+                .largest_files
+                .iter()
+                .map(|file| JsonSizedFile {
+                    path: &file.path,
+                    size: file.size,
+                })
+                .collect(),
+            file_classes: stats
+                .file_classes
+                .iter()
+                .map(|(class, class_stats)| {
+                    let json = JsonClassStats {
+                        files: class_stats.files,
+                        size_bytes: class_stats.size_bytes,
+                    };
+                    (class_name(*class), json)
+                })
+                .collect(),
+            errors: stats
+                .errors
+                .iter()
+                .map(|error| JsonScanError {
+                    kind: error_kind_name(error.kind),
+                    path: error.path.as_ref().map(|p| p.display().to_string()),
+                    message: &error.message,
+                    os_error: error.os_error,
+                })
+                .collect(),
+            error_counts: stats
+                .error_counts
+                .iter()
+                .map(|(kind, count)| (error_kind_name(*kind), *count))
+                .collect(),
+            language_stats: stats
+                .language_stats
+                .iter()
+                .map(|(name, language)| {
+                    let files = language
+                        .files
+                        .iter()
+                        .map(|file| JsonLineCounts {
+                            path: &file.path,
+                            code: file.code,
+                            comments: file.comments,
+                            blanks: file.blanks,
+                        })
+                        .collect();
+                    let json = JsonLanguage {
+                        code: language.code,
+                        comments: language.comments,
+                        blanks: language.blanks,
+                        files,
+                    };
+                    (name.as_str(), json)
+                })
+                .collect(),
+            licenses: stats
+                .licenses
+                .iter()
+                .map(|(expression, license)| {
+                    let json = JsonLicense {
+                        files: license.files,
+                        with_copyright: license.with_copyright,
+                        license_files: &license.license_files,
+                    };
+                    (expression.as_str(), json)
+                })
+                .collect(),
+            files_missing_license: stats.files_missing_license,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSizedFile<'a> {
+    path: &'a str,
+    size: u64,
+}
+
+#[derive(Serialize)]
+struct JsonClassStats {
+    files: usize,
+    size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct JsonScanError<'a> {
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    os_error: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct JsonLanguage<'a> {
+    code: usize,
+    comments: usize,
+    blanks: usize,
+    files: Vec<JsonLineCounts<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonLineCounts<'a> {
+    path: &'a str,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+}
+
+#[derive(Serialize)]
+struct JsonLicense<'a> {
+    files: usize,
+    with_copyright: usize,
+    license_files: &'a [String],
+}
+
+#[derive(Serialize)]
+pub(super) struct JsonDirectory<'a> {
+    path: &'a str,
+    metrics: JsonMetrics,
+}
+
+impl<'a> JsonDirectory<'a> {
+    pub(super) fn new(summary: &'a DirectorySummary) -> Self {
+        let NodeMetrics {
+            files,
+            size,
+            code,
+            comments,
+            blanks,
+            tokens,
+        } = summary.metrics;
+        Self {
+            path: &summary.path,
+            metrics: JsonMetrics {
+                files,
+                size,
+                code,
+                comments,
+                blanks,
+                tokens,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonMetrics {
+    files: usize,
+    size: u64,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+    tokens: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub(super) struct JsonFileMarkers<'a> {
+    path: &'a str,
+    markers: Vec<JsonMarker<'a>>,
+}
+
+impl<'a> JsonFileMarkers<'a> {
+    pub(super) fn new(file: &'a FileMarkers) -> Self {
+        Self {
+            path: &file.relative_path,
+            markers: file
+                .markers
+                .iter()
+                .map(|marker| JsonMarker {
+                    keyword: &marker.keyword,
+                    line: marker.line,
+                    text: &marker.text,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonMarker<'a> {
+    keyword: &'a str,
+    line: usize,
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+pub(super) struct JsonManifest<'a> {
+    path: &'a str,
+    ecosystem: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    dependencies: Vec<JsonDependency<'a>>,
+}
+
+impl<'a> JsonManifest<'a> {
+    pub(super) fn new(manifest: &'a Manifest) -> Self {
+        Self {
+            path: &manifest.path,
+            ecosystem: ecosystem_name(manifest.ecosystem),
+            package: manifest.package.as_deref(),
+            version: manifest.version.as_deref(),
+            dependencies: manifest
+                .dependencies
+                .iter()
+                .map(JsonDependency::new)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDependency<'a> {
+    name: &'a str,
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requirement: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locked: Option<&'a str>,
+}
+
+impl<'a> JsonDependency<'a> {
+    fn new(dependency: &'a Dependency) -> Self {
+        Self {
+            name: &dependency.name,
+            kind: dependency_kind_name(dependency.kind),
+            requirement: dependency.requirement.as_deref(),
+            locked: dependency.locked.as_deref(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct JsonTokenBudget<'a> {
+    max_tokens: usize,
+    strategy: &'static str,
+    report_tokens: usize,
+    fits: bool,
+    dropped: Vec<JsonDroppedFile<'a>>,
+    truncated: Vec<JsonTruncatedFile<'a>>,
+}
+
+impl<'a> JsonTokenBudget<'a> {
+    pub(super) fn new(budget: &'a TokenBudget) -> Self {
+        Self {
+            max_tokens: budget.max_tokens,
+            strategy: strategy_name(budget.strategy),
+            report_tokens: budget.report_tokens,
+            fits: budget.fits,
+            dropped: budget
+                .dropped
+                .iter()
+                .map(|file| JsonDroppedFile {
+                    path: &file.relative_path,
+                    tokens: file.tokens,
+                })
+                .collect(),
+            truncated: budget
+                .truncated
+                .iter()
+                .map(|file| JsonTruncatedFile {
+                    path: &file.relative_path,
+                    original_tokens: file.original_tokens,
+                    kept_tokens: file.kept_tokens,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDroppedFile<'a> {
+    path: &'a str,
+    tokens: usize,
+}
+
+#[derive(Serialize)]
+struct JsonTruncatedFile<'a> {
+    path: &'a str,
+    original_tokens: usize,
+    kept_tokens: usize,
+}
+
+#[derive(Serialize)]
+struct JsonPart {
+    number: usize,
+    total: usize,
+}
+
+fn class_name(class: FileClass) -> &'static str {
+    match class {
+        FileClass::Generated => "generated",
+        FileClass::Vendored => "vendored",
+        FileClass::Documentation => "documentation",
+    }
+}
+
+fn error_kind_name(kind: ScanErrorKind) -> &'static str {
+    match kind {
+        ScanErrorKind::PermissionDenied => "permission_denied",
+        ScanErrorKind::NotFound => "not_found",
+        ScanErrorKind::Metadata => "metadata",
+        ScanErrorKind::Walk => "walk",
+        ScanErrorKind::SymlinkLoop => "symlink_loop",
+        ScanErrorKind::IgnoreFile => "ignore_file",
+        ScanErrorKind::Archive => "archive",
+        ScanErrorKind::Decode => "decode",
+        ScanErrorKind::Watch => "watch",
+        ScanErrorKind::Config => "config",
+        ScanErrorKind::Other => "other",
+    }
+}
+
+fn ecosystem_name(ecosystem: Ecosystem) -> &'static str {
+    match ecosystem {
+        Ecosystem::Cargo => "cargo",
+        Ecosystem::Npm => "npm",
+        Ecosystem::Python => "python",
+        Ecosystem::Go => "go",
+    }
+}
+
+fn dependency_kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Normal => "normal",
+        DependencyKind::Dev => "dev",
+        DependencyKind::Build => "build",
+        DependencyKind::Peer => "peer",
+        DependencyKind::Optional => "optional",
+        DependencyKind::Indirect => "indirect",
+        DependencyKind::Workspace => "workspace",
+    }
+}
+
+fn strategy_name(strategy: FitStrategy) -> &'static str {
+    match strategy {
+        FitStrategy::DropLargest => "drop_largest",
+        FitStrategy::DropLowPriorityDirs => "drop_low_priority_dirs",
+        FitStrategy::TruncateFiles => "truncate_files",
+    }
+}
+
+/// Generates a pretty-printed JSON report from the collected `ReportData`.
+pub fn format_json(data: &ReportData, include_contents: bool) -> anyhow::Result<String> {
+    let files = data
+        .file_details
+        .iter()
+        .map(|detail| {
+            // Without contents every file carries an "excluded" placeholder, not an error.
+            let (content, error) = match &detail.content {
+                _ if !include_contents => (None, None),
+                Ok(content) => (Some(content.as_str()), None),
+                Err(e) => (None, Some(e.as_str())),
+            };
+            JsonFile {
+                path: &detail.relative_path,
+                size: detail.size_bytes,
+                modified: detail.modified_at.map(|datetime| datetime.to_rfc3339()),
+                link_target: detail.link_target.as_deref(),
+                content,
+                error,
+            }
+        })
+        .collect();
+    let report = JsonReport {
+        schema: SCHEMA_NAME,
+        schema_version: JSON_SCHEMA_VERSION,
+        generator: JsonGenerator {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        },
+        project: JsonProject {
+            name: &data.project_name,
+            generated_at: data.generated_at.to_rfc3339(),
+            roots: &data.root_paths,
+        },
+        full_tree: data.full_tree.iter().map(JsonTreeNode::new).collect(),
+        selected_tree: data.selected_tree.iter().map(JsonTreeNode::new).collect(),
+        files,
+        stats: data.stats.as_ref().map(JsonStats::new),
+        directory_summary: data
+            .directory_summary
+            .iter()
+            .map(JsonDirectory::new)
+            .collect(),
+        markers: data
+            .markers
+            .as_ref()
+            .map(|markers| markers.iter().map(JsonFileMarkers::new).collect()),
+        dependencies: data
+            .dependencies
+            .as_ref()
+            .map(|manifests| manifests.iter().map(JsonManifest::new).collect()),
+        token_budget: data.token_budget.as_ref().map(JsonTokenBudget::new),
+        part: data.part.map(|part| JsonPart {
+            number: part.number,
+            total: part.total,
+        }),
+    };
+    serde_json::to_string_pretty(&report).context("Failed to serialize the JSON report")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::{
+            stats::{ClassStats, FileLocStats, FileStatInfo, LanguageStats, LicenseStats},
+            CommentMarker, ScanError,
+        },
+        report::{
+            test_support::{file, report_data},
+            DroppedFile, ReportPart, TruncatedFile,
+        },
+    };
+    use chrono::{Local, TimeZone};
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .expect("an object")
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    fn sample_stats() -> ScanStats {
+        let mut stats = ScanStats {
+            total_files: 1,
+            total_dirs: 1,
+            total_size_bytes: 10,
+            files_missing_license: 1,
+            ..Default::default()
+        };
+        stats.file_types.insert(".rs".to_string(), 1);
+        stats.largest_files.push(FileStatInfo {
+            path: "src/main.rs".to_string(),
+            size: 10,
+            human_size: "10 B".to_string(),
+        });
+        stats.file_classes.insert(
+            FileClass::Generated,
+            ClassStats {
+                files: 1,
+                size_bytes: 5,
+            },
+        );
+        let mut error = ScanError::new(
+            ScanErrorKind::PermissionDenied,
+            Some(PathBuf::from("/projects/demo/secret")),
+            "denied",
+        );
+        error.os_error = Some(13);
+        stats.add_error(error);
+        stats.language_stats.insert(
+            "Rust".to_string(),
+            LanguageStats {
+                code: 1,
+                comments: 0,
+                blanks: 0,
+                files: vec![FileLocStats {
+                    path: "src/main.rs".to_string(),
+                    code: 1,
+                    comments: 0,
+                    blanks: 0,
+                }],
+            },
+        );
+        stats.licenses.insert(
+            "MIT".to_string(),
+            LicenseStats {
+                files: 1,
+                with_copyright: 1,
+                license_files: vec!["LICENSE".to_string()],
+            },
+        );
+        stats
+    }
+
+    #[test]
+    fn pins_schema_fields() {
+        let mut detail = file("src/main.rs", "fn main() {}\n");
+        detail.modified_at = Some(Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap());
+        let mut data = report_data(vec![detail], "");
+        data.full_tree = vec![ReportTreeNode {
+            name: "src".to_string(),
+            path: "src".to_string(),
+            is_dir: true,
+            size: None,
+            link_target: None,
+            children: vec![ReportTreeNode {
+                name: "main.rs".to_string(),
+                path: "src/main.rs".to_string(),
+                is_dir: false,
+                size: Some(10),
+                link_target: Some("../main.rs".to_string()),
+                children: Vec::new(),
+            }],
+        }];
+        data.stats = Some(sample_stats());
+        data.directory_summary = vec![DirectorySummary {
+            path: ".".to_string(),
+            metrics: NodeMetrics {
+                files: 1,
+                size: 10,
+                code: 1,
+                ..Default::default()
+            },
+        }];
+        data.markers = Some(vec![FileMarkers {
+            relative_path: "src/main.rs".to_string(),
+            markers: vec![CommentMarker {
+                keyword: "TODO".to_string(),
+                line: 1,
+                text: "ship it".to_string(),
+            }],
+        }]);
+        data.dependencies = Some(vec![Manifest {
+            path: "Cargo.toml".to_string(),
+            ecosystem: Ecosystem::Cargo,
+            package: Some("demo".to_string()),
+            version: Some("0.1.0".to_string()),
+            dependencies: vec![Dependency {
+                name: "serde".to_string(),
+                kind: DependencyKind::Normal,
+                requirement: Some("1".to_string()),
+                locked: Some("1.0.200".to_string()),
+            }],
+        }]);
+        data.token_budget = Some(TokenBudget {
+            max_tokens: 100,
+            strategy: FitStrategy::DropLargest,
+            report_tokens: 90,
+            fits: true,
+            dropped: vec![DroppedFile {
+                relative_path: "big.rs".to_string(),
+                tokens: 500,
+            }],
+            truncated: vec![TruncatedFile {
+                relative_path: "long.rs".to_string(),
+                original_tokens: 50,
+                kept_tokens: 20,
+            }],
+        });
+        data.part = Some(ReportPart {
+            number: 1,
+            total: 2,
+        });
+
+        let json: Value = serde_json::from_str(&format_json(&data, true).unwrap()).unwrap();
+        assert_eq!(
+            keys(&json),
+            [
+                "dependencies",
+                "directory_summary",
+                "files",
+                "full_tree",
+                "generator",
+                "markers",
+                "part",
+                "project",
+                "schema",
+                "schema_version",
+                "selected_tree",
+                "stats",
+                "token_budget"
+            ]
+        );
+        assert_eq!(json["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(
+            keys(&json["full_tree"][0]),
+            ["children", "is_dir", "name", "path"]
+        );
+        assert_eq!(
+            keys(&json["full_tree"][0]["children"][0]),
+            ["is_dir", "link_target", "name", "path", "size"]
+        );
+        assert_eq!(
+            keys(&json["files"][0]),
+            ["content", "modified", "path", "size"]
+        );
+        let stats = &json["stats"];
+        assert_eq!(
+            keys(stats),
+            [
+                "error_counts",
+                "errors",
+                "file_classes",
+                "file_types",
+                "files_missing_license",
+                "language_stats",
+                "largest_files",
+                "licenses",
+                "total_dirs",
+                "total_files",
+                "total_size_bytes"
+            ]
+        );
+        assert_eq!(keys(&stats["largest_files"][0]), ["path", "size"]);
+        assert_eq!(
+            keys(&stats["file_classes"]["generated"]),
+            ["files", "size_bytes"]
+        );
+        assert_eq!(
+            keys(&stats["errors"][0]),
+            ["kind", "message", "os_error", "path"]
+        );
+        assert_eq!(stats["errors"][0]["kind"], "permission_denied");
+        assert_eq!(stats["error_counts"]["permission_denied"], 1);
+        assert_eq!(
+            keys(&stats["language_stats"]["Rust"]),
+            ["blanks", "code", "comments", "files"]
+        );
+        assert_eq!(
+            keys(&stats["language_stats"]["Rust"]["files"][0]),
+            ["blanks", "code", "comments", "path"]
+        );
+        assert_eq!(
+            keys(&stats["licenses"]["MIT"]),
+            ["files", "license_files", "with_copyright"]
+        );
+        assert_eq!(keys(&json["directory_summary"][0]), ["metrics", "path"]);
+        assert_eq!(
+            keys(&json["directory_summary"][0]["metrics"]),
+            ["blanks", "code", "comments", "files", "size", "tokens"]
+        );
+        assert_eq!(keys(&json["markers"][0]), ["markers", "path"]);
+        assert_eq!(
+            keys(&json["markers"][0]["markers"][0]),
+            ["keyword", "line", "text"]
+        );
+        assert_eq!(
+            keys(&json["dependencies"][0]),
+            ["dependencies", "ecosystem", "package", "path", "version"]
+        );
+        assert_eq!(json["dependencies"][0]["ecosystem"], "cargo");
+        assert_eq!(
+            keys(&json["dependencies"][0]["dependencies"][0]),
+            ["kind", "locked", "name", "requirement"]
+        );
+        assert_eq!(json["dependencies"][0]["dependencies"][0]["kind"], "normal");
+        let budget = &json["token_budget"];
+        assert_eq!(
+            keys(budget),
+            [
+                "dropped",
+                "fits",
+                "max_tokens",
+                "report_tokens",
+                "strategy",
+                "truncated"
+            ]
+        );
+        assert_eq!(budget["strategy"], "drop_largest");
+        assert_eq!(keys(&budget["dropped"][0]), ["path", "tokens"]);
+        assert_eq!(
+            keys(&budget["truncated"][0]),
+            ["kept_tokens", "original_tokens", "path"]
+        );
+        assert_eq!(keys(&json["part"]), ["number", "total"]);
+    }
+
+    #[test]
+    fn emits_rfc3339_timestamps() {
+        let mut dated = file("a.rs", "");
+        let modified_at = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        dated.modified_at = Some(modified_at);
+        let undated = file("b.rs", "");
+        let data = report_data(vec![dated, undated], "");
+
+        let json: Value = serde_json::from_str(&format_json(&data, false).unwrap()).unwrap();
+        let generated_at = json["project"]["generated_at"].as_str().unwrap();
+        assert_eq!(
+            chrono::DateTime::parse_from_rfc3339(generated_at).unwrap(),
+            data.generated_at
+        );
+        let modified = json["files"][0]["modified"].as_str().unwrap();
+        assert_eq!(
+            chrono::DateTime::parse_from_rfc3339(modified).unwrap(),
+            modified_at
+        );
+        assert!(json["files"][1]["modified"].is_null());
+        assert!(json["files"][0].get("content").is_none());
+    }
+}
@@ -0,0 +1,348 @@
+//! Formats the collected `ReportData` as an XML-tagged document for LLM prompts.
+//!
+//! Models find the boundaries of `<file path="...">` elements far more reliably than
+//! Markdown fences, which break as soon as a file itself contains triple backticks. The
+//! output is a well-formed XML document: attributes and short texts are escaped, while
+//! trees and file contents go into CDATA sections so code needs no escaping.
+
+use super::ReportData;
+
+/// Explains the document layout to the model reading it. Whether `<file>` elements hold
+/// complete contents depends on `include_contents` and on how the report was fitted to its
+/// token budget, so the wording follows both.
+fn instructions(data: &ReportData, include_contents: bool) -> String {
+    let mut text = String::from(
+        "This document describes a software project. <tree> shows the full directory \
+         structure and <selected_tree> the part that was selected for this context. ",
+    );
+    if !include_contents {
+        text.push_str(
+            "Each <file> element in <files> names one selected file by its path attribute; \
+             file contents are not included. Base your answers on the structure and metadata, \
+             and say so when a question needs the contents of a file.",
+        );
+        return text;
+    }
+    let (dropped, truncated) = data.token_budget.as_ref().map_or((false, false), |budget| {
+        (!budget.dropped.is_empty(), !budget.truncated.is_empty())
+    });
+    if truncated {
+        text.push_str(
+            "Each <file> element in <files> holds the content of one selected file, identified \
+             by its path attribute. Files listed as <truncated> in <token_budget> were shortened \
+             to fit the token budget and end with a \"[... truncated to fit the token budget \
+             ...]\" note, so only their first lines are present",
+        );
+    } else {
+        text.push_str(
+            "Each <file> element in <files> holds the complete content of one selected file, \
+             identified by its path attribute",
+        );
+    }
+    text.push_str("; files whose content could not be included carry an error attribute instead. ");
+    if dropped {
+        text.push_str(
+            "Some selected files were left out to fit the token budget and appear only as \
+             <dropped> elements in <token_budget>. ",
+        );
+    }
+    text.push_str("Base your answers on these files and cite their paths.");
+    text
+}
+
+/// Generates the XML-tagged LLM context from the provided `ReportData`. Without contents,
+/// files are listed as empty `<file/>` elements.
+pub fn format_llm(data: &ReportData, include_contents: bool) -> String {
+    let mut xml = String::with_capacity(estimate_llm_capacity(data));
+    xml.push_str(&format!(
+        "<codebase project=\"{}\" generated=\"{}\"",
+        escape_xml(&data.project_name),
+        escape_xml(&data.timestamp)
+    ));
+    if let Some(part) = &data.part {
+        xml.push_str(&format!(
+            " part=\"{}\" parts=\"{}\"",
+            part.number, part.total
+        ));
+    }
+    xml.push_str(">\n");
+    if let Some(part) = &data.part {
+        xml.push_str(&format!(
+            "<part_note>{}</part_note>\n",
+            escape_xml(&part.note())
+        ));
+    }
+    xml.push_str(&format!(
+        "<instructions>{}</instructions>\n",
+        escape_xml(&instructions(data, include_contents))
+    ));
+
+    xml.push_str("<roots>\n");
+    for root_path in &data.root_paths {
+        xml.push_str(&format!("  <root>{}</root>\n", escape_xml(root_path)));
+    }
+    xml.push_str("</roots>\n");
+
+    if let Some(stats) = &data.stats {
+        xml.push_str(&format!(
+            "<statistics files=\"{}\" dirs=\"{}\" size=\"{}\">\n",
+            stats.total_files,
+            stats.total_dirs,
+            escape_xml(&stats.total_size_human())
+        ));
+        let mut sorted_langs: Vec<_> = stats.language_stats.iter().collect();
+        sorted_langs.sort_by_key(|(_, lang)| std::cmp::Reverse(lang.code));
+        for (language, lang) in sorted_langs {
+            xml.push_str(&format!(
+                "  <language name=\"{}\" files=\"{}\" code=\"{}\" comments=\"{}\" blanks=\"{}\"/>\n",
+                escape_xml(language),
+                lang.files.len(),
+                lang.code,
+                lang.comments,
+                lang.blanks
+            ));
+        }
+        for (license, counts) in stats.licenses_by_files() {
+            xml.push_str(&format!(
+                "  <license id=\"{}\" files=\"{}\"/>\n",
+                escape_xml(license),
+                counts.files
+            ));
+        }
+        xml.push_str("</statistics>\n");
+    }
+
+    xml.push_str("<tree>");
+    push_cdata(&mut xml, &data.full_tree_structure);
+    xml.push_str("</tree>\n");
+    xml.push_str("<selected_tree>");
+    push_cdata(
+        &mut xml,
+        if data.selected_tree_structure.trim().is_empty() {
+            "(No items selected)"
+        } else {
+            &data.selected_tree_structure
+        },
+    );
+    xml.push_str("</selected_tree>\n");
+
+    if let Some(manifests) = &data.dependencies {
+        xml.push_str("<dependencies>\n");
+        for manifest in manifests {
+            xml.push_str(&format!(
+                "  <manifest path=\"{}\" ecosystem=\"{}\"",
+                escape_xml(&manifest.path),
+                manifest.ecosystem.label()
+            ));
+            if let Some(package) = &manifest.package {
+                xml.push_str(&format!(" package=\"{}\"", escape_xml(package)));
+            }
+            if let Some(version) = &manifest.version {
+                xml.push_str(&format!(" version=\"{}\"", escape_xml(version)));
+            }
+            xml.push_str(">\n");
+            for dependency in &manifest.dependencies {
+                xml.push_str(&format!(
+                    "    <dependency name=\"{}\" kind=\"{}\"",
+                    escape_xml(&dependency.name),
+                    dependency.kind.label()
+                ));
+                if let Some(requirement) = &dependency.requirement {
+                    xml.push_str(&format!(" requirement=\"{}\"", escape_xml(requirement)));
+                }
+                if let Some(locked) = &dependency.locked {
+                    xml.push_str(&format!(" locked=\"{}\"", escape_xml(locked)));
+                }
+                xml.push_str("/>\n");
+            }
+            xml.push_str("  </manifest>\n");
+        }
+        xml.push_str("</dependencies>\n");
+    }
+
+    if let Some(files) = &data.markers {
+        xml.push_str("<markers>\n");
+        for file in files {
+            for marker in &file.markers {
+                xml.push_str(&format!(
+                    "  <marker path=\"{}\" line=\"{}\" keyword=\"{}\">{}</marker>\n",
+                    escape_xml(&file.relative_path),
+                    marker.line,
+                    escape_xml(&marker.keyword),
+                    escape_xml(&marker.text)
+                ));
+            }
+        }
+        xml.push_str("</markers>\n");
+    }
+
+    if let Some(budget) = &data.token_budget {
+        xml.push_str(&format!(
+            "<token_budget max_tokens=\"{}\" report_tokens=\"{}\" fits=\"{}\">\n",
+            budget.max_tokens, budget.report_tokens, budget.fits
+        ));
+        xml.push_str(&format!(
+            "  <summary>{}</summary>\n",
+            escape_xml(&budget.summary())
+        ));
+        for file in &budget.dropped {
+            xml.push_str(&format!(
+                "  <dropped path=\"{}\" tokens=\"{}\"/>\n",
+                escape_xml(&file.relative_path),
+                file.tokens
+            ));
+        }
+        for file in &budget.truncated {
+            xml.push_str(&format!(
+                "  <truncated path=\"{}\" tokens=\"{}\" kept=\"{}\"/>\n",
+                escape_xml(&file.relative_path),
+                file.original_tokens,
+                file.kept_tokens
+            ));
+        }
+        xml.push_str("</token_budget>\n");
+    }
+
+    xml.push_str("<files>\n");
+    for detail in &data.file_details {
+        xml.push_str(&format!(
+            "<file path=\"{}\" size=\"{}\" modified=\"{}\"",
+            escape_xml(&detail.relative_path),
+            escape_xml(&detail.size),
+            escape_xml(&detail.modified)
+        ));
+        if let Some(target) = &detail.link_target {
+            xml.push_str(&format!(" link_target=\"{}\"", escape_xml(target)));
+        }
+        match &detail.content {
+            _ if !include_contents => xml.push_str("/>\n"),
+            Ok(content) => {
+                xml.push_str(">\n");
+                push_cdata(&mut xml, content);
+                xml.push_str("\n</file>\n");
+            }
+            Err(e) => xml.push_str(&format!(" error=\"{}\"/>\n", escape_xml(e))),
+        }
+    }
+    xml.push_str("</files>\n");
+    if let Some(part) = &data.part {
+        xml.push_str(&format!(
+            "<continuation>{}</continuation>\n",
+            escape_xml(&part.continuation())
+        ));
+    }
+    xml.push_str("</codebase>\n");
+    xml
+}
+
+/// Appends `text` as a CDATA section. `]]>` inside the text is split across two sections,
+/// and characters XML doesn't allow are replaced.
+fn push_cdata(xml: &mut String, text: &str) {
+    xml.push_str("<![CDATA[");
+    xml.push_str(&replace_invalid_chars(text).replace("]]>", "]]]]><![CDATA[>"));
+    xml.push_str("]]>");
+}
+
+/// Escapes text for use in XML attributes and elements.
+fn escape_xml(input: &str) -> String {
+    replace_invalid_chars(input)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Replaces control characters XML 1.0 doesn't allow, even in CDATA, with U+FFFD.
+fn replace_invalid_chars(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| match c {
+            '\t' | '\n' | '\r' => c,
+            c if c < ' ' => char::REPLACEMENT_CHARACTER,
+            '\u{FFFE}' | '\u{FFFF}' => char::REPLACEMENT_CHARACTER,
+            c => c,
+        })
+        .collect()
+}
+
+fn estimate_llm_capacity(data: &ReportData) -> usize {
+    let base_size = 2048;
+    let tree_size = data.full_tree_structure.len() + data.selected_tree_structure.len();
+    let files_size: usize = data
+        .file_details
+        .iter()
+        .map(|d| d.relative_path.len() + 128 + d.content.as_ref().map_or(0, |c| c.len()))
+        .sum();
+    base_size + tree_size + files_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{
+        test_support::{file, report_data},
+        DroppedFile, FitStrategy, TokenBudget, TruncatedFile,
+    };
+
+    fn budget(dropped: bool, truncated: bool) -> TokenBudget {
+        TokenBudget {
+            max_tokens: 100,
+            strategy: FitStrategy::DropLargest,
+            report_tokens: 90,
+            fits: true,
+            dropped: dropped
+                .then(|| DroppedFile {
+                    relative_path: "big.rs".to_string(),
+                    tokens: 500,
+                })
+                .into_iter()
+                .collect(),
+            truncated: truncated
+                .then(|| TruncatedFile {
+                    relative_path: "long.rs".to_string(),
+                    original_tokens: 50,
+                    kept_tokens: 20,
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn instructions_promise_complete_contents_only_when_true() {
+        let mut data = report_data(vec![file("a.rs", "fn a() {}\n")], "a.rs\n");
+        let complete = instructions(&data, true);
+        assert!(
+            complete.contains("holds the complete content"),
+            "{complete}"
+        );
+        assert!(!complete.contains("<dropped>"), "{complete}");
+
+        let excluded = instructions(&data, false);
+        assert!(
+            excluded.contains("file contents are not included"),
+            "{excluded}"
+        );
+        assert!(!excluded.contains("complete content"), "{excluded}");
+
+        data.token_budget = Some(budget(true, false));
+        let dropped = instructions(&data, true);
+        assert!(dropped.contains("holds the complete content"), "{dropped}");
+        assert!(dropped.contains("<dropped> elements"), "{dropped}");
+
+        data.token_budget = Some(budget(false, true));
+        let truncated = instructions(&data, true);
+        assert!(!truncated.contains("complete content"), "{truncated}");
+        assert!(truncated.contains("were shortened"), "{truncated}");
+    }
+
+    #[test]
+    fn excluded_contents_are_empty_file_elements() {
+        let data = report_data(vec![file("a.rs", "fn a() {}\n")], "a.rs\n");
+        let xml = format_llm(&data, false);
+        assert!(xml.contains("<file path=\"a.rs\""), "{xml}");
+        assert!(!xml.contains("fn a()"), "{xml}");
+        assert!(xml.contains("file contents are not included"), "{xml}");
+    }
+}
@@ -54,7 +54,18 @@ pub fn format_markdown(data: &ReportData) -> String {
 
     md.push_str(&format!("# {} - Codebase Overview\n\n", data.project_name));
     md.push_str(&format!("Generated on: {}\n", data.timestamp));
-    md.push_str(&format!("Root Path: `{}`\n\n", data.root_path));
+    if let [root_path] = data.root_paths.as_slice() {
+        md.push_str(&format!("Root Path: `{root_path}`\n\n"));
+    } else {
+        md.push_str("Root Paths:\n\n");
+        for root_path in &data.root_paths {
+            md.push_str(&format!("- `{root_path}`\n"));
+        }
+        md.push('\n');
+    }
+    if let Some(part) = &data.part {
+        md.push_str(&format!("> {}\n\n", part.note()));
+    }
     md.push_str("---\n\n");
 
     if let Some(stats) = &data.stats {
@@ -62,6 +73,14 @@ pub fn format_markdown(data: &ReportData) -> String {
         md.push_str(&format!("- **Total Files:** {}\n", stats.total_files));
         md.push_str(&format!("- **Total Dirs:** {}\n", stats.total_dirs));
         md.push_str(&format!("- **Total Size:** {}\n", stats.total_size_human()));
+        for (class, counts) in &stats.file_classes {
+            md.push_str(&format!(
+                "- **{} Files:** {} ({}, not counted below)\n",
+                class.label(),
+                counts.files,
+                counts.size_human()
+            ));
+        }
 
         // MODIFIED: Add Language Statistics Table
         if !stats.language_stats.is_empty() {
@@ -69,12 +88,12 @@ pub fn format_markdown(data: &ReportData) -> String {
             md.push_str("| Language | Files | Lines | Code | Comments | Blanks |\n");
             md.push_str("|---|---:|---:|---:|---:|---:|\n"); // Align columns
             let mut sorted_langs: Vec<_> = stats.language_stats.iter().collect();
-            sorted_langs.sort_by(|a, b| b.1.code.cmp(&a.1.code));
-            for (lang_type, lang) in sorted_langs {
+            sorted_langs.sort_by_key(|(_, lang)| std::cmp::Reverse(lang.code));
+            for (language, lang) in sorted_langs {
                 md.push_str(&format!(
                     "| {} | {} | {} | {} | {} | {} |\n",
-                    lang_type,
-                    lang.reports.len(),
+                    language,
+                    lang.files.len(),
                     lang.lines(),
                     lang.code,
                     lang.comments,
@@ -105,14 +124,65 @@ pub fn format_markdown(data: &ReportData) -> String {
             }
         }
 
+        if !stats.licenses.is_empty() || stats.files_missing_license > 0 {
+            md.push_str("\n**License Summary:**\n\n");
+            if !stats.licenses.is_empty() {
+                md.push_str("| License | Files | With Copyright | License Files |\n");
+                md.push_str("|---|---:|---:|---|\n");
+                for (license, counts) in stats.licenses_by_files() {
+                    let license_files = counts
+                        .license_files
+                        .iter()
+                        .map(|path| format!("`{path}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    md.push_str(&format!(
+                        "| {} | {} | {} | {} |\n",
+                        license, counts.files, counts.with_copyright, license_files
+                    ));
+                }
+                md.push('\n');
+            }
+            md.push_str(&format!(
+                "- **Source Files Without a License Header:** {}\n",
+                stats.files_missing_license
+            ));
+        }
+
+        if !data.directory_summary.is_empty() {
+            md.push_str("\n**Directory Summary:**\n\n");
+            md.push_str(
+                "| Directory | Files | Size | Lines | Code | Comments | Blanks | Tokens |\n",
+            );
+            md.push_str("|---|---:|---:|---:|---:|---:|---:|---:|\n");
+            for dir in &data.directory_summary {
+                let m = &dir.metrics;
+                md.push_str(&format!(
+                    "| `{}` | {} | {} | {} | {} | {} | {} | {} |\n",
+                    dir.path,
+                    m.files,
+                    humansize::format_size(m.size, humansize::DECIMAL),
+                    m.lines(),
+                    m.code,
+                    m.comments,
+                    m.blanks,
+                    m.tokens.map_or("-".to_string(), |t| t.to_string())
+                ));
+            }
+        }
+
         if !stats.errors.is_empty() {
             md.push_str("\n**Scan Errors:**\n\n");
             md.push_str("```log\n");
             for error in stats.errors.iter().take(10) {
                 md.push_str(&format!("- {error}\n"));
             }
-            if stats.errors.len() > 10 {
-                md.push_str("- ... and more errors truncated\n");
+            let listed = stats.errors.len().min(10);
+            if stats.error_count() > listed {
+                md.push_str(&format!(
+                    "- ... and {} more\n",
+                    stats.error_count() - listed
+                ));
             }
             md.push_str("```\n\n");
         }
@@ -137,14 +207,94 @@ pub fn format_markdown(data: &ReportData) -> String {
 
     md.push_str("---\n\n");
 
+    if let Some(files) = &data.markers {
+        md.push_str("## Open Markers\n\n");
+        if files.is_empty() {
+            md.push_str("_(No markers in the selected files)_\n\n");
+        }
+        for file in files {
+            md.push_str(&format!("### `{}`\n\n", file.relative_path));
+            for marker in &file.markers {
+                md.push_str(&format!(
+                    "- Line {}: **{}** {}\n",
+                    marker.line, marker.keyword, marker.text
+                ));
+            }
+            md.push('\n');
+        }
+        md.push_str("---\n\n");
+    }
+
+    if let Some(manifests) = &data.dependencies {
+        md.push_str("## Dependencies\n\n");
+        if manifests.is_empty() {
+            md.push_str("_(No dependency manifests found)_\n\n");
+        }
+        for manifest in manifests {
+            md.push_str(&format!(
+                "### `{}` ({})\n\n",
+                manifest.path,
+                manifest.description()
+            ));
+            if manifest.dependencies.is_empty() {
+                md.push_str("_(No dependencies declared)_\n\n");
+                continue;
+            }
+            md.push_str("| Dependency | Kind | Requirement | Locked |\n");
+            md.push_str("|---|---|---|---|\n");
+            for dependency in &manifest.dependencies {
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    escape_table_cell(&dependency.name),
+                    dependency.kind.label(),
+                    escape_table_cell(dependency.requirement.as_deref().unwrap_or("-")),
+                    escape_table_cell(dependency.locked.as_deref().unwrap_or("-"))
+                ));
+            }
+            md.push('\n');
+        }
+        md.push_str("---\n\n");
+    }
+
+    if let Some(budget) = &data.token_budget {
+        md.push_str("## Token Budget\n\n");
+        md.push_str(&format!("{}\n\n", budget.summary()));
+        if !budget.dropped.is_empty() {
+            md.push_str("| Dropped File | Tokens |\n");
+            md.push_str("|---|---:|\n");
+            for file in &budget.dropped {
+                md.push_str(&format!("| `{}` | {} |\n", file.relative_path, file.tokens));
+            }
+            md.push('\n');
+        }
+        if !budget.truncated.is_empty() {
+            md.push_str("| Truncated File | Tokens | Kept |\n");
+            md.push_str("|---|---:|---:|\n");
+            for file in &budget.truncated {
+                md.push_str(&format!(
+                    "| `{}` | {} | {} |\n",
+                    file.relative_path, file.original_tokens, file.kept_tokens
+                ));
+            }
+            md.push('\n');
+        }
+        md.push_str("---\n\n");
+    }
+
     md.push_str("## Selected File Contents\n\n");
     if !data.file_details.is_empty() {
         for detail in &data.file_details {
             md.push_str(&format!("### `{}`\n\n", detail.relative_path));
-            md.push_str(&format!(
-                "*Size: {} | Modified: {}*\n\n",
-                detail.size, detail.modified
-            ));
+            match &detail.link_target {
+                Some(target) => md.push_str(&format!(
+                    "*Size: {} | Modified: {} | Symlink to: `{}`*\n\n",
+                    detail.size, detail.modified, target
+                )),
+                None => md.push_str(&format!(
+                    "*Size: {} | Modified: {}*\n\n",
+                    detail.size, detail.modified
+                )),
+            }
             let ext = Path::new(&detail.relative_path)
                 .extension()
                 .and_then(|s| s.to_str())
@@ -166,6 +316,10 @@ pub fn format_markdown(data: &ReportData) -> String {
         md.push_str(message);
     }
 
+    if let Some(part) = &data.part {
+        md.push_str(&format!("\n_{}_\n", part.continuation()));
+    }
+
     let cleaned_md = MULTIPLE_BLANKS_RE.replace_all(&md, "\n\n");
     let mut final_output = cleaned_md.trim_end().to_string();
     final_output.push('\n');
@@ -184,3 +338,36 @@ fn estimate_markdown_capacity(data: &ReportData) -> usize {
         .sum();
     base_size + tree_size + stats_size + file_meta_size + file_content_size
 }
+
+/// Escapes pipes so text such as `^16.8.0 || ^17.0.0` stays within one table cell.
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::manifest::{Dependency, DependencyKind, Ecosystem, Manifest},
+        report::test_support::report_data,
+    };
+
+    #[test]
+    fn dependency_cells_escape_pipes() {
+        let mut data = report_data(Vec::new(), "demo\n");
+        data.dependencies = Some(vec![Manifest {
+            path: "package.json".to_string(),
+            ecosystem: Ecosystem::Npm,
+            package: None,
+            version: None,
+            dependencies: vec![Dependency {
+                name: "react".to_string(),
+                kind: DependencyKind::Normal,
+                requirement: Some("^16.8.0 || ^17.0.0".to_string()),
+                locked: None,
+            }],
+        }]);
+        let markdown = format_markdown(&data);
+        assert!(markdown.contains("| react | normal | ^16.8.0 \\|\\| ^17.0.0 | - |\n"));
+    }
+}
@@ -1,11 +1,17 @@
 //! # Report Generation Module
 //!
 //! This module defines the structures and functions for generating
-//! reports (Markdown, HTML, Text) based on the scanned codebase data
+//! reports (Markdown, HTML, Text, JSON, LLM context) based on the scanned codebase data
 //! and user selections.
 
-use crate::{config::AppConfig, fs::ScanStats};
+use crate::{
+    config::AppConfig,
+    fs::{manifest::Manifest, CommentMarker, ScanStats},
+    model::NodeMetrics,
+};
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Defines the output format for the generated report.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
@@ -17,6 +23,10 @@ pub enum ReportFormat {
     Html,
     /// Plain text format (.txt).
     Text,
+    /// JSON following the versioned schema in `report::json` (.json).
+    Json,
+    /// XML-tagged context for LLM prompts, see `report::llm` (.xml).
+    Llm,
 }
 
 impl ReportFormat {
@@ -26,6 +36,73 @@ impl ReportFormat {
             ReportFormat::Markdown => "md",
             ReportFormat::Html => "html",
             ReportFormat::Text => "txt",
+            ReportFormat::Json => "json",
+            ReportFormat::Llm => "xml",
+        }
+    }
+}
+
+/// How a report is cut down to fit `ReportOptions::max_tokens`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FitStrategy {
+    /// Leave out the files with the most tokens first.
+    #[default]
+    DropLargest,
+    /// Leave out whole directories, those matching `AppConfig::low_priority_dirs` first
+    /// and then the deepest ones; files at the top level go last.
+    DropLowPriorityDirs,
+    /// Shorten the longest file bodies to a common length, marking the cut.
+    TruncateFiles,
+}
+
+impl FitStrategy {
+    pub const ALL: [FitStrategy; 3] = [
+        FitStrategy::DropLargest,
+        FitStrategy::DropLowPriorityDirs,
+        FitStrategy::TruncateFiles,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FitStrategy::DropLargest => "Drop largest files",
+            FitStrategy::DropLowPriorityDirs => "Drop low-priority directories",
+            FitStrategy::TruncateFiles => "Truncate file bodies",
+        }
+    }
+}
+
+/// The size limit each part of a split report stays under.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitLimit {
+    /// Tokens, as counted by `llm::token_counter`.
+    Tokens(usize),
+    /// Bytes of UTF-8 text.
+    Bytes(usize),
+}
+
+impl SplitLimit {
+    /// The limit, in the limit's unit.
+    pub fn value(self) -> usize {
+        match self {
+            SplitLimit::Tokens(value) | SplitLimit::Bytes(value) => value,
+        }
+    }
+
+    /// Measures `text` in the limit's unit.
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            SplitLimit::Tokens(_) => crate::llm::token_counter::count_tokens_local(text),
+            SplitLimit::Bytes(_) => text.len(),
+        }
+    }
+
+    /// Name of the limit's unit, for messages.
+    pub fn unit(self) -> &'static str {
+        match self {
+            SplitLimit::Tokens(_) => "tokens",
+            SplitLimit::Bytes(_) => "bytes",
         }
     }
 }
@@ -37,6 +114,25 @@ pub struct ReportOptions {
     pub include_stats: bool,
     pub include_contents: bool,
     pub include_line_numbers: bool,
+    /// Include an "Open Markers" section listing the comment markers of selected files.
+    #[serde(default)]
+    pub include_markers: bool,
+    /// Include a "Dependencies" section listing the dependencies declared in the project's
+    /// manifests (`Cargo.toml`, `package.json`, ...), whether or not they are selected.
+    #[serde(default)]
+    pub include_dependencies: bool,
+    /// User template rendered instead of the built-in `format`, see `report::template`.
+    #[serde(default)]
+    pub template: Option<PathBuf>,
+    /// Token budget the report is fitted under, see `report::budget`. `None` for no limit.
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// How the report is cut down when it exceeds `max_tokens`.
+    #[serde(default)]
+    pub fit_strategy: FitStrategy,
+    /// Split the report into numbered parts under this limit, see `report::split`.
+    #[serde(default)]
+    pub split: Option<SplitLimit>,
 }
 
 impl ReportOptions {
@@ -46,13 +142,30 @@ impl ReportOptions {
             format: match config.export_format.as_str() {
                 "html" => ReportFormat::Html,
                 "text" => ReportFormat::Text,
+                "json" => ReportFormat::Json,
+                "llm" => ReportFormat::Llm,
                 _ => ReportFormat::Markdown,
             },
             include_stats: config.export_include_stats,
             include_contents: config.export_include_contents,
             include_line_numbers: config.export_include_line_numbers,
+            include_markers: config.export_include_markers,
+            include_dependencies: config.export_include_dependencies,
+            template: None,
+            max_tokens: config.export_max_tokens,
+            fit_strategy: config.export_fit_strategy,
+            split: config.export_split,
         }
     }
+
+    /// Returns the extension of the generated file: the template's output type when a
+    /// template is used, otherwise the format's.
+    pub fn output_extension(&self) -> &str {
+        self.template
+            .as_deref()
+            .and_then(template::output_extension)
+            .unwrap_or_else(|| self.format.extension())
+    }
 }
 
 /// Holds details about a single file included in the report content section.
@@ -60,30 +173,253 @@ impl ReportOptions {
 pub struct FileDetail {
     pub relative_path: String,
     pub size: String,
+    pub size_bytes: u64,
+    /// Local modification time as `YYYY-MM-DD HH:MM:SS`, or `N/A`.
     pub modified: String,
+    /// The modification time itself, for formats that need an unambiguous timestamp.
+    pub modified_at: Option<DateTime<Local>>,
+    /// Target of the symbolic link the file was reached through, if any.
+    pub link_target: Option<String>,
     pub content: Result<String, String>,
 }
 
+/// A node of the project tree, for structured (JSON) reports and templates.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportTreeNode {
+    pub name: String,
+    /// Path relative to the project (prefixed with the root name in a workspace).
+    pub path: String,
+    pub is_dir: bool,
+    /// Size in bytes; files only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Target of the symbolic link the entry was reached through, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ReportTreeNode>,
+}
+
+/// Rolled-up metrics of one directory, for the directory summary table.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectorySummary {
+    /// Path relative to the project, ending in `/` (`.` for the root of a single-root project).
+    pub path: String,
+    pub metrics: NodeMetrics,
+}
+
+/// The comment markers of one selected file, for the open markers section.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMarkers {
+    pub relative_path: String,
+    pub markers: Vec<CommentMarker>,
+}
+
+/// A file left out of a report to fit its token budget.
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedFile {
+    pub relative_path: String,
+    /// Tokens the file's entry took up in the report.
+    pub tokens: usize,
+}
+
+/// A file whose content was shortened to fit a report's token budget.
+#[derive(Debug, Clone, Serialize)]
+pub struct TruncatedFile {
+    pub relative_path: String,
+    pub original_tokens: usize,
+    pub kept_tokens: usize,
+}
+
+/// The outcome of fitting a report under `ReportOptions::max_tokens`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenBudget {
+    pub max_tokens: usize,
+    pub strategy: FitStrategy,
+    /// Tokens in the fitted report, as counted by `llm::token_counter`.
+    pub report_tokens: usize,
+    /// Whether the report ended up within the budget.
+    pub fits: bool,
+    pub dropped: Vec<DroppedFile>,
+    pub truncated: Vec<TruncatedFile>,
+}
+
+impl TokenBudget {
+    /// One sentence describing the outcome, shared by all report formats.
+    pub fn summary(&self) -> String {
+        if !self.fits {
+            format!(
+                "The report has {} tokens and could not be cut under the budget of {} tokens ({}).",
+                self.report_tokens,
+                self.max_tokens,
+                self.strategy.label().to_lowercase()
+            )
+        } else if self.dropped.is_empty() && self.truncated.is_empty() {
+            format!(
+                "The report has {} tokens and fits the budget of {} tokens.",
+                self.report_tokens, self.max_tokens
+            )
+        } else {
+            format!(
+                "The report was cut to {} tokens to fit the budget of {} tokens ({}).",
+                self.report_tokens,
+                self.max_tokens,
+                self.strategy.label().to_lowercase()
+            )
+        }
+    }
+}
+
+/// Position of one part in a report split by `report::split`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ReportPart {
+    /// 1-based part number.
+    pub number: usize,
+    pub total: usize,
+}
+
+impl ReportPart {
+    /// Note shown at the top of a part, shared by all report formats.
+    pub fn note(&self) -> String {
+        let mut note = format!(
+            "Part {} of {}. The report is split at file boundaries; every part repeats the \
+             project tree",
+            self.number, self.total
+        );
+        if self.number == 1 {
+            note.push('.');
+        } else {
+            note.push_str(", and the statistics and other sections are in part 1.");
+        }
+        note
+    }
+
+    /// Note shown at the end of a part.
+    pub fn continuation(&self) -> String {
+        if self.number < self.total {
+            format!("Continued in part {} of {}.", self.number + 1, self.total)
+        } else {
+            format!(
+                "End of the report (part {} of {}).",
+                self.number, self.total
+            )
+        }
+    }
+}
+
 /// Contains all the necessary data collected from the application state
 /// required to generate a report in any supported format.
 #[derive(Debug, Clone)]
 pub struct ReportData {
     pub project_name: String,
+    /// Local generation time as `YYYY-MM-DD HH:MM:SS`.
     pub timestamp: String,
-    pub root_path: String,
+    /// The generation time itself, for formats that need an unambiguous timestamp.
+    pub generated_at: DateTime<Local>,
+    /// Absolute paths of the scanned roots (more than one for a workspace).
+    pub root_paths: Vec<String>,
     pub full_tree_structure: String,
     pub selected_tree_structure: String,
+    /// The full tree as nested nodes; only collected for JSON reports and templates.
+    pub full_tree: Vec<ReportTreeNode>,
+    /// The selected nodes and their ancestors as nested nodes; only collected for JSON
+    /// reports and templates.
+    pub selected_tree: Vec<ReportTreeNode>,
     pub file_details: Vec<FileDetail>,
     pub stats: Option<ScanStats>,
+    /// Top-level directories with their rolled-up metrics; empty when statistics are excluded.
+    pub directory_summary: Vec<DirectorySummary>,
+    /// Selected files with comment markers; `None` when the section is excluded.
+    pub markers: Option<Vec<FileMarkers>>,
+    /// Parsed dependency manifests; `None` when the section is excluded or not yet parsed.
+    pub dependencies: Option<Vec<Manifest>>,
+    /// Manifests waiting to be parsed into `dependencies` by `parse_report_dependencies`
+    /// (absolute path, project-relative path and scan root); `None` once parsed or when
+    /// the section is excluded.
+    pub pending_manifests: Option<Vec<(PathBuf, String, PathBuf)>>,
+    /// What was dropped or truncated to fit the token budget; `None` without a budget.
+    pub token_budget: Option<TokenBudget>,
+    /// Which part of a split report this is; `None` for a report in one piece.
+    pub part: Option<ReportPart>,
 }
 
 // --- Submodules ---
+pub mod budget;
 pub mod generator;
 pub mod html;
+pub mod json;
+pub mod llm;
 pub mod markdown;
+pub mod split;
+pub mod template;
 pub mod text;
 
 // --- Re-exports ---
 pub use generator::{
-    collect_report_data, format_report_content, generate_report, preview_file_details,
+    collect_report_data, fit_report_data, format_report_content, format_report_parts,
+    generate_report, generate_report_parts, parse_report_dependencies, preview_file_details,
 };
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::{FileDetail, ReportData, ReportFormat, ReportOptions};
+    use crate::config::AppConfig;
+    use chrono::{Local, TimeZone};
+
+    /// A readable file entry with `content`.
+    pub fn file(relative_path: &str, content: &str) -> FileDetail {
+        FileDetail {
+            relative_path: relative_path.to_string(),
+            size: format!("{} B", content.len()),
+            size_bytes: content.len() as u64,
+            modified: "2024-01-01 00:00:00".to_string(),
+            modified_at: None,
+            link_target: None,
+            content: Ok(content.to_string()),
+        }
+    }
+
+    /// Report data for a project named "demo" holding `files`, with `tree` as both trees.
+    pub fn report_data(files: Vec<FileDetail>, tree: &str) -> ReportData {
+        ReportData {
+            project_name: "demo".to_string(),
+            timestamp: "2024-01-01 00:00:00".to_string(),
+            generated_at: Local.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            root_paths: vec!["/projects/demo".to_string()],
+            full_tree_structure: tree.to_string(),
+            selected_tree_structure: tree.to_string(),
+            full_tree: Vec::new(),
+            selected_tree: Vec::new(),
+            file_details: files,
+            stats: None,
+            directory_summary: Vec::new(),
+            markers: None,
+            dependencies: None,
+            pending_manifests: None,
+            token_budget: None,
+            part: None,
+        }
+    }
+
+    /// Markdown report options with contents and nothing else.
+    pub fn options() -> ReportOptions {
+        ReportOptions {
+            format: ReportFormat::Markdown,
+            include_stats: false,
+            include_markers: false,
+            include_dependencies: false,
+            include_line_numbers: false,
+            include_contents: true,
+            max_tokens: None,
+            split: None,
+            ..ReportOptions::from_config(&AppConfig::default())
+        }
+    }
+
+    /// `lines` numbered lines of code, a few tokens each.
+    pub fn lines(prefix: &str, lines: usize) -> String {
+        (0..lines)
+            .map(|i| format!("let {prefix}_{i} = compute({i});\n"))
+            .collect()
+    }
+}
@@ -0,0 +1,327 @@
+//! Splits an oversized report into numbered parts under a token or byte limit.
+//!
+//! Parts only break between files. Every part has the report header with a "part N of M"
+//! note, the project trees and a continuation note at the end; the statistics, markers,
+//! dependencies and token budget sections are only in the first part. A file too large for
+//! any part gets a part of its own, which then exceeds the limit.
+
+use super::{format_report_content, FileDetail, ReportData, ReportOptions, ReportPart, SplitLimit};
+use anyhow::bail;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Formats `data` as parts that each stay under `limit`. A report that fits is returned
+/// whole, without part notes.
+pub fn split_report(
+    data: &ReportData,
+    options: &ReportOptions,
+    limit: SplitLimit,
+) -> anyhow::Result<Vec<String>> {
+    let max = limit.value();
+    let whole = format_report_content(data, options)?;
+    let whole_size = limit.measure(&whole);
+    if whole_size <= max || data.file_details.len() <= 1 {
+        if whole_size > max {
+            log::warn!(
+                "Report has {whole_size} {} but can't be split further",
+                limit.unit()
+            );
+        }
+        return Ok(vec![whole]);
+    }
+
+    let mut skeleton = data.clone();
+    skeleton.file_details = Vec::new();
+    let first_base = limit.measure(&format_part(&skeleton, options, 1, 2, &[])?);
+    let later_base = limit.measure(&format_part(&skeleton, options, 2, 2, &[])?);
+    if later_base >= max {
+        bail!(
+            "The report's header and project tree alone take up {later_base} {unit}, more than \
+             the split limit of {max} {unit}; raise the limit or select fewer files",
+            unit = limit.unit()
+        );
+    }
+
+    let sizes = entry_sizes(&skeleton, options, limit, &data.file_details)?;
+    let mut parts: Vec<Range<usize>> = Vec::new();
+    let mut start = 0;
+    let mut used = first_base;
+    for (index, size) in sizes.iter().enumerate() {
+        if index > start && used + size > max {
+            parts.push(start..index);
+            start = index;
+            used = later_base;
+        }
+        used += size;
+    }
+    parts.push(start..sizes.len());
+
+    // Entry sizes don't add up exactly (tokens merge across boundaries, the part count in
+    // the notes may grow a digit), so check each part once and move trailing files on to
+    // the next part where one came out too large.
+    loop {
+        let total = parts.len();
+        let mut outputs = Vec::with_capacity(total);
+        let mut index = 0;
+        while index < parts.len() {
+            let range = parts[index].clone();
+            let text = format_part(
+                &skeleton,
+                options,
+                index + 1,
+                total,
+                &data.file_details[range.clone()],
+            )?;
+            let size = limit.measure(&text);
+            if size > max && range.len() > 1 {
+                let overshoot = size - max;
+                let mut end = range.end;
+                let mut freed = 0;
+                while end - 1 > range.start && freed < overshoot {
+                    end -= 1;
+                    freed += sizes[end];
+                }
+                parts[index].end = end;
+                match parts.get_mut(index + 1) {
+                    Some(next) => next.start = end,
+                    None => parts.push(end..range.end),
+                }
+                continue;
+            }
+            if size > max {
+                log::warn!(
+                    "Part {} only holds '{}' and still has {size} {}, over the limit of {max}",
+                    index + 1,
+                    data.file_details[range.start].relative_path,
+                    limit.unit()
+                );
+            }
+            outputs.push(text);
+            index += 1;
+        }
+        // Parts formatted before a new part was added name the wrong total; redo them.
+        if parts.len() == total {
+            log::info!("Report split into {total} parts");
+            return Ok(outputs);
+        }
+    }
+}
+
+/// Returns the paths the parts of a report saved to `path` are written to:
+/// `report_part1.md`, `report_part2.md`, ... (zero-padded for ten or more parts).
+pub fn part_paths(path: &Path, count: usize) -> Vec<PathBuf> {
+    if count <= 1 {
+        return vec![path.to_path_buf()];
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "report".to_string());
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let width = count.to_string().len();
+    (1..=count)
+        .map(|number| path.with_file_name(format!("{stem}_part{number:0width$}{extension}")))
+        .collect()
+}
+
+/// Formats part `number` of `total` holding `files`. Only the first part keeps the
+/// sections that aren't about individual files.
+fn format_part(
+    skeleton: &ReportData,
+    options: &ReportOptions,
+    number: usize,
+    total: usize,
+    files: &[FileDetail],
+) -> anyhow::Result<String> {
+    let mut part = skeleton.clone();
+    part.file_details = files.to_vec();
+    part.part = Some(ReportPart { number, total });
+    if number > 1 {
+        part.stats = None;
+        part.directory_summary = Vec::new();
+        part.markers = None;
+        part.dependencies = None;
+        part.token_budget = None;
+    }
+    format_report_content(&part, options)
+}
+
+/// Measures the entry of each file in the report: a part holding only that file, without
+/// the trees and other sections, less the same part without the file. Each file is
+/// formatted once on its own, so this stays linear in the number of files.
+fn entry_sizes(
+    skeleton: &ReportData,
+    options: &ReportOptions,
+    limit: SplitLimit,
+    files: &[FileDetail],
+) -> anyhow::Result<Vec<usize>> {
+    let mut bare = skeleton.clone();
+    bare.full_tree_structure = String::new();
+    bare.selected_tree_structure = String::new();
+    bare.full_tree = Vec::new();
+    bare.selected_tree = Vec::new();
+    let bare_size = limit.measure(&format_part(&bare, options, 2, 2, &[])?);
+    files
+        .iter()
+        .map(|file| {
+            let text = format_part(&bare, options, 2, 2, std::slice::from_ref(file))?;
+            Ok(limit.measure(&text).saturating_sub(bare_size))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::test_support::{file, lines, options, report_data};
+
+    const TREE: &str = "demo\n├── big.rs\n└── src\n";
+
+    fn project(files: usize, lines_per_file: usize) -> ReportData {
+        report_data(
+            (0..files)
+                .map(|i| {
+                    file(
+                        &format!("src/file_{i:02}.rs"),
+                        &lines(&format!("f{i}"), lines_per_file),
+                    )
+                })
+                .collect(),
+            TREE,
+        )
+    }
+
+    /// Paths of the files whose headings appear in a Markdown part, in order.
+    fn files_in(part: &str) -> Vec<&str> {
+        part.lines()
+            .filter_map(|line| line.strip_prefix("### `"))
+            .filter_map(|line| line.strip_suffix('`'))
+            .collect()
+    }
+
+    #[test]
+    fn part_paths_number_parts_with_padding() {
+        let path = Path::new("/out/report.md");
+        assert_eq!(part_paths(path, 1), [PathBuf::from("/out/report.md")]);
+        assert_eq!(
+            part_paths(path, 3),
+            [
+                PathBuf::from("/out/report_part1.md"),
+                PathBuf::from("/out/report_part2.md"),
+                PathBuf::from("/out/report_part3.md"),
+            ]
+        );
+        let many = part_paths(path, 12);
+        assert_eq!(many[0], PathBuf::from("/out/report_part01.md"));
+        assert_eq!(many[11], PathBuf::from("/out/report_part12.md"));
+        assert_eq!(
+            part_paths(Path::new("notes"), 2),
+            [PathBuf::from("notes_part1"), PathBuf::from("notes_part2")]
+        );
+    }
+
+    #[test]
+    fn report_under_the_limit_stays_whole() {
+        let data = project(3, 5);
+        let parts = split_report(&data, &options(), SplitLimit::Bytes(1_000_000)).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert!(!parts[0].contains("Part 1 of"));
+        assert_eq!(parts[0], format_report_content(&data, &options()).unwrap());
+    }
+
+    #[test]
+    fn parts_break_at_file_boundaries_and_repeat_the_tree() {
+        let data = project(12, 40);
+        for limit in [SplitLimit::Bytes(6_000), SplitLimit::Tokens(1_500)] {
+            let parts = split_report(&data, &options(), limit).unwrap();
+            let total = parts.len();
+            assert!(total > 2, "{limit:?} gave {total} parts");
+
+            let mut seen = Vec::new();
+            for (index, part) in parts.iter().enumerate() {
+                let number = index + 1;
+                assert!(
+                    limit.measure(part) <= limit.value(),
+                    "part {number} too large"
+                );
+                assert!(part.contains(&format!("Part {number} of {total}.")));
+                assert!(
+                    part.contains(TREE.trim_end()),
+                    "part {number} lacks the tree"
+                );
+                let continuation = ReportPart { number, total }.continuation();
+                assert!(part.trim_end().ends_with(&format!("_{continuation}_")));
+
+                for path in files_in(part) {
+                    // Every file's content is complete within the part that lists it
+                    let detail = data
+                        .file_details
+                        .iter()
+                        .find(|f| f.relative_path == path)
+                        .unwrap();
+                    assert!(part.contains(detail.content.as_deref().unwrap()));
+                    seen.push(path.to_string());
+                }
+            }
+            let expected: Vec<String> = data
+                .file_details
+                .iter()
+                .map(|f| f.relative_path.clone())
+                .collect();
+            assert_eq!(seen, expected, "each file once, in order");
+        }
+    }
+
+    #[test]
+    fn oversized_file_gets_a_part_of_its_own() {
+        let mut data = project(4, 10);
+        data.file_details
+            .insert(2, file("big.rs", &lines("big", 2_000)));
+        let parts = split_report(&data, &options(), SplitLimit::Bytes(8_000)).unwrap();
+
+        let big_parts: Vec<&String> = parts
+            .iter()
+            .filter(|part| files_in(part).contains(&"big.rs"))
+            .collect();
+        assert_eq!(big_parts.len(), 1);
+        assert_eq!(files_in(big_parts[0]), ["big.rs"]);
+        assert!(big_parts[0].len() > 8_000);
+        for part in parts
+            .iter()
+            .filter(|part| !files_in(part).contains(&"big.rs"))
+        {
+            assert!(part.len() <= 8_000);
+        }
+    }
+
+    #[test]
+    fn later_parts_leave_out_project_wide_sections() {
+        let mut data = project(8, 40);
+        data.token_budget = Some(crate::report::TokenBudget {
+            max_tokens: 1_000_000,
+            strategy: crate::report::FitStrategy::DropLargest,
+            report_tokens: 5_000,
+            fits: true,
+            dropped: Vec::new(),
+            truncated: Vec::new(),
+        });
+        let parts = split_report(&data, &options(), SplitLimit::Bytes(6_000)).unwrap();
+        assert!(parts.len() > 1);
+        assert!(parts[0].contains("## Token Budget"));
+        assert!(parts[1..]
+            .iter()
+            .all(|part| !part.contains("## Token Budget")));
+    }
+
+    #[test]
+    fn limit_below_the_header_is_an_error() {
+        let data = project(3, 40);
+        let error = split_report(&data, &options(), SplitLimit::Bytes(100)).unwrap_err();
+        assert!(error.to_string().contains("split limit of 100 bytes"));
+    }
+}
@@ -0,0 +1,263 @@
+//! Renders user-defined report templates against the collected `ReportData`.
+//!
+//! Templates use Jinja syntax (via `minijinja`). `{% include %}` and `{% extends %}` are
+//! resolved relative to the template's directory. Output is HTML-escaped when the template
+//! name ends in `.html`, `.htm` or `.xml` (optionally followed by `.j2`, `.jinja` or
+//! `.jinja2`), and left as is otherwise. Blocks trim their trailing newline, and printing an
+//! undefined variable is an error so typos don't go unnoticed.
+//!
+//! The template context holds:
+//!
+//! - `project`: `{ name, generated_at, roots }`.
+//! - `generator`: `{ name, version }` of the application.
+//! - `options`: the `ReportOptions` the report was generated with.
+//! - `full_tree_text`, `selected_tree_text`: the trees as drawn in the built-in formats.
+//! - `full_tree`, `selected_tree`: the trees as nested `{ name, path, is_dir, size?,
+//!   link_target?, children? }` nodes.
+//! - `files`: the selected files, sorted by path, as `{ path, dir, name, extension, size,
+//!   size_bytes, modified, link_target, content, error }`. `dir` is the parent directory
+//!   (`""` at the top level), `size` is human-readable and `content` is `none` when it was
+//!   excluded or couldn't be read, in which case `error` says why.
+//! - `stats`, `directory_summary`, `markers`, `dependencies`, `token_budget`: the same
+//!   objects as in the JSON report (see `super::json`), so sizes are in bytes and enum
+//!   values are snake_case strings; `none` (or empty) when excluded.
+//! - `part`: `{ number, total, note, continuation }` when the report is split into parts,
+//!   `none` otherwise.
+
+use super::{
+    json::{JsonDirectory, JsonFileMarkers, JsonManifest, JsonStats, JsonTokenBudget},
+    ReportData, ReportOptions, ReportTreeNode,
+};
+use anyhow::{anyhow, bail};
+use minijinja::{path_loader, Environment, UndefinedBehavior};
+use serde::Serialize;
+use std::{fmt::Write, path::Path};
+
+/// Extensions that mark a file as a template rather than naming its output type.
+const TEMPLATE_EXTENSIONS: &[&str] = &["j2", "jinja", "jinja2", "tmpl", "tpl"];
+
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    project: TemplateProject<'a>,
+    generator: TemplateGenerator,
+    options: &'a ReportOptions,
+    full_tree_text: &'a str,
+    selected_tree_text: &'a str,
+    full_tree: &'a [ReportTreeNode],
+    selected_tree: &'a [ReportTreeNode],
+    files: Vec<TemplateFile<'a>>,
+    stats: Option<JsonStats<'a>>,
+    directory_summary: Vec<JsonDirectory<'a>>,
+    markers: Option<Vec<JsonFileMarkers<'a>>>,
+    dependencies: Option<Vec<JsonManifest<'a>>>,
+    token_budget: Option<JsonTokenBudget<'a>>,
+    part: Option<TemplatePart>,
+}
+
+#[derive(Serialize)]
+struct TemplatePart {
+    number: usize,
+    total: usize,
+    note: String,
+    continuation: String,
+}
+
+#[derive(Serialize)]
+struct TemplateProject<'a> {
+    name: &'a str,
+    generated_at: &'a str,
+    roots: &'a [String],
+}
+
+#[derive(Serialize)]
+struct TemplateGenerator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct TemplateFile<'a> {
+    path: &'a str,
+    dir: &'a str,
+    name: &'a str,
+    extension: &'a str,
+    size: &'a str,
+    size_bytes: u64,
+    modified: &'a str,
+    link_target: Option<&'a str>,
+    content: Option<&'a str>,
+    error: Option<&'a str>,
+}
+
+/// Renders the template at `template_path` against `data`.
+///
+/// Syntax and rendering errors name the template, the line and the offending source line.
+pub fn render_template(
+    data: &ReportData,
+    options: &ReportOptions,
+    template_path: &Path,
+) -> anyhow::Result<String> {
+    if !template_path.is_file() {
+        bail!("Template file '{}' not found", template_path.display());
+    }
+    let (Some(dir), Some(name)) = (
+        template_path.parent(),
+        template_path.file_name().and_then(|name| name.to_str()),
+    ) else {
+        bail!("Invalid template path '{}'", template_path.display());
+    };
+
+    let mut env = Environment::new();
+    env.set_loader(path_loader(dir));
+    env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    env.set_debug(true);
+
+    let template = env
+        .get_template(name)
+        .map_err(|e| anyhow!("{}", describe_error(&e)))?;
+    template
+        .render(build_context(data, options))
+        .map_err(|e| anyhow!("{}", describe_error(&e)))
+}
+
+/// Returns the extension of the file a template produces: `md` for `pr.md.j2` or
+/// `summary.md`, `None` for a bare `report.j2`.
+pub fn output_extension(template_path: &Path) -> Option<&str> {
+    let extension = template_path.extension()?.to_str()?;
+    if !TEMPLATE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) {
+        return Some(extension);
+    }
+    Path::new(template_path.file_stem()?).extension()?.to_str()
+}
+
+fn build_context<'a>(data: &'a ReportData, options: &'a ReportOptions) -> TemplateContext<'a> {
+    let files = data
+        .file_details
+        .iter()
+        .map(|detail| {
+            let (dir, name) = detail
+                .relative_path
+                .rsplit_once(['/', '\\'])
+                .unwrap_or(("", &detail.relative_path));
+            let extension = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+            let (content, error) = match &detail.content {
+                _ if !options.include_contents => (None, None),
+                Ok(content) => (Some(content.as_str()), None),
+                Err(e) => (None, Some(e.as_str())),
+            };
+            TemplateFile {
+                path: &detail.relative_path,
+                dir,
+                name,
+                extension,
+                size: &detail.size,
+                size_bytes: detail.size_bytes,
+                modified: &detail.modified,
+                link_target: detail.link_target.as_deref(),
+                content,
+                error,
+            }
+        })
+        .collect();
+    TemplateContext {
+        project: TemplateProject {
+            name: &data.project_name,
+            generated_at: &data.timestamp,
+            roots: &data.root_paths,
+        },
+        generator: TemplateGenerator {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        },
+        options,
+        full_tree_text: &data.full_tree_structure,
+        selected_tree_text: &data.selected_tree_structure,
+        full_tree: &data.full_tree,
+        selected_tree: &data.selected_tree,
+        files,
+        stats: data.stats.as_ref().map(JsonStats::new),
+        directory_summary: data
+            .directory_summary
+            .iter()
+            .map(JsonDirectory::new)
+            .collect(),
+        markers: data
+            .markers
+            .as_ref()
+            .map(|markers| markers.iter().map(JsonFileMarkers::new).collect()),
+        dependencies: data
+            .dependencies
+            .as_ref()
+            .map(|manifests| manifests.iter().map(JsonManifest::new).collect()),
+        token_budget: data.token_budget.as_ref().map(JsonTokenBudget::new),
+        part: data.part.map(|part| TemplatePart {
+            number: part.number,
+            total: part.total,
+            note: part.note(),
+            continuation: part.continuation(),
+        }),
+    }
+}
+
+/// Describes a template error with its location and the offending source line, following
+/// errors raised in included templates. minijinja's own debug output is avoided because it
+/// dumps every referenced variable, file contents included.
+fn describe_error(error: &minijinja::Error) -> String {
+    let mut message = String::from("Template error");
+    let mut current: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(err) = current {
+        let Some(err) = err.downcast_ref::<minijinja::Error>() else {
+            let _ = write!(message, "\n  caused by: {err}");
+            break;
+        };
+        let _ = write!(message, "\n  {}", err);
+        if let (Some(source), Some(line)) = (err.template_source(), err.line()) {
+            if let Some(text) = source.lines().nth(line.saturating_sub(1)) {
+                let _ = write!(message, "\n  {line:>4} | {}", text.trim_end());
+            }
+        }
+        current = std::error::Error::source(err);
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::{stats::FileStatInfo, ScanError, ScanErrorKind, ScanStats},
+        report::test_support::{file, options, report_data},
+    };
+
+    #[test]
+    fn renders_stats_in_the_json_report_shape() {
+        let dir = tempfile::tempdir().unwrap();
+        let template_path = dir.path().join("stats.txt.j2");
+        std::fs::write(
+            &template_path,
+            "{% for f in stats.largest_files %}{{ f.path }}={{ f.size }}\n{% endfor %}\
+             {% for e in stats.errors %}{{ e.kind }}\n{% endfor %}\
+             {{ stats.error_counts.permission_denied }}\n",
+        )
+        .unwrap();
+        let mut stats = ScanStats::default();
+        stats.largest_files.push(FileStatInfo {
+            path: "src/main.rs".to_string(),
+            size: 1200,
+            human_size: "1.20 kB".to_string(),
+        });
+        stats.add_error(ScanError::new(
+            ScanErrorKind::PermissionDenied,
+            None,
+            "Permission denied",
+        ));
+        let mut data = report_data(vec![file("src/main.rs", "fn main() {}\n")], "");
+        data.stats = Some(stats);
+
+        let output = render_template(&data, &options(), &template_path).unwrap();
+        assert_eq!(output, "src/main.rs=1200\npermission_denied\n1\n");
+    }
+}
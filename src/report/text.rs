@@ -15,7 +15,17 @@ pub fn format_text(data: &ReportData) -> String {
     ));
     txt.push_str(&format!("{sep}\n"));
     txt.push_str(&format!("Generated on: {}\n", data.timestamp));
-    txt.push_str(&format!("Root Path:    {}\n", data.root_path));
+    if let [root_path] = data.root_paths.as_slice() {
+        txt.push_str(&format!("Root Path:    {root_path}\n"));
+    } else {
+        txt.push_str("Root Paths:\n");
+        for root_path in &data.root_paths {
+            txt.push_str(&format!("  - {root_path}\n"));
+        }
+    }
+    if let Some(part) = &data.part {
+        txt.push_str(&format!("{}\n", part.note()));
+    }
     txt.push_str(&format!("{sep}\n\n"));
 
     if let Some(stats) = &data.stats {
@@ -24,17 +34,25 @@ pub fn format_text(data: &ReportData) -> String {
         txt.push_str(&format!("Total Files:    {}\n", stats.total_files));
         txt.push_str(&format!("Total Dirs:     {}\n", stats.total_dirs));
         txt.push_str(&format!("Total Size:     {}\n", stats.total_size_human()));
+        for (class, counts) in &stats.file_classes {
+            txt.push_str(&format!(
+                "{:<16}{} ({}, not counted below)\n",
+                format!("{}:", class.label()),
+                counts.files,
+                counts.size_human()
+            ));
+        }
 
         // MODIFIED: Add Language Statistics
         if !stats.language_stats.is_empty() {
             txt.push_str("\nLanguage Statistics:\n");
             let mut sorted_langs: Vec<_> = stats.language_stats.iter().collect();
-            sorted_langs.sort_by(|a, b| b.1.code.cmp(&a.1.code));
-            for (lang_type, lang) in sorted_langs {
+            sorted_langs.sort_by_key(|(_, lang)| std::cmp::Reverse(lang.code));
+            for (language, lang) in sorted_langs {
                 txt.push_str(&format!(
                     "  - {:<15} | Files: {:<5} | Lines: {:<7} (Code: {}, Comments: {}, Blanks: {})\n",
-                    lang_type,
-                    lang.reports.len(),
+                    language,
+                    lang.files.len(),
                     lang.lines(),
                     lang.code,
                     lang.comments,
@@ -65,13 +83,56 @@ pub fn format_text(data: &ReportData) -> String {
             }
         }
 
+        if !stats.licenses.is_empty() || stats.files_missing_license > 0 {
+            txt.push_str("\nLicense Summary:\n");
+            for (license, counts) in stats.licenses_by_files() {
+                txt.push_str(&format!(
+                    "  - {:<20} | Files: {:<5} | With Copyright: {}",
+                    license, counts.files, counts.with_copyright
+                ));
+                if !counts.license_files.is_empty() {
+                    txt.push_str(&format!(
+                        " | License Files: {}",
+                        counts.license_files.join(", ")
+                    ));
+                }
+                txt.push('\n');
+            }
+            txt.push_str(&format!(
+                "  Source files without a license header: {}\n",
+                stats.files_missing_license
+            ));
+        }
+
+        if !data.directory_summary.is_empty() {
+            txt.push_str("\nDirectory Summary:\n");
+            for dir in &data.directory_summary {
+                let m = &dir.metrics;
+                txt.push_str(&format!(
+                    "  - {:<30} | Files: {:<5} | Size: {:<9} | Lines: {:<7} (Code: {}, Comments: {}, Blanks: {}) | Tokens: {}\n",
+                    dir.path,
+                    m.files,
+                    humansize::format_size(m.size, humansize::DECIMAL),
+                    m.lines(),
+                    m.code,
+                    m.comments,
+                    m.blanks,
+                    m.tokens.map_or("-".to_string(), |t| t.to_string())
+                ));
+            }
+        }
+
         if !stats.errors.is_empty() {
             txt.push_str("\nScan Errors:\n");
             for error in stats.errors.iter().take(10) {
                 txt.push_str(&format!("- {error}\n"));
             }
-            if stats.errors.len() > 10 {
-                txt.push_str("- ... and more errors truncated\n");
+            let listed = stats.errors.len().min(10);
+            if stats.error_count() > listed {
+                txt.push_str(&format!(
+                    "- ... and {} more\n",
+                    stats.error_count() - listed
+                ));
             }
         }
         txt.push_str(&format!("{sub_sep}\n\n"));
@@ -91,15 +152,82 @@ pub fn format_text(data: &ReportData) -> String {
     });
     txt.push_str(&format!("\n{sub_sep}\n\n"));
 
+    if let Some(files) = &data.markers {
+        txt.push_str("OPEN MARKERS\n");
+        txt.push_str(&format!("{sub_sep}\n"));
+        if files.is_empty() {
+            txt.push_str("(No markers in the selected files)\n");
+        }
+        for file in files {
+            txt.push_str(&format!("{}\n", file.relative_path));
+            for marker in &file.markers {
+                txt.push_str(&format!(
+                    "  {:>5}: {} {}\n",
+                    marker.line, marker.keyword, marker.text
+                ));
+            }
+        }
+        txt.push_str(&format!("{sub_sep}\n\n"));
+    }
+
+    if let Some(manifests) = &data.dependencies {
+        txt.push_str("DEPENDENCIES\n");
+        txt.push_str(&format!("{sub_sep}\n"));
+        if manifests.is_empty() {
+            txt.push_str("(No dependency manifests found)\n");
+        }
+        for manifest in manifests {
+            txt.push_str(&format!("{} ({})\n", manifest.path, manifest.description()));
+            if manifest.dependencies.is_empty() {
+                txt.push_str("  (No dependencies declared)\n");
+            }
+            for dependency in &manifest.dependencies {
+                txt.push_str(&format!(
+                    "  - {:<30} | {:<8} | Requirement: {} | Locked: {}\n",
+                    dependency.name,
+                    dependency.kind.label(),
+                    dependency.requirement.as_deref().unwrap_or("-"),
+                    dependency.locked.as_deref().unwrap_or("-")
+                ));
+            }
+        }
+        txt.push_str(&format!("{sub_sep}\n\n"));
+    }
+
+    if let Some(budget) = &data.token_budget {
+        txt.push_str("TOKEN BUDGET\n");
+        txt.push_str(&format!("{sub_sep}\n"));
+        txt.push_str(&format!("{}\n", budget.summary()));
+        for file in &budget.dropped {
+            txt.push_str(&format!(
+                "  - Dropped:   {} ({} tokens)\n",
+                file.relative_path, file.tokens
+            ));
+        }
+        for file in &budget.truncated {
+            txt.push_str(&format!(
+                "  - Truncated: {} ({} of {} tokens kept)\n",
+                file.relative_path, file.kept_tokens, file.original_tokens
+            ));
+        }
+        txt.push_str(&format!("{sub_sep}\n\n"));
+    }
+
     txt.push_str("SELECTED FILE CONTENTS\n");
     txt.push_str(&format!("{sep}\n"));
     if !data.file_details.is_empty() {
         for detail in &data.file_details {
             txt.push_str(&format!("\n--- File: {} ---\n", detail.relative_path));
-            txt.push_str(&format!(
-                "(Size: {} | Modified: {})\n",
-                detail.size, detail.modified
-            ));
+            match &detail.link_target {
+                Some(target) => txt.push_str(&format!(
+                    "(Size: {} | Modified: {} | Symlink to: {})\n",
+                    detail.size, detail.modified, target
+                )),
+                None => txt.push_str(&format!(
+                    "(Size: {} | Modified: {})\n",
+                    detail.size, detail.modified
+                )),
+            }
             txt.push_str(&format!("{sub_sep}\n"));
             match &detail.content {
                 Ok(content) => txt.push_str(content.trim_end()),
@@ -120,6 +248,10 @@ pub fn format_text(data: &ReportData) -> String {
         txt.push_str(&format!("{sep}\n"));
     }
 
+    if let Some(part) = &data.part {
+        txt.push_str(&format!("{}\n", part.continuation()));
+    }
+
     txt.trim_end().to_string() + "\n"
 }
 
@@ -17,19 +17,24 @@ struct SelectionData {
     /// Timestamp when the selection was saved (RFC3339 format).
     timestamp: String,
     /// The absolute root path of the directory when the selection was saved.
+    /// For workspaces this is the first root; all roots are listed in `roots`.
     root_path: String,
+    /// All absolute root paths of a multi-root workspace. Empty for a single root.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roots: Vec<String>,
     /// Map where keys are relative paths from the root, and values are the `Check` state.
+    /// With several roots, keys are prefixed by the root's directory name.
     selection: HashMap<String, Check>,
 }
 
 /// Saves the current selection state of the tree nodes to a JSON file.
 ///
-/// Only saves the state of nodes relative to the `root_id`.
+/// Only saves the state of nodes below the roots in `root_ids`.
 ///
 /// # Arguments
 /// * `nodes` - Slice containing all `FileNode`s in the application's arena.
-/// * `root_id` - The `FileId` of the root node of the current tree.
-/// * `root_path` - The absolute path of the root directory.
+/// * `root_ids` - The `FileId`s of the root nodes of the current tree.
+/// * `roots` - The absolute paths of the root directories.
 /// * `file_path` - The path where the JSON selection file should be saved.
 ///
 /// # Returns
@@ -37,34 +42,33 @@ struct SelectionData {
 /// * `Err(anyhow::Error)` on failure (e.g., I/O error, serialization error).
 pub fn save_selection_to_file(
     nodes: &[FileNode],
-    root_id: Option<FileId>,
-    root_path: &Path,
+    root_ids: &[FileId],
+    roots: &[PathBuf],
     file_path: &Path,
 ) -> anyhow::Result<()> {
-    let root_id = match root_id {
-        Some(id) => id,
-        None => {
-            log::warn!("Attempted to save selection, but no root node ID is set.");
-            return Ok(()); // Nothing to save if no root
-        }
-    };
+    if root_ids.is_empty() {
+        log::warn!("Attempted to save selection, but no root node ID is set.");
+        return Ok(()); // Nothing to save if no root
+    }
 
     log::info!("Collecting selection state for saving...");
     let mut selection_map = HashMap::new();
 
-    // Start recursion from the children of the root node.
-    // The root node itself isn't typically saved by relative path,
-    // unless a special key like "." is used (currently not implemented).
-    if let Some(root_node) = nodes.get(root_id) {
-        let root_relative_path = PathBuf::new(); // Start with an empty relative path
-        for &child_id in &root_node.children {
-            collect_selection_recursive(nodes, child_id, &root_relative_path, &mut selection_map);
+    for &root_id in root_ids {
+        let Some(root_node) = nodes.get(root_id) else {
+            log::error!("Root node ID {root_id} is invalid during save selection.");
+            return Err(anyhow::anyhow!("Invalid root node ID during save."));
+        };
+        if root_ids.len() > 1 {
+            // Start at the root itself so keys carry the root's name, as in reports.
+            collect_selection_recursive(nodes, root_id, Path::new(""), &mut selection_map);
+        } else {
+            // Start recursion from the children of the root node.
+            // The root node itself isn't saved by relative path.
+            for &child_id in &root_node.children {
+                collect_selection_recursive(nodes, child_id, Path::new(""), &mut selection_map);
+            }
         }
-        // Optionally save root state itself if needed:
-        // selection_map.insert(".".to_string(), root_node.state);
-    } else {
-        log::error!("Root node ID {root_id} is invalid during save selection.");
-        return Err(anyhow::anyhow!("Invalid root node ID during save."));
     }
 
     log::info!("Collected state for {} nodes.", selection_map.len());
@@ -73,7 +77,18 @@ pub fn save_selection_to_file(
     let data = SelectionData {
         app_version: env!("CARGO_PKG_VERSION").to_string(),
         timestamp: chrono::Local::now().to_rfc3339(), // Use standard RFC3339 timestamp
-        root_path: root_path.display().to_string(),
+        root_path: roots
+            .first()
+            .map(|root| root.display().to_string())
+            .unwrap_or_default(),
+        roots: if roots.len() > 1 {
+            roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect()
+        } else {
+            Vec::new()
+        },
         selection: selection_map,
     };
 
@@ -125,21 +140,22 @@ fn collect_selection_recursive(
 
 /// Loads selection state from a JSON file and applies it to the current tree nodes.
 ///
-/// Matches nodes based on their relative paths from the root.
+/// Matches nodes based on their relative paths from the root (prefixed by the root's name
+/// when several roots are open).
 ///
 /// # Arguments
 /// * `nodes` - Mutable slice containing all `FileNode`s in the application's arena.
-/// * `root_id` - The `FileId` of the root node of the current tree.
+/// * `root_ids` - The `FileId`s of the root nodes of the current tree.
 /// * `file_path` - The path to the JSON selection file to load.
 ///
 /// # Returns
-/// * `Ok(String)` containing the root path stored in the loaded selection file.
+/// * `Ok(Vec<String>)` containing the root paths stored in the loaded selection file.
 /// * `Err(anyhow::Error)` on failure (e.g., I/O error, deserialization error, missing root).
 pub fn load_selection_from_file(
     nodes: &mut [FileNode],
-    root_id: Option<FileId>,
+    root_ids: &[FileId],
     file_path: &Path,
-) -> anyhow::Result<String> {
+) -> anyhow::Result<Vec<String>> {
     log::info!("Loading selection state from {}", file_path.display());
 
     // Read and deserialize the file
@@ -157,38 +173,39 @@ pub fn load_selection_from_file(
             e
         )
     })?;
-    let saved_root_path = data.root_path.clone();
+    let saved_roots = if data.roots.is_empty() {
+        vec![data.root_path.clone()]
+    } else {
+        data.roots.clone()
+    };
     log::info!(
-        "Selection file (v{}) loaded successfully. Saved for root: {}",
+        "Selection file (v{}) loaded successfully. Saved for root(s): {}",
         data.app_version,
-        saved_root_path
+        saved_roots.join(", ")
     );
 
-    let root_id = match root_id {
-        Some(id) => id,
-        None => {
-            log::warn!(
-                "Attempted to load selection, but no root node ID is set in the current tree."
-            );
-            // Return the saved path, but indicate that nothing was applied
-            return Ok(saved_root_path);
-        }
-    };
+    if root_ids.is_empty() {
+        log::warn!("Attempted to load selection, but no root node ID is set in the current tree.");
+        // Return the saved paths, but indicate that nothing was applied
+        return Ok(saved_roots);
+    }
 
     // --- Apply Selection ---
     // 1. Build a map from relative path string to FileId for the *current* tree
     log::debug!("Building path map of current tree for applying selection...");
     let mut path_to_id_map: HashMap<String, FileId> = HashMap::new();
-    if let Some(root_node) = nodes.get(root_id) {
-        let root_relative_path = PathBuf::new();
-        for &child_id in &root_node.children {
-            build_path_map_recursive(nodes, child_id, &root_relative_path, &mut path_to_id_map);
+    for &root_id in root_ids {
+        let Some(root_node) = nodes.get(root_id) else {
+            log::error!("Root node ID {root_id} is invalid during load selection.");
+            return Err(anyhow::anyhow!("Invalid root node ID during load."));
+        };
+        if root_ids.len() > 1 {
+            build_path_map_recursive(nodes, root_id, Path::new(""), &mut path_to_id_map);
+        } else {
+            for &child_id in &root_node.children {
+                build_path_map_recursive(nodes, child_id, Path::new(""), &mut path_to_id_map);
+            }
         }
-        // If root state was saved with ".", map it:
-        // path_to_id_map.insert(".".to_string(), root_id);
-    } else {
-        log::error!("Root node ID {root_id} is invalid during load selection.");
-        return Err(anyhow::anyhow!("Invalid root node ID during load."));
     }
     log::debug!("Path map built with {} entries.", path_to_id_map.len());
 
@@ -229,7 +246,7 @@ pub fn load_selection_from_file(
     // The caller (`CodebaseApp::perform_load_selection`) is responsible for calling
     // `recalculate_all_parent_states` on the root node to fix this.
 
-    Ok(saved_root_path) // Return the root path stored in the file for comparison/warning
+    Ok(saved_roots) // Return the root paths stored in the file for comparison/warning
 }
 
 /// Recursive helper to build a map from relative path string to `FileId`.
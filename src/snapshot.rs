@@ -0,0 +1,641 @@
+//! Scan snapshots: the per-language totals and per-file metrics of a scan saved to a
+//! JSON file, so the codebase can be compared between releases.
+//!
+//! Files are keyed by their path relative to the project (prefixed by the root's name
+//! in a multi-root workspace), the same keys used by selection files and reports, so
+//! snapshots of different checkouts of a project can be compared. Nothing else in a
+//! snapshot refers to absolute paths except `roots`, which is informational.
+
+use crate::{
+    fs::{LanguageStats, ScanStats},
+    model::{FileNode, NodeMetrics},
+    workspace,
+};
+use humansize::{format_size, DECIMAL};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+/// Bumped whenever the snapshot file layout changes.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Metrics of a single file in a snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotFile {
+    pub size: u64,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<usize>,
+}
+
+impl SnapshotFile {
+    /// Returns `true` if the metrics differ. Token counts are only compared when both
+    /// snapshots have one, so counting tokens in only one of them isn't a change.
+    fn differs_from(&self, other: &Self) -> bool {
+        let tokens_differ = matches!((self.tokens, other.tokens), (Some(a), Some(b)) if a != b);
+        self.size != other.size
+            || self.code != other.code
+            || self.comments != other.comments
+            || self.blanks != other.blanks
+            || tokens_differ
+    }
+
+    fn from_metrics(metrics: &NodeMetrics) -> Self {
+        Self {
+            size: metrics.size,
+            code: metrics.code,
+            comments: metrics.comments,
+            blanks: metrics.blanks,
+            tokens: metrics.tokens,
+        }
+    }
+}
+
+/// Line counts of one language in a snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotLanguage {
+    pub files: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+impl SnapshotLanguage {
+    fn from_stats(stats: &LanguageStats) -> Self {
+        Self {
+            files: stats.files.len(),
+            code: stats.code,
+            comments: stats.comments,
+            blanks: stats.blanks,
+        }
+    }
+}
+
+/// The per-language totals and per-file metrics of a scan at one point in time.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+    pub version: u32,
+    /// Version of the application that took the snapshot.
+    pub app_version: String,
+    /// When the snapshot was taken (RFC3339 format).
+    pub timestamp: String,
+    pub project_name: String,
+    /// Absolute root paths at the time of the snapshot.
+    pub roots: Vec<String>,
+    /// Per-language line counts keyed by language name.
+    pub languages: BTreeMap<String, SnapshotLanguage>,
+    /// Per-file metrics keyed by relative path.
+    pub files: BTreeMap<String, SnapshotFile>,
+}
+
+impl Snapshot {
+    /// Captures a snapshot of the scanned files below `roots`.
+    /// Expects the nodes' metrics to have been rolled up.
+    pub fn capture(
+        project_name: String,
+        nodes: &[FileNode],
+        roots: &[PathBuf],
+        stats: &ScanStats,
+    ) -> Self {
+        let files = nodes
+            .iter()
+            .filter(|node| !node.is_dir())
+            .map(|node| {
+                let path = workspace::relative_path(node.path(), roots)
+                    .to_string_lossy()
+                    .to_string();
+                (path, SnapshotFile::from_metrics(&node.metrics))
+            })
+            .collect();
+        Self {
+            version: SNAPSHOT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            project_name,
+            roots: roots.iter().map(|r| r.display().to_string()).collect(),
+            languages: stats
+                .language_stats
+                .iter()
+                .map(|(name, lang)| (name.clone(), SnapshotLanguage::from_stats(lang)))
+                .collect(),
+            files,
+        }
+    }
+
+    /// Writes the snapshot to `path` as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        log::info!("Saving scan snapshot to {}", path.display());
+        let file = BufWriter::new(File::create(path).map_err(|e| {
+            anyhow::anyhow!("Failed to create snapshot file '{}': {}", path.display(), e)
+        })?);
+        serde_json::to_writer_pretty(file, self)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize or write snapshot: {}", e))?;
+        Ok(())
+    }
+
+    /// Loads a snapshot file, rejecting files written by a newer, incompatible version.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = BufReader::new(File::open(path).map_err(|e| {
+            anyhow::anyhow!("Failed to open snapshot file '{}': {}", path.display(), e)
+        })?);
+        let snapshot: Snapshot = serde_json::from_reader(file).map_err(|e| {
+            anyhow::anyhow!("Failed to parse snapshot file '{}': {}", path.display(), e)
+        })?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            anyhow::bail!(
+                "Snapshot file '{}' has version {}, but this build only reads up to version {}.",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Short description for diff headers, e.g. `my_project @ 2026-01-31T10:00:00+00:00`.
+    pub fn label(&self) -> String {
+        format!("{} @ {}", self.project_name, self.timestamp)
+    }
+
+    fn total_code(&self) -> usize {
+        self.languages.values().map(|l| l.code).sum()
+    }
+
+    fn total_tokens(&self) -> Option<usize> {
+        self.files
+            .values()
+            .filter_map(|f| f.tokens)
+            .reduce(|a, b| a + b)
+    }
+}
+
+/// A file present in only one of two snapshots.
+#[derive(Serialize, Debug, Clone)]
+pub struct FileEntry {
+    pub path: String,
+    #[serde(flatten)]
+    pub file: SnapshotFile,
+}
+
+/// A file present in both snapshots whose metrics changed.
+#[derive(Serialize, Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub old: SnapshotFile,
+    pub new: SnapshotFile,
+}
+
+impl FileChange {
+    pub fn size_delta(&self) -> i64 {
+        self.new.size as i64 - self.old.size as i64
+    }
+
+    pub fn code_delta(&self) -> i64 {
+        self.new.code as i64 - self.old.code as i64
+    }
+
+    /// Token delta, if both snapshots have token counts for the file.
+    pub fn tokens_delta(&self) -> Option<i64> {
+        Some(self.new.tokens? as i64 - self.old.tokens? as i64)
+    }
+}
+
+/// Per-language line counts in the old and new snapshot.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageDelta {
+    pub language: String,
+    pub old_files: usize,
+    pub new_files: usize,
+    pub old_code: usize,
+    pub new_code: usize,
+    pub old_comments: usize,
+    pub new_comments: usize,
+}
+
+impl LanguageDelta {
+    pub fn files_delta(&self) -> i64 {
+        self.new_files as i64 - self.old_files as i64
+    }
+
+    pub fn code_delta(&self) -> i64 {
+        self.new_code as i64 - self.old_code as i64
+    }
+
+    pub fn comments_delta(&self) -> i64 {
+        self.new_comments as i64 - self.old_comments as i64
+    }
+}
+
+/// Differences between two snapshots.
+#[derive(Serialize, Debug, Clone)]
+pub struct SnapshotDiff {
+    pub old_label: String,
+    pub new_label: String,
+    pub old_files: usize,
+    pub new_files: usize,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub old_code: usize,
+    pub new_code: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_tokens: Option<usize>,
+    /// Files only in the new snapshot, largest first.
+    pub added: Vec<FileEntry>,
+    /// Files only in the old snapshot, largest first.
+    pub removed: Vec<FileEntry>,
+    /// Files in both snapshots whose metrics changed, largest growth first.
+    pub changed: Vec<FileChange>,
+    /// Languages whose file count or line counts changed, largest code change first.
+    pub languages: Vec<LanguageDelta>,
+}
+
+impl SnapshotDiff {
+    /// Compares an older snapshot with a newer one.
+    pub fn new(old: &Snapshot, new: &Snapshot) -> Self {
+        let only_in = |files: &BTreeMap<String, SnapshotFile>,
+                       other: &BTreeMap<String, SnapshotFile>| {
+            let mut entries: Vec<FileEntry> = files
+                .iter()
+                .filter(|(path, _)| !other.contains_key(*path))
+                .map(|(path, file)| FileEntry {
+                    path: path.clone(),
+                    file: *file,
+                })
+                .collect();
+            entries.sort_by_key(|entry| std::cmp::Reverse(entry.file.size));
+            entries
+        };
+        let added = only_in(&new.files, &old.files);
+        let removed = only_in(&old.files, &new.files);
+        let mut changed: Vec<_> = old
+            .files
+            .iter()
+            .filter_map(|(path, old_file)| {
+                let new_file = new.files.get(path)?;
+                new_file.differs_from(old_file).then(|| FileChange {
+                    path: path.clone(),
+                    old: *old_file,
+                    new: *new_file,
+                })
+            })
+            .collect();
+        changed.sort_by_key(|change| std::cmp::Reverse(change.size_delta()));
+
+        let names: BTreeSet<&String> = old.languages.keys().chain(new.languages.keys()).collect();
+        let mut languages: Vec<LanguageDelta> = names
+            .into_iter()
+            .map(|name| {
+                let old_lang = old.languages.get(name);
+                let new_lang = new.languages.get(name);
+                LanguageDelta {
+                    language: name.clone(),
+                    old_files: old_lang.map_or(0, |l| l.files),
+                    new_files: new_lang.map_or(0, |l| l.files),
+                    old_code: old_lang.map_or(0, |l| l.code),
+                    new_code: new_lang.map_or(0, |l| l.code),
+                    old_comments: old_lang.map_or(0, |l| l.comments),
+                    new_comments: new_lang.map_or(0, |l| l.comments),
+                }
+            })
+            .filter(|delta| {
+                delta.files_delta() != 0 || delta.code_delta() != 0 || delta.comments_delta() != 0
+            })
+            .collect();
+        languages.sort_by_key(|delta| std::cmp::Reverse(delta.code_delta().abs()));
+
+        Self {
+            old_label: old.label(),
+            new_label: new.label(),
+            old_files: old.files.len(),
+            new_files: new.files.len(),
+            old_size: old.files.values().map(|f| f.size).sum(),
+            new_size: new.files.values().map(|f| f.size).sum(),
+            old_code: old.total_code(),
+            new_code: new.total_code(),
+            old_tokens: old.total_tokens(),
+            new_tokens: new.total_tokens(),
+            added,
+            removed,
+            changed,
+            languages,
+        }
+    }
+
+    /// Changed files that got bigger.
+    pub fn grown(&self) -> impl Iterator<Item = &FileChange> {
+        self.changed.iter().filter(|c| c.size_delta() > 0)
+    }
+
+    /// Changed files that got smaller.
+    pub fn shrunk(&self) -> impl Iterator<Item = &FileChange> {
+        self.changed.iter().rev().filter(|c| c.size_delta() < 0)
+    }
+
+    /// Changed files whose size stayed the same, e.g. edits that kept the byte count or
+    /// files whose token count changed with the tokenizer.
+    pub fn same_size(&self) -> impl Iterator<Item = &FileChange> {
+        self.changed.iter().filter(|c| c.size_delta() == 0)
+    }
+
+    /// Returns `true` if the snapshots have the same files with the same metrics.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Renders the diff as plain text, listing at most `limit` files per section.
+    pub fn to_text(&self, limit: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Old: {}", self.old_label);
+        let _ = writeln!(out, "New: {}", self.new_label);
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "Files:  {} -> {} ({})",
+            self.old_files,
+            self.new_files,
+            signed(self.new_files as i64 - self.old_files as i64)
+        );
+        let _ = writeln!(
+            out,
+            "Size:   {} -> {} ({})",
+            format_size(self.old_size, DECIMAL),
+            format_size(self.new_size, DECIMAL),
+            signed_size(self.new_size as i64 - self.old_size as i64)
+        );
+        let _ = writeln!(
+            out,
+            "Code:   {} -> {} ({})",
+            self.old_code,
+            self.new_code,
+            signed(self.new_code as i64 - self.old_code as i64)
+        );
+        if let (Some(old), Some(new)) = (self.old_tokens, self.new_tokens) {
+            let _ = writeln!(
+                out,
+                "Tokens: {old} -> {new} ({})",
+                signed(new as i64 - old as i64)
+            );
+        }
+        if self.is_empty() {
+            let _ = writeln!(out, "\nNo file changes.");
+            return out;
+        }
+
+        if !self.languages.is_empty() {
+            let _ = writeln!(out, "\nLanguages:");
+            for delta in &self.languages {
+                let _ = writeln!(
+                    out,
+                    "  {:<15} files {:>6}  code {:>8}  comments {:>7}",
+                    delta.language,
+                    signed(delta.files_delta()),
+                    signed(delta.code_delta()),
+                    signed(delta.comments_delta())
+                );
+            }
+        }
+
+        let mut section = |title: &str, rows: Vec<String>| {
+            if rows.is_empty() {
+                return;
+            }
+            let _ = writeln!(out, "\n{title} ({}):", rows.len());
+            for row in rows.iter().take(limit) {
+                let _ = writeln!(out, "  {row}");
+            }
+            if rows.len() > limit {
+                let _ = writeln!(out, "  ... and {} more", rows.len() - limit);
+            }
+        };
+        let entry_row = |entry: &FileEntry| {
+            format!(
+                "{} ({}, {} code lines)",
+                entry.path,
+                format_size(entry.file.size, DECIMAL),
+                entry.file.code
+            )
+        };
+        let change_row = |change: &FileChange| {
+            let tokens = change
+                .tokens_delta()
+                .map(|delta| format!(", {} tokens", signed(delta)))
+                .unwrap_or_default();
+            format!(
+                "{} ({}, {} code lines{tokens})",
+                change.path,
+                signed_size(change.size_delta()),
+                signed(change.code_delta())
+            )
+        };
+        section("Added", self.added.iter().map(entry_row).collect());
+        section("Removed", self.removed.iter().map(entry_row).collect());
+        section("Grown", self.grown().map(change_row).collect());
+        section("Shrunk", self.shrunk().map(change_row).collect());
+        section("Same size", self.same_size().map(change_row).collect());
+        out
+    }
+}
+
+/// Formats a delta with an explicit sign, e.g. `+12` or `-3`.
+pub fn signed(delta: i64) -> String {
+    if delta > 0 {
+        format!("+{delta}")
+    } else {
+        delta.to_string()
+    }
+}
+
+/// Formats a size delta with an explicit sign, e.g. `+1.20 kB`.
+pub fn signed_size(delta: i64) -> String {
+    let size = format_size(delta.unsigned_abs(), DECIMAL);
+    match delta.signum() {
+        1 => format!("+{size}"),
+        -1 => format!("-{size}"),
+        _ => size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64, code: usize, tokens: Option<usize>) -> SnapshotFile {
+        SnapshotFile {
+            size,
+            code,
+            tokens,
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(files: &[(&str, SnapshotFile)], rust_code: usize) -> Snapshot {
+        let languages = BTreeMap::from([(
+            "Rust".to_string(),
+            SnapshotLanguage {
+                code: rust_code,
+                ..Default::default()
+            },
+        )]);
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            app_version: String::new(),
+            timestamp: String::new(),
+            project_name: "demo".to_string(),
+            roots: Vec::new(),
+            languages,
+            files: files
+                .iter()
+                .map(|(path, file)| (path.to_string(), *file))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn classifies_added_removed_and_changed_files() {
+        let old = snapshot(
+            &[
+                ("gone.rs", file(10, 1, None)),
+                ("grows.rs", file(100, 10, None)),
+                ("shrinks.rs", file(100, 10, None)),
+                ("same.rs", file(50, 5, None)),
+            ],
+            26,
+        );
+        let new = snapshot(
+            &[
+                ("new.rs", file(20, 2, None)),
+                ("grows.rs", file(150, 15, None)),
+                ("shrinks.rs", file(40, 4, None)),
+                ("same.rs", file(50, 5, None)),
+            ],
+            26,
+        );
+        let diff = SnapshotDiff::new(&old, &new);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].path, "new.rs");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].path, "gone.rs");
+        let paths = |changes: Vec<&FileChange>| -> Vec<String> {
+            changes.into_iter().map(|c| c.path.clone()).collect()
+        };
+        assert_eq!(paths(diff.grown().collect()), ["grows.rs"]);
+        assert_eq!(paths(diff.shrunk().collect()), ["shrinks.rs"]);
+        assert_eq!(diff.same_size().count(), 0);
+        assert_eq!((diff.old_size, diff.new_size), (260, 260));
+        assert!(diff.languages.is_empty());
+    }
+
+    #[test]
+    fn lists_token_only_changes_as_same_size() {
+        let old = snapshot(&[("lib.rs", file(100, 10, Some(40)))], 10);
+        let new = snapshot(&[("lib.rs", file(100, 10, Some(55)))], 10);
+        let diff = SnapshotDiff::new(&old, &new);
+
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.grown().count() + diff.shrunk().count(), 0);
+        assert_eq!(diff.same_size().count(), 1);
+        assert_eq!(diff.changed[0].tokens_delta(), Some(15));
+        let text = diff.to_text(10);
+        assert!(text.contains("Same size (1):"), "{text}");
+        assert!(
+            text.contains("lib.rs (0 B, 0 code lines, +15 tokens)"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn ignores_token_counts_missing_from_one_snapshot() {
+        let old = snapshot(&[("lib.rs", file(100, 10, None))], 10);
+        let new = snapshot(&[("lib.rs", file(100, 10, Some(40)))], 10);
+        let diff = SnapshotDiff::new(&old, &new);
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.old_tokens, None);
+        assert_eq!(diff.new_tokens, Some(40));
+        assert!(diff.to_text(10).contains("No file changes."));
+    }
+
+    #[test]
+    fn reports_language_deltas() {
+        let old = snapshot(&[("lib.rs", file(100, 10, None))], 10);
+        let new = snapshot(&[("lib.rs", file(120, 12, None))], 12);
+        let diff = SnapshotDiff::new(&old, &new);
+
+        assert_eq!(diff.languages.len(), 1);
+        assert_eq!(diff.languages[0].code_delta(), 2);
+        assert_eq!((diff.old_code, diff.new_code), (10, 12));
+        let text = diff.to_text(10);
+        assert!(text.contains("Code:   10 -> 12 (+2)"), "{text}");
+        assert!(
+            text.contains("Grown (1):\n  lib.rs (+20 B, +2 code lines)"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn captures_language_totals_without_per_file_lists_or_errors() {
+        let mut stats = ScanStats::default();
+        stats.language_stats.insert(
+            "Rust".to_string(),
+            LanguageStats {
+                code: 12,
+                comments: 3,
+                blanks: 1,
+                files: vec![
+                    crate::fs::FileLocStats {
+                        path: "src/lib.rs".to_string(),
+                        code: 12,
+                        comments: 3,
+                        blanks: 1,
+                    },
+                    Default::default(),
+                ],
+            },
+        );
+        stats.add_error(crate::fs::ScanError::new(
+            crate::fs::ScanErrorKind::PermissionDenied,
+            Some(PathBuf::from("/home/me/demo/secret")),
+            "Permission denied",
+        ));
+        let roots = [PathBuf::from("/home/me/demo")];
+        let snapshot = Snapshot::capture("demo".to_string(), &[], &roots, &stats);
+
+        assert_eq!(
+            snapshot.languages["Rust"],
+            SnapshotLanguage {
+                files: 2,
+                code: 12,
+                comments: 3,
+                blanks: 1,
+            }
+        );
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(!json.contains("secret"), "{json}");
+        assert!(!json.contains("src/lib.rs"), "{json}");
+    }
+
+    #[test]
+    fn limits_listed_files_per_section() {
+        let old = snapshot(&[], 0);
+        let files: Vec<(String, SnapshotFile)> = (0..5)
+            .map(|i| (format!("f{i}.rs"), file(10 + i, 1, None)))
+            .collect();
+        let borrowed: Vec<(&str, SnapshotFile)> =
+            files.iter().map(|(p, f)| (p.as_str(), *f)).collect();
+        let new = snapshot(&borrowed, 0);
+        let text = SnapshotDiff::new(&old, &new).to_text(2);
+
+        assert!(text.contains("Added (5):\n  f4.rs"), "{text}");
+        assert!(text.contains("  ... and 3 more"), "{text}");
+    }
+}
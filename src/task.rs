@@ -1,41 +1,54 @@
 //! Defines message types used for communication between the UI thread and background tasks.
 
 use crate::{
-    fs::ScanStats, // Use ScanStats from fs module
+    fs::{git::GitStatusMap, ScanError, ScanProgress, ScanStats}, // Use ScanStats from fs module
     llm::{
         gemini_service::AppError, // Error type for AI queries
         token_counter::{TokenCountError, TokenCountSummary},
     },
     model::FileNode, // Use FileNode from model module
 };
-use std::path::PathBuf;
+use std::{collections::HashSet, path::PathBuf};
 
 /// Messages sent from the background scanner thread (`fs::scanner`) to the UI thread (`app.rs`).
 #[derive(Debug)]
 pub enum ScanMessage {
-    /// A new file or directory node has been discovered.
-    AddNode(FileNode), // Keep for potential single-node updates if needed later
-    /// A batch of new file or directory nodes has been discovered.
-    AddNodes(Vec<FileNode>),
+    /// A single file or directory node was discovered or changed (used by the filesystem watcher).
+    AddNode(Box<FileNode>),
+    /// A batch of nodes discovered by the scan of `root`, each sent after its parent.
+    AddNodes {
+        root: PathBuf,
+        nodes: Vec<LinkedNode>,
+    },
+    /// A previously scanned path (and everything below it) no longer exists.
+    RemovePath(PathBuf),
     /// An error occurred during scanning (e.g., permission denied).
-    Error(String),
-    /// A progress update message (e.g., "Scanning file X...").
-    Progress(String),
+    Error(ScanError),
+    /// Periodic progress of a running scan (counts, throughput, current directory).
+    Progress(ScanProgress),
     /// Partial scan statistics (optional, if scanner aggregates incrementally).
     Stats(ScanStats),
     /// Indicates that the scan process has finished (normally or due to cancellation/error).
     Finished,
 }
 
+/// A scanned node linked to its parent by position in its scan's output.
+#[derive(Debug)]
+pub struct LinkedNode {
+    pub node: FileNode,
+    /// Index of the parent among all nodes the same scan has sent before, or `None` for the root.
+    pub parent: Option<usize>,
+}
+
 /// Messages sent from generic background tasks (currently report generation) to the UI thread.
 #[derive(Debug)]
 pub enum TaskMessage {
     /// A progress update message from the task (e.g., "Formatting report...").
     ReportProgress(String),
     /// Indicates that the report generation task has finished.
-    /// `Ok` contains the path where the report was saved successfully.
+    /// `Ok` contains the paths the report was saved to: one, or one per part of a split report.
     /// `Err` contains an error message describing the failure.
-    ReportFinished(Result<PathBuf, String>),
+    ReportFinished(Result<Vec<PathBuf>, String>),
     AIResponse(Result<String, AppError>),
     TokenCountFinished {
         job_id: u64,
@@ -50,5 +63,16 @@ pub enum TaskMessage {
     TokenCalculationFinished {
         job_id: u64,
     },
+    /// Git status read for `roots`. `Ok(None)` means no root is inside a git repository.
+    GitStatus {
+        roots: Vec<PathBuf>,
+        result: Result<Option<GitStatusMap>, String>,
+    },
+    /// Files below `roots` that changed since `reference`, for selecting them in the tree.
+    ChangedFiles {
+        roots: Vec<PathBuf>,
+        reference: String,
+        result: Result<HashSet<PathBuf>, String>,
+    },
     // Could add other task types here later, e.g., PreviewFinished(Result<PreviewCache, String>)
 }
@@ -0,0 +1,648 @@
+//! Draws the statistics dashboard: charts of the languages, extensions, file sizes,
+//! modification times and token counts of the scanned files, the largest files and
+//! the scan errors. Clicking a chart segment selects the matching files in the tree.
+
+use crate::{
+    app::{AppAction, CodebaseApp},
+    fs::{stats, ScanErrorKind, ScanStats},
+    model::{FileId, FileNode},
+};
+use egui::{
+    Align2, Color32, Context, CursorIcon, FontId, Pos2, Rect, RichText, ScrollArea, Sense, Shape,
+    Stroke, Ui, Window,
+};
+use std::{
+    f32::consts::{FRAC_PI_2, TAU},
+    time::SystemTime,
+};
+
+/// Colours of chart segments, reused in order.
+pub(crate) const PALETTE: [Color32; 10] = [
+    Color32::from_rgb(0x4E, 0x79, 0xA7),
+    Color32::from_rgb(0xF2, 0x8E, 0x2B),
+    Color32::from_rgb(0xE1, 0x57, 0x59),
+    Color32::from_rgb(0x76, 0xB7, 0xB2),
+    Color32::from_rgb(0x59, 0xA1, 0x4F),
+    Color32::from_rgb(0xED, 0xC9, 0x48),
+    Color32::from_rgb(0xB0, 0x7A, 0xA1),
+    Color32::from_rgb(0xFF, 0x9D, 0xA7),
+    Color32::from_rgb(0x9C, 0x75, 0x5F),
+    Color32::from_rgb(0xBA, 0xB0, 0xAC),
+];
+
+/// Number of languages shown in the pie chart before the rest are grouped as "Other".
+const MAX_SLICES: usize = 8;
+/// Number of extensions shown in the bar chart.
+const MAX_BARS: usize = 12;
+/// Number of entries in the largest files list.
+const MAX_LARGEST: usize = 10;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// A range of values shown as one histogram bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Bucket {
+    pub label: &'static str,
+    /// Inclusive lower bound.
+    pub min: u64,
+    /// Exclusive upper bound.
+    pub max: u64,
+}
+
+const fn bucket(label: &'static str, min: u64, max: u64) -> Bucket {
+    Bucket { label, min, max }
+}
+
+const SIZE_BUCKETS: [Bucket; 6] = [
+    bucket("< 1 KB", 0, 1_000),
+    bucket("1–10 KB", 1_000, 10_000),
+    bucket("10–100 KB", 10_000, 100_000),
+    bucket("100 KB–1 MB", 100_000, 1_000_000),
+    bucket("1–10 MB", 1_000_000, 10_000_000),
+    bucket("> 10 MB", 10_000_000, u64::MAX),
+];
+
+/// File ages in seconds.
+const AGE_BUCKETS: [Bucket; 6] = [
+    bucket("< 1 day", 0, DAY),
+    bucket("1–7 days", DAY, 7 * DAY),
+    bucket("1–4 weeks", 7 * DAY, 30 * DAY),
+    bucket("1–6 months", 30 * DAY, 182 * DAY),
+    bucket("6–12 months", 182 * DAY, 365 * DAY),
+    bucket("> 1 year", 365 * DAY, u64::MAX),
+];
+
+const TOKEN_BUCKETS: [Bucket; 5] = [
+    bucket("< 100", 0, 100),
+    bucket("100–1k", 100, 1_000),
+    bucket("1k–5k", 1_000, 5_000),
+    bucket("5k–20k", 5_000, 20_000),
+    bucket("> 20k", 20_000, u64::MAX),
+];
+
+/// A group of files represented by one chart segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ChartSegment {
+    /// Unclassified files of a language, by its display name.
+    Language(String),
+    /// Unclassified files with an extension key as used in `ScanStats::file_types`.
+    Extension(String),
+    /// Files whose size in bytes falls in the bucket.
+    Size(Bucket),
+    /// Files whose time since last modification, in seconds, falls in the bucket.
+    ModifiedAge(Bucket),
+    /// Files whose token count falls in the bucket.
+    Tokens(Bucket),
+}
+
+impl ChartSegment {
+    /// Returns `true` if `node` is a file belonging to this segment.
+    pub(crate) fn matches(&self, node: &FileNode, now: SystemTime) -> bool {
+        if node.is_dir() {
+            return false;
+        }
+        let info = &node.info;
+        let in_bucket = |bucket: &Bucket, value: u64| (bucket.min..bucket.max).contains(&value);
+        match self {
+            ChartSegment::Language(name) => {
+                info.class.is_none() && stats::language_name(info) == Some(name.as_str())
+            }
+            ChartSegment::Extension(key) => {
+                info.class.is_none() && stats::extension_key(info) == *key
+            }
+            ChartSegment::Size(bucket) => in_bucket(bucket, info.size),
+            ChartSegment::ModifiedAge(bucket) => info.modified.is_some_and(|modified| {
+                let age = now.duration_since(modified).unwrap_or_default();
+                in_bucket(bucket, age.as_secs())
+            }),
+            ChartSegment::Tokens(bucket) => node
+                .token_count
+                .is_some_and(|tokens| in_bucket(bucket, tokens as u64)),
+        }
+    }
+
+    /// Describes the selected files, e.g. `Rust file(s)` or `file(s) of 1–10 KB`.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            ChartSegment::Language(name) => format!("{name} file(s)"),
+            ChartSegment::Extension(key) => format!("{key} file(s)"),
+            ChartSegment::Size(bucket) => format!("file(s) of {}", bucket.label),
+            ChartSegment::ModifiedAge(bucket) => {
+                format!("file(s) last modified {} ago", bucket.label)
+            }
+            ChartSegment::Tokens(bucket) => format!("file(s) with {} tokens", bucket.label),
+        }
+    }
+}
+
+/// Chart data computed from the tree, kept until the scan results change.
+#[derive(Debug, Default)]
+pub(crate) struct DashboardData {
+    /// Node count, total size, total tokens and error count the data was computed from.
+    key: (usize, u64, Option<usize>, usize),
+    /// Lines of code per language, largest first.
+    languages: Vec<(String, u64)>,
+    /// File counts per extension, largest first.
+    extensions: Vec<(String, u64)>,
+    sizes: Vec<u64>,
+    ages: Vec<u64>,
+    /// Files per token bucket, if any token counts are known.
+    tokens: Option<Vec<u64>>,
+    largest: Vec<(FileId, String, u64)>,
+    errors: Vec<(ScanErrorKind, u64)>,
+}
+
+impl DashboardData {
+    fn key(app: &CodebaseApp) -> (usize, u64, Option<usize>, usize) {
+        let stats = app.scan_stats.as_ref();
+        let tokens = app
+            .root_ids
+            .iter()
+            .filter_map(|&id| app.nodes.get(id)?.metrics.tokens)
+            .reduce(|a, b| a + b);
+        (
+            app.nodes.len(),
+            stats.map_or(0, |s| s.total_size_bytes),
+            tokens,
+            stats.map_or(0, ScanStats::error_count),
+        )
+    }
+
+    fn compute(app: &CodebaseApp) -> Self {
+        let now = SystemTime::now();
+        let mut data = DashboardData {
+            key: Self::key(app),
+            sizes: vec![0; SIZE_BUCKETS.len()],
+            ages: vec![0; AGE_BUCKETS.len()],
+            ..Default::default()
+        };
+
+        if let Some(stats) = &app.scan_stats {
+            data.languages = stats
+                .language_stats
+                .iter()
+                .map(|(name, lang)| (name.clone(), lang.code as u64))
+                .filter(|&(_, code)| code > 0)
+                .collect();
+            data.languages
+                .sort_by_key(|&(_, code)| std::cmp::Reverse(code));
+            data.extensions = stats
+                .file_types
+                .iter()
+                .map(|(ext, &count)| (ext.clone(), count as u64))
+                .collect();
+            data.extensions
+                .sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+            data.extensions.truncate(MAX_BARS);
+            for kind in ScanErrorKind::ALL {
+                let count = stats.error_counts.get(&kind).copied().unwrap_or(0);
+                if count > 0 {
+                    data.errors.push((kind, count as u64));
+                }
+            }
+        }
+
+        let bucket_of = |buckets: &[Bucket], value: u64| {
+            buckets.iter().position(|b| (b.min..b.max).contains(&value))
+        };
+        let mut tokens = vec![0; TOKEN_BUCKETS.len()];
+        let mut any_tokens = false;
+        let mut largest = Vec::new();
+        for (id, node) in app.nodes.iter().enumerate() {
+            if node.is_dir() {
+                continue;
+            }
+            let info = &node.info;
+            if let Some(i) = bucket_of(&SIZE_BUCKETS, info.size) {
+                data.sizes[i] += 1;
+            }
+            if let Some(modified) = info.modified {
+                let age = now.duration_since(modified).unwrap_or_default().as_secs();
+                if let Some(i) = bucket_of(&AGE_BUCKETS, age) {
+                    data.ages[i] += 1;
+                }
+            }
+            if let Some(count) = node.token_count {
+                any_tokens = true;
+                if let Some(i) = bucket_of(&TOKEN_BUCKETS, count as u64) {
+                    tokens[i] += 1;
+                }
+            }
+            if info.class.is_none() && info.size > 0 {
+                largest.push((id, info.size));
+            }
+        }
+        data.tokens = any_tokens.then_some(tokens);
+        largest.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        data.largest = largest
+            .into_iter()
+            .take(MAX_LARGEST)
+            .map(|(id, size)| {
+                let path = app.relative_path(app.nodes[id].path());
+                (id, path.display().to_string(), size)
+            })
+            .collect();
+        data
+    }
+}
+
+/// Draws the statistics dashboard window.
+pub fn draw_stats_dashboard_window(app: &mut CodebaseApp, ctx: &Context) {
+    if !app.show_stats_dashboard {
+        return;
+    }
+    if app
+        .dashboard_data
+        .as_ref()
+        .map_or(true, |data| data.key != DashboardData::key(app))
+    {
+        app.dashboard_data = Some(DashboardData::compute(app));
+    }
+    let Some(data) = app.dashboard_data.take() else {
+        return;
+    };
+
+    let mut is_open = true;
+    let mut segment_clicked = None;
+    let mut node_to_select = None;
+    let mut error_kind_clicked = None;
+
+    Window::new("Statistics Dashboard")
+        .open(&mut is_open)
+        .resizable(true)
+        .default_size([860.0, 640.0])
+        .show(ctx, |ui| {
+            let Some(stats) = &app.scan_stats else {
+                ui.label("Open a directory to see its statistics.");
+                return;
+            };
+            if app.root_ids.is_empty() {
+                ui.label("Open a directory to see its statistics.");
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(format!(
+                        "{} files, {} dirs, {}",
+                        stats.total_files,
+                        stats.total_dirs,
+                        stats.total_size_human()
+                    ))
+                    .strong(),
+                );
+                if app.is_scanning {
+                    ui.spinner();
+                }
+            });
+            ui.label(
+                RichText::new(
+                    "Click a chart segment to add the matching files to the selection; \
+                     Ctrl-click (⌘-click) to select only them.",
+                )
+                .small()
+                .weak(),
+            );
+            ui.separator();
+
+            ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .show(ui, |ui| {
+                    ui.columns(2, |columns| {
+                        let ui = &mut columns[0];
+                        ui.heading("Languages (lines of code)");
+                        if data.languages.is_empty() {
+                            ui.label(RichText::new("No source files with line counts.").weak());
+                        } else if let Some(i) = pie_chart(ui, &data.languages, "lines of code") {
+                            segment_clicked =
+                                Some(ChartSegment::Language(data.languages[i].0.clone()));
+                        }
+                        ui.add_space(12.0);
+
+                        ui.heading("File Sizes");
+                        if let Some(i) = histogram(ui, &SIZE_BUCKETS, &data.sizes) {
+                            segment_clicked = Some(ChartSegment::Size(SIZE_BUCKETS[i]));
+                        }
+                        ui.add_space(12.0);
+
+                        ui.heading("Tokens per File");
+                        match &data.tokens {
+                            Some(tokens) => {
+                                if let Some(i) = histogram(ui, &TOKEN_BUCKETS, tokens) {
+                                    segment_clicked = Some(ChartSegment::Tokens(TOKEN_BUCKETS[i]));
+                                }
+                            }
+                            None => {
+                                ui.label(
+                                    RichText::new(
+                                        "No token counts yet (View ▸ Show Token Counts).",
+                                    )
+                                    .weak(),
+                                );
+                            }
+                        }
+
+                        let ui = &mut columns[1];
+                        ui.heading("Extensions (files)");
+                        let bars: Vec<(String, u64, String)> = data
+                            .extensions
+                            .iter()
+                            .map(|(ext, count)| (ext.clone(), *count, count.to_string()))
+                            .collect();
+                        if bars.is_empty() {
+                            ui.label(RichText::new("No files.").weak());
+                        } else if let Some(i) = bar_chart(ui, &bars) {
+                            segment_clicked =
+                                Some(ChartSegment::Extension(data.extensions[i].0.clone()));
+                        }
+                        ui.add_space(12.0);
+
+                        ui.heading("Last Modified");
+                        if let Some(i) = histogram(ui, &AGE_BUCKETS, &data.ages) {
+                            segment_clicked = Some(ChartSegment::ModifiedAge(AGE_BUCKETS[i]));
+                        }
+                        ui.add_space(12.0);
+
+                        ui.heading("Largest Files");
+                        for (id, path, size) in &data.largest {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(humansize::format_size(
+                                        *size,
+                                        humansize::DECIMAL,
+                                    ))
+                                    .monospace()
+                                    .small(),
+                                );
+                                if ui.link(path).on_hover_text("Select in tree").clicked() {
+                                    node_to_select = Some(*id);
+                                }
+                            });
+                        }
+                        ui.add_space(12.0);
+
+                        ui.heading("Scan Errors");
+                        if data.errors.is_empty() {
+                            ui.label(RichText::new("The last scan reported no errors.").weak());
+                        } else {
+                            let bars: Vec<(String, u64, String)> = data
+                                .errors
+                                .iter()
+                                .map(|(kind, count)| {
+                                    (kind.label().to_string(), *count, count.to_string())
+                                })
+                                .collect();
+                            if let Some(i) = bar_chart(ui, &bars) {
+                                error_kind_clicked = Some(data.errors[i].0);
+                            }
+                        }
+                    });
+                });
+        });
+
+    app.dashboard_data = Some(data);
+    if let Some(segment) = segment_clicked {
+        let replace = ctx.input(|i| i.modifiers.command);
+        app.queue_action(AppAction::SelectMatching { segment, replace });
+    }
+    if let Some(id) = node_to_select {
+        app.selected_node_id = Some(id);
+        if app.show_preview_panel {
+            app.trigger_preview_load(id, ctx);
+        }
+    }
+    if let Some(kind) = error_kind_clicked {
+        app.scan_error_kind_filter = Some(kind);
+        app.show_scan_errors_window = true;
+    }
+    if !is_open {
+        app.show_stats_dashboard = false;
+        app.dashboard_data = None;
+    }
+}
+
+/// Draws a pie chart with a legend. Slices beyond `MAX_SLICES` are grouped as "Other",
+/// which can't be clicked. `unit` names what the values count, for hover texts. Returns
+/// the index of the clicked slice.
+fn pie_chart(ui: &mut Ui, slices: &[(String, u64)], unit: &str) -> Option<usize> {
+    let shown = slices.len().min(MAX_SLICES);
+    let other: u64 = slices[shown..].iter().map(|(_, v)| v).sum();
+    let total = (slices.iter().map(|(_, v)| v).sum::<u64>()).max(1) as f32;
+    let mut clicked = None;
+
+    ui.horizontal(|ui| {
+        let size = 150.0;
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), Sense::click());
+        let center = rect.center();
+        let radius = size / 2.0 - 4.0;
+
+        // Slice index under the pointer, measured clockwise from 12 o'clock.
+        let hovered = response.hover_pos().and_then(|pos| {
+            let offset = pos - center;
+            if offset.length() > radius {
+                return None;
+            }
+            let angle = (offset.y.atan2(offset.x) + FRAC_PI_2).rem_euclid(TAU);
+            let mut start = 0.0;
+            for (i, (_, value)) in slices[..shown].iter().enumerate() {
+                let end = start + *value as f32 / total * TAU;
+                if angle < end {
+                    return Some(i);
+                }
+                start = end;
+            }
+            None
+        });
+
+        let painter = ui.painter_at(rect);
+        let mut start = -FRAC_PI_2;
+        let values = slices[..shown]
+            .iter()
+            .map(|(_, v)| *v)
+            .chain((other > 0).then_some(other));
+        for (i, value) in values.enumerate() {
+            let sweep = value as f32 / total * TAU;
+            let color = if i < shown {
+                PALETTE[i % PALETTE.len()]
+            } else {
+                ui.visuals().weak_text_color()
+            };
+            let r = if hovered == Some(i) {
+                radius + 3.0
+            } else {
+                radius
+            };
+            paint_slice(&painter, center, r, start, sweep, color);
+            start += sweep;
+        }
+
+        if let Some(i) = hovered {
+            let (name, value) = &slices[i];
+            let percent = *value as f32 / total * 100.0;
+            response
+                .clone()
+                .on_hover_cursor(CursorIcon::PointingHand)
+                .on_hover_text_at_pointer(format!("{name}: {value} {unit} ({percent:.1}%)"));
+            if response.clicked() {
+                clicked = Some(i);
+            }
+        }
+
+        ui.vertical(|ui| {
+            for (i, (name, value)) in slices[..shown].iter().enumerate() {
+                ui.horizontal(|ui| {
+                    swatch(ui, PALETTE[i % PALETTE.len()]);
+                    let percent = *value as f32 / total * 100.0;
+                    if ui
+                        .selectable_label(false, format!("{name} ({percent:.0}%)"))
+                        .on_hover_text(format!("{value} {unit}"))
+                        .clicked()
+                    {
+                        clicked = Some(i);
+                    }
+                });
+            }
+            if other > 0 {
+                ui.horizontal(|ui| {
+                    swatch(ui, ui.visuals().weak_text_color());
+                    ui.label(format!("Other ({:.0}%)", other as f32 / total * 100.0));
+                });
+            }
+        });
+    });
+    clicked
+}
+
+/// Paints a pie slice as convex pieces of at most a quarter turn each.
+fn paint_slice(
+    painter: &egui::Painter,
+    center: Pos2,
+    radius: f32,
+    start: f32,
+    sweep: f32,
+    color: Color32,
+) {
+    let pieces = (sweep / FRAC_PI_2).ceil().max(1.0) as usize;
+    let piece_sweep = sweep / pieces as f32;
+    for piece in 0..pieces {
+        let piece_start = start + piece as f32 * piece_sweep;
+        let steps = ((piece_sweep * radius / 4.0).ceil() as usize).max(2);
+        let mut points = vec![center];
+        points.extend((0..=steps).map(|step| {
+            let angle = piece_start + piece_sweep * step as f32 / steps as f32;
+            center + radius * egui::vec2(angle.cos(), angle.sin())
+        }));
+        painter.add(Shape::convex_polygon(points, color, Stroke::NONE));
+    }
+}
+
+/// Draws a small colour square for a legend entry.
+fn swatch(ui: &mut Ui, color: Color32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), Sense::hover());
+    ui.painter().rect_filled(rect, 2.0, color);
+}
+
+/// Draws horizontal bars labelled with `(label, value, value_text)`.
+/// Returns the index of the clicked bar.
+fn bar_chart(ui: &mut Ui, bars: &[(String, u64, String)]) -> Option<usize> {
+    let max = bars.iter().map(|(_, v, _)| *v).max().unwrap_or(0).max(1) as f32;
+    let text_color = ui.visuals().text_color();
+    let font = FontId::proportional(12.0);
+    let mut clicked = None;
+    for (i, (label, value, value_text)) in bars.iter().enumerate() {
+        let (rect, response) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 18.0), Sense::click());
+        let color = PALETTE[i % PALETTE.len()];
+        let alpha = if response.hovered() { 200 } else { 130 };
+        let bar = Rect::from_min_size(
+            rect.min,
+            egui::vec2(rect.width() * *value as f32 / max, rect.height() - 2.0),
+        );
+        let painter = ui.painter();
+        painter.rect_filled(
+            bar,
+            2.0,
+            Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha),
+        );
+        painter.text(
+            rect.left_center() + egui::vec2(4.0, -1.0),
+            Align2::LEFT_CENTER,
+            label,
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            rect.right_center() + egui::vec2(-4.0, -1.0),
+            Align2::RIGHT_CENTER,
+            value_text,
+            font.clone(),
+            text_color,
+        );
+        if response.on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            clicked = Some(i);
+        }
+    }
+    clicked
+}
+
+/// Draws a vertical bar per bucket with its count above and label below.
+/// Returns the index of the clicked bar.
+fn histogram(ui: &mut Ui, buckets: &[Bucket], counts: &[u64]) -> Option<usize> {
+    let height = 120.0;
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), height), Sense::click());
+    let label_height = 16.0;
+    let chart = Rect::from_min_max(
+        rect.min + egui::vec2(0.0, label_height),
+        rect.max - egui::vec2(0.0, label_height),
+    );
+    let slot = rect.width() / buckets.len().max(1) as f32;
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let hovered = response
+        .hover_pos()
+        .map(|pos| (((pos.x - rect.left()) / slot) as usize).min(buckets.len() - 1));
+
+    let painter = ui.painter_at(rect);
+    let text_color = ui.visuals().text_color();
+    let weak_color = ui.visuals().weak_text_color();
+    let font = FontId::proportional(11.0);
+    for (i, (bucket, &count)) in buckets.iter().zip(counts).enumerate() {
+        let left = rect.left() + i as f32 * slot;
+        let bar_height = chart.height() * count as f32 / max;
+        let bar = Rect::from_min_max(
+            egui::pos2(left + slot * 0.15, chart.bottom() - bar_height),
+            egui::pos2(left + slot * 0.85, chart.bottom()),
+        );
+        let color = PALETTE[0];
+        let alpha = if hovered == Some(i) { 230 } else { 160 };
+        painter.rect_filled(
+            bar,
+            2.0,
+            Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha),
+        );
+        painter.text(
+            egui::pos2(bar.center().x, bar.top() - 2.0),
+            Align2::CENTER_BOTTOM,
+            count.to_string(),
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            egui::pos2(bar.center().x, rect.bottom()),
+            Align2::CENTER_BOTTOM,
+            bucket.label,
+            font.clone(),
+            weak_color,
+        );
+    }
+    painter.hline(
+        chart.x_range(),
+        chart.bottom(),
+        Stroke::new(1.0, weak_color),
+    );
+
+    let i = hovered?;
+    let response = response.on_hover_cursor(CursorIcon::PointingHand);
+    let response =
+        response.on_hover_text_at_pointer(format!("{}: {} file(s)", buckets[i].label, counts[i]));
+    response.clicked().then_some(i)
+}
@@ -2,10 +2,22 @@
 
 use crate::{
     app::{state::TokenStatus, AppAction, CodebaseApp},
-    report::ReportFormat,
+    config::AppConfig,
+    fs::{scanner::ScanOptions, FileClass, ScanErrorKind},
+    report::{FitStrategy, ReportFormat, SplitLimit},
+    snapshot::{signed, signed_size, FileEntry},
 };
 use egui::{Button, Color32, Context, DragValue, Grid, RichText, ScrollArea, TextEdit, Window};
+use egui_extras::{Column, TableBuilder};
 use egui_phosphor::regular::*;
+use humansize::{format_size, DECIMAL};
+use std::path::PathBuf;
+
+/// Token budget offered when a limit is first enabled.
+const DEFAULT_MAX_TOKENS: usize = 100_000;
+
+/// Part size offered when splitting is first enabled.
+const DEFAULT_SPLIT: SplitLimit = SplitLimit::Tokens(50_000);
 
 /// Draws the Preferences window (modal).
 /// Uses a draft copy of the config to allow cancellation.
@@ -58,6 +70,24 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
                             ui.checkbox(&mut draft.respect_cbvignore, "Use .cbvignore files for custom ignores");
                             ui.end_row();
 
+                            ui.label("Follow Symlinks:");
+                            ui.checkbox(&mut draft.follow_symlinks, "Scan the targets of symbolic links (loops are skipped)");
+                            ui.end_row();
+
+                            ui.label("Scan Archives:");
+                            ui.checkbox(&mut draft.scan_archives, "Show the contents of .zip, .tar and .tar.gz files")
+                                .on_hover_text("Archive members are read in memory for previews and reports; nothing is extracted to disk.");
+                            ui.end_row();
+
+                            ui.label("Watch for Changes:");
+                            ui.checkbox(&mut draft.watch_filesystem, "Update the tree live when files change on disk");
+                            ui.end_row();
+
+                            ui.label("Use Scan Index:");
+                            ui.checkbox(&mut draft.use_scan_index, "Reuse analysis of unchanged files between scans")
+                                .on_hover_text("Binary checks, line counts and token counts are cached per project in the config directory. Use File > Clear Scan Index to reset.");
+                            ui.end_row();
+
                             ui.label("Auto-expand Limit:");
                             ui.add(
                                 DragValue::new(&mut draft.auto_expand_limit)
@@ -68,6 +98,65 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
                             ui.end_row();
                         });
 
+                    ui.separator();
+                    ui.heading("Scan Filters");
+                    ui.add_space(4.0);
+                    ui.label(RichText::new("One glob per line, relative to the project root. Applied on top of .gitignore/.cbvignore.").small().weak());
+                    Grid::new("prefs_filters_grid")
+                        .num_columns(2)
+                        .spacing([40.0, 8.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Include Globs:");
+                            glob_list_editor(ui, &mut draft.include_globs, "src/**/*.rs")
+                                .on_hover_text("Only files matching one of these globs are scanned. Leave empty to include everything. Prefix a glob with '!' to exclude it.");
+                            ui.end_row();
+
+                            ui.label("Exclude Globs:");
+                            glob_list_editor(ui, &mut draft.exclude_globs, "**/generated/**")
+                                .on_hover_text("Files and directories matching these globs are skipped.");
+                            ui.end_row();
+
+                            ui.label("Classify Files:");
+                            ui.checkbox(&mut draft.classify_files, "Flag generated, vendored and documentation files")
+                                .on_hover_text("Uses linguist-generated/linguist-vendored/linguist-documentation in .gitattributes, '@generated'/'DO NOT EDIT' headers and common paths (lockfiles, vendor/, docs/). Flagged files are greyed out and left out of the statistics.");
+                            ui.end_row();
+
+                            ui.label("Deselect After Scan:");
+                            ui.add_enabled_ui(draft.classify_files, |ui| {
+                                ui.horizontal(|ui| {
+                                    for class in FileClass::ALL {
+                                        let mut deselect = draft.deselect_file_classes.contains(&class);
+                                        if ui.checkbox(&mut deselect, class.label()).changed() {
+                                            if deselect {
+                                                draft.deselect_file_classes.push(class);
+                                            } else {
+                                                draft.deselect_file_classes.retain(|c| *c != class);
+                                            }
+                                        }
+                                    }
+                                });
+                            })
+                            .response
+                            .on_hover_text("Flagged files of these kinds start out deselected. Takes effect on the next scan.");
+                            ui.end_row();
+
+                            ui.label("Comment Markers:");
+                            let mut keywords = draft.marker_keywords.join(",");
+                            if ui
+                                .add(TextEdit::singleline(&mut keywords).hint_text("TODO, FIXME, HACK"))
+                                .on_hover_text("Comma-separated keywords collected from comments into View > Open Markers. Matched case-sensitively as whole words. Leave empty to disable.")
+                                .changed()
+                            {
+                                draft.marker_keywords = if keywords.is_empty() {
+                                    Vec::new()
+                                } else {
+                                    keywords.split(',').map(str::to_string).collect()
+                                };
+                            }
+                            ui.end_row();
+                        });
+
                     ui.separator();
                     ui.heading("Preview & Export");
                     ui.add_space(4.0);
@@ -92,6 +181,8 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
                                     ui.selectable_value(&mut draft.export_format, "markdown".into(), "Markdown");
                                     ui.selectable_value(&mut draft.export_format, "html".into(), "HTML");
                                     ui.selectable_value(&mut draft.export_format, "text".into(), "Text");
+                                    ui.selectable_value(&mut draft.export_format, "json".into(), "JSON");
+                                    ui.selectable_value(&mut draft.export_format, "llm".into(), "LLM (XML tags)");
                                 });
                             ui.end_row();
 
@@ -100,8 +191,23 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
                                 ui.checkbox(&mut draft.export_include_stats, "Include Statistics Section");
                                 ui.checkbox(&mut draft.export_include_contents, "Include Selected File Contents");
                                 ui.checkbox(&mut draft.export_include_line_numbers, "Include Line Numbers in File Contents");
+                                ui.checkbox(&mut draft.export_include_markers, "Include Open Markers Section");
+                                ui.checkbox(&mut draft.export_include_dependencies, "Include Dependencies Section");
                             });
                             ui.end_row();
+
+                            ui.label("Default Token Budget:");
+                            token_budget_editor(ui, &mut draft.export_max_tokens, &mut draft.export_fit_strategy);
+                            ui.end_row();
+
+                            ui.label("Default Split:");
+                            split_editor(ui, &mut draft.export_split);
+                            ui.end_row();
+
+                            ui.label("Low-Priority Directories:");
+                            glob_list_editor(ui, &mut draft.low_priority_dirs, "**/tests")
+                                .on_hover_text("Globs for directories whose files are dropped first when fitting a report by dropping directories. Earlier entries are dropped first.");
+                            ui.end_row();
                         });
 
                     ui.separator();
@@ -185,10 +291,43 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
     }
 
     if save_clicked {
+        if let Some(draft) = app.prefs_draft.as_mut() {
+            for globs in [
+                &mut draft.include_globs,
+                &mut draft.exclude_globs,
+                &mut draft.marker_keywords,
+            ] {
+                globs.retain(|g| !g.trim().is_empty());
+                for glob in globs.iter_mut() {
+                    *glob = glob.trim().to_string();
+                }
+            }
+            let root = app
+                .roots
+                .first()
+                .cloned()
+                .unwrap_or_else(|| PathBuf::from("."));
+            if let Err(e) = ScanOptions::from_config(draft).build_overrides(&root) {
+                app.status_message = format!("Invalid scan glob: {e}");
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Invalid Scan Glob")
+                    .set_description(format!("{e}"))
+                    .show();
+                return;
+            }
+        }
         if let Some(new_cfg) = app.prefs_draft.take() {
             let theme_changed = new_cfg.theme != app.config.theme;
             let hidden_changed = new_cfg.show_hidden_files != app.config.show_hidden_files;
             let cbvignore_changed = new_cfg.respect_cbvignore != app.config.respect_cbvignore;
+            let symlinks_changed = new_cfg.follow_symlinks != app.config.follow_symlinks;
+            let archives_changed = new_cfg.scan_archives != app.config.scan_archives;
+            let classify_changed = new_cfg.classify_files != app.config.classify_files;
+            let globs_changed = new_cfg.include_globs != app.config.include_globs
+                || new_cfg.exclude_globs != app.config.exclude_globs;
+            let markers_changed = new_cfg.marker_keywords != app.config.marker_keywords;
+            let watch_changed = new_cfg.watch_filesystem != app.config.watch_filesystem;
             let token_settings_changed = new_cfg.max_file_size_token_count
                 != app.config.max_file_size_token_count
                 || new_cfg.max_file_size_token_cache != app.config.max_file_size_token_cache;
@@ -204,16 +343,27 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
                     }
                 }
             }
-            if hidden_changed || cbvignore_changed {
-                if let Some(root) = app.root_path.clone() {
-                    log::info!(
-                        "Scan setting changed (hidden files or .cbvignore), triggering rescan of '{}'",
-                        root.display()
-                    );
-                    app.queue_action(AppAction::StartScan(root));
+            let rescan_needed = hidden_changed
+                || cbvignore_changed
+                || symlinks_changed
+                || archives_changed
+                || classify_changed
+                || globs_changed
+                || markers_changed;
+            if rescan_needed && !app.roots.is_empty() {
+                log::info!(
+                    "Scan setting changed (hidden files, .cbvignore, symlinks, archives, classification, globs or marker keywords), triggering rescan"
+                );
+                app.queue_action(AppAction::Rescan);
+            }
+            if watch_changed {
+                if !app.config.watch_filesystem {
+                    app.queue_action(AppAction::StopWatching);
+                } else if !app.root_ids.is_empty() && !rescan_needed {
+                    app.queue_action(AppAction::StartWatching);
                 }
             }
-            if token_settings_changed && app.config.show_token_counts && app.root_id.is_some() {
+            if token_settings_changed && app.config.show_token_counts && !app.root_ids.is_empty() {
                 app.queue_action(AppAction::CancelTokenCalculation);
                 app.queue_action(AppAction::CalculateTokens);
             }
@@ -238,6 +388,26 @@ pub fn draw_preferences_window(app: &mut CodebaseApp, ctx: &Context) {
     }
 }
 
+/// Edits a list of globs as a multi-line text box, one glob per line.
+/// Blank lines are kept while editing and dropped when the preferences are saved.
+fn glob_list_editor(ui: &mut egui::Ui, globs: &mut Vec<String>, hint: &str) -> egui::Response {
+    let mut text = globs.join("\n");
+    let response = ui.add(
+        TextEdit::multiline(&mut text)
+            .desired_rows(3)
+            .code_editor()
+            .hint_text(hint),
+    );
+    if response.changed() {
+        *globs = if text.is_empty() {
+            Vec::new()
+        } else {
+            text.split('\n').map(str::to_string).collect()
+        };
+    }
+    response
+}
+
 pub fn draw_ai_query_window(app: &mut CodebaseApp, ctx: &Context) {
     if !app.show_ai_query_window {
         return;
@@ -258,15 +428,44 @@ pub fn draw_ai_query_window(app: &mut CodebaseApp, ctx: &Context) {
             );
             ui.add_space(8.0);
 
-            let send_enabled = !app.ai_query_text.trim().is_empty() && !app.is_querying_ai;
-            if ui
-                .add_enabled(send_enabled, Button::new("Send Query"))
-                .on_hover_text("Generate a fresh report context and send it to Gemini")
-                .clicked()
-            {
-                let prompt = app.ai_query_text.trim().to_owned();
-                app.queue_action(AppAction::QueryAI(prompt));
-            }
+            ui.horizontal(|ui| {
+                let send_enabled = !app.ai_query_text.trim().is_empty() && !app.is_querying_ai;
+                if ui
+                    .add_enabled(send_enabled, Button::new("Send Query"))
+                    .on_hover_text("Generate a fresh report context and send it to Gemini")
+                    .clicked()
+                {
+                    let prompt = app.ai_query_text.trim().to_owned();
+                    app.queue_action(AppAction::QueryAI(prompt));
+                }
+
+                ui.label("Context format:");
+                let previous = app.config.ai_context_format;
+                egui::ComboBox::from_id_salt("ai_context_format")
+                    .selected_text(match previous {
+                        ReportFormat::Llm => "XML tags",
+                        _ => "Markdown",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut app.config.ai_context_format,
+                            ReportFormat::Markdown,
+                            "Markdown",
+                        );
+                        ui.selectable_value(
+                            &mut app.config.ai_context_format,
+                            ReportFormat::Llm,
+                            "XML tags",
+                        )
+                        .on_hover_text("File boundaries survive code that contains ``` fences");
+                    });
+                if app.config.ai_context_format != previous {
+                    if let Err(e) = app.config.save() {
+                        log::error!("Failed to save config after context format change: {e}");
+                        app.status_message = format!("Error saving config: {e}");
+                    }
+                }
+            });
 
             ui.separator();
             ui.heading("Response");
@@ -289,6 +488,635 @@ pub fn draw_ai_query_window(app: &mut CodebaseApp, ctx: &Context) {
     }
 }
 
+/// Draws the token budget controls: a checkbox enabling the limit, the number of tokens
+/// and the strategy used to fit the report.
+fn token_budget_editor(
+    ui: &mut egui::Ui,
+    max_tokens: &mut Option<usize>,
+    strategy: &mut FitStrategy,
+) {
+    ui.horizontal(|ui| {
+        let mut limited = max_tokens.is_some();
+        if ui
+            .checkbox(&mut limited, "Limit to")
+            .on_hover_text("Drop or truncate files until the report fits this many tokens. What was left out is listed in the report.")
+            .changed()
+        {
+            *max_tokens = limited.then_some(DEFAULT_MAX_TOKENS);
+        }
+        ui.add_enabled_ui(limited, |ui| {
+            let mut tokens = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+            if ui
+                .add(
+                    DragValue::new(&mut tokens)
+                        .speed(1000.0)
+                        .range(1000..=10_000_000)
+                        .suffix(" tokens"),
+                )
+                .changed()
+            {
+                *max_tokens = Some(tokens);
+            }
+            egui::ComboBox::from_id_salt(ui.id().with("fit_strategy"))
+                .selected_text(strategy.label())
+                .show_ui(ui, |ui| {
+                    for option in FitStrategy::ALL {
+                        ui.selectable_value(strategy, option, option.label());
+                    }
+                });
+        });
+    });
+}
+
+/// Draws the report splitting controls: a checkbox enabling it, the part size and whether
+/// it's counted in tokens or bytes.
+fn split_editor(ui: &mut egui::Ui, split: &mut Option<SplitLimit>) {
+    ui.horizontal(|ui| {
+        let mut enabled = split.is_some();
+        if ui
+            .checkbox(&mut enabled, "Split into parts of")
+            .on_hover_text("Save or copy the report as numbered parts under this size, split between files. Every part repeats the project tree.")
+            .changed()
+        {
+            *split = enabled.then_some(DEFAULT_SPLIT);
+        }
+        ui.add_enabled_ui(enabled, |ui| {
+            let limit = split.unwrap_or(DEFAULT_SPLIT);
+            let mut value = limit.value();
+            let mut in_bytes = matches!(limit, SplitLimit::Bytes(_));
+            let value_changed = ui
+                .add(
+                    DragValue::new(&mut value)
+                        .speed(1000.0)
+                        .range(1000..=100_000_000),
+                )
+                .changed();
+            let mut unit_changed = false;
+            egui::ComboBox::from_id_salt(ui.id().with("split_unit"))
+                .selected_text(limit.unit())
+                .show_ui(ui, |ui| {
+                    unit_changed |= ui.selectable_value(&mut in_bytes, false, "tokens").changed();
+                    unit_changed |= ui.selectable_value(&mut in_bytes, true, "bytes").changed();
+                });
+            if value_changed || unit_changed {
+                *split = Some(if in_bytes {
+                    SplitLimit::Bytes(value)
+                } else {
+                    SplitLimit::Tokens(value)
+                });
+            }
+        });
+    });
+}
+
+/// Draws the report template selector with buttons to add and remove templates from the
+/// list kept in the config.
+fn draw_template_picker(ui: &mut egui::Ui, config: &mut AppConfig, template: &mut Option<PathBuf>) {
+    let file_name = |path: &PathBuf| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    };
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("report_template_combo")
+            .selected_text(
+                template
+                    .as_ref()
+                    .map_or("None (built-in format)".to_string(), file_name),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(template, None, "None (built-in format)");
+                for path in &config.report_templates {
+                    ui.selectable_value(template, Some(path.clone()), file_name(path))
+                        .on_hover_text(path.display().to_string());
+                }
+            });
+
+        let mut config_changed = false;
+        if ui
+            .button("Add…")
+            .on_hover_text("Add a Jinja template file to the list")
+            .clicked()
+        {
+            if let Some(path) = rfd::FileDialog::new()
+                .set_title("Select Report Template")
+                .pick_file()
+            {
+                if !config.report_templates.contains(&path) {
+                    config.report_templates.push(path.clone());
+                    config_changed = true;
+                }
+                *template = Some(path);
+            }
+        }
+        if ui
+            .add_enabled(template.is_some(), Button::new("Remove"))
+            .on_hover_text("Remove the selected template from the list")
+            .clicked()
+        {
+            if let Some(path) = template.take() {
+                config.report_templates.retain(|p| *p != path);
+                config_changed = true;
+            }
+        }
+        if config_changed {
+            if let Err(e) = config.save() {
+                log::error!("Failed to save config after changing report templates: {e}");
+            }
+        }
+    });
+}
+
+/// Draws the window listing scan errors, with filters and JSON export.
+pub fn draw_scan_errors_window(app: &mut CodebaseApp, ctx: &Context) {
+    if !app.show_scan_errors_window {
+        return;
+    }
+    let mut is_open = true;
+    let mut node_to_select = None;
+
+    Window::new("Scan Errors")
+        .open(&mut is_open)
+        .resizable(true)
+        .default_size([760.0, 420.0])
+        .show(ctx, |ui| {
+            let (total, omitted) = app
+                .scan_stats
+                .as_ref()
+                .map_or((0, 0), |s| (s.errors.len(), s.omitted_errors()));
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut app.scan_error_filter)
+                        .hint_text("Filter by path or message")
+                        .desired_width(240.0),
+                );
+                let kind_text = app
+                    .scan_error_kind_filter
+                    .map_or("All kinds", ScanErrorKind::label);
+                egui::ComboBox::from_id_salt("scan_error_kind")
+                    .selected_text(kind_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut app.scan_error_kind_filter, None, "All kinds");
+                        for kind in ScanErrorKind::ALL {
+                            ui.selectable_value(
+                                &mut app.scan_error_kind_filter,
+                                Some(kind),
+                                kind.label(),
+                            );
+                        }
+                    });
+                if ui
+                    .add_enabled(total > 0, Button::new(format!("{EXPORT} Export JSON...")))
+                    .on_hover_text("Save the errors shown below as a JSON file")
+                    .clicked()
+                {
+                    app.queue_action(AppAction::ExportScanErrors);
+                }
+            });
+
+            let errors = app.filtered_scan_errors();
+            let mut summary = format!("Showing {} of {} error(s)", errors.len(), total);
+            if omitted > 0 {
+                summary.push_str(&format!(", {omitted} more not listed"));
+            }
+            ui.label(RichText::new(summary).small().weak());
+            ui.separator();
+
+            if errors.is_empty() {
+                ui.label(if total == 0 {
+                    "The last scan reported no errors."
+                } else {
+                    "No errors match the filter."
+                });
+                return;
+            }
+
+            let row_height = ui.spacing().interact_size.y;
+            TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .column(Column::auto().at_least(110.0))
+                .column(Column::initial(300.0).at_least(120.0).clip(true))
+                .column(Column::remainder().clip(true))
+                .header(row_height, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("Kind");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Path");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Message");
+                    });
+                })
+                .body(|body| {
+                    body.rows(row_height, errors.len(), |mut row| {
+                        let error = errors[row.index()];
+                        row.col(|ui| {
+                            ui.label(error.kind.label());
+                        });
+                        row.col(|ui| match &error.path {
+                            Some(path) => {
+                                let text = app.relative_path(path).display().to_string();
+                                match app.path_to_id_map.get(path) {
+                                    Some(&id) => {
+                                        if ui.link(text).on_hover_text("Select in tree").clicked() {
+                                            node_to_select = Some(id);
+                                        }
+                                    }
+                                    None => {
+                                        ui.label(text).on_hover_text(path.display().to_string());
+                                    }
+                                }
+                            }
+                            None => {
+                                ui.label(RichText::new("(no path)").weak());
+                            }
+                        });
+                        row.col(|ui| {
+                            let mut message = error.message.clone();
+                            if let Some(code) = error.os_error {
+                                message.push_str(&format!(" (OS error {code})"));
+                            }
+                            ui.label(&message).on_hover_text(&message);
+                        });
+                    });
+                });
+        });
+
+    if let Some(id) = node_to_select {
+        app.selected_node_id = Some(id);
+        if app.show_preview_panel {
+            app.trigger_preview_load(id, ctx);
+        }
+    }
+    if !is_open {
+        app.show_scan_errors_window = false;
+    }
+}
+
+/// A list shown in the snapshot diff window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SnapshotDiffSection {
+    Languages,
+    Added,
+    Removed,
+    Grown,
+    Shrunk,
+    SameSize,
+}
+
+impl SnapshotDiffSection {
+    const ALL: [SnapshotDiffSection; 6] = [
+        SnapshotDiffSection::Languages,
+        SnapshotDiffSection::Added,
+        SnapshotDiffSection::Removed,
+        SnapshotDiffSection::Grown,
+        SnapshotDiffSection::Shrunk,
+        SnapshotDiffSection::SameSize,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SnapshotDiffSection::Languages => "Languages",
+            SnapshotDiffSection::Added => "Added",
+            SnapshotDiffSection::Removed => "Removed",
+            SnapshotDiffSection::Grown => "Grown",
+            SnapshotDiffSection::Shrunk => "Shrunk",
+            SnapshotDiffSection::SameSize => "Same size",
+        }
+    }
+}
+
+/// Draws the result of a snapshot comparison: totals, language deltas and file lists.
+pub fn draw_snapshot_diff_window(app: &mut CodebaseApp, ctx: &Context) {
+    if !app.show_snapshot_diff_window {
+        return;
+    }
+    let Some(diff) = app.snapshot_diff.take() else {
+        app.show_snapshot_diff_window = false;
+        return;
+    };
+    let mut is_open = true;
+    let mut path_to_reveal: Option<String> = None;
+
+    Window::new("Snapshot Diff")
+        .open(&mut is_open)
+        .resizable(true)
+        .default_size([720.0, 460.0])
+        .show(ctx, |ui| {
+            ui.label(format!("Old: {}", diff.old_label));
+            ui.label(format!("New: {}", diff.new_label));
+            ui.add_space(4.0);
+            Grid::new("snapshot_diff_totals")
+                .num_columns(4)
+                .spacing([24.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.strong("Old");
+                    ui.strong("New");
+                    ui.strong("Change");
+                    ui.end_row();
+                    ui.label("Files");
+                    ui.label(diff.old_files.to_string());
+                    ui.label(diff.new_files.to_string());
+                    ui.label(signed(diff.new_files as i64 - diff.old_files as i64));
+                    ui.end_row();
+                    ui.label("Size");
+                    ui.label(format_size(diff.old_size, DECIMAL));
+                    ui.label(format_size(diff.new_size, DECIMAL));
+                    ui.label(signed_size(diff.new_size as i64 - diff.old_size as i64));
+                    ui.end_row();
+                    ui.label("Code lines");
+                    ui.label(diff.old_code.to_string());
+                    ui.label(diff.new_code.to_string());
+                    ui.label(signed(diff.new_code as i64 - diff.old_code as i64));
+                    ui.end_row();
+                    if let (Some(old), Some(new)) = (diff.old_tokens, diff.new_tokens) {
+                        ui.label("Tokens");
+                        ui.label(old.to_string());
+                        ui.label(new.to_string());
+                        ui.label(signed(new as i64 - old as i64));
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                for section in SnapshotDiffSection::ALL {
+                    let count = match section {
+                        SnapshotDiffSection::Languages => diff.languages.len(),
+                        SnapshotDiffSection::Added => diff.added.len(),
+                        SnapshotDiffSection::Removed => diff.removed.len(),
+                        SnapshotDiffSection::Grown => diff.grown().count(),
+                        SnapshotDiffSection::Shrunk => diff.shrunk().count(),
+                        SnapshotDiffSection::SameSize => diff.same_size().count(),
+                    };
+                    ui.selectable_value(
+                        &mut app.snapshot_diff_section,
+                        section,
+                        format!("{} ({count})", section.label()),
+                    );
+                }
+            });
+            ui.separator();
+
+            let row_height = ui.spacing().interact_size.y;
+            let mut path_cell = |ui: &mut egui::Ui, path: &str, linked: bool| {
+                if !linked {
+                    ui.label(path).on_hover_text(path);
+                } else if ui.link(path).on_hover_text("Select in tree").clicked() {
+                    path_to_reveal = Some(path.to_string());
+                }
+            };
+            match app.snapshot_diff_section {
+                SnapshotDiffSection::Languages => {
+                    if diff.languages.is_empty() {
+                        ui.label("No language changes.");
+                        return;
+                    }
+                    TableBuilder::new(ui)
+                        .striped(true)
+                        .resizable(true)
+                        .column(Column::initial(160.0).at_least(100.0).clip(true))
+                        .columns(Column::auto().at_least(90.0), 3)
+                        .header(row_height, |mut header| {
+                            for title in ["Language", "Files", "Code lines", "Comment lines"] {
+                                header.col(|ui| {
+                                    ui.strong(title);
+                                });
+                            }
+                        })
+                        .body(|body| {
+                            body.rows(row_height, diff.languages.len(), |mut row| {
+                                let delta = &diff.languages[row.index()];
+                                row.col(|ui| {
+                                    ui.label(&delta.language);
+                                });
+                                for (old, new, change) in [
+                                    (delta.old_files, delta.new_files, delta.files_delta()),
+                                    (delta.old_code, delta.new_code, delta.code_delta()),
+                                    (
+                                        delta.old_comments,
+                                        delta.new_comments,
+                                        delta.comments_delta(),
+                                    ),
+                                ] {
+                                    row.col(|ui| {
+                                        ui.label(signed(change))
+                                            .on_hover_text(format!("{old} → {new}"));
+                                    });
+                                }
+                            });
+                        });
+                }
+                SnapshotDiffSection::Added | SnapshotDiffSection::Removed => {
+                    let added = app.snapshot_diff_section == SnapshotDiffSection::Added;
+                    let files: &[FileEntry] = if added { &diff.added } else { &diff.removed };
+                    if files.is_empty() {
+                        ui.label("No files.");
+                        return;
+                    }
+                    TableBuilder::new(ui)
+                        .striped(true)
+                        .resizable(true)
+                        .column(Column::remainder().at_least(200.0).clip(true))
+                        .columns(Column::auto().at_least(80.0), 2)
+                        .header(row_height, |mut header| {
+                            for title in ["Path", "Size", "Code lines"] {
+                                header.col(|ui| {
+                                    ui.strong(title);
+                                });
+                            }
+                        })
+                        .body(|body| {
+                            body.rows(row_height, files.len(), |mut row| {
+                                let entry = &files[row.index()];
+                                row.col(|ui| path_cell(ui, &entry.path, added));
+                                row.col(|ui| {
+                                    ui.label(format_size(entry.file.size, DECIMAL));
+                                });
+                                row.col(|ui| {
+                                    ui.label(entry.file.code.to_string());
+                                });
+                            });
+                        });
+                }
+                SnapshotDiffSection::Grown
+                | SnapshotDiffSection::Shrunk
+                | SnapshotDiffSection::SameSize => {
+                    let changes: Vec<_> = match app.snapshot_diff_section {
+                        SnapshotDiffSection::Grown => diff.grown().collect(),
+                        SnapshotDiffSection::Shrunk => diff.shrunk().collect(),
+                        _ => diff.same_size().collect(),
+                    };
+                    if changes.is_empty() {
+                        ui.label("No files.");
+                        return;
+                    }
+                    TableBuilder::new(ui)
+                        .striped(true)
+                        .resizable(true)
+                        .column(Column::remainder().at_least(200.0).clip(true))
+                        .columns(Column::auto().at_least(80.0), 3)
+                        .header(row_height, |mut header| {
+                            for title in ["Path", "Size", "Code lines", "Tokens"] {
+                                header.col(|ui| {
+                                    ui.strong(title);
+                                });
+                            }
+                        })
+                        .body(|body| {
+                            body.rows(row_height, changes.len(), |mut row| {
+                                let change = changes[row.index()];
+                                row.col(|ui| path_cell(ui, &change.path, true));
+                                row.col(|ui| {
+                                    ui.label(signed_size(change.size_delta())).on_hover_text(
+                                        format!(
+                                            "{} → {}",
+                                            format_size(change.old.size, DECIMAL),
+                                            format_size(change.new.size, DECIMAL)
+                                        ),
+                                    );
+                                });
+                                row.col(|ui| {
+                                    ui.label(signed(change.code_delta())).on_hover_text(format!(
+                                        "{} → {}",
+                                        change.old.code, change.new.code
+                                    ));
+                                });
+                                row.col(|ui| {
+                                    ui.label(change.tokens_delta().map_or("-".to_string(), signed));
+                                });
+                            });
+                        });
+                }
+            }
+        });
+
+    if let Some(path) = path_to_reveal {
+        let found = app
+            .nodes
+            .iter()
+            .position(|node| app.relative_path(node.path()).to_string_lossy() == path);
+        match found {
+            Some(id) => {
+                app.reveal_node(id);
+                if app.show_preview_panel {
+                    app.trigger_preview_load(id, ctx);
+                }
+            }
+            None => app.status_message = format!("'{path}' is not part of the current scan."),
+        }
+    }
+    app.snapshot_diff = Some(diff);
+    if !is_open {
+        app.show_snapshot_diff_window = false;
+    }
+}
+
+/// Draws the window for selecting files changed since a git ref.
+pub fn draw_changed_since_window(app: &mut CodebaseApp, ctx: &Context) {
+    if !app.show_changed_since_window {
+        return;
+    }
+    let mut is_open = true;
+    let mut submitted = false;
+
+    Window::new("Select Changed Files")
+        .open(&mut is_open)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label("Select only the files that differ from a git ref (branch, tag or commit):");
+            let response = ui.add(
+                TextEdit::singleline(&mut app.changed_since_ref)
+                    .hint_text("e.g. main, origin/main, HEAD~3")
+                    .desired_width(f32::INFINITY),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                submitted = true;
+            }
+            ui.label(
+                RichText::new("Staged, unstaged and untracked changes are included.")
+                    .small()
+                    .weak(),
+            );
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !app.changed_since_ref.trim().is_empty(),
+                        Button::new("Select"),
+                    )
+                    .clicked()
+                {
+                    submitted = true;
+                }
+            });
+        });
+
+    let reference = app.changed_since_ref.trim().to_string();
+    if submitted && !reference.is_empty() {
+        app.queue_action(AppAction::SelectChangedFiles(reference));
+        app.show_changed_since_window = false;
+    } else if !is_open {
+        app.show_changed_since_window = false;
+    }
+}
+
+/// Draws the window for copying a split report to the clipboard one part at a time.
+pub fn draw_report_parts_window(app: &mut CodebaseApp, ctx: &Context) {
+    let Some(pending) = &app.pending_report_parts else {
+        return;
+    };
+    let (copied, total) = (pending.copied, pending.parts.len());
+    let mut is_open = true;
+    let mut copy_next = false;
+    let mut close = false;
+
+    Window::new("Copy Report Parts")
+        .open(&mut is_open)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "The report was split into {total} parts. Part {copied} of {total} is on the clipboard."
+            ));
+            ui.label(
+                RichText::new("Paste each part before copying the next one.")
+                    .small()
+                    .weak(),
+            );
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if copied < total {
+                    if ui
+                        .button(format!("Copy Part {} of {total}", copied + 1))
+                        .clicked()
+                    {
+                        copy_next = true;
+                    }
+                } else {
+                    ui.label("All parts copied.");
+                }
+                if ui.button("Close").clicked() {
+                    close = true;
+                }
+            });
+        });
+
+    if copy_next {
+        app.queue_action(AppAction::CopyNextReportPart);
+    }
+    if close || !is_open {
+        app.pending_report_parts = None;
+    }
+}
+
 // ... (rest of the file is unchanged) ...
 pub fn draw_report_options_window(app: &mut CodebaseApp, ctx: &Context) {
     if !app.show_report_options_window {
@@ -320,6 +1148,7 @@ pub fn draw_report_options_window(app: &mut CodebaseApp, ctx: &Context) {
                     .striped(true)
                     .show(ui, |ui| {
                         ui.label("Format:");
+                        ui.add_enabled_ui(draft.template.is_none(), |ui| {
                         egui::ComboBox::from_id_salt("report_format_combo")
                             .selected_text(format!("{:?}", draft.format))
                             .show_ui(ui, |ui| {
@@ -338,7 +1167,32 @@ pub fn draw_report_options_window(app: &mut CodebaseApp, ctx: &Context) {
                                     ReportFormat::Text,
                                     "Text (.txt)",
                                 );
+                                ui.selectable_value(
+                                    &mut draft.format,
+                                    ReportFormat::Json,
+                                    "JSON (.json)",
+                                );
+                                ui.selectable_value(
+                                    &mut draft.format,
+                                    ReportFormat::Llm,
+                                    "LLM context (.xml)",
+                                );
                             });
+                        })
+                        .response
+                        .on_disabled_hover_text("The selected template determines the output");
+                        ui.end_row();
+
+                        ui.label("Token Budget:");
+                        token_budget_editor(ui, &mut draft.max_tokens, &mut draft.fit_strategy);
+                        ui.end_row();
+
+                        ui.label("Split:");
+                        split_editor(ui, &mut draft.split);
+                        ui.end_row();
+
+                        ui.label("Template:");
+                        draw_template_picker(ui, &mut app.config, &mut draft.template);
                         ui.end_row();
 
                         ui.label("Include Statistics:");
@@ -358,6 +1212,20 @@ pub fn draw_report_options_window(app: &mut CodebaseApp, ctx: &Context) {
                             .on_hover_text("Only applies if 'Include File Contents' is checked");
                         });
                         ui.end_row();
+
+                        ui.label("Include Open Markers:");
+                        ui.checkbox(
+                            &mut draft.include_markers,
+                            "List the TODO/FIXME/... comments of selected files",
+                        );
+                        ui.end_row();
+
+                        ui.label("Include Dependencies:");
+                        ui.checkbox(
+                            &mut draft.include_dependencies,
+                            "List the dependencies declared in the project's manifests",
+                        );
+                        ui.end_row();
                     });
 
                 if draft != previous_options {
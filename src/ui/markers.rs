@@ -0,0 +1,208 @@
+//! Draws the open markers window: the `TODO`/`FIXME`/... comments collected during the
+//! scan, filterable by keyword and text. Clicking a marker shows its line in the preview.
+
+use crate::{
+    app::CodebaseApp,
+    fs::CommentMarker,
+    model::{Check, FileId},
+};
+use egui::{Context, RichText, TextEdit, Window};
+use egui_extras::{Column, TableBuilder};
+
+/// One marker in the list.
+#[derive(Debug)]
+struct MarkerRow {
+    id: FileId,
+    path: String,
+    /// Position in the file's marker list.
+    index: usize,
+}
+
+/// The markers of all files sorted by path, kept until the scan results change.
+#[derive(Debug, Default)]
+pub(crate) struct MarkerList {
+    /// Node count, total size and marker count the list was built from.
+    key: (usize, u64, usize),
+    rows: Vec<MarkerRow>,
+    /// Distinct keywords found, in the configured order followed by any others.
+    keywords: Vec<String>,
+}
+
+impl MarkerList {
+    fn key(app: &CodebaseApp) -> (usize, u64, usize) {
+        let markers = app.nodes.iter().map(|node| node.info.markers().len()).sum();
+        (
+            app.nodes.len(),
+            app.scan_stats.as_ref().map_or(0, |s| s.total_size_bytes),
+            markers,
+        )
+    }
+
+    fn compute(app: &CodebaseApp) -> Self {
+        let mut rows = Vec::new();
+        let mut keywords: Vec<String> = app.config.marker_keywords.clone();
+        for (id, node) in app.nodes.iter().enumerate() {
+            let markers = node.info.markers();
+            if markers.is_empty() {
+                continue;
+            }
+            let path = app.relative_path(node.path()).display().to_string();
+            for (index, marker) in markers.iter().enumerate() {
+                if !keywords.contains(&marker.keyword) {
+                    keywords.push(marker.keyword.clone());
+                }
+                rows.push(MarkerRow {
+                    id,
+                    path: path.clone(),
+                    index,
+                });
+            }
+        }
+        rows.sort_by(|a, b| a.path.cmp(&b.path).then(a.index.cmp(&b.index)));
+        Self {
+            key: Self::key(app),
+            rows,
+            keywords,
+        }
+    }
+}
+
+/// Draws the open markers window.
+pub fn draw_markers_window(app: &mut CodebaseApp, ctx: &Context) {
+    if !app.show_markers_window {
+        return;
+    }
+    if app
+        .marker_list
+        .as_ref()
+        .map_or(true, |list| list.key != MarkerList::key(app))
+    {
+        app.marker_list = Some(MarkerList::compute(app));
+    }
+    let Some(list) = app.marker_list.take() else {
+        return;
+    };
+    let mut is_open = true;
+    let mut target: Option<(FileId, usize)> = None;
+
+    Window::new("Open Markers")
+        .open(&mut is_open)
+        .resizable(true)
+        .default_size([760.0, 420.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut app.marker_filter)
+                        .hint_text("Filter by path or text")
+                        .desired_width(240.0),
+                );
+                egui::ComboBox::from_id_salt("marker_keyword")
+                    .selected_text(
+                        app.marker_keyword_filter
+                            .as_deref()
+                            .unwrap_or("All markers"),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut app.marker_keyword_filter, None, "All markers");
+                        for keyword in &list.keywords {
+                            ui.selectable_value(
+                                &mut app.marker_keyword_filter,
+                                Some(keyword.clone()),
+                                keyword,
+                            );
+                        }
+                    });
+                ui.checkbox(&mut app.marker_selected_only, "Selected files only");
+            });
+
+            let needle = app.marker_filter.trim().to_lowercase();
+            let visible: Vec<(&MarkerRow, &CommentMarker)> = list
+                .rows
+                .iter()
+                .filter_map(|row| {
+                    let node = app.nodes.get(row.id)?;
+                    let marker = node.info.markers().get(row.index)?;
+                    let keep = (!app.marker_selected_only || node.state == Check::Checked)
+                        && app
+                            .marker_keyword_filter
+                            .as_ref()
+                            .map_or(true, |keyword| marker.keyword == *keyword)
+                        && (needle.is_empty()
+                            || row.path.to_lowercase().contains(&needle)
+                            || marker.text.to_lowercase().contains(&needle));
+                    keep.then_some((row, marker))
+                })
+                .collect();
+            ui.label(
+                RichText::new(format!(
+                    "Showing {} of {} marker(s)",
+                    visible.len(),
+                    list.rows.len()
+                ))
+                .small()
+                .weak(),
+            );
+            ui.separator();
+
+            if visible.is_empty() {
+                ui.label(if list.rows.is_empty() {
+                    if app.config.marker_keywords.is_empty() {
+                        "No marker keywords are configured (Preferences > Scan Filters)."
+                    } else {
+                        "No markers were found in the scanned files."
+                    }
+                } else {
+                    "No markers match the filter."
+                });
+                return;
+            }
+
+            let row_height = ui.spacing().interact_size.y;
+            TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .column(Column::auto().at_least(60.0))
+                .column(Column::initial(280.0).at_least(120.0).clip(true))
+                .column(Column::remainder().clip(true))
+                .header(row_height, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("Marker");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Location");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Text");
+                    });
+                })
+                .body(|body| {
+                    body.rows(row_height, visible.len(), |mut row| {
+                        let (marker_row, marker) = visible[row.index()];
+                        row.col(|ui| {
+                            ui.label(RichText::new(&marker.keyword).strong());
+                        });
+                        row.col(|ui| {
+                            let location = format!("{}:{}", marker_row.path, marker.line);
+                            if ui.link(location).on_hover_text("Show in preview").clicked() {
+                                target = Some((marker_row.id, marker.line));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.label(&marker.text).on_hover_text(&marker.text);
+                        });
+                    });
+                });
+        });
+
+    app.marker_list = Some(list);
+    if let Some((id, line)) = target {
+        app.reveal_node(id);
+        app.show_preview_panel = true;
+        app.preview_target_line = Some((id, line));
+        app.preview_scroll_pending = true;
+        app.trigger_preview_load(id, ctx);
+    }
+    if !is_open {
+        app.show_markers_window = false;
+    }
+}
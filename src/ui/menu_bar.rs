@@ -3,6 +3,8 @@
 use crate::{
     app::{AppAction, CodebaseApp},
     config, external,
+    fs::ScanStats,
+    workspace,
 };
 use egui::{Context, Key, Modifiers, TopBottomPanel};
 use std::path::PathBuf;
@@ -26,6 +28,41 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                     }
                 }
 
+                if ui.button("Open Workspace...").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter(
+                            "Codebase Viewer Workspace",
+                            &[workspace::WORKSPACE_EXTENSION],
+                        )
+                        .pick_file()
+                    {
+                        app.queue_action(AppAction::OpenWorkspace(path));
+                    }
+                }
+
+                let workspace_enabled = !app.roots.is_empty() && !app.is_scanning;
+                if ui
+                    .add_enabled(
+                        workspace_enabled,
+                        egui::Button::new("Add Folder to Workspace..."),
+                    )
+                    .on_hover_text("Scan another directory alongside the open one")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        app.queue_action(AppAction::AddWorkspaceRoot(path));
+                    }
+                }
+                if ui
+                    .add_enabled(workspace_enabled, egui::Button::new("Save Workspace As..."))
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.queue_action(AppAction::SaveWorkspace);
+                }
+
                 let recent_enabled = !app.config.recent_projects.is_empty();
                 ui.add_enabled_ui(recent_enabled, |ui| {
                     ui.menu_button("Recent Projects", |ui| {
@@ -40,6 +77,7 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                                 .enumerate()
                             {
                                 let display_path = path.display().to_string();
+                                let is_workspace = workspace::is_workspace_file(path);
                                 let label = path
                                     .file_name()
                                     .map(|name| name.to_string_lossy().to_string())
@@ -58,6 +96,15 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                                     9 => "0".to_string(),
                                     _ => "".to_string(),
                                 };
+                                let label = if is_workspace {
+                                    let stem = path.file_stem().map_or_else(
+                                        || label.clone(),
+                                        |s| s.to_string_lossy().into_owned(),
+                                    );
+                                    format!("{stem} (workspace)")
+                                } else {
+                                    label
+                                };
                                 let button_text = if shortcut_num.is_empty() {
                                     label
                                 } else {
@@ -85,7 +132,11 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                                 ui.close_menu();
                             }
                             if let Some(path) = path_to_open {
-                                app.queue_action(AppAction::StartScan(path));
+                                if workspace::is_workspace_file(&path) {
+                                    app.queue_action(AppAction::OpenWorkspace(path));
+                                } else {
+                                    app.queue_action(AppAction::StartScan(path));
+                                }
                             }
                         }
                     })
@@ -95,7 +146,7 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
 
                 ui.separator();
 
-                let selection_enabled = app.root_path.is_some();
+                let selection_enabled = !app.roots.is_empty();
                 let save_shortcut = ui
                     .ctx()
                     .format_shortcut(&egui::KeyboardShortcut::new(Modifiers::COMMAND, Key::S));
@@ -125,8 +176,35 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
 
                 ui.separator();
 
+                let snapshot_enabled = app.scan_stats.is_some() && !app.is_scanning;
+                if ui
+                    .add_enabled(snapshot_enabled, egui::Button::new("Save Snapshot..."))
+                    .on_hover_text("Save the statistics and per-file metrics of this scan")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.queue_action(AppAction::SaveSnapshot);
+                }
+                if ui
+                    .add_enabled(
+                        snapshot_enabled,
+                        egui::Button::new("Compare with Snapshot..."),
+                    )
+                    .on_hover_text("Show what changed since a saved snapshot")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.queue_action(AppAction::CompareWithSnapshot);
+                }
+                if ui.button("Compare Two Snapshots...").clicked() {
+                    ui.close_menu();
+                    app.queue_action(AppAction::CompareSnapshots);
+                }
+
+                ui.separator();
+
                 let report_enabled =
-                    app.root_path.is_some() && !app.is_scanning && !app.is_generating_report;
+                    !app.roots.is_empty() && !app.is_scanning && !app.is_generating_report;
                 let report_shortcut = ui
                     .ctx()
                     .format_shortcut(&egui::KeyboardShortcut::new(Modifiers::COMMAND, Key::G));
@@ -141,7 +219,7 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                     app.show_report_options_window = true;
                 }
 
-                let ai_enabled = app.root_path.is_some()
+                let ai_enabled = !app.roots.is_empty()
                     && !app.is_scanning
                     && !app.is_generating_report
                     && !app.is_querying_ai;
@@ -158,6 +236,28 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
 
                 ui.separator();
 
+                let can_clear_index = !app.is_scanning && !app.roots.is_empty();
+                if ui
+                    .add_enabled(can_clear_index, egui::Button::new("Clear Scan Index"))
+                    .on_hover_text(
+                        "Forget this project's cached file analysis so the next scan re-reads every file",
+                    )
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.queue_action(AppAction::ClearScanIndex);
+                }
+                if ui
+                    .add_enabled(!app.is_scanning, egui::Button::new("Clear All Scan Indexes"))
+                    .on_hover_text("Forget the cached file analysis of every project")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.queue_action(AppAction::ClearAllScanIndexes);
+                }
+
+                ui.separator();
+
                 let exit_shortcut = ui
                     .ctx()
                     .format_shortcut(&egui::KeyboardShortcut::new(Modifiers::COMMAND, Key::Q));
@@ -167,7 +267,7 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
             });
 
             ui.menu_button("Edit", |ui| {
-                let tree_loaded = app.root_id.is_some();
+                let tree_loaded = !app.root_ids.is_empty();
 
                 let select_all_shortcut = ui
                     .ctx()
@@ -200,6 +300,32 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
 
                 ui.separator();
 
+                let git_available = tree_loaded && app.git_status.is_some();
+                if ui
+                    .add_enabled(
+                        git_available,
+                        egui::Button::new("Select Changed Files (vs HEAD)"),
+                    )
+                    .on_hover_text("Select only files with uncommitted or untracked changes")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.queue_action(AppAction::SelectChangedFiles("HEAD".to_string()));
+                }
+                if ui
+                    .add_enabled(
+                        git_available,
+                        egui::Button::new("Select Changed Since Ref..."),
+                    )
+                    .on_hover_text("Select only files that differ from a branch, tag or commit")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.show_changed_since_window = true;
+                }
+
+                ui.separator();
+
                 let find_shortcut = ui
                     .ctx()
                     .format_shortcut(&egui::KeyboardShortcut::new(Modifiers::COMMAND, Key::F));
@@ -229,7 +355,7 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
             });
 
             ui.menu_button("View", |ui| {
-                let tree_loaded = app.root_id.is_some();
+                let tree_loaded = !app.root_ids.is_empty();
 
                 let expand_shortcut = ui.ctx().format_shortcut(&egui::KeyboardShortcut::new(
                     Modifiers::COMMAND,
@@ -299,6 +425,42 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                     ui.close_menu();
                 }
 
+                let error_count = app.scan_stats.as_ref().map_or(0, ScanStats::error_count);
+                if ui
+                    .button(format!("Scan Errors ({error_count})..."))
+                    .on_hover_text("Browse, filter and export errors from the last scan")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.show_scan_errors_window = true;
+                }
+                if ui
+                    .button("Statistics Dashboard...")
+                    .on_hover_text("Charts of languages, file sizes, ages and tokens")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.show_stats_dashboard = true;
+                }
+                if ui
+                    .button("Treemap...")
+                    .on_hover_text("See where the bytes, lines of code or tokens are")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.show_treemap_window = true;
+                }
+                let marker_count: usize =
+                    app.nodes.iter().map(|node| node.info.markers().len()).sum();
+                if ui
+                    .button(format!("Open Markers ({marker_count})..."))
+                    .on_hover_text("List the TODO/FIXME/... comments found during the scan")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    app.show_markers_window = true;
+                }
+
                 ui.separator();
 
                 let current_theme_str = app.config.theme.clone();
@@ -343,9 +505,7 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                         "Show hidden files toggled via menu to: {}",
                         app.config.show_hidden_files
                     );
-                    if let Some(root_path) = app.root_path.clone() {
-                        app.queue_action(AppAction::StartScan(root_path));
-                    }
+                    app.queue_action(AppAction::Rescan);
                     if let Err(e) = app.config.save() {
                         log::error!("Failed to save config after hidden file toggle: {e}");
                         app.status_message = format!("Error saving config: {e}");
@@ -364,15 +524,67 @@ pub fn draw_menu_bar(app: &mut CodebaseApp, ctx: &Context) {
                         "Respect .cbvignore toggled via menu to: {}",
                         app.config.respect_cbvignore
                     );
-                    if let Some(root_path) = app.root_path.clone() {
-                        app.queue_action(AppAction::StartScan(root_path));
-                    }
+                    app.queue_action(AppAction::Rescan);
                     if let Err(e) = app.config.save() {
                         log::error!("Failed to save config after .cbvignore toggle: {e}");
                         app.status_message = format!("Error saving config: {e}");
                     }
                     ui.close_menu();
                 }
+
+                if ui
+                    .checkbox(&mut app.config.watch_filesystem, "Watch for File Changes")
+                    .on_hover_text(
+                        "Update the tree automatically when files are created, changed or deleted",
+                    )
+                    .changed()
+                {
+                    log::info!(
+                        "Filesystem watching toggled via menu to: {}",
+                        app.config.watch_filesystem
+                    );
+                    if app.config.watch_filesystem {
+                        if !app.root_ids.is_empty() {
+                            app.queue_action(AppAction::StartWatching);
+                        }
+                    } else {
+                        app.queue_action(AppAction::StopWatching);
+                    }
+                    if let Err(e) = app.config.save() {
+                        log::error!("Failed to save config after watch toggle: {e}");
+                        app.status_message = format!("Error saving config: {e}");
+                    }
+                    ui.close_menu();
+                }
+
+                if ui
+                    .checkbox(&mut app.config.show_git_status, "Show Git Status")
+                    .on_hover_text("Mark modified, added, untracked and renamed files in the tree")
+                    .changed()
+                {
+                    log::info!(
+                        "Git status display toggled via menu to: {}",
+                        app.config.show_git_status
+                    );
+                    app.queue_action(AppAction::RefreshGitStatus);
+                    if let Err(e) = app.config.save() {
+                        log::error!("Failed to save config after git status toggle: {e}");
+                        app.status_message = format!("Error saving config: {e}");
+                    }
+                    ui.close_menu();
+                }
+
+                if ui
+                    .checkbox(&mut app.config.show_license_badges, "Show License Badges")
+                    .on_hover_text("Show the license detected in each file's header in the tree")
+                    .changed()
+                {
+                    if let Err(e) = app.config.save() {
+                        log::error!("Failed to save config after license badge toggle: {e}");
+                        app.status_message = format!("Error saving config: {e}");
+                    }
+                    ui.close_menu();
+                }
             });
 
             ui.menu_button("Help", |ui| {
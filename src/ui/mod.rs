@@ -4,19 +4,26 @@
 //! application's user interface using `egui`. It includes panels, dialogs,
 //! menus, and status bars.
 
+pub mod dashboard;
 pub mod dialogs;
+pub mod markers;
 pub mod menu_bar;
 pub mod preview_panel;
 pub mod status_bar;
 pub mod tree_panel;
+pub mod treemap;
 
 // Re-export the main drawing functions for each UI component
 // This allows `app.rs` to call `ui::draw_menu_bar(...)` etc.
+pub use dashboard::draw_stats_dashboard_window;
 pub use dialogs::{
-    draw_about_window, draw_ai_query_window, draw_preferences_window, draw_report_options_window,
-    draw_shortcuts_window,
+    draw_about_window, draw_ai_query_window, draw_changed_since_window, draw_preferences_window,
+    draw_report_options_window, draw_report_parts_window, draw_scan_errors_window,
+    draw_shortcuts_window, draw_snapshot_diff_window,
 };
+pub use markers::draw_markers_window;
 pub use menu_bar::draw_menu_bar;
 pub use preview_panel::draw_preview_panel;
 pub use status_bar::draw_status_bar;
 pub use tree_panel::draw_tree_panel;
+pub use treemap::draw_treemap_window;
@@ -62,6 +62,9 @@ pub fn draw_preview_panel(app: &mut CodebaseApp, ui: &mut Ui) {
                         "Size: {} | Modified: {}",
                         node.info.human_size, modified_str
                     );
+                    if let Some(encoding) = node.info.encoding {
+                        meta_text += &format!(" | Encoding: {encoding}");
+                    }
                     // MODIFIED: Access fields directly from `tokei::Language`
                     if let Some(loc_stats) = &node.info.loc_stats {
                         meta_text += &format!(
@@ -86,12 +89,20 @@ pub fn draw_preview_panel(app: &mut CodebaseApp, ui: &mut Ui) {
                         Some(cache_mutex) => match cache_mutex.try_lock() {
                             Some(cache) => {
                                 if cache.node_id == selected_id {
-                                    preview::render_preview_content(
+                                    let target_line = app
+                                        .preview_target_line
+                                        .filter(|(id, _)| *id == selected_id)
+                                        .map(|(_, line)| line);
+                                    if preview::render_preview_content(
                                         ui,
                                         &cache.content,
                                         app.preview_word_wrap,
                                         app.preview_selectable_line_numbers,
-                                    );
+                                        target_line,
+                                        app.preview_scroll_pending,
+                                    ) {
+                                        app.preview_scroll_pending = false;
+                                    }
                                 } else {
                                     ui.horizontal(|ui| {
                                         ui.spinner();
@@ -1,7 +1,15 @@
 //! Draws the status bar at the bottom of the application window.
 
-use crate::app::{AppAction, CodebaseApp};
-use egui::{Align, Context, Layout, RichText, TopBottomPanel};
+use crate::{
+    app::{AppAction, CodebaseApp},
+    fs::{git::GitStatus, ScanProgress},
+    workspace,
+};
+use egui::{Align, Context, Layout, ProgressBar, RichText, TopBottomPanel};
+use egui_phosphor::regular::WARNING;
+
+/// Deleted files listed in the git status tooltip before the rest are summarised.
+const MAX_LISTED_DELETIONS: usize = 15;
 
 /// Draws the bottom status bar, showing messages, scan progress, and file counts.
 pub fn draw_status_bar(app: &mut CodebaseApp, ctx: &Context) {
@@ -40,6 +48,74 @@ pub fn draw_status_bar(app: &mut CodebaseApp, ctx: &Context) {
                             // Total files already shown above
                             // ui.label(format!("{} Files", stats.total_files));
                             // ui.separator();
+                            if stats.error_count() > 0 {
+                                let label = format!("{WARNING} {} errors", stats.error_count());
+                                if ui
+                                    .small_button(label)
+                                    .on_hover_text("Show the scan errors")
+                                    .clicked()
+                                {
+                                    app.show_scan_errors_window = true;
+                                }
+                                ui.separator();
+                            }
+                        }
+
+                        if let Some(git_status) = &app.git_status {
+                            let mut counts = std::collections::BTreeMap::new();
+                            for status in git_status.values() {
+                                *counts.entry(*status).or_insert(0usize) += 1;
+                            }
+                            let summary = if counts.is_empty() {
+                                "Git: clean".to_string()
+                            } else {
+                                let parts: Vec<String> = counts
+                                    .iter()
+                                    .map(|(status, count)| format!("{count}{}", status.badge()))
+                                    .collect();
+                                format!("Git: {}", parts.join(" "))
+                            };
+                            let details: Vec<String> = counts
+                                .iter()
+                                .map(|(status, count)| format!("{count} {}", status.label()))
+                                .collect();
+                            // Deleted files aren't in the tree to carry a badge; list them here.
+                            let mut deleted: Vec<String> = git_status
+                                .iter()
+                                .filter(|(_, status)| **status == GitStatus::Deleted)
+                                .map(|(path, _)| {
+                                    workspace::relative_path(path, &app.roots)
+                                        .display()
+                                        .to_string()
+                                })
+                                .collect();
+                            deleted.sort();
+                            let mut hover = if details.is_empty() {
+                                "No changes relative to HEAD".to_string()
+                            } else {
+                                details.join(", ")
+                            };
+                            if !deleted.is_empty() {
+                                hover.push_str("\n\nDeleted:");
+                                for path in deleted.iter().take(MAX_LISTED_DELETIONS) {
+                                    hover.push_str(&format!("\n  {path}"));
+                                }
+                                if deleted.len() > MAX_LISTED_DELETIONS {
+                                    hover.push_str(&format!(
+                                        "\n  ... and {} more",
+                                        deleted.len() - MAX_LISTED_DELETIONS
+                                    ));
+                                }
+                            }
+                            ui.label(RichText::new(summary).small())
+                                .on_hover_text(hover);
+                            ui.separator();
+                        }
+
+                        if !app.watchers.is_empty() {
+                            ui.label(RichText::new("Watching").small().weak())
+                                .on_hover_text("The tree updates automatically when files change");
+                            ui.separator();
                         }
 
                         // Spinner and Cancel Button (if scanning)
@@ -47,7 +123,24 @@ pub fn draw_status_bar(app: &mut CodebaseApp, ctx: &Context) {
                             if ui.button("Cancel Scan").clicked() {
                                 app.queue_action(AppAction::CancelScan); // Queue cancellation
                             }
-                            ui.spinner(); // Show spinner last (far right)
+                            if let Some(progress) = ScanProgress::combine(&app.scan_progress) {
+                                let mut hover = progress.summary();
+                                if let Some(dir) = &progress.current_dir {
+                                    hover.push_str(&format!("\nIn: {}", dir.display()));
+                                }
+                                if progress.expected_files.is_some() {
+                                    hover.push_str("\nETA is estimated from the previous scan.");
+                                }
+                                ui.add(
+                                    ProgressBar::new(progress.fraction().unwrap_or(0.0))
+                                        .desired_width(320.0)
+                                        .animate(progress.fraction().is_none())
+                                        .text(RichText::new(progress.summary()).small()),
+                                )
+                                .on_hover_text(hover);
+                            } else {
+                                ui.spinner(); // Show spinner last (far right)
+                            }
                             ui.separator();
                         }
                         // Spinner (if generating report) - No cancel button for now
@@ -2,13 +2,17 @@
 
 use crate::{
     app::{AppAction, CodebaseApp},
-    model::{Check, FileId},
+    fs::{git::GitStatus, license},
+    model::{format_count, Check, FileId, TreeColumn},
 };
 use egui::{Align, Button, Color32, CornerRadius, Id, Layout, RichText, ScrollArea, Ui};
 use egui_phosphor::regular::*;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 
+/// Width of each metric column in the tree view.
+const COLUMN_WIDTH: f32 = 60.0;
+
 // --- Static Icon Mapping ---
 // (This section remains unchanged)
 static ICONS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
@@ -100,24 +104,25 @@ pub fn draw_tree_panel(app: &mut CodebaseApp, ui: &mut Ui) {
         // Path Display
         ui.horizontal(|ui| {
             ui.label(FOLDER_NOTCH_OPEN);
-            let path_text = match &app.root_path {
-                Some(p) => p.file_name().map_or_else(
-                    || p.display().to_string(),
-                    |n| n.to_string_lossy().into_owned(),
-                ),
-                None => "No directory selected".to_string(),
+            let path_text = if app.roots.is_empty() {
+                "No directory selected".to_string()
+            } else {
+                app.project_name()
             };
-            ui.label(RichText::new(&path_text).strong()).on_hover_text(
-                app.root_path
-                    .as_ref()
-                    .map_or("", |p| p.to_str().unwrap_or("")),
-            );
+            let hover_text = app
+                .roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            ui.label(RichText::new(&path_text).strong())
+                .on_hover_text(hover_text);
         });
         ui.add_space(2.0);
 
         // Tree Control Buttons
         ui.horizontal(|ui| {
-            let tree_loaded = app.root_id.is_some();
+            let tree_loaded = !app.root_ids.is_empty();
             let button_size = egui::vec2(20.0, 20.0);
 
             if ui
@@ -164,6 +169,23 @@ pub fn draw_tree_panel(app: &mut CodebaseApp, ui: &mut Ui) {
             {
                 app.queue_action(AppAction::DeselectAllNodes);
             }
+            if ui
+                .add_enabled(
+                    tree_loaded,
+                    Button::new(FLAG)
+                        .small()
+                        .min_size(button_size)
+                        .selected(app.tree_markers_only),
+                )
+                .on_hover_text("Only show files with open markers (TODO, FIXME, ...)")
+                .clicked()
+            {
+                app.tree_markers_only = !app.tree_markers_only;
+                app.tree_rows_dirty = true;
+            }
+            ui.menu_button(COLUMNS, |ui| draw_columns_menu(app, ui))
+                .response
+                .on_hover_text("Columns and Sorting");
         });
         ui.add_space(4.0);
 
@@ -197,33 +219,50 @@ pub fn draw_tree_panel(app: &mut CodebaseApp, ui: &mut Ui) {
     ui.separator();
 
     // --- Tree View Area (with Virtual Scrolling) ---
-    if let Some(root_id) = app.root_id {
+    if !app.root_ids.is_empty() {
         let lower_search = app.search_text.to_lowercase();
         let needs_rebuild = app.tree_rows_dirty
-            || app.tree_rows_root_id != Some(root_id)
+            || app.tree_rows_root_ids != app.root_ids
             || app.tree_rows_search != lower_search;
         if needs_rebuild {
             let mut rows = Vec::new();
-            flatten_tree(app, root_id, 0, &lower_search, &mut rows);
+            for root_id in app.root_ids.clone() {
+                flatten_tree(app, root_id, 0, &lower_search, &mut rows);
+            }
             app.tree_rows_cache = rows;
             app.tree_rows_dirty = false;
-            app.tree_rows_root_id = Some(root_id);
+            app.tree_rows_root_ids.clone_from(&app.root_ids);
             app.tree_rows_search = lower_search;
         }
 
         // 2. Use `show_rows` for efficient virtual scrolling (handles its own scrolling)
         let row_height = ui.spacing().interact_size.y;
+        let mut columns = app.config.tree_columns.clone();
+        if app.config.show_token_counts
+            && !columns.is_empty()
+            && !columns.contains(&TreeColumn::Tokens)
+        {
+            // Token counts join the other columns instead of floating after the name.
+            columns.push(TreeColumn::Tokens);
+        }
         ui.scope(|ui| {
             ui.style_mut().spacing.scroll.floating = false;
             ui.style_mut().spacing.scroll.bar_inner_margin = 8.0;
-            ScrollArea::vertical().auto_shrink([false; 2]).show_rows(
+            let mut scroll_area = ScrollArea::vertical().auto_shrink([false; 2]);
+            if !columns.is_empty() {
+                // Keep the scroll bar's space reserved so the header lines up with the rows.
+                scroll_area = scroll_area
+                    .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible);
+                draw_column_header(app, ui, &columns);
+            }
+            scroll_area.show_rows(
                 ui,
                 row_height,
                 app.tree_rows_cache.len(),
                 |ui, row_range| {
                     for i in row_range {
                         if let Some((id, depth)) = app.tree_rows_cache.get(i).copied() {
-                            draw_single_row(app, ui, id, depth);
+                            draw_single_row(app, ui, id, depth, &columns);
                         }
                     }
                 },
@@ -241,8 +280,88 @@ pub fn draw_tree_panel(app: &mut CodebaseApp, ui: &mut Ui) {
     }
 }
 
+/// Draws the toolbar menu for choosing tree columns and the sort order.
+fn draw_columns_menu(app: &mut CodebaseApp, ui: &mut Ui) {
+    ui.label(RichText::new("Columns").strong());
+    let mut changed = false;
+    for column in TreeColumn::ALL {
+        let mut shown = app.config.tree_columns.contains(&column);
+        if ui.checkbox(&mut shown, column.label()).changed() {
+            changed = true;
+            if shown {
+                app.config.tree_columns.push(column);
+            } else {
+                app.config.tree_columns.retain(|&c| c != column);
+            }
+        }
+    }
+    if changed {
+        // Keep columns in a fixed order regardless of the order they were enabled in.
+        app.config
+            .tree_columns
+            .sort_by_key(|c| TreeColumn::ALL.iter().position(|a| a == c));
+        if let Err(e) = app.config.save() {
+            log::error!("Failed to save config after changing tree columns: {e}");
+        }
+    }
+
+    ui.separator();
+    ui.label(RichText::new("Sort By").strong());
+    let mut sort = app.config.tree_sort;
+    ui.radio_value(&mut sort, None, "Name");
+    for column in TreeColumn::ALL {
+        ui.radio_value(&mut sort, Some(column), column.label());
+    }
+    if sort != app.config.tree_sort {
+        app.queue_action(AppAction::SetTreeSort(sort));
+        ui.close_menu();
+    }
+}
+
+/// Draws the header above the tree's metric columns. Clicking a header sorts by it.
+fn draw_column_header(app: &mut CodebaseApp, ui: &mut Ui, columns: &[TreeColumn]) {
+    let scroll = &ui.spacing().scroll;
+    let scroll_bar_width = scroll.bar_width + scroll.bar_inner_margin + scroll.bar_outer_margin;
+    let sort = app.config.tree_sort;
+    ui.horizontal(|ui| {
+        if ui
+            .selectable_label(sort.is_none(), RichText::new("Name").small())
+            .on_hover_text("Sort by name")
+            .clicked()
+        {
+            app.queue_action(AppAction::SetTreeSort(None));
+        }
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.add_space(scroll_bar_width);
+            for &column in columns.iter().rev() {
+                let active = sort == Some(column);
+                let text = if active {
+                    format!("{SORT_DESCENDING} {}", column.label())
+                } else {
+                    column.label().to_string()
+                };
+                ui.allocate_ui_with_layout(
+                    egui::vec2(COLUMN_WIDTH, ui.available_height()),
+                    Layout::right_to_left(Align::Center),
+                    |ui| {
+                        ui.set_min_width(COLUMN_WIDTH);
+                        if ui
+                            .selectable_label(active, RichText::new(text).small())
+                            .on_hover_text(format!("Sort by {}", column.label().to_lowercase()))
+                            .clicked()
+                        {
+                            app.queue_action(AppAction::SetTreeSort(Some(column)));
+                        }
+                    },
+                );
+            }
+        });
+    });
+    ui.separator();
+}
+
 /// Recursively flattens the tree into a `Vec<TreeRow>` for virtual scrolling.
-/// Only includes nodes that match the search filter.
+/// Only includes nodes that match the search filter and, if enabled, the open markers filter.
 fn flatten_tree(
     app: &CodebaseApp,
     node_id: FileId,
@@ -250,7 +369,7 @@ fn flatten_tree(
     lower_search: &str,
     rows: &mut Vec<(FileId, usize)>,
 ) {
-    if !check_search_match_recursive(app, node_id, lower_search) {
+    if !check_search_match_recursive(app, node_id, lower_search, app.tree_markers_only) {
         return;
     }
 
@@ -265,10 +384,27 @@ fn flatten_tree(
     }
 }
 
+/// Returns the badge colour used for a git status.
+fn git_status_color(status: GitStatus) -> Color32 {
+    match status {
+        GitStatus::Modified => Color32::from_rgb(0xE2, 0xA0, 0x3F),
+        GitStatus::Added => Color32::from_rgb(0x5F, 0xB8, 0x5F),
+        GitStatus::Untracked => Color32::from_rgb(0x73, 0xC9, 0x91),
+        GitStatus::Deleted => Color32::from_rgb(0xE0, 0x5D, 0x5D),
+        GitStatus::Renamed => Color32::from_rgb(0x4F, 0x9D, 0xE0),
+    }
+}
+
 /// Draws a single row in the tree view.
 /// This function contains the logic previously in `draw_tree_node_recursive`,
 /// but without the recursion itself.
-fn draw_single_row(app: &mut CodebaseApp, ui: &mut Ui, node_id: FileId, depth: usize) {
+fn draw_single_row(
+    app: &mut CodebaseApp,
+    ui: &mut Ui,
+    node_id: FileId,
+    depth: usize,
+    columns: &[TreeColumn],
+) {
     let node = match app.nodes.get(node_id) {
         Some(n) => n,
         None => return,
@@ -281,9 +417,33 @@ fn draw_single_row(app: &mut CodebaseApp, ui: &mut Ui, node_id: FileId, depth: u
     let is_expanded = node.is_expanded;
     let extension = node.info.extension.as_deref().unwrap_or("");
     let token_count = node.token_count;
+    let metrics = node.metrics;
+    let symlink_target = node.info.symlink_target.clone();
+    let git_status = node.git_status;
+    let license = node
+        .info
+        .license
+        .as_ref()
+        .filter(|_| app.config.show_license_badges && !is_dir)
+        .cloned();
+    let missing_license = app.config.show_license_badges && license::is_missing_header(&node.info);
+    let class = node.info.class;
+    let is_archive_root = node
+        .info
+        .archive
+        .as_ref()
+        .is_some_and(|member| member.entry.is_empty());
 
     // --- Icon Selection ---
-    let icon = if is_dir {
+    let icon = if is_archive_root {
+        FILE_ZIP
+    } else if symlink_target.is_some() {
+        if is_dir {
+            FOLDER_DASHED
+        } else {
+            FILE_DASHED
+        }
+    } else if is_dir {
         if is_expanded {
             FOLDER_OPEN
         } else {
@@ -337,13 +497,64 @@ fn draw_single_row(app: &mut CodebaseApp, ui: &mut Ui, node_id: FileId, depth: u
             RichText::new(label_text)
                 .strong()
                 .color(ui.visuals().hyperlink_color)
+        } else if class.is_some() {
+            RichText::new(label_text).weak()
         } else {
             RichText::new(label_text)
         };
-        let previous_wrap = ui.style().wrap_mode;
-        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
-        let label_response = ui.add(egui::SelectableLabel::new(is_selected, display_text));
-        ui.style_mut().wrap_mode = previous_wrap;
+        // Leave room on the right for the metric columns.
+        let columns_width = columns.len() as f32 * (COLUMN_WIDTH + ui.spacing().item_spacing.x);
+        let mut label_response = ui
+            .scope(|ui| {
+                ui.set_max_width((ui.available_width() - columns_width).max(0.0));
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+                ui.add(egui::SelectableLabel::new(is_selected, display_text))
+            })
+            .inner;
+        if let Some(target) = &symlink_target {
+            label_response =
+                label_response.on_hover_text(format!("Symlink to {}", target.display()));
+        }
+        if let Some(class) = class {
+            label_response = label_response.on_hover_text(format!(
+                "{} {}",
+                class.label(),
+                if is_dir { "directory" } else { "file" }
+            ));
+        }
+
+        if let Some(status) = git_status {
+            ui.label(
+                RichText::new(status.badge())
+                    .small()
+                    .strong()
+                    .color(git_status_color(status)),
+            )
+            .on_hover_text(status.label());
+        }
+
+        if let Some(license) = &license {
+            if let Some(name) = &license.license {
+                let mut hover = format!(
+                    "License: {name} (from {})",
+                    license.source.map_or("header", |source| source.label())
+                );
+                if let Some(copyright) = &license.copyright {
+                    hover.push('\n');
+                    hover.push_str(copyright);
+                }
+                ui.label(RichText::new(name).small().weak())
+                    .on_hover_text(hover);
+            }
+        }
+        if missing_license {
+            ui.label(
+                RichText::new("no license")
+                    .small()
+                    .color(ui.visuals().warn_fg_color),
+            )
+            .on_hover_text("This source file has no SPDX tag or license header");
+        }
 
         if label_response.clicked() {
             app.selected_node_id = Some(node_id);
@@ -352,14 +563,23 @@ fn draw_single_row(app: &mut CodebaseApp, ui: &mut Ui, node_id: FileId, depth: u
             app.queue_action(AppAction::ToggleExpandState(node_id));
         }
 
-        // 4. Token Count Badge
-        if app.config.show_token_counts {
+        // 4. Metric Columns, or the Token Count Badge when no columns are shown
+        if !columns.is_empty() {
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                for &column in columns.iter().rev() {
+                    ui.allocate_ui_with_layout(
+                        egui::vec2(COLUMN_WIDTH, ui.available_height()),
+                        Layout::right_to_left(Align::Center),
+                        |ui| {
+                            ui.set_min_width(COLUMN_WIDTH);
+                            ui.label(RichText::new(column.format(&metrics)).small().weak());
+                        },
+                    );
+                }
+            });
+        } else if app.config.show_token_counts {
             if let Some(tokens) = token_count {
-                let text = if tokens >= 1000 {
-                    format!("{:.1}k", tokens as f32 / 1000.0)
-                } else {
-                    tokens.to_string()
-                };
+                let text = format_count(tokens as u64);
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     ui.label(RichText::new(text).small().weak());
                 });
@@ -427,26 +647,44 @@ fn draw_single_row(app: &mut CodebaseApp, ui: &mut Ui, node_id: FileId, depth: u
                 app.queue_action(AppAction::OpenNodeExternally(node_id_clone));
                 ui.close_menu();
             }
+
+            if app.root_ids.len() > 1
+                && app.root_ids.contains(&node_id_clone)
+                && ui.button("Remove from Workspace").clicked()
+            {
+                if let Some(node) = app.nodes.get(node_id_clone) {
+                    let path = node.path().to_path_buf();
+                    app.queue_action(AppAction::RemoveWorkspaceRoot(path));
+                }
+                ui.close_menu();
+            }
         });
     });
 }
 
 /// Helper function to check if a node or any of its descendants match the search text.
-fn check_search_match_recursive(app: &CodebaseApp, node_id: FileId, lower_search: &str) -> bool {
-    if lower_search.is_empty() {
+/// With `markers_only`, only files with comment markers (and their directories) match.
+fn check_search_match_recursive(
+    app: &CodebaseApp,
+    node_id: FileId,
+    lower_search: &str,
+    markers_only: bool,
+) -> bool {
+    if lower_search.is_empty() && !markers_only {
         return true;
     }
 
     if let Some(node) = app.nodes.get(node_id) {
-        if node.name().to_lowercase().contains(lower_search) {
-            return true;
-        }
         if node.is_dir() {
-            return node
-                .children
-                .iter()
-                .any(|&child_id| check_search_match_recursive(app, child_id, lower_search));
+            if !markers_only && node.name().to_lowercase().contains(lower_search) {
+                return true;
+            }
+            return node.children.iter().any(|&child_id| {
+                check_search_match_recursive(app, child_id, lower_search, markers_only)
+            });
         }
+        return (!markers_only || !node.info.markers().is_empty())
+            && node.name().to_lowercase().contains(lower_search);
     }
     false
 }
@@ -0,0 +1,413 @@
+//! Draws the treemap window: a zoomable squarified treemap of the scanned tree, sized by
+//! bytes, lines of code or tokens. Directories are drawn as framed groups with a name
+//! header and files as coloured tiles; unselected files are dimmed.
+
+use crate::{
+    app::{AppAction, CodebaseApp},
+    model::{Check, FileId, TreeColumn},
+    ui::dashboard::PALETTE,
+};
+use egui::{
+    Align2, Color32, Context, CursorIcon, FontId, Rect, RichText, Sense, Stroke, StrokeKind, Ui,
+    Window,
+};
+
+/// Metrics the treemap can be sized by.
+const METRICS: [TreeColumn; 3] = [TreeColumn::Size, TreeColumn::Code, TreeColumn::Tokens];
+
+/// Height of the name header drawn at the top of a directory.
+const HEADER_HEIGHT: f32 = 14.0;
+/// Directories smaller than this (in either dimension) are drawn as a single tile.
+const MIN_DIR_SIDE: f32 = 32.0;
+/// Tiles smaller than this (in either dimension) are not drawn.
+const MIN_TILE_SIDE: f32 = 2.0;
+
+/// A laid-out rectangle for one node.
+struct Tile {
+    id: FileId,
+    rect: Rect,
+    /// Whether the directory's children were laid out inside it.
+    expanded: bool,
+}
+
+/// Draws the treemap window.
+pub fn draw_treemap_window(app: &mut CodebaseApp, ctx: &Context) {
+    if !app.show_treemap_window {
+        return;
+    }
+    if app
+        .treemap_root
+        .is_some_and(|id| app.nodes.get(id).map_or(true, |node| !node.is_dir()))
+    {
+        app.treemap_root = None;
+    }
+    let mut is_open = true;
+
+    Window::new("Treemap")
+        .open(&mut is_open)
+        .resizable(true)
+        .default_size([800.0, 560.0])
+        .show(ctx, |ui| {
+            if app.root_ids.is_empty() {
+                ui.label("Open a directory to see its treemap.");
+                return;
+            }
+            draw_toolbar(app, ui);
+            ui.separator();
+            draw_treemap(app, ui, ctx);
+        });
+
+    if !is_open {
+        app.show_treemap_window = false;
+    }
+}
+
+/// Draws the metric switch and the breadcrumb path of the zoomed directory.
+fn draw_toolbar(app: &mut CodebaseApp, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Size by:");
+        for metric in METRICS {
+            let label = match metric {
+                TreeColumn::Size => "Bytes",
+                TreeColumn::Code => "Code Lines",
+                _ => metric.label(),
+            };
+            ui.selectable_value(&mut app.treemap_metric, metric, label);
+        }
+        if app.treemap_metric == TreeColumn::Tokens && !app.config.show_token_counts {
+            ui.label(
+                RichText::new("(enable View ▸ Show Token Counts)")
+                    .small()
+                    .weak(),
+            );
+        }
+    });
+
+    let mut crumbs = Vec::new();
+    let mut ancestor = app.treemap_root;
+    while let Some(id) = ancestor {
+        crumbs.push(id);
+        ancestor = if app.root_ids.contains(&id) {
+            None
+        } else {
+            app.nodes
+                .get(id)
+                .and_then(|node| node.path().parent())
+                .and_then(|parent| app.path_to_id_map.get(parent).copied())
+        };
+    }
+    crumbs.reverse();
+
+    ui.horizontal_wrapped(|ui| {
+        let mut zoom_to = None;
+        if ui
+            .selectable_label(crumbs.is_empty(), app.project_name())
+            .on_hover_text("Zoom out to the whole project")
+            .clicked()
+        {
+            zoom_to = Some(None);
+        }
+        for (i, &id) in crumbs.iter().enumerate() {
+            // A single root is already represented by the project name.
+            if i == 0 && app.root_ids.len() == 1 {
+                continue;
+            }
+            ui.label(RichText::new("›").weak());
+            let name = app.nodes[id].name().to_string();
+            if ui.selectable_label(i == crumbs.len() - 1, name).clicked() {
+                zoom_to = Some(Some(id));
+            }
+        }
+        if let Some(zoom_to) = zoom_to {
+            app.treemap_root = zoom_to;
+        }
+    });
+    ui.label(
+        RichText::new(
+            "Click a tile to select and preview it, double-click a directory to zoom in, \
+             right-click for more.",
+        )
+        .small()
+        .weak(),
+    );
+}
+
+/// Lays out and paints the treemap, and handles clicks on its tiles.
+fn draw_treemap(app: &mut CodebaseApp, ui: &mut Ui, ctx: &Context) {
+    let metric = app.treemap_metric;
+    let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click());
+
+    let top: Vec<FileId> = match app.treemap_root {
+        Some(id) => vec![id],
+        None => app.root_ids.clone(),
+    };
+    let total: u64 = top
+        .iter()
+        .map(|&id| metric.value(&app.nodes[id].metrics))
+        .sum();
+    if total == 0 {
+        ui.painter().text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            format!("Nothing to show: no {} counted yet.", metric_noun(metric)),
+            FontId::proportional(14.0),
+            ui.visuals().weak_text_color(),
+        );
+        return;
+    }
+
+    let mut tiles = Vec::new();
+    let items = sized_children(app, &top, metric);
+    for (id, tile_rect) in squarify(&items, rect) {
+        layout_node(app, id, tile_rect, metric, &mut tiles);
+    }
+
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| tiles.iter().rev().find(|tile| tile.rect.contains(pos)))
+        .map(|tile| tile.id);
+    paint_tiles(app, ui, &tiles, hovered);
+
+    if let Some(id) = hovered {
+        let node = &app.nodes[id];
+        let text = format!(
+            "{}\n{}: {}",
+            app.relative_path(node.path()).display(),
+            metric.label(),
+            metric.format(&node.metrics)
+        );
+        let response = response
+            .clone()
+            .on_hover_cursor(CursorIcon::PointingHand)
+            .on_hover_text_at_pointer(text);
+        if response.double_clicked() && node.is_dir() {
+            app.treemap_root = Some(id);
+        } else if response.clicked() {
+            app.reveal_node(id);
+            if !app.nodes[id].is_dir() {
+                app.show_preview_panel = true;
+                app.trigger_preview_load(id, ctx);
+            }
+        }
+        if response.secondary_clicked() {
+            app.treemap_context_node = Some(id);
+        }
+    }
+
+    response.context_menu(|ui| {
+        let Some(id) = app.treemap_context_node.filter(|&id| id < app.nodes.len()) else {
+            ui.close_menu();
+            return;
+        };
+        let node = &app.nodes[id];
+        let is_dir = node.is_dir();
+        let checked = node.state != Check::Unchecked;
+        ui.label(RichText::new(node.name()).strong());
+        ui.separator();
+        if ui
+            .button(if checked { "Deselect" } else { "Select" })
+            .clicked()
+        {
+            app.queue_action(AppAction::ToggleCheckState(id));
+            ui.close_menu();
+        }
+        if ui.button("Show in Tree").clicked() {
+            app.reveal_node(id);
+            ui.close_menu();
+        }
+        if is_dir && app.treemap_root != Some(id) && ui.button("Zoom In").clicked() {
+            app.treemap_root = Some(id);
+            ui.close_menu();
+        }
+    });
+}
+
+/// Word for what a metric counts, for the empty-treemap message.
+fn metric_noun(metric: TreeColumn) -> &'static str {
+    match metric {
+        TreeColumn::Size => "bytes",
+        TreeColumn::Code => "lines of code",
+        _ => "tokens",
+    }
+}
+
+/// Returns the nodes with a non-zero metric value, largest first.
+fn sized_children(app: &CodebaseApp, ids: &[FileId], metric: TreeColumn) -> Vec<(FileId, f32)> {
+    let mut items: Vec<(FileId, f32)> = ids
+        .iter()
+        .map(|&id| (id, metric.value(&app.nodes[id].metrics) as f32))
+        .filter(|&(_, value)| value > 0.0)
+        .collect();
+    items.sort_by(|a, b| b.1.total_cmp(&a.1));
+    items
+}
+
+/// Adds the tile of a node and, for directories with enough room, the tiles of its children.
+fn layout_node(
+    app: &CodebaseApp,
+    id: FileId,
+    rect: Rect,
+    metric: TreeColumn,
+    tiles: &mut Vec<Tile>,
+) {
+    if rect.width() < MIN_TILE_SIDE || rect.height() < MIN_TILE_SIDE {
+        return;
+    }
+    let node = &app.nodes[id];
+    let expanded = node.is_dir()
+        && rect.width() >= MIN_DIR_SIDE
+        && rect.height() >= MIN_DIR_SIDE + HEADER_HEIGHT;
+    tiles.push(Tile { id, rect, expanded });
+    if !expanded {
+        return;
+    }
+    let inner = Rect::from_min_max(
+        rect.min + egui::vec2(2.0, HEADER_HEIGHT),
+        rect.max - egui::vec2(2.0, 2.0),
+    );
+    let items = sized_children(app, &node.children, metric);
+    for (child_id, child_rect) in squarify(&items, inner) {
+        layout_node(app, child_id, child_rect, metric, tiles);
+    }
+}
+
+/// Paints the tiles in layout order, so children are drawn over their directories.
+fn paint_tiles(app: &CodebaseApp, ui: &Ui, tiles: &[Tile], hovered: Option<FileId>) {
+    let painter = ui.painter();
+    let visuals = ui.visuals();
+    let separator = visuals.window_fill();
+    let font = FontId::proportional(11.0);
+    for tile in tiles {
+        let node = &app.nodes[tile.id];
+        let selected = app.selected_node_id == Some(tile.id);
+        if node.is_dir() {
+            let fill = if tile.expanded {
+                visuals.faint_bg_color
+            } else {
+                visuals.widgets.inactive.bg_fill
+            };
+            painter.rect_filled(tile.rect, 2.0, fill);
+            painter.rect_stroke(
+                tile.rect,
+                2.0,
+                Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color),
+                StrokeKind::Inside,
+            );
+            if tile.rect.width() > 24.0 && tile.rect.height() >= HEADER_HEIGHT {
+                painter.with_clip_rect(tile.rect.shrink(1.0)).text(
+                    tile.rect.left_top() + egui::vec2(3.0, 1.0),
+                    Align2::LEFT_TOP,
+                    node.name(),
+                    font.clone(),
+                    visuals.text_color(),
+                );
+            }
+        } else {
+            let base = PALETTE[color_index(node.info.extension.as_deref())];
+            let alpha = match (node.state, hovered == Some(tile.id)) {
+                (_, true) => 255,
+                (Check::Unchecked, false) => 70,
+                _ => 200,
+            };
+            let fill = Color32::from_rgba_unmultiplied(base.r(), base.g(), base.b(), alpha);
+            painter.rect_filled(tile.rect, 1.0, fill);
+            painter.rect_stroke(
+                tile.rect,
+                1.0,
+                Stroke::new(0.5, separator),
+                StrokeKind::Inside,
+            );
+            if tile.rect.width() > 40.0 && tile.rect.height() > 14.0 {
+                painter.with_clip_rect(tile.rect.shrink(2.0)).text(
+                    tile.rect.left_top() + egui::vec2(3.0, 2.0),
+                    Align2::LEFT_TOP,
+                    node.name(),
+                    font.clone(),
+                    Color32::BLACK,
+                );
+            }
+        }
+        if selected {
+            painter.rect_stroke(
+                tile.rect,
+                2.0,
+                Stroke::new(2.0, visuals.selection.stroke.color),
+                StrokeKind::Inside,
+            );
+        }
+    }
+}
+
+/// Picks a stable palette colour for a file extension.
+fn color_index(extension: Option<&str>) -> usize {
+    let hash = extension.unwrap_or("").bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+    hash % PALETTE.len()
+}
+
+/// Lays out `items` (sorted largest first) in `rect` using the squarified treemap
+/// algorithm, which keeps tiles close to square by filling rows along the shorter side.
+fn squarify(items: &[(FileId, f32)], rect: Rect) -> Vec<(FileId, Rect)> {
+    let total: f32 = items.iter().map(|(_, value)| value).sum();
+    if total <= 0.0 || rect.area() <= 0.0 {
+        return Vec::new();
+    }
+    let scale = rect.area() / total;
+    let areas: Vec<f32> = items.iter().map(|(_, value)| value * scale).collect();
+
+    let mut out = Vec::with_capacity(items.len());
+    let mut rect = rect;
+    let mut start = 0;
+    while start < items.len() {
+        let side = rect.width().min(rect.height());
+        let mut end = start + 1;
+        let mut best = worst_ratio(&areas[start..end], side);
+        while end < items.len() {
+            let ratio = worst_ratio(&areas[start..=end], side);
+            if ratio > best {
+                break;
+            }
+            best = ratio;
+            end += 1;
+        }
+
+        let row_area: f32 = areas[start..end].iter().sum();
+        let thickness = row_area / side;
+        if rect.width() >= rect.height() {
+            // Column along the left edge.
+            let mut y = rect.top();
+            for i in start..end {
+                let height = areas[i] / thickness;
+                let tile =
+                    Rect::from_min_size(egui::pos2(rect.left(), y), egui::vec2(thickness, height));
+                out.push((items[i].0, tile));
+                y += height;
+            }
+            rect.min.x += thickness;
+        } else {
+            // Row along the top edge.
+            let mut x = rect.left();
+            for i in start..end {
+                let width = areas[i] / thickness;
+                let tile =
+                    Rect::from_min_size(egui::pos2(x, rect.top()), egui::vec2(width, thickness));
+                out.push((items[i].0, tile));
+                x += width;
+            }
+            rect.min.y += thickness;
+        }
+        start = end;
+    }
+    out
+}
+
+/// The largest aspect ratio of the tiles if `row` were laid out along a side of length `side`.
+fn worst_ratio(row: &[f32], side: f32) -> f32 {
+    let sum: f32 = row.iter().sum();
+    let max = row.iter().copied().fold(f32::MIN, f32::max);
+    let min = row.iter().copied().fold(f32::MAX, f32::min);
+    let side_sq = side * side;
+    let sum_sq = sum * sum;
+    (side_sq * max / sum_sq).max(sum_sq / (side_sq * min))
+}
@@ -0,0 +1,261 @@
+//! Multi-root workspaces: a named set of directories that are opened and scanned together.
+//!
+//! A workspace is stored as a small JSON file with the `.cbv-workspace` extension. Root
+//! paths are written relative to the workspace file's directory where possible, so a
+//! workspace file kept next to sibling repositories keeps working after the checkout
+//! moves. Workspace files can be opened from the GUI, the recent projects list and the CLI.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Component, Path, PathBuf},
+};
+
+/// File extension of workspace files (without the leading dot).
+pub const WORKSPACE_EXTENSION: &str = "cbv-workspace";
+
+/// Bumped whenever the workspace file layout changes.
+const WORKSPACE_VERSION: u32 = 1;
+
+/// On-disk layout of a workspace file.
+#[derive(Serialize, Deserialize, Debug)]
+struct WorkspaceFile {
+    version: u32,
+    name: String,
+    /// Root directories, relative to the workspace file's directory or absolute.
+    roots: Vec<PathBuf>,
+}
+
+/// A named set of root directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub name: String,
+    /// Absolute root directories, in display order.
+    pub roots: Vec<PathBuf>,
+    /// The file this workspace was loaded from or last saved to.
+    pub file: Option<PathBuf>,
+}
+
+impl Workspace {
+    /// Creates an unsaved workspace.
+    pub fn new(name: String, roots: Vec<PathBuf>) -> Self {
+        Self {
+            name,
+            roots,
+            file: None,
+        }
+    }
+
+    /// Loads a workspace file, resolving its roots to absolute paths.
+    ///
+    /// Fails if the file can't be parsed, any root is missing, or roots overlap.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = BufReader::new(File::open(path).map_err(|e| {
+            anyhow::anyhow!("Failed to open workspace file '{}': {}", path.display(), e)
+        })?);
+        let data: WorkspaceFile = serde_json::from_reader(file).map_err(|e| {
+            anyhow::anyhow!("Failed to parse workspace file '{}': {}", path.display(), e)
+        })?;
+        if data.version > WORKSPACE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Workspace file '{}' has version {}, but this build only supports up to version {}",
+                path.display(),
+                data.version,
+                WORKSPACE_VERSION
+            ));
+        }
+
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        let roots = data
+            .roots
+            .iter()
+            .map(|root| {
+                let joined = base.join(root);
+                joined.canonicalize().map_err(|e| {
+                    anyhow::anyhow!(
+                        "Workspace root '{}' could not be resolved: {}",
+                        joined.display(),
+                        e
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        validate_roots(&roots)?;
+
+        log::info!(
+            "Loaded workspace '{}' with {} root(s) from {}",
+            data.name,
+            roots.len(),
+            path.display()
+        );
+        Ok(Self {
+            name: data.name,
+            roots,
+            file: Some(path.canonicalize().unwrap_or_else(|_| path.to_path_buf())),
+        })
+    }
+
+    /// Writes the workspace to `path` and remembers it as the workspace's file.
+    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
+        let base = path
+            .parent()
+            .map(|p| p.canonicalize().unwrap_or_else(|_| p.to_path_buf()))
+            .unwrap_or_default();
+        let data = WorkspaceFile {
+            version: WORKSPACE_VERSION,
+            name: self.name.clone(),
+            roots: self
+                .roots
+                .iter()
+                .map(|root| relative_to(root, &base).unwrap_or_else(|| root.clone()))
+                .collect(),
+        };
+        let file = BufWriter::new(File::create(path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to create workspace file '{}': {}",
+                path.display(),
+                e
+            )
+        })?);
+        serde_json::to_writer_pretty(file, &data)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize or write workspace: {}", e))?;
+        log::info!("Saved workspace '{}' to {}", self.name, path.display());
+        self.file = Some(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        Ok(())
+    }
+}
+
+/// Returns `true` if `path` names a workspace file.
+pub fn is_workspace_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(WORKSPACE_EXTENSION))
+}
+
+/// Checks that no root is listed twice, nested inside another root or named like another
+/// root. Paths are prefixed with the root's name in a workspace, so two roots named `app`
+/// would mix up their files.
+pub fn validate_roots(roots: &[PathBuf]) -> anyhow::Result<()> {
+    for (i, a) in roots.iter().enumerate() {
+        for b in &roots[i + 1..] {
+            if a.starts_with(b) || b.starts_with(a) {
+                return Err(anyhow::anyhow!(
+                    "Workspace roots '{}' and '{}' overlap",
+                    a.display(),
+                    b.display()
+                ));
+            }
+            if root_label(a) == root_label(b) {
+                return Err(anyhow::anyhow!(
+                    "Workspace roots '{}' and '{}' have the same name '{}'",
+                    a.display(),
+                    b.display(),
+                    root_label(a)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Display name of a root directory (its final path component).
+pub fn root_label(root: &Path) -> String {
+    root.file_name().map_or_else(
+        || root.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Returns the root in `roots` that contains `path`, if any.
+pub fn root_for<'a>(path: &Path, roots: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    roots.iter().find(|root| path.starts_with(root))
+}
+
+/// Returns `path` relative to the root containing it, as shown in reports, selection
+/// files and statistics.
+///
+/// With a single root this is the plain relative path. With several roots it is
+/// prefixed by the root's name (e.g. `api/src/main.rs`) so files from different roots
+/// can't be confused. Paths outside every root are returned unchanged.
+pub fn relative_path(path: &Path, roots: &[PathBuf]) -> PathBuf {
+    let Some(root) = root_for(path, roots) else {
+        return path.to_path_buf();
+    };
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    if roots.len() > 1 {
+        PathBuf::from(root_label(root)).join(relative)
+    } else {
+        relative.to_path_buf()
+    }
+}
+
+/// Expresses absolute `path` relative to absolute directory `base`, using `..` as needed.
+/// Returns `None` if the two share no common prefix (e.g. different drives).
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_roots_rejects_overlap_and_duplicate_names() {
+        let roots = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert!(validate_roots(&roots(&["/work/api", "/work/web"])).is_ok());
+
+        let error = validate_roots(&roots(&["/work/api", "/work/api/src"])).unwrap_err();
+        assert!(error.to_string().contains("overlap"));
+        assert!(validate_roots(&roots(&["/work/api", "/work/api"])).is_err());
+
+        let error = validate_roots(&roots(&["/a/app", "/b/app"])).unwrap_err();
+        assert!(error.to_string().contains("same name 'app'"), "{error}");
+    }
+
+    #[test]
+    fn relative_paths_are_prefixed_in_workspaces() {
+        let single = [PathBuf::from("/work/api")];
+        let path = Path::new("/work/api/src/main.rs");
+        assert_eq!(relative_path(path, &single), PathBuf::from("src/main.rs"));
+
+        let several = [PathBuf::from("/work/api"), PathBuf::from("/work/web")];
+        assert_eq!(
+            relative_path(path, &several),
+            PathBuf::from("api/src/main.rs")
+        );
+        let outside = Path::new("/elsewhere/file.rs");
+        assert_eq!(relative_path(outside, &several), outside);
+    }
+
+    #[test]
+    fn relative_to_walks_up_with_dot_dot() {
+        assert_eq!(
+            relative_to(Path::new("/work/api"), Path::new("/work/config")),
+            Some(PathBuf::from("../api"))
+        );
+        assert_eq!(
+            relative_to(Path::new("/work/api/src"), Path::new("/work")),
+            Some(PathBuf::from("api/src"))
+        );
+    }
+}